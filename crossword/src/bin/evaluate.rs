@@ -0,0 +1,304 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::{ArgGroup, Parser};
+use crossword::{
+    memory::{proposals, InMemoryPuzzle},
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{
+        APIKey, Budget, KeyOpts, LLMSolver, LlmError, MockLLM, ModelConfig, Ordering, PuzzleResult,
+        SolveReport,
+    },
+    wordlist::WordList,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+
+#[derive(Parser)]
+#[clap(group(ArgGroup::new("llm").required(true).multiple(true).args(["openai", "cohere", "anthropic", "gemini", "ollama", "mock_answers"])))]
+struct Opts {
+    // Directory of puzzle YAML files written by save_crossword, including solutions
+    puzzles: PathBuf,
+    #[clap(flatten)]
+    key: KeyOpts,
+    // YAML map of clue text to answer used in place of an LLM
+    #[clap(long)]
+    mock_answers: Option<PathBuf>,
+    // File to save the JSON report into. The solver logs to stdout, so this is required
+    #[clap(long)]
+    output: PathBuf,
+    // Only evaluate the first N puzzles, in order of file name
+    #[clap(long)]
+    limit: Option<usize>,
+    // Number of puzzles to solve at the same time
+    #[clap(long, default_value_t = 1)]
+    concurrency: usize,
+    // Comma separated orderings to compare, each solving every puzzle
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "most-constrained"
+    )]
+    ordering: Vec<Ordering>,
+    // Give up on a puzzle after this many LLM calls
+    #[clap(long)]
+    max_llm_calls: Option<usize>,
+    // Give up on a puzzle after this many seconds
+    #[clap(long)]
+    max_seconds: Option<u64>,
+    // Words ranked from most to least common, one per line, tried for clues the LLM can't answer
+    #[clap(long)]
+    wordlist: Option<PathBuf>,
+    // Enter answers missing from the word list as readily as known words, to measure the check
+    #[clap(long)]
+    no_word_check: bool,
+}
+
+/// LLM to create a fresh solver with for each puzzle
+#[derive(Clone)]
+enum Llm {
+    Keys(Vec<APIKey>),
+    Mock(MockLLM),
+}
+
+/// Everything shared by the solvers of every puzzle
+#[derive(Clone)]
+struct Backend {
+    llm: Llm,
+    wordlist: Option<WordList>,
+    check_words: bool,
+}
+
+impl Backend {
+    fn solver(&self, ordering: Ordering, budget: Budget) -> Result<LLMSolver, LlmError> {
+        let mut solver = match &self.llm {
+            Llm::Keys(keys) => LLMSolver::with_keys(keys.clone(), ModelConfig::default())?,
+            Llm::Mock(llm) => LLMSolver::with_model(Box::new(llm.clone())),
+        };
+        if let Some(wordlist) = &self.wordlist {
+            solver = solver.wordlist(wordlist.clone());
+        }
+        Ok(solver
+            .ordering(ordering)
+            .budget(budget)
+            .check_words(self.check_words))
+    }
+}
+
+#[derive(Serialize)]
+struct EvaluatedPuzzle {
+    puzzle: PathBuf,
+    // Same fields as the JSON written by `crossword --output json`
+    #[serde(flatten)]
+    result: PuzzleResult,
+    seconds: f64,
+}
+
+#[derive(Serialize)]
+struct StrategyReport {
+    ordering: Ordering,
+    solved: usize,
+    solve_rate: f64,
+    llm_calls: usize,
+    cache_hits: usize,
+    cache_hit_rate: f64,
+    prompt_chars: usize,
+    conflicts: usize,
+    checkpoints: usize,
+    backtracks: usize,
+    unknown_answers: usize,
+    results: Vec<EvaluatedPuzzle>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    puzzles: usize,
+    strategies: Vec<StrategyReport>,
+}
+
+async fn solve(
+    path: &Path,
+    backend: &Backend,
+    ordering: Ordering,
+    budget: Budget,
+) -> Result<PuzzleResult, Box<dyn Error>> {
+    let puzzle = InMemoryPuzzle::from_yaml(File::open(path)?)?;
+    let mut solver = backend.solver(ordering, budget)?;
+    let report = solver.solve(&puzzle).await?;
+    let verification = puzzle.verify().await;
+    let incorrect = puzzle.incorrect_cells().await;
+    let entries = puzzle.generate_entries().await?;
+    let incorrect_entries = entries
+        .iter()
+        .filter(|e| e.positions().iter().any(|p| incorrect.contains(p)))
+        .map(|e| e.clue().to_string())
+        .collect();
+    Ok(PuzzleResult {
+        date: puzzle.date(),
+        has_solution: !verification.weak,
+        answers: proposals(&entries, solver.entered()).await,
+        incorrect_entries,
+        error: None,
+        report,
+    })
+}
+
+async fn evaluate(
+    path: PathBuf,
+    backend: Backend,
+    ordering: Ordering,
+    budget: Budget,
+) -> EvaluatedPuzzle {
+    let start = Instant::now();
+    let result = solve(&path, &backend, ordering, budget)
+        .await
+        .unwrap_or_else(|e| PuzzleResult {
+            error: Some(e.to_string()),
+            ..PuzzleResult::default()
+        });
+    EvaluatedPuzzle {
+        puzzle: path,
+        result,
+        seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// Solve every puzzle with the given ordering, sorted by path
+async fn evaluate_all(
+    paths: &[PathBuf],
+    backend: &Backend,
+    ordering: Ordering,
+    budget: Budget,
+    concurrency: usize,
+) -> Vec<EvaluatedPuzzle> {
+    // Each puzzle gets its own task so that a panic inside the solver only fails that puzzle
+    let mut results: Vec<EvaluatedPuzzle> = stream::iter(paths.iter().cloned())
+        .map(|path| {
+            let backend = backend.clone();
+            async move {
+                match tokio::spawn(evaluate(path.clone(), backend, ordering, budget)).await {
+                    Ok(result) => result,
+                    Err(e) => EvaluatedPuzzle {
+                        puzzle: path,
+                        result: PuzzleResult {
+                            error: Some(e.to_string()),
+                            ..PuzzleResult::default()
+                        },
+                        seconds: 0.0,
+                    },
+                }
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .inspect(|evaluated| {
+            let result = &evaluated.result;
+            eprintln!(
+                "{:?} {}: {}",
+                ordering,
+                evaluated.puzzle.display(),
+                match (&result.error, result.report.solved) {
+                    (Some(e), _) => format!("error ({})", e),
+                    (None, true) => String::from("solved"),
+                    (None, false) => String::from("unsolved"),
+                }
+            )
+        })
+        .collect()
+        .await;
+    results.sort_by(|a, b| a.puzzle.cmp(&b.puzzle));
+    results
+}
+
+#[tokio::main]
+async fn main() {
+    let opts: Opts = Opts::parse();
+    let llm = match opts.mock_answers {
+        Some(path) => Llm::Mock(
+            MockLLM::from_yaml(File::open(path).expect("Failed to open mock answers"))
+                .expect("Failed to parse mock answers"),
+        ),
+        None => Llm::Keys(opts.key.api_keys()),
+    };
+    let backend = Backend {
+        llm,
+        wordlist: opts
+            .wordlist
+            .map(|path| WordList::from_path(path).expect("Failed to read word list")),
+        check_words: !opts.no_word_check,
+    };
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&opts.puzzles)
+        .expect("Failed to read puzzle directory")
+        .map(|entry| entry.expect("Failed to read puzzle directory").path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        })
+        .collect();
+    paths.sort();
+    paths.truncate(opts.limit.unwrap_or(usize::MAX));
+
+    let budget = Budget {
+        llm_calls: opts.max_llm_calls,
+        time: opts.max_seconds.map(Duration::from_secs),
+        ..Budget::default()
+    };
+    let mut strategies = vec![];
+    for ordering in opts.ordering {
+        let results = evaluate_all(&paths, &backend, ordering, budget, opts.concurrency).await;
+        let solved = results.iter().filter(|r| r.result.report.solved).count();
+        let total = |count: fn(&SolveReport) -> usize| -> usize {
+            results.iter().map(|r| count(&r.result.report)).sum()
+        };
+        let strategy = StrategyReport {
+            ordering,
+            solved,
+            solve_rate: if results.is_empty() {
+                0.0
+            } else {
+                solved as f64 / results.len() as f64
+            },
+            llm_calls: total(|r| r.llm_calls),
+            cache_hits: total(|r| r.cache_hits),
+            cache_hit_rate: {
+                let hits = total(|r| r.cache_hits);
+                let questions = hits + total(|r| r.clue_latency.len());
+                if questions == 0 {
+                    0.0
+                } else {
+                    hits as f64 / questions as f64
+                }
+            },
+            prompt_chars: total(|r| r.prompt_chars),
+            conflicts: total(|r| r.conflicts),
+            checkpoints: total(|r| r.checkpoints),
+            backtracks: total(|r| r.backtracks),
+            unknown_answers: total(|r| r.unknown_answers),
+            results,
+        };
+        eprintln!(
+            "{:?}: solved {} of {} puzzles ({:.1}%) with {} LLM calls and {} backtracks",
+            ordering,
+            strategy.solved,
+            paths.len(),
+            strategy.solve_rate * 100.0,
+            strategy.llm_calls,
+            strategy.backtracks,
+        );
+        strategies.push(strategy);
+    }
+    let report = Report {
+        puzzles: paths.len(),
+        strategies,
+    };
+    serde_json::to_writer_pretty(
+        File::create(opts.output).expect("Failed to create output file"),
+        &report,
+    )
+    .expect("Failed to serialize Report");
+}
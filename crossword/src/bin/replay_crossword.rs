@@ -0,0 +1,117 @@
+use std::{error::Error, fs::File, path::PathBuf, process::ExitCode};
+
+use clap::{ArgGroup, Parser};
+use crossword::{
+    memory::InMemoryPuzzle,
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{BatchStrategy, KeyOpts, LLMSolver, MockLLM, ModelConfig, SolverEvent},
+    wordlist::WordList,
+};
+
+#[derive(Parser)]
+#[clap(group(ArgGroup::new("llm").required(true).multiple(true).args(["openai", "cohere", "anthropic", "gemini", "ollama", "mock_answers"])))]
+struct Opts {
+    // Puzzle YAML written by save_crossword
+    #[clap(long)]
+    puzzle: PathBuf,
+    #[clap(flatten)]
+    key: KeyOpts,
+    // YAML map of clue text to answer used in place of an LLM
+    #[clap(long)]
+    mock_answers: Option<PathBuf>,
+    // Print each clue, answer and the state of the grid while solving
+    #[clap(long)]
+    verbose: bool,
+    // JSON file used to reuse LLM answers between runs
+    #[clap(long)]
+    cache: Option<PathBuf>,
+    // Number of clues that share no cells to ask the LLM about at the same time
+    #[clap(long, default_value_t = 1)]
+    llm_concurrency: usize,
+    // Ask for every answer in a single prompt before solving clue by clue
+    #[clap(long, value_enum, default_value = "none")]
+    batch: BatchStrategy,
+    // Words ranked from most to least common, one per line, tried for clues the LLM can't answer
+    #[clap(long)]
+    wordlist: Option<PathBuf>,
+    // Prompt template file used in place of the default prompt for each clue
+    #[clap(long)]
+    prompt_template: Option<PathBuf>,
+    // Tell the solver which entries are wrong when the grid is not solved
+    #[clap(long)]
+    use_check: bool,
+    // Ask for the most likely answers only, so that solves can be reproduced
+    #[clap(long)]
+    deterministic: bool,
+    // Write every decision of the solver to this file as JSON lines
+    #[clap(long)]
+    trace: Option<PathBuf>,
+}
+
+async fn replay(opts: Opts) -> Result<bool, Box<dyn Error>> {
+    let puzzle = InMemoryPuzzle::from_yaml(File::open(&opts.puzzle)?)?.check(opts.use_check);
+    let mut solver = match opts.mock_answers {
+        Some(path) => LLMSolver::with_model(Box::new(MockLLM::from_yaml(File::open(path)?)?)),
+        None => LLMSolver::with_keys(
+            opts.key.api_keys(),
+            ModelConfig {
+                temperature: opts.deterministic.then_some(0.0),
+                ..ModelConfig::default()
+            },
+        )?,
+    };
+    if let Some(path) = opts.cache {
+        solver = solver.with_cache_file(path)?.on_event(|event| {
+            if let SolverEvent::CacheSaveFailed { path, error } = event {
+                eprintln!(
+                    "Failed to save answer cache to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        });
+    }
+    if let Some(path) = opts.prompt_template {
+        solver = solver.with_template(path)?;
+    }
+    if let Some(path) = opts.wordlist {
+        solver = solver.wordlist(WordList::from_path(path)?);
+    }
+    if let Some(path) = opts.trace {
+        solver = solver.trace(path)?;
+    }
+    let solved = solver
+        .verbose(opts.verbose)
+        .concurrency(opts.llm_concurrency)
+        .batch(opts.batch)
+        .solve(&puzzle)
+        .await?
+        .solved;
+
+    println!();
+    println!("{}", puzzle.render().await);
+    for entry in puzzle.generate_entries().await? {
+        println!("{} = {}", entry.clue(), entry.value().await);
+    }
+    println!();
+    Ok(solved)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let opts: Opts = Opts::parse();
+    match replay(opts).await {
+        Ok(true) => {
+            println!("Successfully solved Puzzle!");
+            ExitCode::SUCCESS
+        }
+        Ok(false) => {
+            println!("Failed to solve Puzzle!");
+            ExitCode::from(1)
+        }
+        Err(e) => {
+            eprintln!("Error replaying Puzzle: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use crossword::{
+    memory::SavedPuzzle,
+    web::{archive_url, LoginArgs, MiniCrosswordWebDriver, MINI_URL},
+    PuzzleDate,
+};
+use webdriver_common::{DriverArgs, DriverOptions, Reporter};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    driver: DriverArgs,
+    #[clap(flatten)]
+    login: LoginArgs,
+    // URL of the puzzle to save, e.g. https://www.nytimes.com/crosswords/game/daily
+    #[clap(long, default_value = MINI_URL)]
+    url: String,
+    // Save the puzzle of --url published on this day, written as YYYY-MM-DD, from the archive.
+    // The date is saved with the puzzle
+    #[clap(long)]
+    date: Option<PuzzleDate>,
+    // File to save the puzzle YAML into. Written to stdout if not provided
+    #[clap(long)]
+    output: Option<PathBuf>,
+    // Also save the solution, read with the Reveal feature. The puzzle is left revealed, and no
+    // longer counts as solved by NYT
+    #[clap(long)]
+    with_solution: bool,
+    // Clear the puzzle and its timer once the solution has been read
+    #[clap(long, requires = "with_solution")]
+    reset_after: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    let opts: Opts = Opts::parse();
+    let server = &opts.driver.chromedriver_server_url;
+    let options = DriverOptions {
+        reporter: Some(Reporter::new(|message| eprintln!("{}", message))),
+        ..opts.driver.options()
+    };
+    let url = match opts.date {
+        Some(date) => archive_url(&opts.url, date),
+        None => opts.url.clone(),
+    };
+    let driver = match opts.login.cookies().expect("Failed to load cookies") {
+        Some(cookies) => MiniCrosswordWebDriver::login(server, &url, &options, &cookies)
+            .await
+            .expect("Failed to log in"),
+        None => MiniCrosswordWebDriver::open(server, &url, &options)
+            .await
+            .expect("Failed to create WebDriver"),
+    };
+    let structure = driver
+        .get_puzzle_via_api()
+        .await
+        .expect("Failed to read Puzzle information");
+    let solution = if opts.with_solution {
+        let solution = driver
+            .reveal_solution()
+            .await
+            .expect("Failed to reveal the solution");
+        if opts.reset_after {
+            driver
+                .clear_puzzle()
+                .await
+                .expect("Failed to clear the revealed puzzle");
+        }
+        Some(solution)
+    } else {
+        None
+    };
+    let puzzle = SavedPuzzle::new(structure, solution).date(opts.date);
+    match opts.output {
+        Some(path) => serde_yaml::to_writer(
+            File::create(path).expect("Failed to create output file"),
+            &puzzle,
+        ),
+        None => serde_yaml::to_writer(std::io::stdout(), &puzzle),
+    }
+    .expect("Failed to serialize Puzzle");
+}
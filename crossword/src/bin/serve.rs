@@ -0,0 +1,92 @@
+use std::{
+    error::Error, fs::File, net::SocketAddr, path::PathBuf, process::ExitCode, time::Duration,
+};
+
+use clap::{ArgGroup, Parser};
+use crossword::{
+    server::{router, RateLimit},
+    solver::{KeyOpts, LLMSolver, MockLLM, ModelConfig, SolverEvent},
+    wordlist::WordList,
+};
+
+#[derive(Parser)]
+#[clap(group(ArgGroup::new("llm").required(true).multiple(true).args(["openai", "cohere", "anthropic", "gemini", "ollama", "mock_answers"])))]
+struct Opts {
+    #[clap(flatten)]
+    key: KeyOpts,
+    // YAML map of clue text to answer used in place of an LLM
+    #[clap(long)]
+    mock_answers: Option<PathBuf>,
+    // Model to ask, e.g. gpt-4. Defaults to the backend's own default
+    #[clap(long)]
+    model: Option<String>,
+    // Address to listen on
+    #[clap(long, default_value = "127.0.0.1:3000")]
+    address: SocketAddr,
+    // Requests accepted from each client address every minute
+    #[clap(long, default_value_t = 30)]
+    requests_per_minute: u32,
+    // JSON file used to reuse LLM answers between requests and runs
+    #[clap(long)]
+    cache: Option<PathBuf>,
+    // Words ranked from most to least common, one per line, tried for clues the LLM can't answer
+    #[clap(long)]
+    wordlist: Option<PathBuf>,
+    // Prompt template file used in place of the default prompt for each clue
+    #[clap(long)]
+    prompt_template: Option<PathBuf>,
+}
+
+async fn serve(opts: Opts) -> Result<(), Box<dyn Error>> {
+    let mut solver = match opts.mock_answers {
+        Some(path) => LLMSolver::with_model(Box::new(MockLLM::from_yaml(File::open(path)?)?)),
+        None => LLMSolver::with_keys(
+            opts.key.api_keys(),
+            ModelConfig {
+                model: opts.model,
+                ..ModelConfig::default()
+            },
+        )?,
+    };
+    if let Some(path) = opts.cache {
+        solver = solver.with_cache_file(path)?.on_event(|event| {
+            if let SolverEvent::CacheSaveFailed { path, error } = event {
+                eprintln!(
+                    "Failed to save answer cache to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        });
+    }
+    if let Some(path) = opts.prompt_template {
+        solver = solver.with_template(path)?;
+    }
+    if let Some(path) = opts.wordlist {
+        solver = solver.wordlist(WordList::from_path(path)?);
+    }
+    let limit = RateLimit {
+        requests: opts.requests_per_minute,
+        per: Duration::from_secs(60),
+    };
+    let listener = tokio::net::TcpListener::bind(opts.address).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(
+        listener,
+        router(solver, limit).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let opts: Opts = Opts::parse();
+    match serve(opts).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error serving: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
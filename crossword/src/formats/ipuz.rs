@@ -0,0 +1,279 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{grid::Grid, memory::Solution, Clue, Direction, Position, Puzzle};
+
+/// Value of a shaded square unless the document sets its own with `block`
+static DEFAULT_BLOCK: &str = "#";
+
+#[derive(Debug)]
+pub enum IpuzError {
+    Json(serde_json::Error),
+    /// A field is missing or does not have the expected type
+    Missing(&'static str),
+    /// The named grid does not have `height` rows of `width` cells
+    Size(&'static str),
+    /// Number and direction of a clue that does not begin an entry of the grid
+    UnknownClue(usize, Direction),
+}
+
+impl fmt::Display for IpuzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpuzError::Json(e) => write!(f, "document is not valid JSON: {}", e),
+            IpuzError::Missing(field) => write!(f, "document is missing {}", field),
+            IpuzError::Size(field) => write!(f, "{} does not match the dimensions", field),
+            IpuzError::UnknownClue(number, direction) => write!(
+                f,
+                "{}-{:?} does not begin an entry of the grid",
+                number, direction
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IpuzError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IpuzError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for IpuzError {
+    fn from(value: serde_json::Error) -> Self {
+        IpuzError::Json(value)
+    }
+}
+
+/// Cells can be written as a bare value or as an object with the value under `cell` or `value`
+fn cell_value(cell: &Value) -> &Value {
+    cell.get("cell")
+        .or_else(|| cell.get("value"))
+        .unwrap_or(cell)
+}
+
+/// Shaded squares have the value of `block`, and omitted cells are null
+fn is_block(cell: &Value, block: &str) -> bool {
+    match cell_value(cell) {
+        Value::Null => true,
+        Value::String(s) => s == block,
+        _ => false,
+    }
+}
+
+/// Rows of a grid of the document, checked against the dimensions of the puzzle
+fn grid_rows<'a>(
+    document: &'a Value,
+    field: &'static str,
+    width: usize,
+    height: usize,
+) -> Result<Vec<&'a Vec<Value>>, IpuzError> {
+    let rows = document
+        .get(field)
+        .and_then(Value::as_array)
+        .ok_or(IpuzError::Missing(field))?;
+    let rows: Vec<&Vec<Value>> = rows
+        .iter()
+        .map(|row| row.as_array().ok_or(IpuzError::Size(field)))
+        .collect::<Result<_, _>>()?;
+    if rows.len() != height || rows.iter().any(|row| row.len() != width) {
+        return Err(IpuzError::Size(field));
+    }
+    Ok(rows)
+}
+
+/// Number and text of a clue written as `[1, "Text"]` or `{"number": 1, "clue": "Text"}`.
+/// Numbers can also be strings
+fn clue_parts(clue: &Value) -> Option<(usize, String)> {
+    let (number, text) = match clue {
+        Value::Array(parts) => (parts.first()?, parts.get(1)?),
+        Value::Object(fields) => (fields.get("number")?, fields.get("clue")?),
+        _ => return None,
+    };
+    let number = match number {
+        Value::Number(n) => n.as_u64()? as usize,
+        Value::String(s) => s.trim().parse().ok()?,
+        _ => return None,
+    };
+    Some((number, text.as_str()?.trim().to_string()))
+}
+
+/// Parse an ipuz document. Only the grid, circled squares, clues and solution are read
+pub fn parse(json: &str) -> Result<(Puzzle, Option<Solution>), IpuzError> {
+    let document: Value = serde_json::from_str(json)?;
+    let dimension = |name| {
+        document
+            .get("dimensions")
+            .and_then(|d| d.get(name))
+            .and_then(Value::as_u64)
+            .map(|d| d as usize)
+            .ok_or(IpuzError::Missing("dimensions"))
+    };
+    let (width, height) = (dimension("width")?, dimension("height")?);
+    let block = document
+        .get("block")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_BLOCK);
+
+    let mut shaded_squares = vec![];
+    let mut circled = vec![];
+    for (row, cells) in grid_rows(&document, "puzzle", width, height)?
+        .into_iter()
+        .enumerate()
+    {
+        for (column, cell) in cells.iter().enumerate() {
+            let position = Position { row, column };
+            if is_block(cell, block) {
+                shaded_squares.push(position);
+            }
+            if cell.pointer("/style/shapebg").and_then(Value::as_str) == Some("circle") {
+                circled.push(position);
+            }
+        }
+    }
+    let grid = Grid::new(width, height, &shaded_squares);
+    let entries = grid.entries();
+
+    let sections = document
+        .get("clues")
+        .and_then(Value::as_object)
+        .ok_or(IpuzError::Missing("clues"))?;
+    let mut clues = vec![];
+    for (name, list) in sections {
+        // Sections may be given a display name after a colon, e.g. `Across:Horizontal`
+        let direction = match name.split(':').next().map(str::to_ascii_lowercase) {
+            Some(name) if name == "across" => Direction::Across,
+            Some(name) if name == "down" => Direction::Down,
+            _ => continue,
+        };
+        let list = list.as_array().ok_or(IpuzError::Missing("clues"))?;
+        for clue in list {
+            let (number, text) = clue_parts(clue).ok_or(IpuzError::Missing("clue text"))?;
+            let Some((.., positions)) = entries
+                .iter()
+                .find(|(n, d, _)| *n == number && *d == direction)
+            else {
+                return Err(IpuzError::UnknownClue(number, direction));
+            };
+            clues.push(Clue {
+                number,
+                direction,
+                text,
+                position: positions[0],
+            });
+        }
+    }
+    // Match the order the clues are listed on the NYT site
+    clues.sort_by_key(|clue| (clue.direction == Direction::Down, clue.number));
+
+    let solution = match document.get("solution") {
+        None | Some(Value::Null) => None,
+        Some(_) => {
+            let rows: Vec<Vec<Option<String>>> = grid_rows(&document, "solution", width, height)?
+                .into_iter()
+                .enumerate()
+                .map(|(row, cells)| {
+                    cells
+                        .iter()
+                        .enumerate()
+                        .map(|(column, cell)| match cell_value(cell) {
+                            _ if !grid.is_open(Position { row, column }) => None,
+                            Value::String(s) if !s.is_empty() && s != block => {
+                                Some(s.to_ascii_uppercase())
+                            }
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .collect();
+            // Documents without answers often fill the solution with empty cells
+            rows.iter()
+                .flatten()
+                .any(Option::is_some)
+                .then_some(Solution(rows))
+        }
+    };
+    Ok((
+        Puzzle {
+            width,
+            height,
+            shaded_squares,
+            clues,
+            circled,
+        },
+        solution,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, IpuzError};
+    use crate::{Direction, Position};
+
+    const MINI1: &str = include_str!("../../tests/fixtures/mini1.ipuz");
+    const MINI1_YAML: &str = include_str!("../../tests/fixtures/mini1.yaml");
+
+    #[test]
+    fn parse_matches_saved_puzzle() {
+        let saved: crate::memory::SavedPuzzle = serde_yaml::from_str(MINI1_YAML).unwrap();
+        let (puzzle, solution) = parse(MINI1).unwrap();
+        assert_eq!(puzzle, saved.puzzle);
+        assert_eq!(solution, saved.solution);
+    }
+
+    #[test]
+    fn cell_styles() {
+        // Circles, clues written as objects and a grid without a solution
+        let document = serde_json::json!({
+            "version": "http://ipuz.org/v2",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 3, "height": 2},
+            "block": ".",
+            "puzzle": [[{"cell": 1, "style": {"shapebg": "circle"}}, 2, 0], [3, 0, "."]],
+            "solution": [[null, null, null], [null, null, "."]],
+            "clues": {
+                "Across:Horizontal": [{"number": "1", "clue": " Bet "}, [3, "Up"]],
+                "Down": [[1, "Bu"], [2, "Ep"]],
+            },
+        });
+        let (puzzle, solution) = parse(&document.to_string()).unwrap();
+        assert_eq!(puzzle.shaded_squares, vec![Position { row: 1, column: 2 }]);
+        assert_eq!(puzzle.circled, vec![Position { row: 0, column: 0 }]);
+        let clues: Vec<_> = puzzle
+            .clues
+            .iter()
+            .map(|c| (c.number, c.direction, c.text.as_str()))
+            .collect();
+        assert_eq!(
+            clues,
+            vec![
+                (1, Direction::Across, "Bet"),
+                (3, Direction::Across, "Up"),
+                (1, Direction::Down, "Bu"),
+                (2, Direction::Down, "Ep"),
+            ]
+        );
+        assert_eq!(solution, None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse("{"), Err(IpuzError::Json(_))));
+        assert!(matches!(parse("{}"), Err(IpuzError::Missing("dimensions"))));
+        let mut document: serde_json::Value = serde_json::from_str(MINI1).unwrap();
+        document["puzzle"][0] = serde_json::json!([1, 2]);
+        assert!(matches!(
+            parse(&document.to_string()),
+            Err(IpuzError::Size("puzzle"))
+        ));
+        let mut document: serde_json::Value = serde_json::from_str(MINI1).unwrap();
+        document["clues"]["Down"][0][0] = serde_json::json!(6);
+        assert!(matches!(
+            parse(&document.to_string()),
+            Err(IpuzError::UnknownClue(6, Direction::Down))
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod ipuz;
+pub mod puz;
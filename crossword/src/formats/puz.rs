@@ -0,0 +1,328 @@
+use std::fmt;
+
+use crate::{grid::Grid, memory::Solution, Clue, Direction, Position, Puzzle};
+
+static MAGIC: &[u8] = b"ACROSS&DOWN\0";
+/// XORed with the low and high bytes of the masked checksums
+static MASK: &[u8] = b"ICHEATED";
+
+static MAGIC_OFFSET: usize = 0x02;
+static CIB_OFFSET: usize = 0x2C;
+static GRID_OFFSET: usize = 0x34;
+
+static SHADED: u8 = b'.';
+/// Value of a square in the solution grid when the answer is not included
+static UNKNOWN: u8 = b'-';
+
+#[derive(Debug)]
+pub enum PuzError {
+    /// File ended before all sections could be read
+    Truncated,
+    /// File does not contain the `ACROSS&DOWN` magic string
+    NotPuz,
+    /// Solution is scrambled and would need the four digit key to recover
+    Scrambled,
+    ClueCount {
+        expected: usize,
+        found: usize,
+    },
+    Checksum {
+        name: &'static str,
+        expected: u16,
+        found: u16,
+    },
+}
+
+impl fmt::Display for PuzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzError::Truncated => write!(f, "file ended unexpectedly"),
+            PuzError::NotPuz => write!(f, "file is not in the .puz format"),
+            PuzError::Scrambled => write!(f, "solution is scrambled"),
+            PuzError::ClueCount { expected, found } => write!(
+                f,
+                "grid has {} entries but file has {} clues",
+                expected, found
+            ),
+            PuzError::Checksum {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} checksum is {:#06x}, expected {:#06x}",
+                name, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PuzError {}
+
+/// Checksum used for every region of the file
+fn checksum(data: &[u8], mut sum: u16) -> u16 {
+    for b in data {
+        sum = sum.rotate_right(1).wrapping_add(*b as u16);
+    }
+    sum
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], PuzError> {
+        let slice = self
+            .bytes
+            .get(self.offset..self.offset + n)
+            .ok_or(PuzError::Truncated)?;
+        self.offset += n;
+        Ok(slice)
+    }
+
+    /// Read a NUL terminated string, not including the terminator
+    fn string(&mut self) -> Result<&'a [u8], PuzError> {
+        let length = self.bytes[self.offset..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or(PuzError::Truncated)?;
+        let s = self.take(length)?;
+        self.offset += 1;
+        Ok(s)
+    }
+}
+
+/// Strings are stored as ISO-8859-1, where every byte is the matching code point
+fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+/// Parse a .puz file, validating every checksum
+pub fn parse(bytes: &[u8]) -> Result<(Puzzle, Option<Solution>), PuzError> {
+    parse_with(bytes, true)
+}
+
+/// Parse a .puz file without validating checksums. Some tools write files with incorrect
+/// checksums that are otherwise perfectly readable
+pub fn parse_lenient(bytes: &[u8]) -> Result<(Puzzle, Option<Solution>), PuzError> {
+    parse_with(bytes, false)
+}
+
+fn parse_with(bytes: &[u8], validate: bool) -> Result<(Puzzle, Option<Solution>), PuzError> {
+    if bytes.get(MAGIC_OFFSET..MAGIC_OFFSET + MAGIC.len()) != Some(MAGIC) {
+        return Err(PuzError::NotPuz);
+    }
+    let mut reader = Reader {
+        bytes,
+        offset: CIB_OFFSET,
+    };
+    let cib = reader.take(GRID_OFFSET - CIB_OFFSET)?;
+    let (width, height) = (cib[0] as usize, cib[1] as usize);
+    let clue_count = u16::from_le_bytes([cib[2], cib[3]]) as usize;
+    if u16::from_le_bytes([cib[6], cib[7]]) != 0 {
+        return Err(PuzError::Scrambled);
+    }
+    let solution = reader.take(width * height)?;
+    let state = reader.take(width * height)?;
+    let title = reader.string()?;
+    let author = reader.string()?;
+    let copyright = reader.string()?;
+    let clues = (0..clue_count)
+        .map(|_| reader.string())
+        .collect::<Result<Vec<_>, _>>()?;
+    // Notes were only added in version 1.3 and are missing from some older files
+    let notes = reader.string().unwrap_or_default();
+
+    if validate {
+        validate_checksums(
+            bytes,
+            cib,
+            solution,
+            state,
+            &[title, author, copyright],
+            &clues,
+            notes,
+        )?;
+    }
+
+    let shaded_squares: Vec<Position> = solution
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == SHADED)
+        .map(|(i, _)| Position::from_cell_id(i, width))
+        .collect();
+    let grid = Grid::new(width, height, &shaded_squares);
+    // Clues are ordered by number, with the across clue before the down clue for a shared cell
+    let mut entries = grid.entries();
+    entries.sort_by_key(|(number, direction, _)| (*number, *direction == Direction::Down));
+    if entries.len() != clues.len() {
+        return Err(PuzError::ClueCount {
+            expected: entries.len(),
+            found: clues.len(),
+        });
+    }
+    let mut clues: Vec<Clue> = entries
+        .into_iter()
+        .zip(clues)
+        .map(|((number, direction, positions), text)| Clue {
+            number,
+            direction,
+            text: decode(text),
+            position: positions[0],
+        })
+        .collect();
+    // Match the order the clues are listed on the NYT site
+    clues.sort_by_key(|clue| (clue.direction == Direction::Down, clue.number));
+
+    let known = solution.iter().any(|b| *b != SHADED && *b != UNKNOWN);
+    let solution = known.then(|| {
+        Solution(
+            solution
+                .chunks(width)
+                .map(|row| {
+                    row.iter()
+                        .map(|b| (*b != SHADED).then(|| (*b as char).to_string()))
+                        .collect()
+                })
+                .collect(),
+        )
+    });
+    Ok((
+        Puzzle {
+            width,
+            height,
+            shaded_squares,
+            clues,
+            circled: vec![],
+        },
+        solution,
+    ))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, PuzError> {
+    let b = bytes.get(offset..offset + 2).ok_or(PuzError::Truncated)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn validate_checksums(
+    bytes: &[u8],
+    cib: &[u8],
+    solution: &[u8],
+    state: &[u8],
+    header_strings: &[&[u8]],
+    clues: &[&[u8]],
+    notes: &[u8],
+) -> Result<(), PuzError> {
+    // Title, author, copyright and notes include their NUL terminator but clues do not
+    let text_checksum = |mut sum| {
+        for s in header_strings.iter().filter(|s| !s.is_empty()) {
+            sum = checksum(&[*s, &b"\0"[..]].concat(), sum);
+        }
+        for clue in clues {
+            sum = checksum(clue, sum);
+        }
+        if !notes.is_empty() {
+            sum = checksum(&[notes, &b"\0"[..]].concat(), sum);
+        }
+        sum
+    };
+    let check = |name, expected: u16, found: u16| {
+        if expected == found {
+            Ok(())
+        } else {
+            Err(PuzError::Checksum {
+                name,
+                expected,
+                found,
+            })
+        }
+    };
+
+    let cib_sum = checksum(cib, 0);
+    check("CIB", cib_sum, read_u16(bytes, 0x0E)?)?;
+    let global = text_checksum(checksum(state, checksum(solution, cib_sum)));
+    check("file", global, read_u16(bytes, 0x00)?)?;
+
+    let sums = [
+        cib_sum,
+        checksum(solution, 0),
+        checksum(state, 0),
+        text_checksum(0),
+    ];
+    let low = bytes.get(0x10..0x14).ok_or(PuzError::Truncated)?;
+    let high = bytes.get(0x14..0x18).ok_or(PuzError::Truncated)?;
+    for (i, sum) in sums.into_iter().enumerate() {
+        let [sum_low, sum_high] = sum.to_le_bytes();
+        let expected = u16::from_le_bytes([MASK[i] ^ sum_low, MASK[i + 4] ^ sum_high]);
+        check("masked", expected, u16::from_le_bytes([low[i], high[i]]))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_lenient, PuzError};
+    use crate::{Direction, Position};
+
+    const MINI1: &[u8] = include_bytes!("../../tests/fixtures/mini1.puz");
+    const MINI2: &[u8] = include_bytes!("../../tests/fixtures/mini2.puz");
+    const MINI1_YAML: &str = include_str!("../../tests/fixtures/mini1.yaml");
+
+    #[test]
+    fn parse_matches_saved_puzzle() {
+        let saved: crate::memory::SavedPuzzle = serde_yaml::from_str(MINI1_YAML).unwrap();
+        let (puzzle, solution) = parse(MINI1).unwrap();
+        assert_eq!(puzzle, saved.puzzle);
+        assert_eq!(solution, saved.solution);
+    }
+
+    #[test]
+    fn parse_numbering() {
+        let (puzzle, solution) = parse(MINI2).unwrap();
+        assert_eq!(
+            puzzle.shaded_squares,
+            vec![
+                Position { row: 0, column: 3 },
+                Position { row: 0, column: 4 },
+                Position { row: 4, column: 0 },
+                Position { row: 4, column: 1 },
+            ]
+        );
+        let numbers: Vec<_> = puzzle.clues.iter().map(|c| c.number).collect();
+        assert_eq!(numbers, vec![1, 4, 7, 8, 9, 1, 2, 3, 5, 6]);
+        let jot = &puzzle.clues[8];
+        assert_eq!(
+            (jot.direction, jot.text.as_str(), jot.position),
+            (Direction::Down, "Jot down", Position { row: 1, column: 3 })
+        );
+        assert_eq!(
+            solution.unwrap().get(Position { row: 4, column: 2 }),
+            Some("L")
+        );
+    }
+
+    #[test]
+    fn checksums() {
+        // Change a letter of the first clue without updating any checksum
+        let mut corrupted = MINI1.to_vec();
+        let offset = corrupted.windows(5).position(|w| w == b"Money").unwrap();
+        corrupted[offset] = b'H';
+        assert!(matches!(
+            parse(&corrupted),
+            Err(PuzError::Checksum { name: "file", .. })
+        ));
+        let (puzzle, _) = parse_lenient(&corrupted).unwrap();
+        assert_eq!(puzzle.clues[0].text, "Honey in hand");
+    }
+
+    #[test]
+    fn errors() {
+        let mut scrambled = MINI1.to_vec();
+        scrambled[0x32] = 4;
+        assert!(matches!(parse(&scrambled), Err(PuzError::Scrambled)));
+        assert!(matches!(parse(&MINI1[..0x40]), Err(PuzError::Truncated)));
+        assert!(matches!(parse(b"not a puzzle"), Err(PuzError::NotPuz)));
+    }
+}
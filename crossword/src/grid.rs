@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Clue, Direction, Position};
+
+static SHADED: char = '█';
+static EMPTY: char = '·';
+
+/// Layout of a crossword, independent of its clues
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub shaded_squares: Vec<Position>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, shaded: &[Position]) -> Self {
+        Self {
+            width,
+            height,
+            shaded_squares: shaded.to_vec(),
+        }
+    }
+
+    /// Smallest grid containing all the provided open positions. Every other square is shaded
+    pub fn from_positions(positions: impl IntoIterator<Item = Position>) -> Self {
+        let open: HashSet<Position> = positions.into_iter().collect();
+        let width = open.iter().map(|p| p.column + 1).max().unwrap_or(0);
+        let height = open.iter().map(|p| p.row + 1).max().unwrap_or(0);
+        Self {
+            width,
+            height,
+            shaded_squares: (0..height)
+                .flat_map(|row| (0..width).map(move |column| Position { row, column }))
+                .filter(|p| !open.contains(p))
+                .collect(),
+        }
+    }
+
+    /// Whether the Position is inside the grid and not shaded
+    pub fn is_open(&self, position: Position) -> bool {
+        position.row < self.height
+            && position.column < self.width
+            && !self.shaded_squares.contains(&position)
+    }
+
+    /// Every open Position in reading order
+    pub fn open_positions(&self) -> Vec<Position> {
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |column| Position { row, column }))
+            .filter(|p| self.is_open(*p))
+            .collect()
+    }
+
+    /// Positions of the cells from the start of an entry until the edge of the grid or a
+    /// shaded square
+    fn positions_from(&self, start: Position, direction: Direction) -> Vec<Position> {
+        let mut position = start;
+        let mut positions = vec![];
+        while self.is_open(position) {
+            positions.push(position);
+            match direction {
+                Direction::Across => position.column += 1,
+                Direction::Down => position.row += 1,
+            }
+        }
+        positions
+    }
+
+    /// Get the positions of the Cells for the given Clue
+    pub fn positions_for_clue(&self, clue: &Clue) -> Vec<Position> {
+        self.positions_from(clue.position, clue.direction)
+    }
+
+    /// Positions of the cells for the given Clue, or `None` if the Clue does not begin an entry.
+    /// Clues on a shaded or out of bounds square, with a single cell, or in the middle of a
+    /// longer entry are all rejected
+    pub fn entry_positions(&self, clue: &Clue) -> Option<Vec<Position>> {
+        self.starts_entry(clue.position, clue.direction)
+            .then(|| self.positions_for_clue(clue))
+    }
+
+    /// Whether an entry of at least two cells begins at the Position
+    fn starts_entry(&self, position: Position, direction: Direction) -> bool {
+        let previous_closed = match direction {
+            Direction::Across => {
+                position.column == 0
+                    || !self.is_open(Position {
+                        column: position.column - 1,
+                        ..position
+                    })
+            }
+            Direction::Down => {
+                position.row == 0
+                    || !self.is_open(Position {
+                        row: position.row - 1,
+                        ..position
+                    })
+            }
+        };
+        previous_closed && self.positions_from(position, direction).len() > 1
+    }
+
+    /// Standard crossword numbering. Cells are numbered in reading order if they start an
+    /// across or down entry
+    pub fn numbered_cells(&self) -> Vec<(usize, Position)> {
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |column| Position { row, column }))
+            .filter(|p| {
+                self.is_open(*p)
+                    && (self.starts_entry(*p, Direction::Across)
+                        || self.starts_entry(*p, Direction::Down))
+            })
+            .enumerate()
+            .map(|(i, p)| (i + 1, p))
+            .collect()
+    }
+
+    /// Number, direction and cell positions of every entry. All across entries are returned
+    /// before the down entries
+    pub fn entries(&self) -> Vec<(usize, Direction, Vec<Position>)> {
+        let numbered = self.numbered_cells();
+        [Direction::Across, Direction::Down]
+            .into_iter()
+            .flat_map(|direction| {
+                numbered
+                    .iter()
+                    .filter(move |(_, p)| self.starts_entry(*p, direction))
+                    .map(move |(number, p)| {
+                        (*number, direction, self.positions_from(*p, direction))
+                    })
+            })
+            .collect()
+    }
+
+    /// Draw the grid as text, showing the value of each open cell or a dot if it is empty. Every
+    /// cell is widened to fit the longest rebus
+    pub fn render(&self, value: impl Fn(Position) -> Option<String>) -> String {
+        let values: HashMap<Position, String> = self
+            .open_positions()
+            .into_iter()
+            .filter_map(|p| value(p).map(|v| (p, v)))
+            .collect();
+        let width = values
+            .values()
+            .map(|v| v.chars().count())
+            .max()
+            .unwrap_or(1);
+        let separator = format!(
+            "+{}\n",
+            format!("{}+", "-".repeat(width + 2)).repeat(self.width)
+        );
+        let mut output = separator.clone();
+        for row in 0..self.height {
+            output.push('|');
+            for column in 0..self.width {
+                let position = Position { row, column };
+                let c = if self.is_open(position) {
+                    values
+                        .get(&position)
+                        .cloned()
+                        .unwrap_or_else(|| EMPTY.to_string())
+                } else {
+                    SHADED.to_string().repeat(width)
+                };
+                output.push_str(&format!(" {:^width$} |", c, width = width));
+            }
+            output.push('\n');
+            output.push_str(&separator);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+    use crate::{Clue, Direction, Position, Puzzle};
+
+    const MINI: &str = include_str!("../tests/fixtures/mini1.yaml");
+
+    #[test]
+    fn test_positions_for_clue() {
+        let grid = Grid::new(2, 2, &[Position { row: 0, column: 1 }]);
+        let across = grid.positions_for_clue(&Clue {
+            direction: Direction::Across,
+            position: Position { row: 0, column: 0 },
+            number: 0,
+            text: String::new(),
+        });
+        assert_eq!(across.len(), 1);
+        let down = grid.positions_for_clue(&Clue {
+            direction: Direction::Down,
+            position: Position { row: 0, column: 0 },
+            number: 0,
+            text: String::new(),
+        });
+        assert_eq!(down.len(), 2);
+    }
+
+    #[test]
+    fn entry_positions() {
+        let grid = Grid::new(2, 2, &[Position { row: 0, column: 1 }]);
+        let clue = |row, column, direction| Clue {
+            direction,
+            position: Position { row, column },
+            number: 0,
+            text: String::new(),
+        };
+        assert_eq!(
+            grid.entry_positions(&clue(0, 0, Direction::Down))
+                .map(|p| p.len()),
+            Some(2)
+        );
+        // A single cell, the middle of an entry, a shaded square and off the grid
+        assert_eq!(grid.entry_positions(&clue(0, 0, Direction::Across)), None);
+        assert_eq!(grid.entry_positions(&clue(1, 1, Direction::Across)), None);
+        assert_eq!(grid.entry_positions(&clue(0, 1, Direction::Down)), None);
+        assert_eq!(grid.entry_positions(&clue(2, 0, Direction::Across)), None);
+    }
+
+    #[test]
+    fn numbering_small_grid() {
+        let grid = Grid::new(2, 2, &[Position { row: 0, column: 1 }]);
+        assert_eq!(
+            grid.numbered_cells(),
+            vec![
+                (1, Position { row: 0, column: 0 }),
+                (2, Position { row: 1, column: 0 })
+            ]
+        );
+        assert_eq!(
+            grid.entries(),
+            vec![
+                (
+                    2,
+                    Direction::Across,
+                    vec![
+                        Position { row: 1, column: 0 },
+                        Position { row: 1, column: 1 }
+                    ]
+                ),
+                (
+                    1,
+                    Direction::Down,
+                    vec![
+                        Position { row: 0, column: 0 },
+                        Position { row: 1, column: 0 }
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn render() {
+        let grid = Grid::new(2, 2, &[Position { row: 0, column: 1 }]);
+        let rendered = grid.render(|p| (p.row == 1).then(|| String::from("A")));
+        assert_eq!(
+            rendered,
+            "+---+---+\n| · | █ |\n+---+---+\n| A | A |\n+---+---+\n"
+        );
+        let rebus = grid.render(|p| (p.column == 1).then(|| String::from("HE")));
+        assert_eq!(
+            rebus,
+            "+----+----+\n| ·  | ██ |\n+----+----+\n| ·  | HE |\n+----+----+\n"
+        );
+    }
+
+    #[test]
+    fn from_positions() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let grid = puzzle.grid();
+        let positions = puzzle
+            .clues
+            .iter()
+            .flat_map(|clue| grid.positions_for_clue(clue));
+        assert_eq!(Grid::from_positions(positions), grid);
+    }
+
+    #[test]
+    fn numbering_matches_mini() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let grid = puzzle.grid();
+        let expected: Vec<_> = puzzle
+            .clues
+            .iter()
+            .map(|clue| (clue.number, clue.direction, grid.positions_for_clue(clue)))
+            .collect();
+        assert_eq!(grid.entries(), expected);
+        assert_eq!(grid.numbered_cells().len(), 8);
+    }
+}
@@ -1,10 +1,29 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+pub mod formats;
+pub mod grid;
+pub mod memory;
+pub mod prompt;
+pub mod puzzle;
+pub mod render;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod solver;
 pub mod web;
+pub mod wordlist;
 
+use grid::Grid;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     Down,
     Across,
@@ -18,7 +37,16 @@ impl Direction {
         }
     }
 }
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Across => write!(f, "Across"),
+            Direction::Down => write!(f, "Down"),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 /// Zero-indexed grid Position
 pub struct Position {
     pub row: usize,
@@ -34,87 +62,98 @@ impl Position {
     }
 }
 
+/// Value of every filled cell in a grid. Cells and entries created from the same state share
+/// it, so two handles to one Position can never disagree. Rebus cells hold more than one letter
+#[derive(Debug, Clone, Default)]
+pub struct GridState(Arc<RwLock<HashMap<Position, String>>>);
+
+impl GridState {
+    pub fn new(values: HashMap<Position, String>) -> Self {
+        Self(Arc::new(RwLock::new(values)))
+    }
+
+    /// Independent copy of the current values
+    pub async fn snapshot(&self) -> Self {
+        Self::new(self.0.read().await.clone())
+    }
+
+    async fn get(&self, position: Position) -> Option<String> {
+        self.0.read().await.get(&position).cloned()
+    }
+
+    async fn values(&self, positions: &[Position]) -> Vec<Option<String>> {
+        let values = self.0.read().await;
+        positions.iter().map(|p| values.get(p).cloned()).collect()
+    }
+
+    /// Write each value to its Position, clearing Positions given `None`
+    async fn write(&self, positions: &[Position], values: Vec<Option<String>>) {
+        let mut guard = self.0.write().await;
+        for (position, value) in positions.iter().zip(values) {
+            match value {
+                Some(v) => guard.insert(*position, v),
+                None => guard.remove(position),
+            };
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Entry whose cells are kept in memory
 pub struct InMemoryEntry {
-    cells: Vec<InMemoryCell>,
+    clue: Clue,
+    positions: Vec<Position>,
+    circled: Vec<Position>,
+    state: GridState,
 }
 
 impl InMemoryEntry {
-    pub async fn write(&self, value: String) {
-        for (cell, c) in self.cells.iter().zip(value.chars()) {
-            cell.write(c).await
-        }
+    /// Values of the grid the entry is part of, shared with every entry created from it
+    pub fn state(&self) -> &GridState {
+        &self.state
     }
 
-    pub async fn clear(&self) {
-        for c in self.cells.iter() {
-            c.clear().await
-        }
+    /// Whether both entries read and write the same grid
+    pub fn shares_state(&self, other: &InMemoryEntry) -> bool {
+        Arc::ptr_eq(&self.state.0, &other.state.0)
     }
 
-    pub async fn chars(&self) -> Vec<Option<char>> {
-        let mut v = vec![];
-        for c in self.cells.iter() {
-            v.push(c.value().await);
-        }
-        v
+    /// Number of cells of the entry with a value
+    pub async fn filled_cells(&self) -> usize {
+        let values = self.state.values(&self.positions).await;
+        values.iter().filter(|v| v.is_some()).count()
     }
 }
 
 #[derive(Debug, Clone)]
-/// Cell whose value is kept in memory
+/// Handle to the value of a single Position in a GridState
 pub struct InMemoryCell {
-    value: Arc<RwLock<Option<char>>>,
+    state: GridState,
     position: Position,
 }
 
 impl InMemoryCell {
-    pub fn new(position: Position, value: Option<char>) -> Self {
+    pub fn new(position: Position, state: &GridState) -> Self {
         Self {
-            value: Arc::new(RwLock::new(value)),
+            state: state.clone(),
             position,
         }
     }
-    async fn value(&self) -> Option<char> {
-        *self.value.read().await
+    async fn value(&self) -> Option<String> {
+        self.state.get(self.position).await
     }
 
-    async fn write(&self, c: char) {
-        let mut guard = self.value.write().await;
-        let _ = guard.insert(c);
+    async fn write(&self, value: String) {
+        self.state.write(&[self.position], vec![Some(value)]).await
     }
 
     async fn clear(&self) {
-        self.value.write().await.take();
-    }
-}
-
-/// Get the positions of the Cells for the given Clue
-fn positions_for_clue(
-    clue: &Clue,
-    width: usize,
-    height: usize,
-    shaded_squares: &[Position],
-) -> Vec<Position> {
-    let mut position = clue.position;
-    let mut cell_positions = vec![];
-    loop {
-        // Stop conditions are either the edge of the puzzle or a shaded cell
-        if position.column >= width || position.row >= height || shaded_squares.contains(&position)
-        {
-            break cell_positions;
-        }
-        // Add this Cell to the Entry
-        cell_positions.push(position);
-        // Look at the next cell
-        match clue.direction {
-            Direction::Across => position.column += 1,
-            Direction::Down => position.row += 1,
-        }
+        self.state.write(&[self.position], vec![None]).await
     }
 }
 
 //
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Clue {
     pub number: usize,
     pub direction: Direction,
@@ -122,35 +161,545 @@ pub struct Clue {
     pub position: Position,
 }
 
+impl fmt::Display for Clue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}: {}", self.number, self.direction, self.text)
+    }
+}
+
+/// Number and direction of a Clue, which tell it apart from every other Clue of the puzzle. Cheap
+/// to copy and compare, and unaffected by how the text of the clue was read. Ordered like Clue
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ClueId {
+    pub number: usize,
+    pub direction: Direction,
+}
+
+impl fmt::Display for ClueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.number, self.direction)
+    }
+}
+
+/// What a clue says about the form of its answer, e.g. "Church area: 2 wds." or "Doctor, for
+/// short"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClueHints {
+    /// Number of words in the answer, if the clue gives it
+    pub words: Option<usize>,
+    /// The answer is an abbreviation or shortened form
+    pub abbreviation: bool,
+}
+
+impl ClueHints {
+    /// Whether the answer can be expected to be a single word found in a dictionary
+    pub fn dictionary_word(&self) -> bool {
+        self.words.unwrap_or(1) < 2 && !self.abbreviation
+    }
+}
+
+impl Clue {
+    pub fn id(&self) -> ClueId {
+        ClueId {
+            number: self.number,
+            direction: self.direction,
+        }
+    }
+
+    /// Hints at the end of the clue about the form of the answer
+    pub fn hints(&self) -> ClueHints {
+        static WORDS: OnceLock<Regex> = OnceLock::new();
+        static ABBREVIATION: OnceLock<Regex> = OnceLock::new();
+        let words = WORDS.get_or_init(|| {
+            Regex::new(r"(?i)\b(\d+|two|three|four)[\s-]*(?:wds?|words?)\b\W*$")
+                .expect("Invalid word count regex")
+        });
+        let abbreviation = ABBREVIATION.get_or_init(|| {
+            Regex::new(r"(?i)\b(?:abbr|abbrev)\b\.?\W*$|\bfor short\b")
+                .expect("Invalid abbreviation regex")
+        });
+        ClueHints {
+            words: words
+                .captures(&self.text)
+                .and_then(|c| match c[1].to_lowercase().as_str() {
+                    "two" => Some(2),
+                    "three" => Some(3),
+                    "four" => Some(4),
+                    n => n.parse().ok(),
+                }),
+            abbreviation: abbreviation.is_match(&self.text),
+        }
+    }
+
+    /// Other clues named in the text, e.g. "See 17-Across" or "With 1- and 5-Down", in the order
+    /// they appear
+    pub fn references(&self) -> Vec<(usize, Direction)> {
+        static REFERENCE: OnceLock<Regex> = OnceLock::new();
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        let re = REFERENCE.get_or_init(|| {
+            Regex::new(r"(?i)\b((?:\d+-\s*(?:,\s*|and\s+|&\s*)?)+)(across|down)\b")
+                .expect("Invalid cross-reference regex")
+        });
+        let number = NUMBER.get_or_init(|| Regex::new(r"\d+").expect("Invalid number regex"));
+        let mut references = vec![];
+        for captures in re.captures_iter(&self.text) {
+            let direction = if captures[2].eq_ignore_ascii_case("across") {
+                Direction::Across
+            } else {
+                Direction::Down
+            };
+            for n in number.find_iter(&captures[1]) {
+                let Ok(n) = n.as_str().parse() else {
+                    continue;
+                };
+                let reference = (n, direction);
+                if reference != (self.number, self.direction) && !references.contains(&reference) {
+                    references.push(reference);
+                }
+            }
+        }
+        references
+    }
+
+    /// Whether either clue names the other, so their answers are best found together
+    pub fn linked(&self, other: &Clue) -> bool {
+        self.references().contains(&(other.number, other.direction))
+            || other.references().contains(&(self.number, self.direction))
+    }
+}
+
+/// Day a puzzle was published, written as YYYY-MM-DD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PuzzleDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl PuzzleDate {
+    /// Today's date in UTC. NYT publishes by the time in New York, so this is a day ahead for
+    /// a few hours every evening there
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_days(i64::try_from(seconds / 86_400).unwrap_or(0))
+    }
+
+    /// Date `days` after 1970-01-01, by the algorithm of
+    /// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        // Months counted from March, so the leap day is last
+        let march_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * march_month + 2) / 5 + 1;
+        let month = if march_month < 10 {
+            march_month + 3
+        } else {
+            march_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        PuzzleDate {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+
+    /// Path of the puzzle in the NYT archive, e.g. 2024/03/14
+    pub fn path(&self) -> String {
+        format!("{:04}/{:02}/{:02}", self.year, self.month, self.day)
+    }
+
+    fn days_in_month(year: u16, month: u8) -> u8 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+impl fmt::Display for PuzzleDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for PuzzleDate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{:?} is not a date written as YYYY-MM-DD", s);
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        let [year, month, day] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        if [(year, 4), (month, 2), (day, 2)]
+            .iter()
+            .any(|(part, len)| part.len() != *len || !part.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let date = PuzzleDate {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        if !(1..=12).contains(&date.month)
+            || !(1..=Self::days_in_month(date.year, date.month)).contains(&date.day)
+        {
+            return Err(format!("{} is not a day of the calendar", s.trim()));
+        }
+        Ok(date)
+    }
+}
+
+impl TryFrom<String> for PuzzleDate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PuzzleDate> for String {
+    fn from(value: PuzzleDate) -> Self {
+        value.to_string()
+    }
+}
+
+/// Answer known before the solve, written as the clue's number and direction then the answer,
+/// e.g. `5A=BAGEL` or `3-Down=ORE`. A rebus square is written in brackets, e.g. `1A=T[HE]N`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub number: usize,
+    pub direction: Direction,
+    pub answer: String,
+}
+
+impl Hint {
+    /// The Clue the hint answers
+    pub fn id(&self) -> ClueId {
+        ClueId {
+            number: self.number,
+            direction: self.direction,
+        }
+    }
+}
+
+impl FromStr for Hint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{:?} is not a hint written as e.g. 5A=BAGEL", s);
+        let (clue, answer) = s.split_once('=').ok_or_else(invalid)?;
+        let clue = clue.trim();
+        let digits = clue.chars().take_while(char::is_ascii_digit).count();
+        let (number, direction) = clue.split_at(digits);
+        let direction = match direction
+            .trim_start_matches(|c: char| c == '-' || c.is_whitespace())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "a" | "across" => Direction::Across,
+            "d" | "down" => Direction::Down,
+            _ => return Err(invalid()),
+        };
+        let answer = answer.trim().to_ascii_uppercase();
+        if answer.is_empty()
+            || !answer
+                .chars()
+                .all(|c| c.is_ascii_alphabetic() || c == '[' || c == ']')
+        {
+            return Err(format!("{:?} is not an answer", answer));
+        }
+        Ok(Hint {
+            number: number.parse().map_err(|_| invalid())?,
+            direction,
+            answer,
+        })
+    }
+}
+
+/// Structure of a crossword, independent of any answers entered into it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub width: usize,
+    pub height: usize,
+    pub shaded_squares: Vec<Position>,
+    pub clues: Vec<Clue>,
+    /// Squares marked with a circle, usually spelling out part of the theme
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub circled: Vec<Position>,
+}
+
+impl Puzzle {
+    /// Layout of the puzzle without its clues
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.width, self.height, &self.shaded_squares)
+    }
+
+    /// The Clue with the given number and direction
+    pub fn clue(&self, id: ClueId) -> Option<&Clue> {
+        self.clues.iter().find(|clue| clue.id() == id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{positions_for_clue, Clue, Position};
+    use crate::{Clue, ClueHints, ClueId, Direction, Hint, Position, Puzzle, PuzzleDate};
+
+    const MINI: &str = include_str!("../tests/fixtures/mini1.yaml");
+    // On-disk format of a Clue. Changing this breaks every saved puzzle
+    const CLUE: &str = "number: 5
+direction: across
+text: Slapstick comedy
+position:
+  row: 1
+  column: 0
+";
+
+    fn slapstick() -> Clue {
+        Clue {
+            number: 5,
+            direction: Direction::Across,
+            text: String::from("Slapstick comedy"),
+            position: Position { row: 1, column: 0 },
+        }
+    }
+
+    #[test]
+    fn deserialize_puzzle() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        assert_eq!((puzzle.width, puzzle.height), (5, 5));
+        assert_eq!(
+            puzzle.shaded_squares,
+            vec![
+                Position { row: 0, column: 0 },
+                Position { row: 4, column: 4 }
+            ]
+        );
+        assert_eq!(puzzle.clues.len(), 10);
+        assert_eq!(puzzle.clues[1], slapstick());
+    }
+
+    #[test]
+    fn clue_format() {
+        assert_eq!(serde_yaml::to_string(&slapstick()).unwrap(), CLUE);
+        assert_eq!(serde_yaml::from_str::<Clue>(CLUE).unwrap(), slapstick());
+        assert_eq!(serde_yaml::to_string(&Direction::Down).unwrap(), "down\n");
+    }
+
+    #[test]
+    fn puzzle_date() {
+        let date: PuzzleDate = "2024-03-14".parse().unwrap();
+        assert_eq!(
+            date,
+            PuzzleDate {
+                year: 2024,
+                month: 3,
+                day: 14
+            }
+        );
+        assert_eq!(date.to_string(), "2024-03-14");
+        assert_eq!(date.path(), "2024/03/14");
+        assert_eq!(serde_json::to_string(&date).unwrap(), "\"2024-03-14\"");
+        assert_eq!(
+            serde_json::from_str::<PuzzleDate>("\"2024-03-14\"").unwrap(),
+            date
+        );
+
+        assert!("2024-02-29".parse::<PuzzleDate>().is_ok());
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-04-31",
+            "2024-3-14",
+            "14/03/2024",
+            "",
+        ] {
+            assert!(invalid.parse::<PuzzleDate>().is_err(), "{}", invalid);
+        }
+        assert!(serde_json::from_str::<PuzzleDate>("\"2024-00-10\"").is_err());
+
+        assert_eq!(PuzzleDate::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(PuzzleDate::from_days(11_016).to_string(), "2000-02-29");
+        assert_eq!(PuzzleDate::from_days(19_796), date);
+        assert!(PuzzleDate::today() > date);
+    }
+
+    #[test]
+    fn parse_hint() {
+        let hint = |number, direction, answer: &str| Hint {
+            number,
+            direction,
+            answer: answer.to_string(),
+        };
+        assert_eq!("5A=BAGEL".parse(), Ok(hint(5, Direction::Across, "BAGEL")));
+        assert_eq!("3D=ore".parse(), Ok(hint(3, Direction::Down, "ORE")));
+        assert_eq!(
+            " 12-across = Bagel ".parse(),
+            Ok(hint(12, Direction::Across, "BAGEL"))
+        );
+        assert_eq!(
+            "1 Down=T[HE]N".parse(),
+            Ok(hint(1, Direction::Down, "T[HE]N"))
+        );
+        for invalid in ["5A", "A=BAGEL", "5X=BAGEL", "5=BAGEL", "-5A=BAGEL"] {
+            assert!(invalid.parse::<Hint>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            "5A=ALE HOUSE".parse::<Hint>(),
+            Err(String::from("\"ALE HOUSE\" is not an answer"))
+        );
+        assert!("5A=".parse::<Hint>().is_err());
+    }
+
+    #[test]
+    fn clue_id() {
+        let id = slapstick().id();
+        assert_eq!(
+            id,
+            ClueId {
+                number: 5,
+                direction: Direction::Across
+            }
+        );
+        assert_eq!(id.to_string(), "5-Across");
+        // The text is not part of the id, however it was read
+        let reworded = Clue {
+            text: String::from("Slapstick  comedy "),
+            ..slapstick()
+        };
+        assert_eq!(reworded.id(), id);
+
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        assert_eq!(
+            puzzle.clue(id),
+            puzzle.clues.iter().find(|c| c.text == "Slapstick comedy")
+        );
+        let missing = ClueId {
+            number: 9,
+            direction: Direction::Across,
+        };
+        assert_eq!(puzzle.clue(missing), None);
+    }
+
+    #[test]
+    fn clue_round_trip() {
+        let clue = slapstick();
+        let json = serde_json::to_string(&clue).unwrap();
+        assert_eq!(serde_json::from_str::<Clue>(&json).unwrap(), clue);
+    }
+
+    fn references(text: &str) -> Vec<(usize, Direction)> {
+        Clue {
+            text: String::from(text),
+            ..slapstick()
+        }
+        .references()
+    }
 
     #[test]
-    fn test_positions_for_clue() {
-        let across = positions_for_clue(
-            &Clue {
-                direction: crate::Direction::Across,
-                position: crate::Position { row: 0, column: 0 },
-                number: 0,
-                text: String::new(),
-            },
-            2,
-            2,
-            &[Position { row: 0, column: 1 }],
+    fn cross_references() {
+        assert_eq!(references("See 17-Across"), [(17, Direction::Across)]);
+        assert_eq!(
+            references("With 3-Down, a breakfast order"),
+            [(3, Direction::Down)]
+        );
+        assert_eq!(
+            references("See 1- and 7-down"),
+            [(1, Direction::Down), (7, Direction::Down)]
         );
-        assert_eq!(across.len(), 1);
-        let down = positions_for_clue(
-            &Clue {
-                direction: crate::Direction::Down,
-                position: crate::Position { row: 0, column: 0 },
-                number: 0,
-                text: String::new(),
-            },
-            2,
-            2,
-            &[Position { row: 0, column: 1 }],
+        assert_eq!(
+            references("1-, 2- & 4-Across, or a hint to 6-Down"),
+            [
+                (1, Direction::Across),
+                (2, Direction::Across),
+                (4, Direction::Across),
+                (6, Direction::Down)
+            ]
         );
-        assert_eq!(down.len(), 2);
+        assert_eq!(
+            references("Like 1-Across and 1-Across"),
+            [(1, Direction::Across)]
+        );
+        // A clue never refers to itself, and numbers without a direction are not references
+        assert!(references("5-Across, backwards").is_empty());
+        assert!(references("Catch-22 or 9-to-5, e.g.").is_empty());
+        assert!(references("Come across").is_empty());
+
+        let see = Clue {
+            text: String::from("See 5-Across"),
+            number: 1,
+            direction: Direction::Down,
+            ..slapstick()
+        };
+        assert!(see.linked(&slapstick()));
+        assert!(slapstick().linked(&see));
+    }
+
+    fn hints(text: &str) -> ClueHints {
+        Clue {
+            text: String::from(text),
+            ..slapstick()
+        }
+        .hints()
+    }
+
+    #[test]
+    fn clue_hints() {
+        for text in [
+            "Participate: 2 wds.",
+            "Participate (2 wds.)",
+            "Participate, in two words",
+            "Participate: 2 words",
+            "Participate -- 2 wds",
+        ] {
+            assert_eq!(hints(text).words, Some(2), "{}", text);
+            assert!(!hints(text).abbreviation);
+        }
+        assert_eq!(hints("Stay up late, 3 wds.").words, Some(3));
+        for text in [
+            "Doctor's org.: Abbr.",
+            "Doctor's org. (abbr.)",
+            "Doctor's org., Abbrev.",
+            "Doctor, for short",
+            "Mister, for short: 2 wds.",
+        ] {
+            assert!(hints(text).abbreviation, "{}", text);
+        }
+        assert_eq!(hints("Mister, for short: 2 wds.").words, Some(2));
+        // Numbers and words earlier in the clue are not hints
+        for text in [
+            "Slapstick comedy",
+            "2 words that rhyme, e.g.",
+            "Words of wisdom",
+        ] {
+            assert_eq!(hints(text), ClueHints::default(), "{}", text);
+        }
+        assert!(hints("Slapstick comedy").dictionary_word());
+        assert!(!hints("Participate: 2 wds.").dictionary_word());
+        assert!(!hints("Doctor, for short").dictionary_word());
+    }
+
+    #[test]
+    fn display_clue() {
+        assert_eq!(slapstick().to_string(), "5-Across: Slapstick comedy");
+    }
+
+    #[test]
+    fn puzzle_round_trip() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let yaml = serde_yaml::to_string(&puzzle).unwrap();
+        assert_eq!(serde_yaml::from_str::<Puzzle>(&yaml).unwrap(), puzzle);
     }
 }
@@ -1,61 +1,503 @@
-use clap::{Args, Parser};
+use std::{
+    collections::HashMap, error::Error, fmt, fs, path::PathBuf, process::ExitCode, str::FromStr,
+    time::Duration,
+};
+
+use clap::{ArgGroup, Args, Parser};
 use crossword::{
-    solver::{APIKey, LLMSolver},
-    web::MiniWebPuzzle,
+    memory::{proposals, InMemoryPuzzle},
+    puzzle::{grid_state, CrosswordPuzzle, Entry},
+    render,
+    solver::{
+        BatchStrategy, BudgetExceeded, KeyOpts, LLMSolver, LlmError, ModelConfig, PuzzleResult,
+        SolveReport, SolverError, SolverEvent,
+    },
+    web::{
+        archive_url, CompletionState, LoginArgs, MiniWebPuzzle, ScrapeCache, WebEntry,
+        DEFAULT_ENTRY_DELAY, MINI_URL,
+    },
+    wordlist::WordList,
+    Hint, PuzzleDate,
 };
+use thirtyfour::prelude::WebDriverError;
+use webdriver_common::{DriverArgs, DriverOptions, Reporter};
 
 #[derive(Parser)]
+#[clap(group(ArgGroup::new("llm").required(true).multiple(true).args(["openai", "cohere", "anthropic", "gemini", "ollama"])))]
 struct Opts {
-    // Path to the Chrome binary. The 'thirtyfour' library will attempt to
-    // find the binary itself, but certain installations may require this
-    // to be passed explicitly.
-    #[clap(long)]
-    chrome_binary_path: Option<String>,
-    // URL of running chromedriver application
-    #[clap(long, default_value = "http://localhost:9515")]
-    chromedriver_server_url: String,
+    #[clap(flatten)]
+    driver: DriverArgs,
+    #[clap(flatten)]
+    login: LoginArgs,
     #[clap(flatten)]
     key: KeyOpts,
+    // Model to ask, e.g. gpt-4. Defaults to the backend's own default
+    #[clap(long)]
+    model: Option<String>,
+    // Sampling temperature. Lower values give more predictable answers
+    #[clap(long)]
+    temperature: Option<f32>,
+    // URL of the puzzle to solve, e.g. https://www.nytimes.com/crosswords/game/daily
+    #[clap(long, default_value = MINI_URL)]
+    url: String,
+    // Solve the puzzle of --url published on this day, written as YYYY-MM-DD, from the archive
+    #[clap(long)]
+    date: Option<PuzzleDate>,
+    // Print each clue, answer and the state of the grid while solving
+    #[clap(long)]
+    verbose: bool,
+    // JSON file used to reuse LLM answers between runs
+    #[clap(long)]
+    cache: Option<PathBuf>,
+    // Directory to save the structure of each day's puzzle into, so that running again on the
+    // same puzzle doesn't read it from NYT
+    #[clap(long)]
+    scrape_cache: Option<PathBuf>,
+    // Number of clues that share no cells to ask the LLM about at the same time
+    #[clap(long, default_value_t = 1)]
+    llm_concurrency: usize,
+    // Ask for every answer in a single prompt before solving clue by clue
+    #[clap(long, value_enum, default_value = "none")]
+    batch: BatchStrategy,
+    // Words ranked from most to least common, one per line, tried for clues the LLM can't answer
+    #[clap(long)]
+    wordlist: Option<PathBuf>,
+    // Prompt template file used in place of the default prompt for each clue
+    #[clap(long)]
+    prompt_template: Option<PathBuf>,
+    // Use the Check feature to find wrong answers when the grid is not solved
+    #[clap(long)]
+    use_check: bool,
+    // Answer known before the solve, e.g. 5A=BAGEL, entered first and never replaced. Can be
+    // given more than once
+    #[clap(long)]
+    hint: Vec<Hint>,
+    // Continue from the letters already in the grid, e.g. after an interrupted run. Without it
+    // a grid that has letters in it is not touched
+    #[clap(long)]
+    resume: bool,
+    // Empty the grid before solving, so letters left in it by hand are not built on
+    #[clap(long, conflicts_with_all = ["resume", "dry_run"])]
+    clear_first: bool,
+    // Clear a puzzle that was already solved and solve it again. Without it the solver stops
+    #[clap(long)]
+    force: bool,
+    // Shortest time in milliseconds spent typing each letter into the page
+    #[clap(long)]
+    entry_delay_ms: Option<u64>,
+    // Solve in memory and only type the answers into the page once the grid is full
+    #[clap(long)]
+    in_memory: bool,
+    // Ask for the most likely answers only, so that solves can be reproduced
+    #[clap(long)]
+    deterministic: bool,
+    // Write every decision of the solver to this file as JSON lines
+    #[clap(long)]
+    trace: Option<PathBuf>,
+    // Solve a copy of the puzzle in memory and print the answers it would enter, without typing
+    // anything into the page or checking them
+    #[clap(long, conflicts_with = "force")]
+    dry_run: bool,
+    // Write the result as JSON, either `json` to print it in place of the summary or
+    // `json=PATH` to save it to a file
+    #[clap(long)]
+    output: Option<Output>,
+    #[clap(flatten)]
+    export: ExportOpts,
+}
+
+/// Where the result is written by `--output`
+#[derive(Clone)]
+enum Output {
+    Stdout,
+    File(PathBuf),
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            None if s == "json" => Ok(Output::Stdout),
+            Some(("json", path)) if !path.is_empty() => Ok(Output::File(path.into())),
+            _ => Err(format!("expected json or json=PATH, not {:?}", s)),
+        }
+    }
+}
+
+impl Output {
+    fn write(&self, result: &PuzzleResult) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(result)?;
+        match self {
+            Output::Stdout => println!("{}", json),
+            Output::File(path) => fs::write(path, json)?,
+        }
+        Ok(())
+    }
+}
+
+/// Kind of error that stopped the solve, each with its own exit code
+#[derive(Clone, Copy)]
+enum Failure {
+    WebDriver,
+    Llm,
+    Timeout,
+    Other,
+}
+
+impl Failure {
+    /// The first error in the chain of sources that says what kind of failure it was
+    fn of(error: &(dyn Error + 'static)) -> Self {
+        let mut source = Some(error);
+        while let Some(error) = source {
+            if let Some(SolverError::Budget(BudgetExceeded::Time)) =
+                error.downcast_ref::<SolverError>()
+            {
+                return Failure::Timeout;
+            }
+            match error.downcast_ref::<LlmError>() {
+                Some(LlmError::Timeout) => return Failure::Timeout,
+                Some(_) => return Failure::Llm,
+                None => {}
+            }
+            if error.is::<WebDriverError>() {
+                return Failure::WebDriver;
+            }
+            source = error.source();
+        }
+        Failure::Other
+    }
+
+    fn exit_code(self) -> u8 {
+        match self {
+            Failure::WebDriver | Failure::Other => 2,
+            Failure::Llm => 3,
+            Failure::Timeout => 4,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Failure::WebDriver => "webdriver",
+            Failure::Llm => "llm",
+            Failure::Timeout => "timeout",
+            Failure::Other => "error",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 #[derive(Args)]
-#[group(required = true, multiple = false)]
-struct KeyOpts {
+struct ExportOpts {
+    // Save an SVG image of the grid after a successful solve
     #[clap(long)]
-    openai: Option<String>,
+    export_svg: Option<PathBuf>,
+    // Save the grid as text after a successful solve
     #[clap(long)]
-    cohere: Option<String>,
+    export_txt: Option<PathBuf>,
+    // Save a PNG image of the grid after a successful solve
+    #[cfg(feature = "png")]
+    #[clap(long)]
+    export_png: Option<PathBuf>,
+}
+
+/// Write the final grid to every requested export path
+async fn export(
+    opts: &ExportOpts,
+    puzzle: &MiniWebPuzzle,
+    entries: &[WebEntry],
+) -> Result<(), Box<dyn Error>> {
+    let structure = puzzle.puzzle().await?;
+    let state = grid_state(entries).await;
+    if let Some(path) = &opts.export_svg {
+        fs::write(path, render::to_svg(&structure, &state).await)?;
+    }
+    if let Some(path) = &opts.export_txt {
+        fs::write(path, render::to_text(&structure, &state).await)?;
+    }
+    #[cfg(feature = "png")]
+    if let Some(path) = &opts.export_png {
+        fs::write(path, render::to_png(&structure, &state).await?)?;
+    }
+    Ok(())
+}
+
+/// Whether any cell of the grid has a letter in it
+async fn has_letters(entries: &[WebEntry]) -> bool {
+    for entry in entries {
+        if entry.cells().await.iter().any(Option::is_some) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Solve a copy of the puzzle in memory, starting from the letters on the page, and print the
+/// answer proposed for each clue with the grid they fill. The result is only solved if every
+/// cell was filled
+async fn dry_run(
+    solver: &mut LLMSolver,
+    puzzle: &MiniWebPuzzle,
+    entries: &[WebEntry],
+    quiet: bool,
+) -> Result<PuzzleResult, Box<dyn Error>> {
+    let mut letters = HashMap::new();
+    for entry in entries {
+        for (position, value) in entry.positions().into_iter().zip(entry.cells().await) {
+            if let Some(value) = value {
+                letters.insert(position, value);
+            }
+        }
+    }
+    let memory = InMemoryPuzzle::new(puzzle.puzzle().await?, None).with_letters(letters);
+    let report = solver.solve(&memory).await?;
+    let dry_run = memory.dry_run(solver.entered()).await?;
+    let result = PuzzleResult {
+        answers: dry_run.proposals,
+        report: SolveReport {
+            solved: dry_run.complete,
+            ..report
+        },
+        ..PuzzleResult::default()
+    };
+    if quiet {
+        return Ok(result);
+    }
+    print_report(&result.report);
+    for proposal in &result.answers {
+        println!(
+            "{:<50}{:<12}{:>10}",
+            proposal.clue.to_string(),
+            proposal.answer.as_deref().unwrap_or("-"),
+            proposal
+                .confidence
+                .map_or_else(|| String::from("-"), |c| format!("{}%", c))
+        );
+    }
+    println!();
+    println!("{}", memory.render().await);
+    Ok(result)
+}
+
+/// Summarize the work done by the solver, with the slowest clues last
+fn print_report(report: &SolveReport) {
+    let rows = [
+        ("LLM calls", report.llm_calls.to_string()),
+        ("Cache hits", report.cache_hits.to_string()),
+        (
+            "Cache hit rate",
+            format!("{:.1}%", report.cache_hit_rate() * 100.0),
+        ),
+        ("Batch answers", report.batch_answers.to_string()),
+        ("Prompt characters", report.prompt_chars.to_string()),
+        ("Retries", report.retries.to_string()),
+        ("Conflicts", report.conflicts.to_string()),
+        ("Checkpoints", report.checkpoints.to_string()),
+        ("Backtracks", report.backtracks.to_string()),
+        ("Duplicate states", report.duplicate_states.to_string()),
+        ("Rejected answers", report.rejected_answers.to_string()),
+        ("Incorrect entries", report.incorrect_entries.to_string()),
+        ("Suspects cleared", report.suspects_cleared.to_string()),
+        ("Word list answers", report.wordlist_answers.to_string()),
+        ("Unknown answers", report.unknown_answers.to_string()),
+        (
+            "Wall time",
+            format!("{:.1}s", report.wall_time.as_secs_f64()),
+        ),
+    ];
+    println!();
+    for (name, value) in rows {
+        println!("{:<20}{:>10}", name, value);
+    }
+    if let Some(time) = report.page_time {
+        let time = format!("{:.0}s", time.solve_time().as_secs_f64());
+        println!("{:<20}{:>10}", "Page time", time);
+    }
+    if !report.backends.is_empty() {
+        println!();
+        println!(
+            "{:<20}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "Backend", "Calls", "Errors", "Chosen", "Agreed", "Latency"
+        );
+        for backend in &report.backends {
+            println!(
+                "{:<20}{:>10}{:>10}{:>10}{:>10}{:>9.1}s",
+                backend.name,
+                backend.calls,
+                backend.errors,
+                backend.chosen,
+                backend.agreed,
+                backend.latency.as_secs_f64()
+            );
+        }
+    }
+    let mut latencies = report.clue_latency.clone();
+    latencies.sort_by_key(|l| l.latency);
+    if !latencies.is_empty() {
+        println!();
+        for latency in latencies {
+            println!(
+                "{:<50}{:>9.2}s",
+                format!("{} ({})", latency.clue, latency.pattern),
+                latency.latency.as_secs_f64()
+            );
+        }
+    }
+    println!();
 }
 
-impl From<KeyOpts> for APIKey {
-    fn from(opts: KeyOpts) -> Self {
-        if let Some(k) = opts.openai {
-            APIKey::OpenAI(k)
-        } else if let Some(k) = opts.cohere {
-            APIKey::Cohere(k)
-        } else {
-            panic!("No key provided")
+async fn run(opts: Opts, date: PuzzleDate) -> Result<PuzzleResult, Box<dyn Error>> {
+    let server = &opts.driver.chromedriver_server_url;
+    let options = DriverOptions {
+        reporter: Some(Reporter::new(|message| eprintln!("{}", message))),
+        ..opts.driver.options()
+    };
+    let url = match opts.date {
+        Some(date) => archive_url(&opts.url, date),
+        None => opts.url.clone(),
+    };
+    let mut puzzle = match opts.login.cookies()? {
+        Some(cookies) => MiniWebPuzzle::login(server, &url, &options, &cookies).await?,
+        None => MiniWebPuzzle::open(server, &url, &options).await?,
+    }
+    .use_check(opts.use_check)
+    .entry_delay(
+        opts.entry_delay_ms
+            .map_or(DEFAULT_ENTRY_DELAY, Duration::from_millis),
+    );
+    if let Some(dir) = opts.scrape_cache {
+        puzzle = puzzle.scrape_cache(ScrapeCache::new(dir, date));
+    }
+    let config = ModelConfig {
+        model: opts.model,
+        // Sampling at zero temperature always gives the same answer
+        temperature: opts.temperature.or(opts.deterministic.then_some(0.0)),
+        ..ModelConfig::default()
+    };
+    let mut solver = LLMSolver::with_keys(opts.key.api_keys(), config)?
+        .verbose(opts.verbose)
+        .concurrency(opts.llm_concurrency)
+        .batch(opts.batch)
+        .hints(
+            opts.hint
+                .into_iter()
+                .map(|hint| (hint.id(), hint.answer))
+                .collect(),
+        )
+        .in_memory(opts.in_memory);
+    if let Some(path) = opts.cache {
+        solver = solver.with_cache_file(path)?.on_event(|event| {
+            if let SolverEvent::CacheSaveFailed { path, error } = event {
+                eprintln!(
+                    "Failed to save answer cache to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        });
+    }
+    if let Some(path) = opts.prompt_template {
+        solver = solver.with_template(path)?;
+    }
+    if let Some(path) = opts.wordlist {
+        solver = solver.wordlist(WordList::from_path(path)?);
+    }
+    if let Some(path) = opts.trace {
+        solver = solver.trace(path)?;
+    }
+    match puzzle.completion_state().await? {
+        CompletionState::Completed if opts.force => puzzle.clear_puzzle().await?,
+        CompletionState::Completed => {
+            return Err(
+                "the puzzle has already been solved, pass --force to clear it and solve it again"
+                    .into(),
+            )
         }
+        CompletionState::InProgress | CompletionState::NotStarted => {}
+    }
+    if opts.clear_first {
+        puzzle.clear_grid().await?;
+    }
+    let entries = puzzle.generate_entries().await?;
+    if !opts.resume && has_letters(&entries).await {
+        return Err(
+            "the grid already has letters in it, pass --resume to continue from them".into(),
+        );
+    }
+    if opts.resume {
+        solver = solver.wrong_letters(puzzle.wrong_letters().await?);
+    }
+    // The summary is left out when the result is printed as JSON, so the output can be parsed
+    let quiet = matches!(opts.output, Some(Output::Stdout));
+    if opts.dry_run {
+        let result = dry_run(&mut solver, &puzzle, &entries, quiet).await?;
+        return Ok(PuzzleResult {
+            date: Some(date),
+            ..result
+        });
     }
+    let report = solver.solve_entries(&puzzle, entries.clone()).await?;
+    if !quiet {
+        print_report(&report);
+    }
+    if report.solved {
+        export(&opts.export, &puzzle, &entries).await?;
+    }
+    Ok(PuzzleResult {
+        date: Some(date),
+        // The puzzle itself says whether the grid is solved
+        has_solution: true,
+        answers: proposals(&entries, solver.entered()).await,
+        report,
+        ..PuzzleResult::default()
+    })
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let opts: Opts = Opts::parse();
-    let puzzle = MiniWebPuzzle::new(
-        &opts.chromedriver_server_url,
-        opts.chrome_binary_path.as_deref(),
-    )
-    .await
-    .expect("Failed to read Puzzle information");
-    let mut solver = LLMSolver::new(opts.key.into()).expect("Failed to load GPTSolver");
-    if solver
-        .solve(&puzzle)
-        .await
-        .expect("Failed to solve Crossword puzzle!")
-    {
-        println!("Successfully solved Puzzle!")
-    } else {
-        println!("Failed to solve Puzzle!")
+    let output = opts.output.clone();
+    let quiet = matches!(output, Some(Output::Stdout));
+    let date = opts.date.unwrap_or_else(PuzzleDate::today);
+    let (result, code) = match run(opts, date).await {
+        Ok(result) => {
+            let code = match (result.report.solved, result.report.timed_out) {
+                (true, _) => 0,
+                (false, false) => 1,
+                (false, true) => Failure::Timeout.exit_code(),
+            };
+            if !quiet {
+                match result.report.solved {
+                    true => println!("Successfully solved Puzzle!"),
+                    false => println!("Failed to solve Puzzle!"),
+                }
+            }
+            (result, code)
+        }
+        Err(e) => {
+            let failure = Failure::of(e.as_ref());
+            // Errors from chromedriver span several lines
+            let message = e
+                .to_string()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            eprintln!("Error solving Puzzle ({}): {}", failure, message);
+            let result = PuzzleResult {
+                date: Some(date),
+                error: Some(message),
+                ..PuzzleResult::default()
+            };
+            (result, failure.exit_code())
+        }
+    };
+    if let Some(output) = output {
+        if let Err(e) = output.write(&result) {
+            eprintln!("Error writing result (error): {}", e);
+            return ExitCode::from(Failure::Other.exit_code());
+        }
     }
+    ExitCode::from(code)
 }
@@ -0,0 +1,808 @@
+use std::{collections::HashMap, io::Read};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thirtyfour::prelude::WebDriverResult;
+
+use crate::{
+    puzzle::{
+        circled_positions, positions_for_clues, CrosswordPuzzle, Entry, Feedback, PuzzleError,
+    },
+    render,
+    solver::Candidate,
+    Clue, ClueId, GridState, InMemoryEntry, Position, Puzzle, PuzzleDate,
+};
+
+/// Correct value of every cell, indexed by row then column. Shaded squares are `None` and
+/// rebus cells hold more than one letter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Solution(pub Vec<Vec<Option<String>>>);
+
+impl Solution {
+    /// Correct value of the cell at the provided Position
+    pub fn get(&self, position: Position) -> Option<&str> {
+        self.0
+            .get(position.row)
+            .and_then(|row| row.get(position.column))
+            .and_then(|cell| cell.as_deref())
+    }
+}
+
+/// Version of the saved puzzle format written by this crate. Files saved before the format was
+/// versioned are version 1, version 2 added circled squares and version 3 the date
+pub static FORMAT_VERSION: u32 = 3;
+
+fn first_version() -> u32 {
+    1
+}
+
+/// Puzzle as written to disk, optionally including the answers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedPuzzle {
+    #[serde(default = "first_version")]
+    pub version: u32,
+    /// Day the puzzle was published, if it was saved from the archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<PuzzleDate>,
+    #[serde(flatten)]
+    pub puzzle: Puzzle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution: Option<Solution>,
+}
+
+impl SavedPuzzle {
+    pub fn new(puzzle: Puzzle, solution: Option<Solution>) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            date: None,
+            puzzle,
+            solution,
+        }
+    }
+
+    pub fn date(mut self, date: Option<PuzzleDate>) -> Self {
+        self.date = date;
+        self
+    }
+}
+
+/// Result of checking the answers entered into an InMemoryPuzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verification {
+    pub solved: bool,
+    /// Set when there is no stored solution, so only the grid being filled was checked
+    pub weak: bool,
+}
+
+/// Answer in an Entry at the end of a solve, or that a dry run would enter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Proposal {
+    pub clue: Clue,
+    /// `None` if the Entry was left with empty cells
+    pub answer: Option<String>,
+    /// How confident the LLM said it was. Answers from a batch prompt or the WordList, and
+    /// those the LLM gave no confidence for, have none
+    pub confidence: Option<u8>,
+}
+
+/// The answer in each Entry, with the confidence of the Candidate the solver entered into it
+/// if the Entry still holds that answer
+pub async fn proposals<E: Entry>(
+    entries: &[E],
+    entered: &HashMap<ClueId, Candidate>,
+) -> Vec<Proposal> {
+    let mut proposals = vec![];
+    for entry in entries {
+        let answer = match entry.filled().await {
+            true => Some(entry.value().await),
+            false => None,
+        };
+        let confidence = entered
+            .get(&entry.id())
+            .filter(|c| answer.as_deref() == Some(c.answer.as_str()))
+            .and_then(|c| c.confidence);
+        proposals.push(Proposal {
+            clue: entry.clue().clone(),
+            answer,
+            confidence,
+        });
+    }
+    proposals
+}
+
+/// Every answer proposed by a solve of an InMemoryPuzzle, written out by `--dry-run`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DryRun {
+    /// Every cell of the grid was filled
+    pub complete: bool,
+    pub proposals: Vec<Proposal>,
+    /// See `InMemoryPuzzle::rows`
+    pub grid: Vec<String>,
+}
+
+/// Puzzle kept entirely in memory so that it can be solved without a browser
+pub struct InMemoryPuzzle {
+    puzzle: Puzzle,
+    solution: Option<Solution>,
+    state: GridState,
+    check: bool,
+    date: Option<PuzzleDate>,
+}
+
+impl InMemoryPuzzle {
+    pub fn new(puzzle: Puzzle, solution: Option<Solution>) -> Self {
+        Self {
+            puzzle,
+            solution,
+            state: GridState::default(),
+            check: false,
+            date: None,
+        }
+    }
+
+    /// Start with letters already in the grid, like a puzzle that was partly solved by hand
+    pub fn with_letters(self, letters: HashMap<Position, String>) -> Self {
+        Self {
+            state: GridState::new(letters),
+            ..self
+        }
+    }
+
+    /// Tell the solver which entries are wrong when verifying them, like the Check feature of
+    /// the NYT puzzle. Only useful with a stored solution
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Load a puzzle saved by `save_crossword`
+    pub fn from_yaml(reader: impl Read) -> Result<Self, serde_yaml::Error> {
+        let saved: SavedPuzzle = serde_yaml::from_reader(reader)?;
+        if saved.version > FORMAT_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "puzzle was saved in format version {}, only up to {} is supported",
+                saved.version, FORMAT_VERSION
+            )));
+        }
+        Ok(Self {
+            date: saved.date,
+            ..Self::new(saved.puzzle, saved.solution)
+        })
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// Day the puzzle was published, if it was saved with one
+    pub fn date(&self) -> Option<PuzzleDate> {
+        self.date
+    }
+
+    pub fn solution(&self) -> Option<&Solution> {
+        self.solution.as_ref()
+    }
+
+    /// Cells whose value does not match the stored solution, ignoring case. Without a solution
+    /// this is every empty cell
+    pub async fn incorrect_cells(&self) -> Vec<Position> {
+        let values = self.state.0.read().await;
+        self.puzzle
+            .grid()
+            .open_positions()
+            .into_iter()
+            .filter(|p| {
+                let value = values.get(p);
+                let correct = match &self.solution {
+                    Some(solution) => value
+                        .zip(solution.get(*p))
+                        .is_some_and(|(v, s)| v.eq_ignore_ascii_case(s)),
+                    None => value.is_some(),
+                };
+                !correct
+            })
+            .collect()
+    }
+
+    /// Compare against the stored solution, or without one just check every cell is filled
+    pub async fn verify(&self) -> Verification {
+        Verification {
+            solved: self.incorrect_cells().await.is_empty(),
+            weak: self.solution.is_none(),
+        }
+    }
+
+    /// Draw the current state of the grid
+    pub async fn render(&self) -> String {
+        render::to_text(&self.puzzle, &self.state).await
+    }
+
+    /// See `proposals`
+    pub async fn dry_run(
+        &self,
+        entered: &HashMap<ClueId, Candidate>,
+    ) -> Result<DryRun, PuzzleError> {
+        let proposals = proposals(&self.generate_entries().await?, entered).await;
+        Ok(DryRun {
+            // Every open cell is part of an Entry
+            complete: proposals.iter().all(|p| p.answer.is_some()),
+            proposals,
+            grid: self.rows().await,
+        })
+    }
+
+    /// Current value of each row, with shaded squares as `#`, empty cells as `_` and rebus
+    /// cells in brackets
+    pub async fn rows(&self) -> Vec<String> {
+        let grid = self.puzzle.grid();
+        let values = self.state.0.read().await;
+        (0..self.puzzle.height)
+            .map(|row| {
+                let mut line = String::new();
+                for column in 0..self.puzzle.width {
+                    let position = Position { row, column };
+                    match values.get(&position) {
+                        _ if !grid.is_open(position) => line.push('#'),
+                        Some(v) if v.chars().count() > 1 => line.push_str(&format!("[{}]", v)),
+                        Some(v) => line.push_str(v),
+                        None => line.push('_'),
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CrosswordPuzzle for InMemoryPuzzle {
+    type Entry = InMemoryEntry;
+
+    async fn generate_entries(&self) -> Result<Vec<InMemoryEntry>, PuzzleError> {
+        let positions = positions_for_clues(&self.puzzle.grid(), &self.puzzle.clues)?;
+        Ok(self
+            .puzzle
+            .clues
+            .iter()
+            .zip(positions)
+            .map(|(clue, positions)| InMemoryEntry {
+                clue: clue.clone(),
+                circled: circled_positions(&positions, &self.puzzle.circled),
+                positions,
+                state: self.state.clone(),
+            })
+            .collect())
+    }
+
+    /// See `InMemoryPuzzle::verify`
+    async fn verify_entries(&self, entries: &[InMemoryEntry]) -> WebDriverResult<Feedback> {
+        if self.verify().await.solved {
+            Ok(Feedback::Solved)
+        } else if self.check && self.solution.is_some() {
+            // Like the NYT, only cells with a letter in them are marked
+            let incorrect = self.incorrect_cells().await;
+            let values = self.state.0.read().await;
+            let marked: Vec<Position> = incorrect
+                .into_iter()
+                .filter(|p| values.contains_key(p))
+                .collect();
+            Ok(Feedback::incorrect(&marked, entries))
+        } else {
+            Ok(Feedback::Unsolved)
+        }
+    }
+}
+
+#[async_trait]
+impl Entry for InMemoryEntry {
+    fn clue(&self) -> &Clue {
+        &self.clue
+    }
+
+    fn positions(&self) -> Vec<Position> {
+        self.positions.clone()
+    }
+
+    fn circled(&self) -> Vec<Position> {
+        self.circled.clone()
+    }
+
+    async fn cells(&self) -> Vec<Option<String>> {
+        self.state.values(&self.positions).await
+    }
+
+    async fn write_cells(&self, cells: Vec<Option<String>>) -> WebDriverResult<()> {
+        self.state.write(&self.positions, cells).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{InMemoryPuzzle, Proposal, SavedPuzzle, Verification, FORMAT_VERSION};
+    use crate::{
+        puzzle::{
+            CrossingMap, CrosswordPuzzle, Entries, Entry, EntryError, Feedback, GridSnapshot,
+            PuzzleError,
+        },
+        solver::Candidate,
+        Clue, Direction, GridState, InMemoryEntry, Position, Puzzle,
+    };
+
+    const MINI: &str = include_str!("../tests/fixtures/mini1.yaml");
+
+    #[tokio::test]
+    async fn existing_letters() {
+        let letters = HashMap::from([
+            (Position { row: 0, column: 1 }, String::from("C")),
+            (Position { row: 0, column: 2 }, String::from("A")),
+        ]);
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes())
+            .unwrap()
+            .with_letters(letters);
+        let entries = puzzle.generate_entries().await.unwrap();
+        let across = &entries[0];
+        assert_eq!(
+            across.cells().await,
+            [Some(String::from("C")), Some(String::from("A")), None, None]
+        );
+        assert!(across.fits("CASH").await);
+        assert!(!across.fits("MOON").await);
+        // Crossing entries see the same letters
+        let down = entries
+            .iter()
+            .find(|e| e.clue().number == 1 && e.clue().direction == Direction::Down)
+            .unwrap();
+        assert_eq!(down.cells().await[0].as_deref(), Some("C"));
+        assert!(!down.filled().await);
+    }
+
+    #[tokio::test]
+    async fn dry_run() {
+        let saved = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let puzzle = InMemoryPuzzle::new(saved.puzzle().clone(), None);
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[0].write(String::from("CASH")).await.unwrap();
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let confident = |answer: &str| Candidate {
+            answer: answer.to_string(),
+            confidence: Some(90),
+        };
+        let entered = HashMap::from([
+            (entries[0].id(), confident("CASH")),
+            // Replaced after it was entered, so its confidence no longer applies
+            (entries[1].id(), confident("FORCE")),
+        ]);
+        let dry_run = puzzle.dry_run(&entered).await.unwrap();
+        assert!(!dry_run.complete);
+        assert_eq!(
+            dry_run.proposals[..3],
+            [
+                Proposal {
+                    clue: entries[0].clue().clone(),
+                    answer: Some(String::from("CASH")),
+                    confidence: Some(90),
+                },
+                Proposal {
+                    clue: entries[1].clue().clone(),
+                    answer: Some(String::from("FARCE")),
+                    confidence: None,
+                },
+                Proposal {
+                    clue: entries[2].clue().clone(),
+                    answer: None,
+                    confidence: None,
+                },
+            ]
+        );
+        assert_eq!(dry_run.grid[..2], ["#CASH", "FARCE"]);
+        for (entry, answer) in entries[2..].iter().zip(["ANGEL", "ROUND", "MEET"]) {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        assert!(puzzle.dry_run(&entered).await.unwrap().complete);
+    }
+
+    fn three_cells() -> InMemoryEntry {
+        InMemoryEntry {
+            clue: Clue {
+                number: 1,
+                direction: Direction::Across,
+                text: String::new(),
+                position: Position { row: 0, column: 0 },
+            },
+            positions: (0..3).map(|column| Position { row: 0, column }).collect(),
+            circled: vec![],
+            state: GridState::default(),
+        }
+    }
+
+    #[test]
+    fn from_yaml() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        assert_eq!(puzzle.puzzle().clues.len(), 10);
+        let solution = puzzle.solution().unwrap();
+        assert_eq!(solution.get(Position { row: 0, column: 0 }), None);
+        assert_eq!(solution.get(Position { row: 1, column: 2 }), Some("R"));
+    }
+
+    #[tokio::test]
+    async fn verify_against_solution() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        assert!(!puzzle.verify_entries(&entries).await.unwrap().solved());
+        for (entry, answer) in entries
+            .iter()
+            .zip(["CASH", "FARCE", "ANGEL", "ROUND", "MEET"])
+        {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        assert!(puzzle.verify_entries(&entries).await.unwrap().solved());
+        assert_eq!(
+            puzzle.rows().await,
+            vec!["#CASH", "FARCE", "ANGEL", "ROUND", "MEET#"]
+        );
+        // A filled but incorrect grid is rejected
+        entries[0].write(String::from("CASK")).await.unwrap();
+        assert!(!puzzle.verify_entries(&entries).await.unwrap().solved());
+    }
+
+    #[tokio::test]
+    async fn verify_without_solution() {
+        let saved = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let puzzle = InMemoryPuzzle::new(saved.puzzle().clone(), None);
+        let entries = puzzle.generate_entries().await.unwrap();
+        for entry in entries.iter().take(5) {
+            let length = entry.length().await;
+            entry.write("X".repeat(length)).await.unwrap();
+        }
+        assert!(puzzle.verify_entries(&entries).await.unwrap().solved());
+    }
+
+    #[tokio::test]
+    async fn rebus_entries() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[1].write(String::from("F[AR]CES")).await.unwrap();
+        assert_eq!(entries[1].value().await, "F[AR]CES");
+        assert_eq!(entries[1].length().await, 5);
+        assert!(entries[1].fits("F[AR]CES").await);
+        assert!(!entries[1].fits("FARCES").await);
+
+        let cells = |cells: [Option<&str>; 5]| cells.map(|c| c.map(String::from)).to_vec();
+        entries[1]
+            .write_padded(cells([Some("S"), None, None, None, Some("E")]))
+            .await
+            .unwrap();
+        // HR or RI could share a cell, until a crossing letter rules one out
+        assert_eq!(entries[1].fit_rebus("SHRINE").await, None);
+        entries[1]
+            .write_padded(cells([Some("S"), None, Some("I"), None, Some("E")]))
+            .await
+            .unwrap();
+        assert_eq!(
+            entries[1].fit_rebus("SHRINE").await,
+            Some(String::from("S[HR]INE"))
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_clues() {
+        let saved = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut puzzle = saved.puzzle().clone();
+        // 1-Across moved onto the shaded corner and 5-Across onto the last column of its row
+        puzzle.clues[0].position = Position { row: 0, column: 0 };
+        puzzle.clues[1].position = Position { row: 1, column: 4 };
+        let puzzle = InMemoryPuzzle::new(puzzle, None);
+        match puzzle.generate_entries().await {
+            Err(PuzzleError::InvalidClues(clues)) => {
+                assert_eq!(clues, vec![(1, Direction::Across), (5, Direction::Across)])
+            }
+            _ => panic!("Invalid clues were accepted"),
+        }
+    }
+
+    #[tokio::test]
+    async fn expected_length() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        for entry in puzzle.generate_entries().await.unwrap() {
+            assert_eq!(entry.expected_length(), entry.length().await);
+        }
+    }
+
+    #[tokio::test]
+    async fn write_length() {
+        let entry = three_cells();
+        entry.write(String::from("CAT")).await.unwrap();
+        assert_eq!(entry.value().await, "CAT");
+        // Neither a long nor a short answer touches the cells
+        for (answer, got) in [("CATS", 4), ("DO", 2)] {
+            assert!(matches!(
+                entry.write(String::from(answer)).await,
+                Err(EntryError::LengthMismatch { expected: 3, got: g }) if g == got
+            ));
+            assert_eq!(entry.value().await, "CAT");
+        }
+        entry
+            .write_padded(vec![None, Some(String::from("O")), None])
+            .await
+            .unwrap();
+        assert_eq!(entry.value().await, "_O_");
+        assert!(entry.write_padded(vec![None, None]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn conflicts_with_unchecked_cell() {
+        // The last square of 1-Across is shaded below, so it has no down entry
+        let clue = |number, direction, column| Clue {
+            number,
+            direction,
+            text: String::new(),
+            position: Position { row: 0, column },
+        };
+        let puzzle = InMemoryPuzzle::new(
+            Puzzle {
+                width: 3,
+                height: 2,
+                shaded_squares: vec![Position { row: 1, column: 2 }],
+                circled: vec![],
+                clues: vec![
+                    clue(1, Direction::Across, 0),
+                    Clue {
+                        position: Position { row: 1, column: 0 },
+                        ..clue(3, Direction::Across, 0)
+                    },
+                    clue(1, Direction::Down, 0),
+                    clue(2, Direction::Down, 1),
+                ],
+            },
+            None,
+        );
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[0].write(String::from("CAT")).await.unwrap();
+        let conflicts = entries[0].conflicting_entries("DOG", &entries).await;
+        let clues: Vec<_> = conflicts.iter().map(|e| e.id()).collect();
+        assert_eq!(clues, vec![entries[2].id(), entries[3].id()]);
+    }
+
+    #[tokio::test]
+    async fn crossing_map_matches_scan() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let clues =
+            |entries: Vec<InMemoryEntry>| entries.iter().map(|e| e.id()).collect::<Vec<_>>();
+        for (entry, answer) in entries
+            .iter()
+            .zip(["CASH", "FARCE", "ANGEL", "ROUND", "MEET"])
+        {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        for entry in entries.iter() {
+            assert_eq!(
+                clues(crossings.crossing_entries(entry, &entries)),
+                clues(entry.crossing_entries(entries.clone()))
+            );
+            let answer = "Z".repeat(entry.expected_length());
+            assert_eq!(
+                clues(
+                    crossings
+                        .conflicting_entries(entry, &answer, &entries)
+                        .await
+                ),
+                clues(entry.conflicting_entries(&answer, &entries).await)
+            );
+        }
+        // 1-Across and 1-Down share their first cell
+        let (cross, index) = crossings.crossings(entries[0].id())[0].unwrap();
+        assert_eq!(
+            (cross.number, cross.direction, index),
+            (1, Direction::Down, 0)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_writes() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let solution = puzzle.solution().unwrap().clone();
+        let entries = puzzle.generate_entries().await.unwrap();
+        // Across and down entries write their shared cells at the same time
+        let tasks: Vec<_> = entries
+            .iter()
+            .cloned()
+            .map(|entry| {
+                let answer = entry
+                    .positions()
+                    .into_iter()
+                    .filter_map(|p| solution.get(p))
+                    .collect();
+                tokio::spawn(async move { entry.write(answer).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert!(puzzle.verify_entries(&entries).await.unwrap().solved());
+
+        // A fork is a snapshot, clearing it leaves the puzzle untouched
+        let forked = GridSnapshot::capture(&entries).await;
+        let first = forked.get(entries[0].id()).unwrap();
+        first.clear().await.unwrap();
+        assert_eq!(first.value().await, "____");
+        let down = forked.get(entries[5].id()).unwrap();
+        assert!(down.value().await.starts_with('_'));
+        assert!(puzzle.verify_entries(&entries).await.unwrap().solved());
+    }
+
+    #[tokio::test]
+    async fn incorrect_cells() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        for (entry, answer) in entries
+            .iter()
+            .zip(["CASH", "FORCE", "angel", "ROUND", "MELT"])
+        {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        assert_eq!(
+            puzzle.incorrect_cells().await,
+            vec![
+                Position { row: 1, column: 1 },
+                Position { row: 4, column: 2 }
+            ]
+        );
+        assert_eq!(
+            puzzle.verify().await,
+            Verification {
+                solved: false,
+                weak: false
+            }
+        );
+
+        // Without a solution the same fill is accepted, but flagged as a weak check
+        let unchecked = InMemoryPuzzle::new(puzzle.puzzle().clone(), None);
+        let entries = unchecked.generate_entries().await.unwrap();
+        assert_eq!(unchecked.incorrect_cells().await.len(), 23);
+        for (entry, answer) in entries
+            .iter()
+            .zip(["CASH", "FORCE", "ANGEL", "ROUND", "MELT"])
+        {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        assert_eq!(
+            unchecked.verify().await,
+            Verification {
+                solved: true,
+                weak: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn check_entries() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        for (entry, answer) in entries
+            .iter()
+            .zip(["CASH", "FORCE", "ANGEL", "ROUND", "MELT"])
+        {
+            entry.write(answer.to_string()).await.unwrap();
+        }
+        assert_eq!(
+            puzzle.verify_entries(&entries).await.unwrap(),
+            Feedback::Unsolved
+        );
+
+        // Every entry through a wrong cell is marked, across and down
+        let puzzle = puzzle.check(true);
+        let Feedback::Incorrect(clues) = puzzle.verify_entries(&entries).await.unwrap() else {
+            panic!("Wrong answers were not found");
+        };
+        // The full clues are looked up on the puzzle
+        let clues: Vec<&str> = clues
+            .iter()
+            .map(|id| puzzle.puzzle().clue(*id).unwrap().text.as_str())
+            .collect();
+        assert_eq!(
+            clues,
+            [
+                "Slapstick comedy",
+                "Get together",
+                "Paddled boat",
+                "Debate heatedly"
+            ]
+        );
+
+        // Empty cells are not marked
+        entries[1].clear().await.unwrap();
+        entries[4].clear().await.unwrap();
+        assert_eq!(
+            puzzle.verify_entries(&entries).await.unwrap(),
+            Feedback::Unsolved
+        );
+    }
+
+    #[test]
+    fn saved_format_version() {
+        // Puzzles saved before the format was versioned still load
+        let old: SavedPuzzle = serde_yaml::from_str(MINI).unwrap();
+        assert_eq!(old.version, 1);
+        assert!(old.puzzle.circled.is_empty());
+
+        let mut puzzle = old.puzzle.clone();
+        puzzle.circled = vec![Position { row: 2, column: 2 }];
+        let yaml = serde_yaml::to_string(&SavedPuzzle::new(puzzle.clone(), None)).unwrap();
+        assert!(yaml.starts_with(&format!("version: {}\n", FORMAT_VERSION)));
+        let loaded = InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap();
+        assert_eq!(loaded.puzzle(), &puzzle);
+        assert_eq!(loaded.date(), None);
+
+        // Puzzles from the archive know when they were published
+        let date = "2024-03-14".parse().unwrap();
+        let saved = SavedPuzzle::new(puzzle.clone(), None).date(Some(date));
+        let yaml = serde_yaml::to_string(&saved).unwrap();
+        assert!(yaml.contains("\ndate: "), "{}", yaml);
+        let loaded = InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap();
+        assert_eq!(loaded.date(), Some(date));
+
+        let newer = format!("version: {}\n{}", FORMAT_VERSION + 1, MINI);
+        assert!(InMemoryPuzzle::from_yaml(newer.as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn stable_entry_order() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let generated = puzzle.generate_entries().await.unwrap();
+        let mut shuffled = generated.clone();
+        shuffled.reverse();
+        shuffled.rotate_left(3);
+        let entries = Entries::new(shuffled);
+        let ordered: Vec<&Clue> = entries.iter().map(|e| e.clue()).collect();
+        let expected: Vec<&Clue> = generated.iter().map(|e| e.clue()).collect();
+        assert_eq!(ordered, expected);
+        let numbers: Vec<_> = entries.across().map(|e| e.clue().number).collect();
+        assert_eq!(numbers, vec![1, 5, 6, 7, 8]);
+        let numbers: Vec<_> = entries.down().map(|e| e.clue().number).collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            entries
+                .by_clue(5, Direction::Down)
+                .map(|e| e.clue().position),
+            Some(Position { row: 1, column: 0 })
+        );
+        assert!(entries.by_clue(9, Direction::Across).is_none());
+    }
+
+    #[tokio::test]
+    async fn fits_length() {
+        let entry = three_cells();
+        entry
+            .write_padded(vec![Some(String::from("C")), None, None])
+            .await
+            .unwrap();
+        assert!(entry.fits("CAT").await);
+        // Too short, too long and a letter that disagrees with the C already entered
+        assert!(!entry.fits("CA").await);
+        assert!(!entry.fits("CATS").await);
+        assert!(!entry.fits("BAT").await);
+        assert!(!entry.fits("C[AT]").await);
+
+        // A long answer is not entered at all, so it does not conflict with any crossing entry
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[0].write(String::from("CASH")).await.unwrap();
+        assert_eq!(
+            entries[5]
+                .conflicting_entries("BANOE", &entries)
+                .await
+                .len(),
+            1
+        );
+        assert!(entries[5]
+            .conflicting_entries("BANOES", &entries)
+            .await
+            .is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use std::{fmt, fs, io, path::Path};
+
+/// Template used for every clue unless another is loaded
+static DEFAULT_TEMPLATE: &str = include_str!("../prompts/clue.txt");
+/// Line separating a preamble of worked examples from the template itself
+static PREAMBLE_SEPARATOR: &str = "---";
+
+/// Value substituted into a PromptTemplate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Clue,
+    Length,
+    Pattern,
+    Count,
+    Notes,
+    OtherClues,
+    Crossings,
+    Rejected,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "clue" => Placeholder::Clue,
+            "length" => Placeholder::Length,
+            "pattern" => Placeholder::Pattern,
+            "count" => Placeholder::Count,
+            "notes" => Placeholder::Notes,
+            "other_clues" => Placeholder::OtherClues,
+            "crossings" => Placeholder::Crossings,
+            "rejected" => Placeholder::Rejected,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Prompt sent to the LLM for a single clue. Templates are plain text where `{clue}`,
+/// `{length}`, `{pattern}`, `{count}`, `{notes}`, `{other_clues}`, `{crossings}` and
+/// `{rejected}` are replaced with PromptValues, and `{{` and `}}` are literal braces.
+///
+/// Text above a line of `---` is a preamble of worked examples placed before every prompt. It
+/// is sent exactly as written, so examples can include braces without escaping them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    preamble: String,
+    segments: Vec<Segment>,
+}
+
+/// Everything known about a clue when it is asked about
+#[derive(Debug, Clone, Default)]
+pub struct PromptValues<'a> {
+    pub clue: &'a str,
+    pub length: usize,
+    /// Letters already in the grid, with `_` for each unknown cell
+    pub pattern: &'a str,
+    /// Number of answers to ask for
+    pub count: usize,
+    /// Lines describing rebus and circled squares, or empty
+    pub notes: &'a str,
+    /// Every clue of the puzzle, one per line
+    pub other_clues: &'a str,
+    /// For each letter shared with another Entry, its clue, pattern and the shared letter if
+    /// known, one per line
+    pub crossings: &'a str,
+    /// A line listing the answers known to be wrong, or empty
+    pub rejected: &'a str,
+}
+
+#[derive(Debug)]
+pub enum TemplateError {
+    Io(io::Error),
+    UnknownPlaceholder {
+        line: usize,
+        name: String,
+    },
+    /// A `{` without a closing `}` on the same line
+    UnclosedBrace {
+        line: usize,
+    },
+    /// A `}` that does not close a placeholder
+    UnmatchedBrace {
+        line: usize,
+    },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Io(e) => write!(f, "unable to read prompt template: {}", e),
+            TemplateError::UnknownPlaceholder { line, name } => {
+                write!(f, "line {}: unknown placeholder {{{}}}", line, name)
+            }
+            TemplateError::UnclosedBrace { line } => {
+                write!(
+                    f,
+                    "line {}: '{{' is never closed, use '{{{{' for a brace",
+                    line
+                )
+            }
+            TemplateError::UnmatchedBrace { line } => {
+                write!(f, "line {}: unmatched '}}', use '}}}}' for a brace", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<io::Error> for TemplateError {
+    fn from(value: io::Error) -> Self {
+        TemplateError::Io(value)
+    }
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).expect("Default template is valid")
+    }
+}
+
+impl PromptTemplate {
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let (preamble, body, first_line) = match lines
+            .iter()
+            .position(|l| l.trim_end() == PREAMBLE_SEPARATOR)
+        {
+            Some(index) => (lines[..index].concat(), &lines[index + 1..], index + 2),
+            None => (String::new(), &lines[..], 1),
+        };
+        let mut segments = vec![];
+        let mut text = String::new();
+        for (offset, l) in body.iter().enumerate() {
+            let line = first_line + offset;
+            let mut chars = l.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '{' if chars.peek() == Some(&'{') => {
+                        chars.next();
+                        text.push('{');
+                    }
+                    '}' if chars.peek() == Some(&'}') => {
+                        chars.next();
+                        text.push('}');
+                    }
+                    '{' => {
+                        let mut name = String::new();
+                        loop {
+                            match chars.next() {
+                                Some('}') => break,
+                                Some('\n') | None => {
+                                    return Err(TemplateError::UnclosedBrace { line })
+                                }
+                                Some(c) => name.push(c),
+                            }
+                        }
+                        let placeholder = Placeholder::parse(&name)
+                            .ok_or(TemplateError::UnknownPlaceholder { line, name })?;
+                        if !text.is_empty() {
+                            segments.push(Segment::Text(std::mem::take(&mut text)));
+                        }
+                        segments.push(Segment::Placeholder(placeholder));
+                    }
+                    '}' => return Err(TemplateError::UnmatchedBrace { line }),
+                    c => text.push(c),
+                }
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { preamble, segments })
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, TemplateError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The preamble followed by the template with every placeholder replaced
+    pub fn render(&self, values: &PromptValues) -> String {
+        let mut prompt = self.preamble.clone();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => prompt.push_str(text),
+                Segment::Placeholder(placeholder) => match placeholder {
+                    Placeholder::Clue => prompt.push_str(values.clue),
+                    Placeholder::Length => prompt.push_str(&values.length.to_string()),
+                    Placeholder::Pattern => prompt.push_str(values.pattern),
+                    Placeholder::Count => prompt.push_str(&values.count.to_string()),
+                    Placeholder::Notes => prompt.push_str(values.notes),
+                    Placeholder::OtherClues => prompt.push_str(values.other_clues),
+                    Placeholder::Crossings => prompt.push_str(values.crossings),
+                    Placeholder::Rejected => prompt.push_str(values.rejected),
+                },
+            }
+        }
+        prompt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PromptTemplate, PromptValues};
+
+    fn values() -> PromptValues<'static> {
+        PromptValues {
+            clue: "Paddled boat",
+            length: 5,
+            pattern: "C____",
+            count: 3,
+            notes: "",
+            other_clues: "1-Across: Money in hand\n",
+            crossings: "1-Across: Money in hand (CASH)\n",
+            rejected: "The answer is NOT any of: CANES.\n",
+        }
+    }
+
+    #[test]
+    fn render() {
+        let template =
+            PromptTemplate::parse("{clue} ({length}, {pattern}) x{count}\n{crossings}{rejected}")
+                .unwrap();
+        assert_eq!(
+            template.render(&values()),
+            "Paddled boat (5, C____) x3\n1-Across: Money in hand (CASH)\nThe answer is NOT any of: CANES.\n"
+        );
+        let escaped = PromptTemplate::parse("{{\"{pattern}\": {{}}}}").unwrap();
+        assert_eq!(escaped.render(&values()), "{\"C____\": {}}");
+    }
+
+    #[test]
+    fn default_template() {
+        let prompt = PromptTemplate::default().render(&values());
+        assert!(prompt.starts_with("Determine the answer"));
+        assert!(prompt.contains("<text>\nPaddled boat\n</text>"));
+        assert!(prompt.contains("5 letters long and may match the pattern C____."));
+        assert!(prompt.contains("Respond with your 3 best answers"));
+        assert!(prompt.contains("NOT any of: CANES.\nThe answer crosses"));
+        assert!(prompt.ends_with("<text>\n1-Across: Money in hand (CASH)\n</text>\n"));
+    }
+
+    #[test]
+    fn preamble() {
+        let template =
+            PromptTemplate::parse("Clue: Big cat\nAnswers: {\"LION\": 90}\n---\nClue: {clue}\n")
+                .unwrap();
+        assert_eq!(
+            template.render(&values()),
+            "Clue: Big cat\nAnswers: {\"LION\": 90}\nClue: Paddled boat\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text| PromptTemplate::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("{clue}\n{answer}"),
+            "line 2: unknown placeholder {answer}"
+        );
+        assert_eq!(
+            error("{clue}\n\n{clue"),
+            "line 3: '{' is never closed, use '{{' for a brace"
+        );
+        assert_eq!(
+            error("{clue\n}"),
+            "line 1: '{' is never closed, use '{{' for a brace"
+        );
+        assert_eq!(
+            error("{clue}}"),
+            "line 1: unmatched '}', use '}}' for a brace"
+        );
+        // Lines are counted from the top of the file, including the preamble
+        assert_eq!(
+            error("Example {\n---\n{pattern} {length\n"),
+            "line 3: '{' is never closed, use '{{' for a brace"
+        );
+    }
+}
@@ -0,0 +1,632 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use thirtyfour::prelude::{WebDriverError, WebDriverResult};
+
+use crate::{grid::Grid, Clue, ClueId, Direction, GridState, InMemoryEntry, Position};
+
+/// A crossword that the solver can read entries from and enter answers into
+#[async_trait]
+pub trait CrosswordPuzzle: Sync {
+    type Entry: Entry;
+
+    /// Create an Entry for every Clue in the puzzle
+    async fn generate_entries(&self) -> Result<Vec<Self::Entry>, PuzzleError>;
+
+    /// Whether the answers currently entered solve the puzzle, and if not which are wrong when
+    /// the puzzle is able to tell
+    async fn verify_entries(&self, entries: &[Self::Entry]) -> WebDriverResult<Feedback>;
+
+    /// Make sure the puzzle's own timer is running, for puzzles that keep one. Called before
+    /// the first answer is entered
+    async fn start_timer(&self) -> WebDriverResult<()> {
+        Ok(())
+    }
+
+    /// Time on the puzzle's own timer, for puzzles that keep one
+    async fn elapsed_time(&self) -> WebDriverResult<Option<Duration>> {
+        Ok(None)
+    }
+}
+
+/// Result of checking the answers entered into a puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    Solved,
+    /// Not solved, without saying which answers are wrong
+    Unsolved,
+    /// Not solved, with the Clue of every Entry that has an incorrect cell
+    Incorrect(Vec<ClueId>),
+}
+
+impl Feedback {
+    /// Feedback for a puzzle that marked the cells at `positions` incorrect
+    pub fn incorrect<E: Entry>(positions: &[Position], entries: &[E]) -> Self {
+        let clues: Vec<ClueId> = entries
+            .iter()
+            .filter(|e| e.positions().iter().any(|p| positions.contains(p)))
+            .map(|e| e.id())
+            .collect();
+        if clues.is_empty() {
+            Feedback::Unsolved
+        } else {
+            Feedback::Incorrect(clues)
+        }
+    }
+
+    pub fn solved(&self) -> bool {
+        matches!(self, Feedback::Solved)
+    }
+}
+
+#[derive(Debug)]
+pub enum PuzzleError {
+    /// Number and direction of each Clue that does not begin an entry of the grid
+    InvalidClues(Vec<(usize, Direction)>),
+    /// Number and direction of each Clue with a square of its entry that no cell is drawn at
+    MissingCells(Vec<(usize, Direction)>),
+    /// An answer could not be written into an Entry
+    Entry(EntryError),
+    WebDriver(WebDriverError),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::InvalidClues(clues) => write!(
+                f,
+                "clues do not match the grid: {}",
+                clues
+                    .iter()
+                    .map(|(number, direction)| format!("{}-{}", number, direction))
+                    .join(", ")
+            ),
+            PuzzleError::MissingCells(clues) => write!(
+                f,
+                "no cell is drawn for every square of {}",
+                clues
+                    .iter()
+                    .map(|(number, direction)| format!("{}-{}", number, direction))
+                    .join(", ")
+            ),
+            PuzzleError::Entry(e) => write!(f, "{}", e),
+            PuzzleError::WebDriver(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PuzzleError::InvalidClues(_) | PuzzleError::MissingCells(_) => None,
+            PuzzleError::Entry(e) => Some(e),
+            PuzzleError::WebDriver(e) => Some(e),
+        }
+    }
+}
+
+impl From<WebDriverError> for PuzzleError {
+    fn from(value: WebDriverError) -> Self {
+        PuzzleError::WebDriver(value)
+    }
+}
+
+impl From<EntryError> for PuzzleError {
+    fn from(value: EntryError) -> Self {
+        match value {
+            EntryError::WebDriver(e) => PuzzleError::WebDriver(e),
+            e => PuzzleError::Entry(e),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EntryError {
+    /// Number of values written does not match the number of cells in the Entry
+    LengthMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// A single cell was written at a Position the Entry does not cover
+    OutsideEntry(Position),
+    WebDriver(WebDriverError),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryError::LengthMismatch { expected, got } => write!(
+                f,
+                "entry has {} cells but {} values were written",
+                expected, got
+            ),
+            EntryError::OutsideEntry(position) => write!(
+                f,
+                "entry has no cell at row {}, column {}",
+                position.row, position.column
+            ),
+            EntryError::WebDriver(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EntryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EntryError::LengthMismatch { .. } | EntryError::OutsideEntry(_) => None,
+            EntryError::WebDriver(e) => Some(e),
+        }
+    }
+}
+
+impl From<WebDriverError> for EntryError {
+    fn from(value: WebDriverError) -> Self {
+        EntryError::WebDriver(value)
+    }
+}
+
+/// Positions out of `positions` that are circled
+pub fn circled_positions(positions: &[Position], circled: &[Position]) -> Vec<Position> {
+    positions
+        .iter()
+        .filter(|p| circled.contains(p))
+        .copied()
+        .collect()
+}
+
+/// Positions of the cells for every Clue, failing with all the Clues that do not begin an
+/// entry of the grid
+pub fn positions_for_clues(grid: &Grid, clues: &[Clue]) -> Result<Vec<Vec<Position>>, PuzzleError> {
+    let mut invalid = vec![];
+    let positions = clues
+        .iter()
+        .map(|clue| {
+            grid.entry_positions(clue).unwrap_or_else(|| {
+                invalid.push((clue.number, clue.direction));
+                vec![]
+            })
+        })
+        .collect();
+    if invalid.is_empty() {
+        Ok(positions)
+    } else {
+        Err(PuzzleError::InvalidClues(invalid))
+    }
+}
+
+/// The cells of the puzzle that make up the answer to a single Clue
+#[async_trait]
+pub trait Entry: Clone + Send + Sync {
+    fn clue(&self) -> &Clue;
+
+    /// Number and direction of the Clue, to tell the Entry apart from the others of the puzzle
+    fn id(&self) -> ClueId {
+        self.clue().id()
+    }
+
+    /// Positions of each cell in the Entry, in reading order
+    fn positions(&self) -> Vec<Position>;
+
+    /// Positions of the cells in the Entry that are circled
+    fn circled(&self) -> Vec<Position>;
+
+    /// Number of cells in the Entry, known without reading any of their values
+    fn expected_length(&self) -> usize {
+        self.positions().len()
+    }
+
+    /// Current value of each cell in the Entry
+    async fn cells(&self) -> Vec<Option<String>>;
+
+    /// Write a value into each cell, clearing cells given `None`. Callers should use
+    /// `write_padded`, which checks there is exactly one value for every cell
+    async fn write_cells(&self, cells: Vec<Option<String>>) -> WebDriverResult<()>;
+
+    /// Write a partial pattern into the Entry, clearing cells given `None`
+    async fn write_padded(&self, cells: Vec<Option<String>>) -> Result<(), EntryError> {
+        if cells.len() != self.expected_length() {
+            return Err(EntryError::LengthMismatch {
+                expected: self.expected_length(),
+                got: cells.len(),
+            });
+        }
+        Ok(self.write_cells(cells).await?)
+    }
+
+    /// Write a value into the cell at `position` alone, clearing it given `None`. Puzzles that
+    /// write one cell at a time should only touch that cell
+    async fn write_cell(
+        &self,
+        position: Position,
+        value: Option<String>,
+    ) -> Result<(), EntryError> {
+        let Some(index) = self.positions().iter().position(|p| *p == position) else {
+            return Err(EntryError::OutsideEntry(position));
+        };
+        let mut cells = self.cells().await;
+        cells[index] = value;
+        self.write_padded(cells).await
+    }
+
+    /// Remove the value of every cell in the Entry
+    async fn clear(&self) -> WebDriverResult<()> {
+        self.write_cells(vec![None; self.expected_length()]).await
+    }
+
+    /// Write a complete answer, which may contain bracketed rebus cells. Answers that are too
+    /// long or too short are rejected without modifying any cell
+    async fn write(&self, answer: String) -> Result<(), EntryError> {
+        self.write_padded(answer_cells(&answer).into_iter().map(Some).collect())
+            .await
+    }
+
+    /// Current value of the Entry with unknown cells shown as underscores and rebus cells
+    /// in brackets
+    async fn value(&self) -> String {
+        self.cells()
+            .await
+            .into_iter()
+            .map(|c| match c {
+                None => String::from("_"),
+                Some(v) if v.chars().count() > 1 => format!("[{}]", v),
+                Some(v) => v,
+            })
+            .collect()
+    }
+
+    /// Number of cells in the full Entry
+    async fn length(&self) -> usize {
+        self.cells().await.len()
+    }
+
+    /// Return a boolean if the provided answer fits with letters already populated in the Entry.
+    /// Answers with a different number of cells than the Entry never fit
+    async fn fits(&self, ans: &str) -> bool {
+        let answer = answer_cells(ans);
+        answer.len() == self.expected_length()
+            && self
+                .cells()
+                .await
+                .iter()
+                .zip(answer)
+                .all(|(cell, c)| cell.is_none() || cell.as_ref() == Some(&c))
+    }
+
+    /// Whether the Entry has been fully populated
+    async fn filled(&self) -> bool {
+        self.cells().await.iter().all(|c| c.is_some())
+    }
+
+    /// Fit an answer that is longer than the Entry by placing the extra letters in a single
+    /// rebus cell. Only succeeds if the letters already entered allow exactly one placement,
+    /// returning the answer with the rebus cell in brackets
+    async fn fit_rebus(&self, answer: &str) -> Option<String> {
+        let cells = self.cells().await;
+        let letters: Vec<char> = answer.chars().collect();
+        if letters.len() <= cells.len() || cells.iter().all(|c| c.is_none()) {
+            return None;
+        }
+        let extra = letters.len() - cells.len();
+        let mut placements = (0..cells.len()).filter_map(|rebus| {
+            let candidate: Vec<String> = (0..cells.len())
+                .map(|i| match i.cmp(&rebus) {
+                    Ordering::Less => letters[i].to_string(),
+                    Ordering::Equal => letters[i..=i + extra].iter().collect(),
+                    Ordering::Greater => letters[i + extra].to_string(),
+                })
+                .collect();
+            cells
+                .iter()
+                .zip(candidate.iter())
+                .all(|(cell, c)| cell.is_none() || cell.as_ref() == Some(c))
+                .then(|| {
+                    candidate
+                        .into_iter()
+                        .map(|c| {
+                            if c.chars().count() > 1 {
+                                format!("[{}]", c)
+                            } else {
+                                c
+                            }
+                        })
+                        .collect::<String>()
+                })
+        });
+        match (placements.next(), placements.next()) {
+            (Some(answer), None) => Some(answer),
+            _ => None,
+        }
+    }
+
+    // Get all crossing Entry values
+    fn crossing_entries(&self, entries: Vec<Self>) -> Vec<Self> {
+        let positions = self.positions();
+        entries
+            .into_iter()
+            .filter_map(|e| {
+                if e.clue().direction != self.clue().direction
+                    && e.positions().iter().any(|p| positions.contains(p))
+                {
+                    Some(e)
+                } else {
+                    None
+                }
+            })
+            .rev()
+            .collect()
+    }
+
+    // Return any Entries that conflict with entering the provided answer. Cells without a
+    // crossing Entry are skipped, writing the answer replaces their value anyway. An answer of
+    // the wrong length can never be entered, so there is nothing to conflict with
+    async fn conflicting_entries(&self, answer: &str, entries: &[Self]) -> Vec<Self> {
+        let mut conflicts = vec![];
+        if answer_cells(answer).len() != self.expected_length() {
+            return conflicts;
+        }
+        for ((position, c), ans) in self
+            .positions()
+            .into_iter()
+            .zip(self.cells().await)
+            .zip(answer_cells(answer))
+        {
+            if c.is_some_and(|v| v != ans) {
+                conflicts.extend(entry_for_cell(
+                    position,
+                    self.clue().direction.cross(),
+                    entries,
+                ));
+            }
+        }
+        conflicts
+    }
+}
+
+/// Entries of a puzzle in a deterministic order, across by clue number then down by clue number,
+/// however they were generated
+#[derive(Clone)]
+pub struct Entries<E: Entry>(Vec<E>);
+
+impl<E: Entry> Entries<E> {
+    pub fn new(mut entries: Vec<E>) -> Self {
+        entries.sort_by_key(|e| {
+            let clue = e.clue();
+            (clue.direction == Direction::Down, clue.number)
+        });
+        Self(entries)
+    }
+
+    pub fn across(&self) -> impl Iterator<Item = &E> {
+        self.0
+            .iter()
+            .filter(|e| e.clue().direction == Direction::Across)
+    }
+
+    pub fn down(&self) -> impl Iterator<Item = &E> {
+        self.0
+            .iter()
+            .filter(|e| e.clue().direction == Direction::Down)
+    }
+
+    pub fn by_clue(&self, number: usize, direction: Direction) -> Option<&E> {
+        self.0.iter().find(|e| {
+            let clue = e.clue();
+            clue.number == number && clue.direction == direction
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.0.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<E> {
+        self.0
+    }
+}
+
+/// For every cell of each Entry, the Clue of the crossing Entry and the index of the shared
+/// cell within it. Built once so the solver does not need to compare positions repeatedly
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrossingMap(HashMap<ClueId, Vec<Option<(ClueId, usize)>>>);
+
+impl CrossingMap {
+    pub fn new<E: Entry>(entries: &[E]) -> Self {
+        let mut cells = HashMap::new();
+        for entry in entries {
+            for (i, position) in entry.positions().into_iter().enumerate() {
+                cells.insert((position, entry.clue().direction), (entry.id(), i));
+            }
+        }
+        Self(
+            entries
+                .iter()
+                .map(|entry| {
+                    let direction = entry.clue().direction.cross();
+                    let crossings = entry
+                        .positions()
+                        .into_iter()
+                        .map(|p| cells.get(&(p, direction)).copied())
+                        .collect();
+                    (entry.id(), crossings)
+                })
+                .collect(),
+        )
+    }
+
+    /// Crossing Clue and index of the shared cell for each cell of the Entry, `None` for
+    /// unchecked cells
+    pub fn crossings(&self, id: ClueId) -> &[Option<(ClueId, usize)>] {
+        self.0.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Entries crossing the provided one, in the same order as `Entry::crossing_entries`
+    pub fn crossing_entries<E: Entry>(&self, entry: &E, entries: &[E]) -> Vec<E> {
+        let clues: HashSet<ClueId> = self
+            .crossings(entry.id())
+            .iter()
+            .flatten()
+            .map(|(id, _)| *id)
+            .collect();
+        entries
+            .iter()
+            .filter(|e| clues.contains(&e.id()))
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// Entries that conflict with entering the provided answer, matching
+    /// `Entry::conflicting_entries`
+    pub async fn conflicting_entries<E: Entry>(
+        &self,
+        entry: &E,
+        answer: &str,
+        entries: &[E],
+    ) -> Vec<E> {
+        let mut conflicts = vec![];
+        if answer_cells(answer).len() != entry.expected_length() {
+            return conflicts;
+        }
+        for ((cross, c), ans) in self
+            .crossings(entry.id())
+            .iter()
+            .zip(entry.cells().await)
+            .zip(answer_cells(answer))
+        {
+            if let (Some((id, _)), Some(v)) = (cross, c) {
+                if v != ans {
+                    conflicts.extend(entries.iter().find(|e| e.id() == *id).cloned());
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Split an answer into the value of each cell. Letters in brackets share a single rebus cell,
+/// e.g. `F[HEART]Y`
+pub fn answer_cells(answer: &str) -> Vec<String> {
+    let mut cells = vec![];
+    let mut rebus: Option<String> = None;
+    for c in answer.chars() {
+        match (c, rebus.as_mut()) {
+            ('[', None) => rebus = Some(String::new()),
+            (']', Some(_)) => cells.extend(rebus.take()),
+            (c, Some(r)) => r.push(c),
+            (c, None) => cells.push(c.to_string()),
+        }
+    }
+    // An unterminated bracket is treated as a normal rebus cell
+    cells.extend(rebus.filter(|r| !r.is_empty()));
+    cells
+}
+
+/// Entry in the given direction that covers the Position. Unchecked cells only belong to a
+/// single Entry, so there may not be one
+pub fn entry_for_cell<E: Entry>(
+    position: Position,
+    direction: Direction,
+    entries: &[E],
+) -> Option<E> {
+    entries
+        .iter()
+        .find(|e| e.positions().contains(&position) && e.clue().direction == direction)
+        .cloned()
+}
+
+/// Copy the current value of every cell in the entries into a new GridState
+pub async fn grid_state<E: Entry>(entries: &[E]) -> GridState {
+    let mut values = HashMap::new();
+    for entry in entries {
+        for (position, value) in entry.positions().into_iter().zip(entry.cells().await) {
+            if let Some(v) = value {
+                values.insert(position, v);
+            }
+        }
+    }
+    GridState::new(values)
+}
+
+/// Copy of a set of entries that exists in memory alone. The entries share their cells with
+/// each other, but not with the ones they were captured from. They are kept in Clue order, so
+/// anything that walks over them always does so in the same order
+#[derive(Debug, Clone)]
+pub struct GridSnapshot(BTreeMap<ClueId, InMemoryEntry>);
+
+impl GridSnapshot {
+    /// Capture the current value of every cell of the entries
+    pub async fn capture<E: Entry>(entries: &[E]) -> Self {
+        let state = grid_state(entries).await;
+        Self(
+            entries
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.id(),
+                        InMemoryEntry {
+                            clue: entry.clue().clone(),
+                            positions: entry.positions(),
+                            circled: entry.circled(),
+                            state: state.clone(),
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Captured entry of the Clue
+    pub fn get(&self, id: ClueId) -> Option<&InMemoryEntry> {
+        self.0.get(&id)
+    }
+
+    /// Captured entries in Clue order
+    pub fn entries(&self) -> impl Iterator<Item = &InMemoryEntry> {
+        self.0.values()
+    }
+
+    pub fn into_entries(self) -> Vec<InMemoryEntry> {
+        self.0.into_values().collect()
+    }
+
+    /// Value of every filled cell, including any written to the captured entries since
+    pub async fn values(&self) -> HashMap<Position, String> {
+        match self.0.values().next() {
+            Some(entry) => entry.state().0.read().await.clone(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// The cells to write to turn `current` into this grid, in reading order. Each is given the
+    /// value it has here, or `None` when it is filled in `current` but empty here
+    pub async fn diff(&self, current: &GridSnapshot) -> Vec<(Position, Option<String>)> {
+        let target = self.values().await;
+        let current = current.values().await;
+        let mut writes: Vec<(Position, Option<String>)> = target
+            .iter()
+            .filter(|(position, value)| current.get(position) != Some(value))
+            .map(|(position, value)| (*position, Some(value.clone())))
+            .chain(
+                current
+                    .keys()
+                    .filter(|position| !target.contains_key(position))
+                    .map(|position| (*position, None)),
+            )
+            .collect();
+        writes.sort_unstable_by_key(|(position, _)| *position);
+        writes
+    }
+}
+
+/// Draw the current values of the entries into the grid
+pub async fn render_entries<E: Entry>(grid: &Grid, entries: &[E]) -> String {
+    let state = grid_state(entries).await;
+    let values = state.0.read().await;
+    grid.render(|p| values.get(&p).cloned())
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use crate::{GridState, Position, Puzzle};
+
+static CELL_SIZE: usize = 36;
+static MARGIN: usize = 2;
+static NUMBER_SIZE: usize = 9;
+static LETTER_SIZE: usize = 20;
+/// Smallest font used to squeeze a long rebus into a cell
+static MIN_LETTER_SIZE: usize = 8;
+
+/// Draw the grid and its current values as text
+pub async fn to_text(puzzle: &Puzzle, state: &GridState) -> String {
+    let values = state.0.read().await;
+    puzzle.grid().render(|p| values.get(&p).cloned())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Draw the grid as an SVG image, with clue numbers in the corner of each cell and circles
+/// around circled squares. Rebus cells use a smaller font so every letter fits
+pub async fn to_svg(puzzle: &Puzzle, state: &GridState) -> String {
+    let values = state.0.read().await;
+    let grid = puzzle.grid();
+    let numbers: HashMap<Position, usize> = grid
+        .numbered_cells()
+        .into_iter()
+        .map(|(number, p)| (p, number))
+        .collect();
+    let width = grid.width * CELL_SIZE + 2 * MARGIN;
+    let height = grid.height * CELL_SIZE + 2 * MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        width, height
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        width, height
+    ));
+    for row in 0..grid.height {
+        for column in 0..grid.width {
+            let position = Position { row, column };
+            let (x, y) = (MARGIN + column * CELL_SIZE, MARGIN + row * CELL_SIZE);
+            let open = grid.is_open(position);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\" stroke=\"black\"/>\n",
+                x,
+                y,
+                CELL_SIZE,
+                if open { "white" } else { "black" }
+            ));
+            if !open {
+                continue;
+            }
+            if puzzle.circled.contains(&position) {
+                svg.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"gray\"/>\n",
+                    x + CELL_SIZE / 2,
+                    y + CELL_SIZE / 2,
+                    CELL_SIZE / 2 - 1
+                ));
+            }
+            if let Some(number) = numbers.get(&position) {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\">{}</text>\n",
+                    x + 2,
+                    y + NUMBER_SIZE + 1,
+                    NUMBER_SIZE,
+                    number
+                ));
+            }
+            if let Some(value) = values.get(&position) {
+                let letters = value.chars().count();
+                let size = if letters > 1 {
+                    (LETTER_SIZE * 2 / (letters + 1)).max(MIN_LETTER_SIZE)
+                } else {
+                    LETTER_SIZE
+                };
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" text-anchor=\"middle\">{}</text>\n",
+                    x + CELL_SIZE / 2,
+                    y + CELL_SIZE - 8,
+                    size,
+                    escape(value)
+                ));
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterize the SVG rendering of the grid into a PNG
+#[cfg(feature = "png")]
+pub async fn to_png(
+    puzzle: &Puzzle,
+    state: &GridState,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    use resvg::usvg::{self, TreeParsing, TreeTextToPath};
+
+    let mut tree = usvg::Tree::from_str(&to_svg(puzzle, state).await, &usvg::Options::default())?;
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_system_fonts();
+    tree.convert_text(&fonts);
+    let tree = resvg::Tree::from_usvg(&tree);
+    let size = tree.size.to_int_size();
+    let mut pixmap =
+        resvg::tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Grid has no size")?;
+    tree.render(resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap.encode_png()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{to_svg, to_text};
+    use crate::{GridState, Position, Puzzle};
+
+    const SMALL_SVG: &str = include_str!("../tests/fixtures/small.svg");
+
+    /// Three by two grid with a shaded corner, a circled rebus cell and an empty cell
+    fn small() -> (Puzzle, GridState) {
+        let puzzle = Puzzle {
+            width: 3,
+            height: 2,
+            shaded_squares: vec![Position { row: 1, column: 2 }],
+            clues: vec![],
+            circled: vec![Position { row: 0, column: 1 }],
+        };
+        let values = [((0, 0), "C"), ((0, 1), "AT"), ((0, 2), "S"), ((1, 0), "O")]
+            .into_iter()
+            .map(|((row, column), v)| (Position { row, column }, v.to_string()))
+            .collect::<HashMap<_, _>>();
+        (puzzle, GridState::new(values))
+    }
+
+    #[tokio::test]
+    async fn text() {
+        let (puzzle, state) = small();
+        assert_eq!(
+            to_text(&puzzle, &state).await,
+            "+----+----+----+\n| C  | AT | S  |\n+----+----+----+\n| O  | ·  | ██ |\n+----+----+----+\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn svg_snapshot() {
+        let (puzzle, state) = small();
+        assert_eq!(to_svg(&puzzle, &state).await, SMALL_SVG);
+    }
+}
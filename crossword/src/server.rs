@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::Serialize;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    formats::ipuz,
+    memory::{proposals, InMemoryPuzzle},
+    puzzle::{CrosswordPuzzle, PuzzleError},
+    solver::{LLMSolver, PuzzleResult, Question, SolverError},
+};
+
+/// Clients tracked by the RateLimit before those whose window has ended are forgotten
+static TRACKED_CLIENTS: usize = 1024;
+
+/// Requests accepted from each client address in every window of `per`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+/// Requests counted for each client in its current window
+struct Limiter {
+    limit: RateLimit,
+    clients: StdMutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl Limiter {
+    fn allow(&self, client: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() >= TRACKED_CLIENTS {
+            clients.retain(|_, (start, _)| now.duration_since(*start) < self.limit.per);
+        }
+        let (start, count) = clients.entry(client).or_insert((now, 0));
+        if now.duration_since(*start) >= self.limit.per {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.limit.requests
+    }
+}
+
+struct ServerState {
+    /// Clues are answered one at a time, so answers to one clue are cached before the next.
+    /// Puzzles are solved by forks of it, which only hold the lock to start and to hand their
+    /// answers back
+    solver: Mutex<LLMSolver>,
+    limiter: Limiter,
+}
+
+/// Reasons a request was not answered
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request was understood but could not be answered as it is
+    Invalid(String),
+    RateLimited,
+    Solver(SolverError),
+}
+
+impl From<SolverError> for ApiError {
+    fn from(value: SolverError) -> Self {
+        match value {
+            SolverError::Question(message) => ApiError::Invalid(message),
+            e => ApiError::Solver(e),
+        }
+    }
+}
+
+impl From<PuzzleError> for ApiError {
+    fn from(value: PuzzleError) -> Self {
+        ApiError::Invalid(value.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ApiError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            // The LLM is the upstream server of this one
+            ApiError::Solver(SolverError::Llm(_)) => StatusCode::BAD_GATEWAY,
+            ApiError::Solver(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match self {
+            ApiError::Invalid(message) => message,
+            ApiError::RateLimited => String::from("too many requests, try again later"),
+            ApiError::Solver(e) => e.to_string(),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+/// An answer to a clue, ranked by the solver
+#[derive(Debug, Serialize)]
+struct Suggestion {
+    answer: String,
+    confidence: u8,
+}
+
+#[derive(Debug, Serialize)]
+struct Suggestions {
+    candidates: Vec<Suggestion>,
+}
+
+/// Answer of every clue of a puzzle, along with the grid they fill
+#[derive(Debug, Serialize)]
+struct SolvedPuzzle {
+    /// See `InMemoryPuzzle::rows`
+    grid: Vec<String>,
+    #[serde(flatten)]
+    result: PuzzleResult,
+}
+
+async fn solve_clue(
+    State(state): State<Arc<ServerState>>,
+    Json(question): Json<Question>,
+) -> Result<Json<Suggestions>, ApiError> {
+    let candidates = state.solver.lock().await.suggest(&question).await?;
+    Ok(Json(Suggestions {
+        candidates: candidates
+            .into_iter()
+            .map(|c| Suggestion {
+                confidence: c.confidence(),
+                answer: c.answer,
+            })
+            .collect(),
+    }))
+}
+
+/// Solve a whole puzzle sent as an ipuz document. Any solution in the document is ignored, so
+/// the puzzle is solved once every cell is filled
+async fn solve_puzzle(
+    State(state): State<Arc<ServerState>>,
+    document: String,
+) -> Result<Json<SolvedPuzzle>, ApiError> {
+    let (puzzle, _) = ipuz::parse(&document).map_err(|e| ApiError::Invalid(e.to_string()))?;
+    let puzzle = InMemoryPuzzle::new(puzzle, None);
+    let entries = puzzle.generate_entries().await?;
+    let mut solver = state.solver.lock().await.fork();
+    let report = solver.solve(&puzzle).await;
+    // Answers are kept even if the puzzle could not be solved
+    state.solver.lock().await.merge_cache(&solver);
+    let report = report?;
+    Ok(Json(SolvedPuzzle {
+        grid: puzzle.rows().await,
+        result: PuzzleResult {
+            answers: proposals(&entries, solver.entered()).await,
+            report,
+            ..PuzzleResult::default()
+        },
+    }))
+}
+
+async fn rate_limit(
+    State(state): State<Arc<ServerState>>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.limiter.allow(address.ip()) {
+        return ApiError::RateLimited.into_response();
+    }
+    next.run(request).await
+}
+
+/// Routes of the HTTP API. The address of each client is read from its `ConnectInfo`, so the
+/// router must be served with `into_make_service_with_connect_info::<SocketAddr>`
+pub fn router(solver: LLMSolver, limit: RateLimit) -> Router {
+    let state = Arc::new(ServerState {
+        solver: Mutex::new(solver),
+        limiter: Limiter {
+            limit,
+            clients: StdMutex::new(HashMap::new()),
+        },
+    });
+    Router::new()
+        .route("/solve-clue", post(solve_clue))
+        .route("/solve-puzzle", post(solve_puzzle))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+        .with_state(state)
+}
@@ -1,283 +1,6559 @@
 use std::{
-    collections::{HashMap, VecDeque},
-    fmt::Write,
+    cmp::Reverse,
+    collections::{
+        hash_map::{DefaultHasher, RandomState},
+        HashMap, HashSet, VecDeque,
+    },
+    fmt::{self, Write},
+    fs,
+    future::Future,
+    hash::{BuildHasher, Hash, Hasher},
+    io,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
     time::Duration,
 };
 
 use async_trait::async_trait;
-use chatgpt::prelude::ChatGPT;
+use chatgpt::{
+    config::{ChatGPTEngine, ModelConfiguration},
+    err::Error as ChatGPTError,
+    prelude::ChatGPT,
+    types::{ChatMessage, Role},
+};
+use clap::{Args, ValueEnum};
+use futures::{future, stream, StreamExt};
 
-use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
 use thirtyfour::prelude::WebDriverError;
+use tokio::{sync::mpsc, time::Instant};
 
 use crate::{
-    web::{fork_entries, MiniWebPuzzle, WebEntry},
-    Clue, InMemoryEntry,
+    grid::Grid,
+    memory::Proposal,
+    prompt::{PromptTemplate, PromptValues, TemplateError},
+    puzzle::{
+        answer_cells, grid_state, render_entries, CrossingMap, CrosswordPuzzle, Entries, Entry,
+        EntryError, Feedback, GridSnapshot, PuzzleError,
+    },
+    wordlist::WordList,
+    Clue, ClueId, Direction, GridState, InMemoryEntry, Position, PuzzleDate,
 };
 
-/// Generate a ChatGPT prompt for a given Clue
-async fn prompt_for_clue(entry: &WebEntry, entries: &[WebEntry]) -> String {
-    let current_answer = entry.value().await;
+/// Number of candidate answers requested from the LLM for each clue
+static CANDIDATES: usize = 3;
+/// Follow up messages sent when none of the answers to a clue can be entered
+static DEFAULT_RETRIES: usize = 2;
+/// Time to wait for a response, the same as the ChatGPT client waits by default
+static DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Messages kept by a Session that remembers earlier questions, not counting the first question
+static SESSION_MESSAGES: usize = 12;
+/// Entries whose best answer is less confident than this wait for a crossing letter
+static DEFAULT_CONFIDENCE_THRESHOLD: u8 = 50;
+/// Answers cleared from a full grid that is not accepted, see `LLMSolver::recover_suspects`
+static DEFAULT_SUSPECTS: usize = 1;
+
+/// A letter of an Entry shared with another
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Crossing {
+    /// Index of the shared cell in the Entry being asked about
+    index: usize,
+    clue: Clue,
+    /// Letters of the crossing Entry, with `_` for unknown cells
+    pattern: String,
+    /// The letter in the shared cell, if known
+    letter: Option<String>,
+}
+
+/// Everything about an Entry and the grid around it that goes into its prompt, read up front so
+/// the prompt itself can be built without touching the puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClueContext {
+    clue: Clue,
+    length: usize,
+    pattern: String,
+    /// Letters of the answer in circled squares, counted from 1
+    circled: Vec<usize>,
+    crossings: Vec<Crossing>,
+    /// Clues named in the text of this one, along with their current patterns
+    references: Vec<(Clue, String)>,
+    /// Every clue of the puzzle
+    clues: Vec<Clue>,
+}
+
+impl ClueContext {
+    async fn new<E: Entry>(entry: &E, entries: &[E], map: &CrossingMap) -> Self {
+        let clue = entry.clue().clone();
+        let circled = entry.circled();
+        let find = |id: ClueId| entries.iter().find(|e| e.id() == id);
+        let mut crossings = vec![];
+        for (index, crossing) in map.crossings(entry.id()).iter().enumerate() {
+            let Some((other, at)) = crossing else {
+                continue;
+            };
+            let Some(other) = find(*other) else {
+                continue;
+            };
+            crossings.push(Crossing {
+                index,
+                clue: other.clue().clone(),
+                pattern: other.value().await,
+                letter: other.cells().await.get(*at).cloned().flatten(),
+            });
+        }
+        let mut references = vec![];
+        for (number, direction) in clue.references() {
+            if let Some(other) = find(ClueId { number, direction }) {
+                references.push((other.clue().clone(), other.value().await));
+            }
+        }
+        Self {
+            length: entry.expected_length(),
+            pattern: entry.value().await,
+            circled: entry
+                .positions()
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| circled.contains(p))
+                .map(|(i, _)| i + 1)
+                .collect(),
+            crossings,
+            references,
+            clues: entries.iter().map(|e| e.clue().clone()).collect(),
+            clue,
+        }
+    }
+}
+
+/// Generate a ChatGPT prompt for a given Clue by filling in the template
+fn prompt_for_clue(
+    template: &PromptTemplate,
+    context: &ClueContext,
+    excluded: &[String],
+) -> String {
+    let rejected = if excluded.is_empty() {
+        String::new()
+    } else {
+        format!("The answer is NOT any of: {}.\n", excluded.join(", "))
+    };
+    let mut notes = String::new();
+    // Only mention a rebus once one has been entered, otherwise the hint just adds noise
+    if context.pattern.contains('[') {
+        notes.push_str("Squares holding more than one letter are shown in brackets; include them the same way in your answer.\n");
+    }
+    if !context.circled.is_empty() {
+        let letters: Vec<String> = context.circled.iter().map(usize::to_string).collect();
+        let _ = writeln!(
+            &mut notes,
+            "Letters {} of the answer are in circled squares, which are usually part of the puzzle's theme.",
+            letters.join(", ")
+        );
+    }
+    let hints = context.clue.hints();
+    if let Some(words) = hints.words.filter(|w| *w > 1) {
+        let _ = writeln!(
+            &mut notes,
+            "The answer is {} words totaling {} letters; respond with the words separated by spaces.",
+            words, context.length
+        );
+    }
+    if hints.abbreviation {
+        notes.push_str("The answer is an abbreviation or shortened form, not the full word.\n");
+    }
+    // The model can't see the answers of the clues this one refers to unless they are spelled out
+    for (clue, pattern) in &context.references {
+        let _ = writeln!(
+            &mut notes,
+            "{}-{} is \"{}\" and currently reads {}.",
+            clue.number, clue.direction, clue.text, pattern
+        );
+    }
+    let mut other_clues = String::new();
+    for clue in &context.clues {
+        let _ = writeln!(&mut other_clues, "{}", clue);
+    }
+    let mut crossings = String::new();
+    for crossing in &context.crossings {
+        let _ = write!(
+            &mut crossings,
+            "Letter {} crosses {}-{} \"{}\", which reads {} so far",
+            crossing.index + 1,
+            crossing.clue.number,
+            crossing.clue.direction,
+            crossing.clue.text,
+            crossing.pattern
+        );
+        match &crossing.letter {
+            Some(letter) => {
+                let _ = writeln!(
+                    &mut crossings,
+                    ", so letter {} is likely {}.",
+                    crossing.index + 1,
+                    letter
+                );
+            }
+            None => crossings.push_str(".\n"),
+        }
+    }
+    template.render(&PromptValues {
+        clue: &context.clue.text,
+        length: context.length,
+        pattern: &context.pattern,
+        count: CANDIDATES,
+        notes: &notes,
+        other_clues: &other_clues,
+        crossings: &crossings,
+        rejected: &rejected,
+    })
+}
+
+/// Generate a prompt asking for the answer to every clue of the puzzle at once
+async fn prompt_for_puzzle<E: Entry>(entries: &[E]) -> String {
+    let positions: Vec<_> = entries.iter().flat_map(|e| e.positions()).collect();
+    let width = positions
+        .iter()
+        .map(|p| p.column + 1)
+        .max()
+        .unwrap_or_default();
+    let height = positions
+        .iter()
+        .map(|p| p.row + 1)
+        .max()
+        .unwrap_or_default();
+    let mut clues = String::new();
+    for entry in entries {
+        let clue = entry.clue();
+        let _ = writeln!(
+            &mut clues,
+            "{}-{} ({} letters, {}): {}",
+            clue.number,
+            clue.direction,
+            entry.expected_length(),
+            entry.value().await,
+            clue.text
+        );
+    }
     format!(
-        "Determine the answer to the following crossword clue.
-        <text>
-        {}
-        </text>
-        The answer is {} letters long and may match the pattern {}. Respond with just the answer, no other text. Do not include punctuation or hyphens.
-        For reference the other clues are included below.
+        "Solve the following {}x{} crossword puzzle. Each clue is listed with the length of its answer and a pattern of the letters already known, with _ for an unknown square. Answers that cross must share the letter in that square.
         <text>
         {}
         </text>
+        Respond with a JSON object mapping the number and direction of each clue, e.g. \"1-Across\", to your answer. Include no other text. Do not include punctuation or hyphens in the answers.
         ",
-        entry.clue().text,
-        current_answer.len(),
-        current_answer,
-        entries.iter().fold(String::new(),
-         |mut output, entry| {
-            let _  = writeln!(&mut output, "{}-{:?}: {} ", entry.clue().number, entry.clue().direction, entry.clue().text);
-             output})
+        width, height, clues
     )
 }
 
-pub enum APIKey {
-    OpenAI(String),
-    Cohere(String),
+/// Answers from a response to `prompt_for_puzzle`, keyed by clue number and direction. Models
+/// wrap the object in markdown or prose, leave trailing commas and change the case or format of
+/// the keys, so all of that is tolerated. `None` if no JSON object could be found
+fn parse_batch(response: &str) -> Option<HashMap<(usize, Direction), String>> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    let json = response.get(start..=end)?;
+    static TRAILING_COMMAS: OnceLock<Regex> = OnceLock::new();
+    let trailing_commas = TRAILING_COMMAS
+        .get_or_init(|| Regex::new(r",\s*([}\]])").expect("Invalid trailing comma regex"));
+    let object: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&trailing_commas.replace_all(json, "$1")).ok()?;
+    let mut answers = HashMap::new();
+    batch_answers(&object, "", &mut answers);
+    Some(answers)
 }
 
-pub struct LLMSolver {
-    llm: Box<dyn LLMModel>,
-    cache: HashMap<String, Option<String>>,
+/// Collect the answers of a JSON object, including those nested under a key such as "Across"
+fn batch_answers(
+    object: &serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    answers: &mut HashMap<(usize, Direction), String>,
+) {
+    for (key, value) in object {
+        let key = format!("{} {}", prefix, key);
+        let answer = match value {
+            serde_json::Value::String(answer) => answer,
+            // Several answers for one clue, the first is the most likely
+            serde_json::Value::Array(values) => match values.first() {
+                Some(serde_json::Value::String(answer)) => answer,
+                _ => continue,
+            },
+            serde_json::Value::Object(nested) => {
+                batch_answers(nested, &key, answers);
+                continue;
+            }
+            _ => continue,
+        };
+        let number = key
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .ok();
+        let direction = match key
+            .chars()
+            .find(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_lowercase())
+        {
+            Some('a') => Some(Direction::Across),
+            Some('d') => Some(Direction::Down),
+            _ => None,
+        };
+        if let (Some(number), Some(direction)) = (number, direction) {
+            answers.insert((number, direction), answer.clone());
+        }
+    }
 }
 
-impl LLMSolver {
-    pub fn new(api_key: APIKey) -> chatgpt::Result<Self> {
-        Ok(Self {
-            llm: match api_key {
-                APIKey::OpenAI(key) => {
-                    Box::new(OpenAI::new(key).expect("Failed to connect to ChatGPT"))
-                }
-                APIKey::Cohere(key) => Box::new(Cohere::new(key)),
+/// Split an LLM response into its candidate answers, either a JSON array or a comma separated
+/// list on the first line. Anything after the first line is usually an unwanted explanation
+fn parse_candidates(response: &str) -> Vec<String> {
+    let mut candidates =
+        serde_json::from_str::<Vec<String>>(response.trim()).unwrap_or_else(|_| {
+            response
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or_default()
+                .split(',')
+                .map(String::from)
+                .collect()
+        });
+    candidates.retain(|c| !c.trim().is_empty());
+    candidates.truncate(CANDIDATES);
+    candidates
+}
+
+/// Split the confidence given for a candidate, e.g. `CASH 90` or `CASH (90%)`, from the answer.
+/// A number must be separated from the answer, otherwise it is left as part of the answer
+fn split_confidence(raw: &str) -> (&str, Option<u8>) {
+    let trimmed = raw
+        .trim()
+        .trim_end_matches(|c: char| matches!(c, '%' | ')') || c.is_whitespace());
+    let answer = trimmed.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = &trimmed[answer.len()..];
+    match answer.chars().last() {
+        Some(c) if !number.is_empty() && !c.is_alphanumeric() && c != ']' => (
+            answer.trim_end_matches(|c: char| !c.is_alphanumeric() && c != ']'),
+            number.parse::<u32>().ok().map(|n| n.min(100) as u8),
+        ),
+        _ => (raw, None),
+    }
+}
+
+/// Strip the formatting an LLM adds to an answer despite being asked not to, returning the
+/// uppercased letters. Brackets are kept so a rebus can be written the same way as the grid
+fn clean_answer(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+    // Drop a leading label such as "Answer:" and a trailing parenthetical explanation
+    let line = line.rsplit(':').next().unwrap_or(line);
+    let line = line.split('(').next().unwrap_or(line);
+    let answer: String = line
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '[' | ']'))
+        .collect::<String>()
+        .to_uppercase();
+    (!answer.is_empty()
+        && answer
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || matches!(c, '[' | ']')))
+    .then_some(answer)
+}
+
+/// Normalize a raw LLM answer so it can be entered in the grid, or `None` if it does not have
+/// exactly `expected_len` cells once cleaned up. Spaces, punctuation and anything after the first
+/// line are dropped before the length is checked, so the words of a multi-word answer are counted
+/// together, but answers with digits or accented letters are rejected rather than guessed
+pub fn normalize_answer(raw: &str, expected_len: usize) -> Option<String> {
+    clean_answer(raw).filter(|answer| answer_cells(answer).len() == expected_len)
+}
+
+/// Candidates from an LLM response that could be entered in the Entry
+async fn accepted_candidates<E: Entry>(entry: &E, response: &str) -> Vec<Candidate> {
+    let length = entry.expected_length();
+    let mut candidates: Vec<Candidate> = vec![];
+    for raw in parse_candidates(response) {
+        let (raw, confidence) = split_confidence(&raw);
+        let answer = match normalize_answer(raw, length) {
+            Some(answer) => Some(answer),
+            // A longer answer may still fit if the crossings place its extra letters in a rebus
+            None => match clean_answer(raw) {
+                Some(answer) if answer.chars().count() > length => entry.fit_rebus(&answer).await,
+                _ => None,
             },
-            cache: HashMap::new(),
-        })
+        };
+        if let Some(answer) = answer.filter(|a| candidates.iter().all(|c| &c.answer != a)) {
+            candidates.push(Candidate { answer, confidence });
+        }
     }
+    candidates
+}
 
-    /// Generate an answer for a provided Clue
-    async fn solve_clue(
-        &mut self,
-        entry: &WebEntry,
-        entries: &[WebEntry],
-    ) -> chatgpt::Result<Option<String>> {
-        let prompt = prompt_for_clue(entry, entries).await;
-        println!("{}-{:?}", entry.clue().number, entry.clue().direction);
-        // If we've asked this before don't bother asking again
-        if let Some(ans) = self.cache.get(&prompt) {
-            println!("Cached answer {:?}", ans);
-            Ok(ans.clone())
-        } else {
-            // Ask ChatGPT
-            let answer = self
-                .llm
-                .chat(prompt.clone())
-                .await
-                .to_ascii_uppercase()
-                // This is frustrating we have to do this... we asked not to include them.
-                .replace('_', "");
-            println!("{}", answer);
-            // Check this answer could plausibly be entered by verifying the length of the response
-            if answer.len() == entry.length().await {
-                self.cache.insert(prompt, Some(answer.clone()));
-                Ok(Some(answer))
-            } else {
-                Ok(None)
-            }
+/// Order in which the solver chooses the next Entry to answer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ordering {
+    /// Entries in clue order, jumping to the crossings of each answer once it is entered
+    Queue,
+    /// The Entry with the largest fraction of its letters known, where answers are most reliable
+    #[default]
+    MostConstrained,
+    /// The Entry the LLM is most confident about, waiting on uncertain entries until one of
+    /// their letters is known
+    Confidence,
+}
+
+/// Whether to ask for every answer in a single prompt before solving clue by clue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BatchStrategy {
+    /// Only ask about one clue at a time
+    #[default]
+    None,
+    /// Enter the answers to a single prompt for the whole puzzle that agree with each other,
+    /// then solve the remaining entries clue by clue. Cheap models do far better with the
+    /// context of the whole puzzle
+    FirstPass,
+}
+
+/// An answer suggested by the LLM
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub answer: String,
+    /// How confident the LLM said it was, from 0 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<u8>,
+}
+
+impl Candidate {
+    /// Answers without a confidence are trusted, so models that ignore the request for one
+    /// solve the grid in the same order as before
+    pub fn confidence(&self) -> u8 {
+        self.confidence.unwrap_or(100)
+    }
+}
+
+/// Written in the same format the LLM is asked to answer in
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.confidence {
+            Some(confidence) => write!(f, "{} {}", self.answer, confidence),
+            None => write!(f, "{}", self.answer),
         }
     }
+}
 
-    pub async fn solve(&mut self, puzzle: &MiniWebPuzzle) -> Result<bool, WebDriverError> {
-        let entries = puzzle.generate_entries().await?;
+/// Follow up message for a response where none of the answers could be entered
+fn correction(response: &str, length: usize, pattern: &str) -> String {
+    let found = parse_candidates(response)
+        .first()
+        .and_then(|raw| clean_answer(raw))
+        .map(|answer| answer_cells(&answer).len());
+    format!(
+        "{} answer must be exactly {} letters long and match the pattern {}. Respond with your {} best answers as a comma separated list, most likely first, each followed by how confident you are that it is correct from 0 to 100. Include no other text.",
+        match found {
+            Some(n) => format!("That has {} letters; the", n),
+            None => String::from("The"),
+        },
+        length,
+        pattern,
+        CANDIDATES
+    )
+}
 
-        let mut checkpoints = VecDeque::from([HashMap::<Clue, InMemoryEntry>::new()]);
-        while let Some(state) = checkpoints.pop_front() {
-            // Set the state back to the checkpoint
-            for entry in entries.iter() {
-                if let Some(cached_entry) = state.get(&entry.clue()) {
-                    entry.write_chars(cached_entry.chars().await).await?;
-                }
-            }
-            match self.solve_grid(entries.clone(), puzzle).await {
-                Ok(_) => return Ok(true),
-                Err(GridSolveError::WebDriverError(e)) => return Err(e),
-                Err(GridSolveError::FailedToSolve(cps)) => {
-                    for checkpoint in cps {
-                        checkpoints.push_front(checkpoint);
-                    }
-                }
-            }
+#[derive(Clone)]
+pub enum APIKey {
+    OpenAI(String),
+    Cohere(String),
+    Anthropic(String),
+    Gemini(String),
+    /// A model served locally by Ollama, which needs no key
+    Ollama {
+        base_url: String,
+        model: String,
+    },
+}
+
+impl APIKey {
+    /// Name of the backend the key is for
+    pub fn name(&self) -> String {
+        match self {
+            APIKey::OpenAI(_) => String::from("OpenAI"),
+            APIKey::Cohere(_) => String::from("Cohere"),
+            APIKey::Anthropic(_) => String::from("Anthropic"),
+            APIKey::Gemini(_) => String::from("Gemini"),
+            APIKey::Ollama { model, .. } => format!("Ollama ({})", model),
         }
-        Ok(false)
     }
-    /// Attempt to solve a Grid until we get stuck
-    async fn solve_grid(
-        &mut self,
-        entries: Vec<WebEntry>,
-        puzzle: &MiniWebPuzzle,
-        // start_entry_idx: usize,
-    ) -> Result<(), GridSolveError> {
-        let mut next_entries = VecDeque::from(entries.clone());
-        // next_entries.rotate_left(start_entry_idx);
-        let mut checkpoints = vec![];
-        while let Some(entry) = next_entries.pop_front() {
-            // Do not attempt to solve an already completed answer
-            if entry.filled().await {
-                continue;
+
+    /// Highest sampling temperature the backend accepts
+    fn max_temperature(&self) -> f32 {
+        match self {
+            APIKey::OpenAI(_) => OPENAI_MAX_TEMPERATURE,
+            APIKey::Cohere(_) => COHERE_MAX_TEMPERATURE,
+            APIKey::Anthropic(_) => ANTHROPIC_MAX_TEMPERATURE,
+            APIKey::Gemini(_) => GEMINI_MAX_TEMPERATURE,
+            APIKey::Ollama { .. } => OLLAMA_MAX_TEMPERATURE,
+        }
+    }
+
+    /// Connect to the backend, retrying messages that fail in a way that may succeed later
+    fn backend(self, config: ModelConfig) -> Result<Box<dyn LLMModel>, LlmError> {
+        Ok(match self {
+            APIKey::OpenAI(key) => Box::new(Retrying::new(OpenAI::new(key, config)?)),
+            APIKey::Cohere(key) => Box::new(Retrying::new(Cohere::new(key, config)?)),
+            APIKey::Anthropic(key) => Box::new(Retrying::new(Anthropic::new(key, config)?)),
+            APIKey::Gemini(key) => Box::new(Retrying::new(Gemini::new(key, config)?)),
+            APIKey::Ollama { base_url, model } => {
+                Box::new(Retrying::new(Ollama::new(base_url, model, config)?))
             }
-            // Request a new answer for the Clue from the ChatGPT
-            let Some(answer) = self
-                .solve_clue(&entry, &entries)
-                .await
-                .expect("Error with ChatGPT API")
-            else {
-                continue;
-            };
-            // If the answer fits in our current Grid continue on
-            if entry.fits(&answer).await {
-                // Enter our new answer into the grid
-                entry.write(answer).await?;
-                // Add any crosses to the front of our queue to try next. They have new information
-                // for us to send to ChatGPT
-                for cross in entry.crossing_entries(entries.clone()) {
-                    next_entries.push_front(cross)
-                }
-            } else {
-                // We want to capture the state of the system if we used this answer
-                // and cleared out any other answers that disagree. This is our "backtrack"
-                // that gives us a chance to clear out old bad answers
-                println!("Capturing backtrack to enter {} in grid", answer);
-                // Capture the current state of all the cells.
-                let state = fork_entries(&entries).await;
-                // Find crossing clues that are creating the conflict and clear them out
-                for cross in entry.conflicting_entries(&answer, &entries).await {
-                    state
-                        .get(&cross.clue())
-                        .expect("Cross not in state!")
-                        .clear()
-                        .await
-                }
-                // Write the new answer in to the old state
-                state
-                    .get(&entry.clue())
-                    .expect("Entry not in state!")
-                    .write(answer)
-                    .await;
-                checkpoints.push(state);
+        })
+    }
+}
+
+/// Command line flags used to select an LLM. Binaries decide whether one is required. Passing
+/// several asks all of them about each clue
+#[derive(Args, Debug)]
+#[group(multiple = true)]
+pub struct KeyOpts {
+    #[clap(long)]
+    pub openai: Option<String>,
+    #[clap(long)]
+    pub cohere: Option<String>,
+    #[clap(long)]
+    pub anthropic: Option<String>,
+    #[clap(long)]
+    pub gemini: Option<String>,
+    // Name of a model served by Ollama, e.g. llama3. Set OLLAMA_HOST if it is not running on
+    // the default port
+    #[clap(long)]
+    pub ollama: Option<String>,
+}
+
+impl KeyOpts {
+    /// A key for every backend that was selected, in a fixed order
+    pub fn api_keys(self) -> Vec<APIKey> {
+        let mut keys = vec![];
+        keys.extend(self.openai.map(APIKey::OpenAI));
+        keys.extend(self.cohere.map(APIKey::Cohere));
+        keys.extend(self.anthropic.map(APIKey::Anthropic));
+        keys.extend(self.gemini.map(APIKey::Gemini));
+        keys.extend(self.ollama.map(|model| APIKey::Ollama {
+            base_url: ollama_url(std::env::var("OLLAMA_HOST").ok()),
+            model,
+        }));
+        keys
+    }
+}
+
+/// Model and generation parameters for an LLM backend. Options left unset use the backend's
+/// own defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelConfig {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    /// Longest response to generate
+    pub max_tokens: Option<u32>,
+    pub timeout: Duration,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            temperature: None,
+            max_tokens: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl ModelConfig {
+    /// Reject options that `backend` would not accept, rather than letting the first request
+    /// fail in the middle of a solve
+    fn validate(&self, backend: &str, max_temperature: f32) -> Result<(), LlmError> {
+        let invalid = |reason: String| Err(LlmError::Config(format!("{} {}", backend, reason)));
+        if self.model.as_ref().is_some_and(|m| m.trim().is_empty()) {
+            return invalid(String::from("model name is empty"));
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=max_temperature).contains(&temperature) {
+                return invalid(format!(
+                    "temperature must be between 0 and {}, not {}",
+                    max_temperature, temperature
+                ));
             }
         }
-        if puzzle.verify_entries(&entries).await? {
-            Ok(())
-        } else {
-            // We reverse here because we want to try states we discovered later in the solve first
-            checkpoints.reverse();
-            Err(GridSolveError::FailedToSolve(checkpoints))
+        if self.max_tokens == Some(0) {
+            return invalid(String::from("max tokens must be at least 1"));
+        }
+        if self.timeout.is_zero() {
+            return invalid(String::from("timeout must be longer than zero"));
         }
+        Ok(())
     }
 }
 
-enum GridSolveError {
-    FailedToSolve(Vec<HashMap<Clue, InMemoryEntry>>),
-    WebDriverError(WebDriverError),
+pub struct LLMSolver {
+    /// Shared with every fork of the solver
+    llm: Arc<dyn LLMModel>,
+    cache: HashMap<CacheKey, Vec<Candidate>>,
+    cache_file: Option<PathBuf>,
+    report: SolveReport,
+    subscribers: Vec<Subscriber>,
+    /// Answers asked for ahead of time that have not been looked up yet
+    prefetched: HashSet<CacheKey>,
+    concurrency: usize,
+    retries: usize,
+    confidence_threshold: u8,
+    ordering: Ordering,
+    batch: BatchStrategy,
+    budget: Budget,
+    /// Answers to avoid, kept until the letters around them change
+    rejected: Vec<Rejection>,
+    reject_dead_ends: bool,
+    /// Letters known to be wrong in their cells, never entered there again
+    wrong_letters: HashMap<Position, String>,
+    /// Answers known before the solve, by the number and direction of their clue
+    hints: HashMap<ClueId, String>,
+    /// Letters of the hinted answers entered for the current solve, which every answer has to
+    /// agree with
+    trusted: HashMap<Position, String>,
+    wordlist: Option<WordList>,
+    check_words: bool,
+    template: PromptTemplate,
+    /// Conversation about each clue that has been asked about during the solve
+    sessions: HashMap<ClueId, Session>,
+    /// Last answer entered into each Entry during the solve
+    entered: HashMap<ClueId, Candidate>,
+    suspects: usize,
+    in_memory: bool,
+    /// When the time Budget of the current solve runs out
+    deadline: Option<Instant>,
 }
 
-impl From<WebDriverError> for GridSolveError {
-    fn from(value: WebDriverError) -> Self {
-        GridSolveError::WebDriverError(value)
+/// Limits on the work done by a single call to `LLMSolver::solve` before it gives up. Nothing is
+/// limited by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Passes over the grid, including the first
+    pub grid_attempts: Option<usize>,
+    /// Prompts sent to the LLM
+    pub llm_calls: Option<usize>,
+    pub time: Option<Duration>,
+}
+
+/// The limit of a Budget that stopped the solver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetExceeded {
+    GridAttempts,
+    LlmCalls,
+    Time,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::GridAttempts => write!(f, "ran out of grid attempts"),
+            BudgetExceeded::LlmCalls => write!(f, "ran out of LLM calls"),
+            BudgetExceeded::Time => write!(f, "ran out of time"),
+        }
     }
 }
 
-#[async_trait]
-trait LLMModel {
-    async fn chat(&self, message: String) -> String;
+/// Work done so far by the current call to `solve`
+struct Usage {
+    started: Instant,
+    /// Passes over the grid that ended without a solution
+    grid_attempts: usize,
 }
 
-struct Cohere {
-    client: reqwest::Client,
-    key: String,
+/// Identifies a question asked of the LLM. Only the clue, its direction and the letters already
+/// known are included, so answers can be reused across backtracks and runs even as the rest of
+/// the prompt changes
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub clue: String,
+    /// Missing from cache files written before it was saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    pub pattern: String,
+    /// Answers the LLM was told are wrong, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<String>,
 }
 
-impl Cohere {
-    fn new(key: String) -> Self {
-        Cohere {
-            client: reqwest::Client::builder().build().unwrap(),
-            key,
+impl CacheKey {
+    pub fn new(clue: &str, pattern: &str) -> Self {
+        Self {
+            clue: clue.split_whitespace().collect::<Vec<_>>().join(" "),
+            // Anything other than a letter or rebus bracket is an unknown cell
+            pattern: pattern
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c {
+                    '[' | ']' => c,
+                    c if c.is_alphanumeric() => c.to_ascii_uppercase(),
+                    _ => '_',
+                })
+                .collect(),
+            direction: None,
+            excluded: vec![],
+        }
+    }
+
+    /// The question about the clue with the given letters known
+    pub fn for_clue(clue: &Clue, pattern: &str) -> Self {
+        Self {
+            direction: Some(clue.direction),
+            ..Self::new(&clue.text, pattern)
+        }
+    }
+
+    /// Whether none of the letters of the answer are known
+    pub fn is_blank(&self) -> bool {
+        answer_cells(&self.pattern).iter().all(|cell| cell == "_")
+    }
+
+    /// The same question, telling the LLM that none of `answers` are correct
+    pub fn excluding(mut self, mut answers: Vec<String>) -> Self {
+        answers.sort();
+        answers.dedup();
+        self.excluded = answers;
+        self
+    }
+}
+
+/// An answer that led to a dead end, along with the letters of the Entry that were known when
+/// it was entered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Rejection {
+    clue: String,
+    direction: Direction,
+    pattern: String,
+    answer: String,
+}
+
+impl Rejection {
+    fn new(clue: &Clue, pattern: &str, answer: String) -> Self {
+        let key = CacheKey::new(&clue.text, pattern);
+        Self {
+            clue: key.clue,
+            direction: clue.direction,
+            pattern: key.pattern,
+            answer,
         }
     }
+
+    fn applies_to(&self, clue: &Clue) -> bool {
+        self.direction == clue.direction && self.clue == CacheKey::new(&clue.text, "").clue
+    }
 }
 
+/// Whether two patterns could describe the same answer, i.e. no known cell differs
+fn compatible(pattern: &str, other: &str) -> bool {
+    answer_cells(pattern)
+        .iter()
+        .zip(answer_cells(other).iter())
+        .all(|(a, b)| a == "_" || b == "_" || a == b)
+}
+
+/// A single cache entry as stored on disk. JSON objects only allow string keys
+#[derive(Serialize, Deserialize)]
+struct CachedAnswers {
+    #[serde(flatten)]
+    key: CacheKey,
+    answers: Vec<Candidate>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    answers: Vec<CachedAnswers>,
+    #[serde(default)]
+    rejected: Vec<Rejection>,
+}
+
+/// Cache files written before rejected answers were saved only hold the answers
 #[derive(Deserialize)]
-struct CohereChatResponse {
-    text: String,
+#[serde(untagged)]
+enum StoredCache {
+    File(CacheFile),
+    Answers(Vec<CachedAnswers>),
 }
 
-#[derive(Serialize)]
-struct CohereRequest {
-    message: String,
+/// Work done by a single call to `LLMSolver::solve`. Every counter is exact
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SolveReport {
+    pub solved: bool,
+    /// Prompts sent to the LLM, not including cached answers
+    pub llm_calls: usize,
+    /// Questions answered from the cache instead of the LLM
+    pub cache_hits: usize,
+    /// Answers entered from the single prompt of `BatchStrategy::FirstPass`
+    pub batch_answers: usize,
+    /// Characters sent to the LLM. A follow up prompt resends the whole conversation
+    pub prompt_chars: usize,
+    /// Follow up prompts sent after an answer of the wrong length. These are included in
+    /// `llm_calls`
+    pub retries: usize,
+    /// Best answers that disagreed with letters already in the grid
+    pub conflicts: usize,
+    /// Grids captured to backtrack to, including those skipped as duplicates
+    pub checkpoints: usize,
+    /// Times the grid was reset to a previously captured checkpoint
+    pub backtracks: usize,
+    /// Checkpoints that were skipped because the same grid had already been queued
+    pub duplicate_states: usize,
+    /// Answers entered to create a checkpoint that led to a dead end
+    pub rejected_answers: usize,
+    /// Entries the puzzle marked incorrect when verifying the grid, counted once per pass
+    pub incorrect_entries: usize,
+    /// Answers cleared from a full grid that was not accepted because they were the most
+    /// likely to be wrong
+    pub suspects_cleared: usize,
+    /// Entries the LLM had no answer for that were given words from the WordList instead.
+    /// Counted each time the Entry is looked up
+    pub wordlist_answers: usize,
+    /// Answers of the LLM that were neither known words nor confirmed by a crossing letter,
+    /// and were ranked after the others. Counted each time the Entry is looked up
+    pub unknown_answers: usize,
+    /// Set if the solve stopped early because of the Budget
+    pub budget_exceeded: Option<BudgetExceeded>,
+    /// The solve stopped early because it ran out of time
+    pub timed_out: bool,
+    /// Time waiting on the LLM for every question that was not cached, in the order asked
+    pub clue_latency: Vec<ClueLatency>,
+    /// Work done by each backend of an Ensemble, empty for a single backend
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<BackendReport>,
+    /// Time on the puzzle's own timer, for puzzles that keep one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_time: Option<PageTime>,
+    #[serde(serialize_with = "as_seconds")]
+    pub wall_time: Duration,
 }
 
-#[async_trait]
-impl LLMModel for Cohere {
-    async fn chat(&self, message: String) -> String {
-        let response = self
-            .client
-            .post("https://api.cohere.ai/v1/chat")
-            .header("Authorization", &format!("Bearer {}", self.key))
-            .header("Content-Type", "application/json")
-            .body(
-                serde_json::to_string(&CohereRequest {
-                    message: message.clone(),
+/// Time shown by the timer of a puzzle when the solve started and when it ended. It starts
+/// ahead of zero when an earlier attempt was resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PageTime {
+    #[serde(serialize_with = "as_seconds")]
+    pub started: Duration,
+    #[serde(serialize_with = "as_seconds")]
+    pub ended: Duration,
+}
+
+impl PageTime {
+    /// Time the timer ran during the solve
+    pub fn solve_time(&self) -> Duration {
+        self.ended.saturating_sub(self.started)
+    }
+}
+
+impl SolveReport {
+    /// Fraction of the questions about a clue that were answered from the cache
+    pub fn cache_hit_rate(&self) -> f64 {
+        let questions = self.cache_hits + self.clue_latency.len();
+        if questions == 0 {
+            0.0
+        } else {
+            self.cache_hits as f64 / questions as f64
+        }
+    }
+}
+
+/// A clue asked about outside of any puzzle, like those sent to the HTTP API of `serve`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Question {
+    pub clue: String,
+    pub length: usize,
+    /// Letters of the answer with `_` for unknown cells. Every cell is unknown without one
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub crossings: Vec<QuestionCrossing>,
+}
+
+/// An answer crossing the one asked about in a Question
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QuestionCrossing {
+    pub clue: String,
+    /// Letters of the crossing answer with `_` for unknown cells
+    pub pattern: String,
+    /// Cell of the answer asked about that the two share, counted from 0
+    pub index: usize,
+    /// Cell of the crossing answer that the two share, counted from 0
+    pub at: usize,
+}
+
+impl Question {
+    /// Check the pattern and every crossing fit an answer of `length` and agree with each other
+    pub fn validate(&self) -> Result<(), String> {
+        if self.length == 0 {
+            return Err(String::from("length must be at least 1"));
+        }
+        let cells = self.cells();
+        if cells.len() != self.length {
+            return Err(format!(
+                "pattern has {} cells but length is {}",
+                cells.len(),
+                self.length
+            ));
+        }
+        let mut shared = HashSet::new();
+        for crossing in &self.crossings {
+            let crossing_cells = answer_cells(&crossing.pattern);
+            if crossing.index >= self.length || !shared.insert(crossing.index) {
+                return Err(format!(
+                    "crossing {:?} does not cross a cell of its own",
+                    crossing.clue
+                ));
+            }
+            let Some(letter) = crossing_cells.get(crossing.at) else {
+                return Err(format!(
+                    "crossing {:?} has no cell {}",
+                    crossing.clue, crossing.at
+                ));
+            };
+            let ours = &cells[crossing.index];
+            if ours != "_" && letter != "_" && !ours.eq_ignore_ascii_case(letter) {
+                return Err(format!(
+                    "crossing {:?} has {} where the pattern has {}",
+                    crossing.clue, letter, ours
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn cells(&self) -> Vec<String> {
+        match &self.pattern {
+            Some(pattern) => answer_cells(pattern),
+            None => vec![String::from("_"); self.length],
+        }
+    }
+
+    /// Entries of a grid with the answer across its lowest row and each crossing down through
+    /// it, holding the known letters. The answer asked about is first
+    fn entries(&self) -> Vec<InMemoryEntry> {
+        let row = self.crossings.iter().map(|c| c.at).max().unwrap_or(0);
+        let mut letters = HashMap::new();
+        let mut known = |positions: &[Position], cells: Vec<String>| {
+            for (position, cell) in positions.iter().zip(cells) {
+                if cell != "_" {
+                    letters.insert(*position, cell.to_ascii_uppercase());
+                }
+            }
+        };
+        let across: Vec<Position> = (0..self.length)
+            .map(|column| Position { row, column })
+            .collect();
+        known(&across, self.cells());
+        let mut entries = vec![(
+            Clue {
+                number: 1,
+                direction: Direction::Across,
+                text: self.clue.clone(),
+                position: across[0],
+            },
+            across,
+        )];
+        for (i, crossing) in self.crossings.iter().enumerate() {
+            let cells = answer_cells(&crossing.pattern);
+            let down: Vec<Position> = (0..cells.len())
+                .map(|offset| Position {
+                    row: row - crossing.at + offset,
+                    column: crossing.index,
                 })
-                .expect("Failed to serialize request"),
-            )
-            .send()
-            .await
-            .expect("Failed to connect to Cohere");
+                .collect();
+            known(&down, cells);
+            entries.push((
+                Clue {
+                    number: i + 2,
+                    direction: Direction::Down,
+                    text: crossing.clue.clone(),
+                    position: down[0],
+                },
+                down,
+            ));
+        }
+        let state = GridState::new(letters);
+        entries
+            .into_iter()
+            .map(|(clue, positions)| InMemoryEntry {
+                clue,
+                positions,
+                circled: vec![],
+                state: state.clone(),
+            })
+            .collect()
+    }
+}
 
-        // 5 requests per minute on the trial license
-        if response.status() == StatusCode::TOO_MANY_REQUESTS {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            return self.chat(message).await;
+/// Outcome of solving a single puzzle. `crossword --output json` writes one, and the report of
+/// `evaluate` one for each puzzle, so both can be read with the same schema
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PuzzleResult {
+    /// Day the puzzle was published, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<PuzzleDate>,
+    /// Whether the grid was checked against the real solution, or only for being filled
+    pub has_solution: bool,
+    /// Final answer of every clue
+    pub answers: Vec<Proposal>,
+    /// Clues with a cell that is wrong, empty when the grid was not checked cell by cell
+    pub incorrect_entries: Vec<String>,
+    /// Set if the puzzle could not be loaded or the solver failed
+    pub error: Option<String>,
+    /// Counts from the solver, including `budget_exceeded` if it gave up before exploring every
+    /// checkpoint
+    #[serde(flatten)]
+    pub report: SolveReport,
+}
+
+/// Work done by one backend of an Ensemble, to see which of them earn their keep
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BackendReport {
+    pub name: String,
+    /// Messages sent, including those that failed
+    pub calls: usize,
+    pub errors: usize,
+    /// Responses whose first answer was also the first answer of the Ensemble
+    pub chosen: usize,
+    /// Responses whose first answer was the same as that of every other backend
+    pub agreed: usize,
+    /// Time spent waiting on the backend, summed over every message
+    #[serde(serialize_with = "as_seconds")]
+    pub latency: Duration,
+}
+
+impl BackendReport {
+    /// The work done since `earlier` was taken
+    fn since(&self, earlier: &BackendReport) -> BackendReport {
+        BackendReport {
+            name: self.name.clone(),
+            calls: self.calls.saturating_sub(earlier.calls),
+            errors: self.errors.saturating_sub(earlier.errors),
+            chosen: self.chosen.saturating_sub(earlier.chosen),
+            agreed: self.agreed.saturating_sub(earlier.agreed),
+            latency: self.latency.saturating_sub(earlier.latency),
         }
-        response
-            .error_for_status()
-            .expect("Unexpected status")
-            .json::<CohereChatResponse>()
-            .await
-            .expect("Failed to parse CohereResponse")
-            .text
     }
 }
 
-struct OpenAI {
-    gpt: ChatGPT,
+/// Time taken to answer a question about a clue, including any follow up prompts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClueLatency {
+    pub clue: Clue,
+    pub pattern: String,
+    #[serde(serialize_with = "as_seconds")]
+    pub latency: Duration,
 }
 
-impl OpenAI {
-    fn new(api_key: String) -> chatgpt::Result<Self> {
-        Ok(Self {
-            gpt: ChatGPT::new(api_key)?,
-        })
+fn as_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Progress of an LLMSolver, sent to every subscriber as the puzzle is solved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SolverEvent {
+    /// A new question is about to be sent to the LLM. `pattern` has `_` for unknown cells
+    ClueAttempted { clue: Clue, pattern: String },
+    /// The raw response of the LLM, or the candidates that were cached for the same question
+    AnswerReceived {
+        clue: Clue,
+        answer: String,
+        cached: bool,
+    },
+    /// None of the answers in a response could fill the Entry
+    AnswerRejected { clue: Clue, reason: String },
+    /// An answer was entered, with the grid afterwards rendered as text
+    EntryWritten {
+        clue: Clue,
+        answer: String,
+        grid: String,
+    },
+    /// The best answer for an Entry disagrees with letters already in the grid
+    ConflictDetected { clue: Clue, answer: String },
+    /// A grid with `answer` entered was captured to backtrack to
+    CheckpointCreated { clue: Clue, answer: String },
+    /// The grid was reset to a checkpoint, leaving `remaining` still to try. `answer` is the
+    /// answer the checkpoint was captured to enter, if any
+    CheckpointRestored {
+        remaining: usize,
+        answer: Option<String>,
+    },
+    /// The puzzle checked the grid after every Entry that could be was solved
+    VerificationResult { solved: bool },
+    /// The solve stopped early because of the Budget
+    GaveUp(BudgetExceeded),
+    /// The answer cache could not be saved to its file. The solve carries on, as losing the
+    /// cache only costs money on the next run
+    CacheSaveFailed { path: PathBuf, error: String },
+}
+
+/// Receives every SolverEvent
+enum Subscriber {
+    Callback(Box<dyn FnMut(&SolverEvent) + Send>),
+    Channel(mpsc::UnboundedSender<SolverEvent>),
+}
+
+/// Console output of a verbose solver. Only new questions and their answers are printed, every
+/// remaining entry is looked up in the cache each time an answer is entered
+fn print_event(event: &SolverEvent) {
+    match event {
+        SolverEvent::ClueAttempted { clue, .. } => println!("{}", clue),
+        SolverEvent::AnswerReceived {
+            answer,
+            cached: false,
+            ..
+        } => println!("{}", answer),
+        SolverEvent::EntryWritten { grid, .. } => println!("{}", grid),
+        SolverEvent::CheckpointCreated { answer, .. } => {
+            println!("Capturing backtrack to enter {} in grid", answer)
+        }
+        SolverEvent::GaveUp(exceeded) => println!("Giving up, the solver {}", exceeded),
+        _ => (),
     }
 }
 
-#[async_trait]
-impl LLMModel for OpenAI {
-    async fn chat(&self, message: String) -> String {
-        self.gpt
-            .send_message(message)
-            .await
-            .expect("Failed to reach ChatGPT")
-            .message()
-            .content
-            .clone()
+impl LLMSolver {
+    pub fn new(api_key: APIKey, config: ModelConfig) -> Result<Self, LlmError> {
+        Ok(Self::with_model(api_key.backend(config)?))
+    }
+
+    /// Create a solver asking every backend with a key about each clue, through an Ensemble if
+    /// there is more than one. Members of an Ensemble each get the config as far as it applies
+    /// to them: the model goes to the one backend besides Ollama, which takes its model from
+    /// its key, and the temperature is lowered to the most each backend accepts
+    pub fn with_keys(mut api_keys: Vec<APIKey>, config: ModelConfig) -> Result<Self, LlmError> {
+        if api_keys.len() < 2 {
+            let key = api_keys
+                .pop()
+                .ok_or_else(|| LlmError::Config(String::from("no LLM backend was selected")))?;
+            return Self::new(key, config);
+        }
+        let named = api_keys
+            .iter()
+            .filter(|key| !matches!(key, APIKey::Ollama { .. }))
+            .count();
+        if config.model.is_some() && named > 1 {
+            return Err(LlmError::Config(String::from(
+                "a model can only be chosen when a single backend besides Ollama is selected",
+            )));
+        }
+        let members = api_keys
+            .into_iter()
+            .map(|key| {
+                let member = ModelConfig {
+                    model: config
+                        .model
+                        .clone()
+                        .filter(|_| !matches!(key, APIKey::Ollama { .. })),
+                    temperature: config.temperature.map(|t| t.min(key.max_temperature())),
+                    ..config.clone()
+                };
+                Ok((key.name(), key.backend(member)?))
+            })
+            .collect::<Result<_, LlmError>>()?;
+        Ok(Self::with_model(Box::new(Ensemble::new(members))))
+    }
+
+    /// Create a solver backed by any LLM
+    pub fn with_model(llm: Box<dyn LLMModel>) -> Self {
+        Self {
+            llm: Arc::from(llm),
+            cache: HashMap::new(),
+            cache_file: None,
+            report: SolveReport::default(),
+            subscribers: vec![],
+            prefetched: HashSet::new(),
+            concurrency: 1,
+            retries: DEFAULT_RETRIES,
+            confidence_threshold: DEFAULT_CONFIDENCE_THRESHOLD,
+            ordering: Ordering::default(),
+            batch: BatchStrategy::default(),
+            budget: Budget::default(),
+            rejected: vec![],
+            reject_dead_ends: true,
+            wrong_letters: HashMap::new(),
+            hints: HashMap::new(),
+            trusted: HashMap::new(),
+            wordlist: None,
+            check_words: true,
+            template: PromptTemplate::default(),
+            sessions: HashMap::new(),
+            entered: HashMap::new(),
+            suspects: DEFAULT_SUSPECTS,
+            in_memory: false,
+            deadline: None,
+        }
+    }
+
+    /// Print each clue, answer and the state of the grid as the puzzle is solved
+    pub fn verbose(self, verbose: bool) -> Self {
+        if verbose {
+            self.on_event(print_event)
+        } else {
+            self
+        }
+    }
+
+    /// Append every SolverEvent to a file as a line of JSON. The solver makes the same decisions
+    /// in the same order given the same answers, so traces of two solves can be compared line by
+    /// line to find where they part ways
+    pub fn trace(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = fs::File::create(path)?;
+        Ok(self.on_event(move |event| {
+            if let Ok(mut line) = serde_json::to_string(event) {
+                line.push('\n');
+                let _ = io::Write::write_all(&mut file, line.as_bytes());
+            }
+        }))
+    }
+
+    /// Call `callback` with every SolverEvent as it happens
+    pub fn on_event(mut self, callback: impl FnMut(&SolverEvent) + Send + 'static) -> Self {
+        self.subscribers
+            .push(Subscriber::Callback(Box::new(callback)));
+        self
+    }
+
+    /// Receive every SolverEvent from now on. Events are buffered until they are read, so the
+    /// solver never waits on a slow receiver
+    pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<SolverEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.subscribers.push(Subscriber::Channel(sender));
+        receiver
+    }
+
+    fn emit(&mut self, event: SolverEvent) {
+        // Dropped receivers are forgotten
+        self.subscribers.retain_mut(|subscriber| match subscriber {
+            Subscriber::Callback(callback) => {
+                callback(&event);
+                true
+            }
+            Subscriber::Channel(sender) => sender.send(event.clone()).is_ok(),
+        });
+    }
+
+    /// Ask the LLM about up to this many entries that share no cells at the same time. Answers
+    /// are still entered one at a time, so this only changes how long the solve takes
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Number of times to ask the LLM to correct a response without any answer of the right
+    /// length before giving up on the clue
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Give up on the puzzle once any limit of the Budget is reached
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// The first limit of the Budget that has been reached
+    fn exceeded(&self, usage: &Usage) -> Option<BudgetExceeded> {
+        let over = |limit: Option<usize>, used: usize| limit.is_some_and(|limit| used >= limit);
+        if over(self.budget.grid_attempts, usage.grid_attempts) {
+            Some(BudgetExceeded::GridAttempts)
+        } else if over(self.budget.llm_calls, self.report.llm_calls) {
+            Some(BudgetExceeded::LlmCalls)
+        } else if self
+            .budget
+            .time
+            .is_some_and(|time| usage.started.elapsed() >= time)
+        {
+            Some(BudgetExceeded::Time)
+        } else {
+            None
+        }
+    }
+
+    /// Fail once the time Budget has run out. Checked before every question to the LLM, so a
+    /// solve overruns its deadline by at most the question already in flight
+    fn check_deadline(&self) -> Result<(), SolverError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                Err(SolverError::Budget(BudgetExceeded::Time))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn ordering(mut self, ordering: Ordering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    pub fn batch(mut self, batch: BatchStrategy) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Remember the answer entered to create a checkpoint if backtracking to it does not lead to
+    /// a solution. The LLM is told the answer is wrong and it is never entered again, until the
+    /// crossing letters it was tried with change. Enabled by default
+    pub fn reject_dead_ends(mut self, reject: bool) -> Self {
+        self.reject_dead_ends = reject;
+        self
+    }
+
+    /// Letters known to be wrong in their cells, like those the Check feature marked in a
+    /// puzzle being resumed. Answers that would put one of them back are never entered
+    pub fn wrong_letters(mut self, letters: HashMap<Position, String>) -> Self {
+        self.wrong_letters = letters;
+        self
+    }
+
+    /// Answers known before the solve, by the number and direction of their clue. They are
+    /// entered before anything else and trusted: answers that disagree with one of their letters
+    /// are never entered, and backtracking never clears them
+    pub fn hints(mut self, hints: HashMap<ClueId, String>) -> Self {
+        self.hints = hints
+            .into_iter()
+            .map(|(clue, answer)| (clue, answer.to_ascii_uppercase()))
+            .collect();
+        self
+    }
+
+    /// Number of answers cleared and rejected when a full grid is not accepted and the puzzle
+    /// does not say which answers are wrong. The least trusted answers are cleared first, see
+    /// `suspicion`. 0 gives up on the grid instead
+    pub fn recover_suspects(mut self, suspects: usize) -> Self {
+        self.suspects = suspects;
+        self
+    }
+
+    /// Solve a copy of the grid kept in memory, only writing to the puzzle once every cell is
+    /// filled. Each pass then costs no more than the time waiting on the LLM, however slowly
+    /// the puzzle accepts letters. Off by default
+    pub fn in_memory(mut self, in_memory: bool) -> Self {
+        self.in_memory = in_memory;
+        self
+    }
+
+    /// Ask about each clue with the template in a file instead of the default prompt. See
+    /// PromptTemplate for the format
+    pub fn with_template(mut self, path: impl AsRef<Path>) -> Result<Self, TemplateError> {
+        self.template = PromptTemplate::from_path(path)?;
+        Ok(self)
+    }
+
+    /// Fall back to the most common words that match an Entry the LLM has no answer for, once
+    /// at least half of its letters are known
+    pub fn wordlist(mut self, wordlist: WordList) -> Self {
+        self.wordlist = Some(wordlist);
+        self
+    }
+
+    /// Check the answers of the LLM against the WordList and the common crossword answers
+    /// bundled with the crate, on by default once there is a WordList. An answer that is not a
+    /// known word is only tried after the others, and is never entered into an empty Entry
+    /// before a confident answer elsewhere, unless a crossing letter already agrees with it
+    pub fn check_words(mut self, check: bool) -> Self {
+        self.check_words = check;
+        self
+    }
+
+    /// With `Ordering::Confidence`, entries whose best answer has a lower confidence than this
+    /// are only solved once one of their letters is known, unless there is nothing else to solve
+    pub fn confidence_threshold(mut self, threshold: u8) -> Self {
+        self.confidence_threshold = threshold;
+        self
+    }
+
+    /// Load previous answers from a JSON cache file, and save every new answer back to it.
+    /// The file is created on the first new answer if it does not exist
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let cached = match serde_json::from_slice(&bytes)? {
+                    StoredCache::File(file) => {
+                        self.rejected.extend(file.rejected);
+                        file.answers
+                    }
+                    StoredCache::Answers(answers) => answers,
+                };
+                self.cache
+                    .extend(cached.into_iter().map(|c| (c.key, c.answers)));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+        self.cache_file = Some(path);
+        Ok(self)
+    }
+
+    /// Write the cache to a temporary file and move it into place, so an interrupted run never
+    /// leaves a partially written cache behind
+    fn save_cache(&self, path: &Path) -> io::Result<()> {
+        let mut cached: Vec<CachedAnswers> = self
+            .cache
+            .iter()
+            .map(|(key, answers)| CachedAnswers {
+                key: key.clone(),
+                answers: answers.clone(),
+            })
+            .collect();
+        cached.sort_by(|a, b| a.key.cmp(&b.key));
+        let file = CacheFile {
+            answers: cached,
+            rejected: self.rejected.clone(),
+        };
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Save the cache if it has a file. A failure is sent to subscribers as
+    /// `SolverEvent::CacheSaveFailed` without stopping the solve
+    fn persist(&mut self) {
+        let Some(path) = self.cache_file.clone() else {
+            return;
+        };
+        if let Err(e) = self.save_cache(&path) {
+            self.emit(SolverEvent::CacheSaveFailed {
+                path,
+                error: e.to_string(),
+            });
+        }
+    }
+
+    /// A solver with the same backend, settings and cached answers, to solve another puzzle
+    /// without waiting for this one. It has no subscribers or cache file, so its new answers
+    /// are only kept once they are passed back with `merge_cache`
+    pub fn fork(&self) -> Self {
+        Self {
+            llm: self.llm.clone(),
+            cache: self.cache.clone(),
+            cache_file: None,
+            report: SolveReport::default(),
+            subscribers: vec![],
+            prefetched: HashSet::new(),
+            concurrency: self.concurrency,
+            retries: self.retries,
+            confidence_threshold: self.confidence_threshold,
+            ordering: self.ordering,
+            batch: self.batch,
+            budget: self.budget,
+            rejected: self.rejected.clone(),
+            reject_dead_ends: self.reject_dead_ends,
+            wrong_letters: HashMap::new(),
+            hints: self.hints.clone(),
+            trusted: HashMap::new(),
+            wordlist: self.wordlist.clone(),
+            check_words: self.check_words,
+            template: self.template.clone(),
+            sessions: HashMap::new(),
+            entered: HashMap::new(),
+            suspects: self.suspects,
+            in_memory: self.in_memory,
+            deadline: None,
+        }
+    }
+
+    /// Cache the answers of another solver, such as a fork of this one, that this one has not
+    /// seen, and save them to the cache file
+    pub fn merge_cache(&mut self, other: &LLMSolver) {
+        let before = self.cache.len();
+        for (key, answers) in other.cache.iter() {
+            if !self.cache.contains_key(key) {
+                self.cache.insert(key.clone(), answers.clone());
+            }
+        }
+        if self.cache.len() > before {
+            self.persist();
+        }
+    }
+
+    /// Answers rejected for the Entry. Rejections made when a letter of the Entry was different
+    /// are forgotten, as the dead end may have been caused by that letter instead
+    async fn rejected_answers<E: Entry>(&mut self, entry: &E) -> Vec<String> {
+        let clue = entry.clue();
+        let pattern = CacheKey::new(&clue.text, &entry.value().await).pattern;
+        self.rejected
+            .retain(|r| !r.applies_to(clue) || compatible(&r.pattern, &pattern));
+        self.rejected
+            .iter()
+            .filter(|r| r.applies_to(clue))
+            .map(|r| r.answer.clone())
+            .collect()
+    }
+
+    /// The question to ask about an Entry in its current state
+    async fn question<E: Entry>(&mut self, entry: &E) -> CacheKey {
+        let rejected = self.rejected_answers(entry).await;
+        CacheKey::for_clue(entry.clue(), &entry.value().await).excluding(rejected)
+    }
+
+    /// Answers already given for a question. Before any letter of the Entry is known, the first
+    /// answers the LLM gave are shared by every later question that only excludes more of them,
+    /// so starting over from a checkpoint does not ask again
+    fn cached(&self, key: &CacheKey) -> Option<Vec<Candidate>> {
+        let undirected = CacheKey {
+            direction: None,
+            ..key.clone()
+        };
+        if let Some(candidates) = self.cache.get(key).or_else(|| self.cache.get(&undirected)) {
+            return Some(candidates.clone());
+        }
+        if !key.is_blank() || key.excluded.is_empty() {
+            return None;
+        }
+        let unconstrained = CacheKey {
+            excluded: vec![],
+            ..key.clone()
+        };
+        let candidates: Vec<Candidate> = self
+            .cache
+            .get(&unconstrained)?
+            .iter()
+            .filter(|c| !key.excluded.contains(&c.answer))
+            .cloned()
+            .collect();
+        (!candidates.is_empty()).then_some(candidates)
+    }
+
+    /// Take the conversation about the Entry's clue, starting one if it was never asked about.
+    /// It is put back once the question is recorded
+    fn session<E: Entry>(&mut self, entry: &E) -> Session {
+        self.sessions
+            .remove(&entry.id())
+            .unwrap_or_else(|| self.llm.start_session())
+    }
+
+    /// Answers to a single Question from the cache, the LLM or the WordList, with those that fit
+    /// the known letters first
+    pub async fn suggest(&mut self, question: &Question) -> Result<Vec<Candidate>, SolverError> {
+        question.validate().map_err(SolverError::Question)?;
+        let entries = question.entries();
+        let crossings = CrossingMap::new(&entries);
+        self.candidates(&entries[0], &entries, &crossings).await
+    }
+
+    /// Report of the current or last solve, which is kept even if the solve failed
+    pub fn report(&self) -> &SolveReport {
+        &self.report
+    }
+
+    /// Last answer entered into each Entry by the per-clue loop of the current or last solve
+    pub fn entered(&self) -> &HashMap<ClueId, Candidate> {
+        &self.entered
+    }
+
+    /// Generate candidate answers for a provided Clue, ranked with those that fit the current
+    /// state of the grid first
+    async fn solve_clue<E: Entry>(
+        &mut self,
+        entry: &E,
+        entries: &[E],
+        crossings: &CrossingMap,
+    ) -> Result<Vec<Candidate>, SolverError> {
+        let key = self.question(entry).await;
+        let excluded = key.excluded.clone();
+        // If we've asked this before don't bother asking again
+        let candidates = if let Some(candidates) = self.cached(&key) {
+            // The first look at a prefetched answer was already reported when it arrived
+            if !self.prefetched.remove(&key) {
+                self.report.cache_hits += 1;
+                self.emit(SolverEvent::AnswerReceived {
+                    clue: entry.clue().clone(),
+                    answer: candidates
+                        .iter()
+                        .map(Candidate::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    cached: true,
+                });
+            }
+            candidates
+        } else {
+            self.check_deadline()?;
+            self.emit(SolverEvent::ClueAttempted {
+                clue: entry.clue().clone(),
+                pattern: key.pattern.clone(),
+            });
+            let context = ClueContext::new(entry, entries, crossings).await;
+            let prompt = prompt_for_clue(&self.template, &context, &key.excluded);
+            let session = self.session(entry);
+            let llm = self.llm.as_ref();
+            let asked = ask(
+                llm,
+                self.retries,
+                entry,
+                session.clone(),
+                prompt,
+                &key.pattern,
+            );
+            let Some(asked) = until(self.deadline, asked).await else {
+                // Nothing is recorded for an abandoned question, so the Entry is asked about
+                // from the same conversation the next time it comes up
+                self.sessions.insert(entry.id(), session);
+                return Err(SolverError::Budget(BudgetExceeded::Time));
+            };
+            self.record(entry, key, asked)?
+        };
+        // Whether an answer fits depends on the grid, so rank after reading from the cache.
+        // Answers that fit come first, and unknown words after the rest of either kind
+        let mut ranked = vec![];
+        // The LLM often repeats an answer it was told is wrong
+        for candidate in candidates
+            .into_iter()
+            .filter(|c| !excluded.contains(&c.answer))
+        {
+            let fits = entry.fits(&candidate.answer).await;
+            let unknown = self.validity(entry, &candidate.answer).await == Validity::Unknown;
+            if unknown {
+                self.report.unknown_answers += 1;
+            }
+            ranked.push((!fits, unknown, candidate));
+        }
+        // Sorting is stable, so ties keep the order the LLM gave
+        ranked.sort_by_key(|(conflicts, unknown, _)| (*conflicts, *unknown));
+        Ok(ranked
+            .into_iter()
+            .map(|(.., candidate)| candidate)
+            .collect())
+    }
+
+    /// Check an answer against the WordList before it is entered. Every answer is a Word
+    /// without a WordList, and for clues hinting at an abbreviation or a phrase
+    async fn validity<E: Entry>(&self, entry: &E, answer: &str) -> Validity {
+        let Some(wordlist) = self.wordlist.as_ref().filter(|_| self.check_words) else {
+            return Validity::Word;
+        };
+        // A rebus square never matches a word of the list
+        if !entry.clue().hints().dictionary_word()
+            || answer.contains('[')
+            || known_word(wordlist, answer)
+        {
+            return Validity::Word;
+        }
+        let confirmed =
+            entry
+                .cells()
+                .await
+                .iter()
+                .zip(answer_cells(answer))
+                .any(|(known, cell)| {
+                    known
+                        .as_ref()
+                        .is_some_and(|known| known.eq_ignore_ascii_case(&cell))
+                });
+        if confirmed {
+            Validity::Confirmed
+        } else {
+            Validity::Unknown
+        }
+    }
+
+    /// Account for a question asked of the LLM, and cache the answers it gave
+    fn record<E: Entry>(
+        &mut self,
+        entry: &E,
+        key: CacheKey,
+        asked: Asked,
+    ) -> Result<Vec<Candidate>, LlmError> {
+        self.report.llm_calls += asked.calls;
+        self.report.retries += asked.calls.saturating_sub(1);
+        self.report.prompt_chars += asked.prompt_chars;
+        self.sessions.insert(entry.id(), asked.session);
+        // Every response before an accepted one was rejected
+        let rejected = asked.responses.len() - usize::from(!asked.candidates.is_empty());
+        for (index, response) in asked.responses.into_iter().enumerate() {
+            self.emit(SolverEvent::AnswerReceived {
+                clue: entry.clue().clone(),
+                answer: response,
+                cached: false,
+            });
+            if index < rejected {
+                self.emit(SolverEvent::AnswerRejected {
+                    clue: entry.clue().clone(),
+                    reason: format!(
+                        "no answer is {} letters long and matches {}",
+                        entry.expected_length(),
+                        key.pattern
+                    ),
+                });
+            }
+        }
+        if let Some(e) = asked.error {
+            return Err(e);
+        }
+        self.report.clue_latency.push(ClueLatency {
+            clue: entry.clue().clone(),
+            pattern: key.pattern.clone(),
+            latency: asked.latency,
+        });
+        // Only the final result is cached, keyed on the original question
+        self.cache.insert(key, asked.candidates.clone());
+        self.persist();
+        Ok(asked.candidates)
+    }
+
+    /// Ask about every uncached Entry in the queue that shares no cells with an earlier one, up
+    /// to `concurrency` at a time. None of them can change the pattern of another, so the
+    /// answers stay valid until they are looked up
+    async fn prefetch<E: Entry>(
+        &mut self,
+        queue: &[E],
+        entries: &[E],
+        crossings: &CrossingMap,
+    ) -> Result<(), SolverError> {
+        if self.concurrency < 2 {
+            return Ok(());
+        }
+        let mut taken = HashSet::new();
+        let mut questions = vec![];
+        for entry in queue {
+            let positions = entry.positions();
+            let key = self.question(entry).await;
+            if self.cached(&key).is_some() || positions.iter().any(|p| taken.contains(p)) {
+                continue;
+            }
+            taken.extend(positions);
+            let context = ClueContext::new(entry, entries, crossings).await;
+            let prompt = prompt_for_clue(&self.template, &context, &key.excluded);
+            questions.push((entry, key, prompt));
+        }
+        // A single question is asked just the same when it is looked up
+        if questions.len() < 2 {
+            return Ok(());
+        }
+        self.check_deadline()?;
+        for (entry, key, _) in questions.iter() {
+            self.emit(SolverEvent::ClueAttempted {
+                clue: entry.clue().clone(),
+                pattern: key.pattern.clone(),
+            });
+        }
+        let sessions: Vec<Session> = questions
+            .iter()
+            .map(|(entry, ..)| self.session(*entry))
+            .collect();
+        let llm = self.llm.as_ref();
+        let retries = self.retries;
+        // Answers are recorded in queue order, whichever arrives first
+        let answers = stream::iter(questions.iter().zip(sessions.clone()))
+            .map(|((entry, key, prompt), session)| {
+                ask(llm, retries, *entry, session, prompt.clone(), &key.pattern)
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<Asked>>();
+        let Some(answers) = until(self.deadline, answers).await else {
+            for ((entry, ..), session) in questions.iter().zip(sessions) {
+                self.sessions.insert(entry.id(), session);
+            }
+            return Err(SolverError::Budget(BudgetExceeded::Time));
+        };
+        for ((entry, key, _), asked) in questions.into_iter().zip(answers) {
+            self.prefetched.insert(key.clone());
+            self.record(entry, key, asked)?;
+        }
+        Ok(())
+    }
+
+    pub async fn solve<P: CrosswordPuzzle>(
+        &mut self,
+        puzzle: &P,
+    ) -> Result<SolveReport, SolverError> {
+        let entries = puzzle.generate_entries().await?;
+        self.solve_entries(puzzle, entries).await
+    }
+
+    /// Solve the puzzle, giving up once `deadline` has passed. This is a time Budget for this
+    /// solve alone, and replaces a longer one given to `budget`
+    pub async fn solve_with_deadline<P: CrosswordPuzzle>(
+        &mut self,
+        puzzle: &P,
+        deadline: Duration,
+    ) -> Result<SolveReport, SolverError> {
+        let budget = self.budget;
+        self.budget.time = Some(budget.time.map_or(deadline, |time| time.min(deadline)));
+        let report = self.solve(puzzle).await;
+        self.budget = budget;
+        report
+    }
+
+    /// Solve the puzzle using entries that were already generated, so the caller can read the
+    /// final values from them afterwards
+    pub async fn solve_entries<P: CrosswordPuzzle>(
+        &mut self,
+        puzzle: &P,
+        entries: Vec<P::Entry>,
+    ) -> Result<SolveReport, SolverError> {
+        self.report = SolveReport::default();
+        self.prefetched.clear();
+        self.sessions.clear();
+        self.entered.clear();
+        let backends = self.llm.backends();
+        puzzle.start_timer().await?;
+        // The time on the page is only of interest, so a timer that can't be read is left out
+        let page_started = puzzle.elapsed_time().await.ok().flatten();
+        let started = Instant::now();
+        self.deadline = self.budget.time.map(|time| started + time);
+        self.enter_hints(&entries).await?;
+        let solved = if self.in_memory {
+            let forked = GridSnapshot::capture(&entries).await.into_entries();
+            let puzzle = WriteOut { puzzle, entries };
+            self.explore(&puzzle, forked, started).await
+        } else {
+            self.explore(puzzle, entries, started).await
+        };
+        self.report.wall_time = started.elapsed();
+        if let (Some(started), Ok(Some(ended))) = (page_started, puzzle.elapsed_time().await) {
+            self.report.page_time = Some(PageTime { started, ended });
+        }
+        self.report.backends = self
+            .llm
+            .backends()
+            .into_iter()
+            .zip(backends)
+            .map(|(now, before)| now.since(&before))
+            .collect();
+        self.report.solved = solved?;
+        Ok(self.report.clone())
+    }
+
+    /// Solve the grid, backtracking to checkpoints until one is solved or none are left
+    async fn explore<P: CrosswordPuzzle>(
+        &mut self,
+        puzzle: &P,
+        entries: Vec<P::Entry>,
+        started: Instant,
+    ) -> Result<bool, SolverError> {
+        // Seed the queue in a stable order, whatever order the puzzle produced the entries in
+        let entries = Entries::new(entries).into_vec();
+        let crossings = CrossingMap::new(&entries);
+        let links = linked_entries(&entries);
+        if self.batch == BatchStrategy::FirstPass {
+            match self.batch_pass(&entries).await {
+                Err(SolverError::Budget(exceeded)) => {
+                    return self.give_up(exceeded, &entries, None).await
+                }
+                result => result?,
+            }
+        }
+        let mut usage = Usage {
+            started,
+            grid_attempts: 0,
+        };
+        // Backtracking often arrives at the same grid from different conflicts, so every grid
+        // that has been queued is remembered and only explored once
+        let mut seen = HashSet::from([state_hash(&entries).await]);
+        // Grid with the most cells filled at the end of any pass, left behind if the solver
+        // gives up
+        let mut best = None;
+
+        // The first pass starts from the grid as it is, every other from a checkpoint
+        let mut checkpoints: VecDeque<Option<Checkpoint>> = VecDeque::from([None]);
+        while let Some(checkpoint) = checkpoints.pop_front() {
+            if let Some(exceeded) = self.exceeded(&usage) {
+                return self.give_up(exceeded, &entries, best).await;
+            }
+            if let Some(checkpoint) = &checkpoint {
+                self.report.backtracks += 1;
+                self.emit(SolverEvent::CheckpointRestored {
+                    remaining: checkpoints.len(),
+                    answer: checkpoint.answer().map(String::from),
+                });
+                restore(&entries, checkpoint).await?;
+                // Clearing a crossing answer also clears the letters it shares with a hint
+                self.restore_hints(&entries).await?;
+            }
+            match self
+                .solve_grid(entries.clone(), &crossings, &links, puzzle, &usage)
+                .await
+            {
+                Ok(_) => return Ok(true),
+                Err(GridSolveError::Error(SolverError::Budget(exceeded))) => {
+                    return self.give_up(exceeded, &entries, best).await
+                }
+                Err(GridSolveError::Error(e)) => return Err(e),
+                Err(GridSolveError::FailedToSolve(cps)) => {
+                    // Only finished passes count, so the limit is not hit partway through one
+                    usage.grid_attempts += 1;
+                    remember_best(&entries, &mut best).await;
+                    let rejection = checkpoint.and_then(|c| c.rejection);
+                    if let Some(rejection) = rejection.filter(|_| self.reject_dead_ends) {
+                        self.report.rejected_answers += 1;
+                        self.rejected.push(rejection);
+                        self.persist();
+                    }
+                    for checkpoint in cps {
+                        if seen.insert(checkpoint.fingerprint().await) {
+                            checkpoints.push_front(Some(checkpoint));
+                        } else {
+                            self.report.duplicate_states += 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Ask for every answer in a single prompt and enter those that fit. Anything that cannot be
+    /// parsed or entered is left for the per-clue loop, which also clears any that were wrong
+    async fn batch_pass<E: Entry>(&mut self, entries: &[E]) -> Result<(), SolverError> {
+        self.check_deadline()?;
+        let prompt = prompt_for_puzzle(entries).await;
+        self.report.llm_calls += 1;
+        self.report.prompt_chars += prompt.chars().count();
+        let Some(response) = until(self.deadline, self.llm.chat(prompt)).await else {
+            return Err(SolverError::Budget(BudgetExceeded::Time));
+        };
+        let Some(answers) = parse_batch(&response?) else {
+            return Ok(());
+        };
+        let grid = Grid::from_positions(entries.iter().flat_map(|e| e.positions()));
+        for entry in entries {
+            let clue = entry.clue();
+            let Some(answer) = answers
+                .get(&(clue.number, clue.direction))
+                .and_then(|raw| normalize_answer(raw, entry.expected_length()))
+            else {
+                continue;
+            };
+            // Entries are written in order, so the first of two answers that disagree wins
+            if entry.filled().await || !entry.fits(&answer).await {
+                continue;
+            }
+            entry.write(answer.clone()).await?;
+            self.report.batch_answers += 1;
+            if !self.subscribers.is_empty() {
+                self.emit(SolverEvent::EntryWritten {
+                    clue,
+                    answer,
+                    grid: render_entries(&grid, entries).await,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Record why the solve stopped early, returning that the puzzle was not solved. The grid
+    /// is set back to the best one seen if backtracking left fewer cells filled
+    async fn give_up<E: Entry>(
+        &mut self,
+        exceeded: BudgetExceeded,
+        entries: &[E],
+        best: Option<(usize, Checkpoint)>,
+    ) -> Result<bool, SolverError> {
+        if let Some((filled, checkpoint)) = best {
+            if filled > filled_cells(entries).await {
+                restore(entries, &checkpoint).await?;
+            }
+        }
+        self.emit(SolverEvent::GaveUp(exceeded));
+        self.report.budget_exceeded = Some(exceeded);
+        self.report.timed_out = exceeded == BudgetExceeded::Time;
+        Ok(false)
+    }
+
+    /// Answers for the Entry that would fill it exactly, best first
+    async fn candidates<E: Entry>(
+        &mut self,
+        entry: &E,
+        entries: &[E],
+        crossings: &CrossingMap,
+    ) -> Result<Vec<Candidate>, SolverError> {
+        let positions = entry.positions();
+        // Request new answers for the Clue from the ChatGPT
+        let candidates: Vec<Candidate> = self
+            .solve_clue(entry, entries, crossings)
+            .await?
+            .into_iter()
+            // Every answer is written by the caller, so it must fill the Entry exactly
+            .filter(|c| answer_cells(&c.answer).len() == entry.expected_length())
+            .filter(|c| !self.repeats_wrong_letter(&positions, &c.answer))
+            .filter(|c| !self.contradicts_hint(&positions, &c.answer))
+            .collect();
+        if candidates.is_empty() {
+            let mut words = self.wordlist_candidates(entry).await;
+            words.retain(|c| {
+                !self.repeats_wrong_letter(&positions, &c.answer)
+                    && !self.contradicts_hint(&positions, &c.answer)
+            });
+            return Ok(words);
+        }
+        Ok(candidates)
+    }
+
+    /// Whether an answer disagrees with a letter of a hinted answer
+    fn contradicts_hint(&self, positions: &[Position], answer: &str) -> bool {
+        positions.iter().zip(answer_cells(answer)).any(|(p, cell)| {
+            self.trusted
+                .get(p)
+                .is_some_and(|letter| !letter.eq_ignore_ascii_case(&cell))
+        })
+    }
+
+    /// Check every hint against the entries, then enter them all into the grid. Nothing is
+    /// entered if any hint does not fit
+    async fn enter_hints<E: Entry>(&mut self, entries: &[E]) -> Result<(), SolverError> {
+        self.trusted.clear();
+        // Sorted so the same hints are always reported the same way
+        let mut hints: Vec<_> = self.hints.iter().collect();
+        hints.sort();
+        let mut hinted = vec![];
+        for (&id, answer) in hints {
+            let Some(entry) = entries.iter().find(|e| e.id() == id) else {
+                return Err(HintError::UnknownClue(id).into());
+            };
+            let cells = answer_cells(answer);
+            if cells.len() != entry.expected_length() {
+                return Err(HintError::Length {
+                    id,
+                    expected: entry.expected_length(),
+                    found: cells.len(),
+                }
+                .into());
+            }
+            for (position, cell) in entry.positions().into_iter().zip(cells) {
+                match self.trusted.get(&position) {
+                    Some(letter) if *letter != cell => return Err(HintError::Conflict(id).into()),
+                    _ => {
+                        self.trusted.insert(position, cell);
+                    }
+                }
+            }
+            hinted.push((entry, answer.clone()));
+        }
+        let grid = Grid::from_positions(entries.iter().flat_map(|e| e.positions()));
+        for (entry, answer) in hinted {
+            entry.write(answer.clone()).await?;
+            if !self.subscribers.is_empty() {
+                self.emit(SolverEvent::EntryWritten {
+                    clue: entry.clue().clone(),
+                    answer,
+                    grid: render_entries(&grid, entries).await,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Enter any letter of a hinted answer that is missing from the grid
+    async fn restore_hints<E: Entry>(&self, entries: &[E]) -> Result<(), SolverError> {
+        for entry in entries {
+            let positions = entry.positions();
+            if !positions.iter().all(|p| self.trusted.contains_key(p)) {
+                continue;
+            }
+            let answer: String = positions
+                .iter()
+                .map(|p| match &self.trusted[p] {
+                    cell if cell.chars().count() > 1 => format!("[{}]", cell),
+                    cell => cell.clone(),
+                })
+                .collect();
+            if entry.value().await != answer {
+                entry.write(answer).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an answer puts a letter known to be wrong back into its cell
+    fn repeats_wrong_letter(&self, positions: &[Position], answer: &str) -> bool {
+        positions.iter().zip(answer_cells(answer)).any(|(p, cell)| {
+            self.wrong_letters
+                .get(p)
+                .is_some_and(|wrong| wrong.eq_ignore_ascii_case(&cell))
+        })
+    }
+
+    /// The most common words in the WordList that match an Entry with at least half of its
+    /// letters known. With fewer letters far too many words match for the first to be a
+    /// reasonable guess
+    async fn wordlist_candidates<E: Entry>(&mut self, entry: &E) -> Vec<Candidate> {
+        if self.wordlist.is_none() {
+            return vec![];
+        }
+        let cells = entry.cells().await;
+        if cells.iter().filter(|c| c.is_some()).count() * 2 < cells.len() {
+            return vec![];
+        }
+        // A rebus square never matches a single letter of the list
+        let pattern: Option<Vec<Option<char>>> = cells
+            .iter()
+            .map(|cell| match cell {
+                None => Some(None),
+                Some(value) => {
+                    let mut letters = value.chars();
+                    match (letters.next(), letters.next()) {
+                        (Some(letter), None) => Some(Some(letter)),
+                        _ => None,
+                    }
+                }
+            })
+            .collect();
+        let Some(pattern) = pattern else {
+            return vec![];
+        };
+        let rejected = self.rejected_answers(entry).await;
+        let Some(wordlist) = &self.wordlist else {
+            return vec![];
+        };
+        // Ranked below any answer from the LLM
+        let candidates: Vec<Candidate> = wordlist
+            .candidates(&pattern)
+            .into_iter()
+            .filter(|word| !rejected.iter().any(|r| r == word))
+            .take(CANDIDATES)
+            .map(|word| Candidate {
+                answer: word.to_string(),
+                confidence: Some(0),
+            })
+            .collect();
+        if !candidates.is_empty() {
+            self.report.wordlist_answers += 1;
+        }
+        candidates
+    }
+
+    /// Remove the next Entry to solve from the queue, along with its answers. Entries that are
+    /// already filled, or that the LLM has no answer for, are dropped from the queue. Entries
+    /// whose clue is in `linked` are taken first, whatever the ordering
+    async fn next_entry<E: Entry>(
+        &mut self,
+        queue: &mut Vec<E>,
+        entries: &[E],
+        crossings: &CrossingMap,
+        linked: &[ClueId],
+    ) -> Result<Option<(E, Vec<Candidate>)>, SolverError> {
+        // Do not attempt to solve an already completed answer
+        let mut unfilled = vec![];
+        for entry in queue.drain(..) {
+            if !entry.filled().await {
+                unfilled.push(entry);
+            }
+        }
+        *queue = unfilled;
+        self.prefetch(queue, entries, crossings).await?;
+        match self.ordering {
+            Ordering::Queue | Ordering::MostConstrained => loop {
+                let index = match (
+                    queue.iter().position(|e| linked.contains(&e.id())),
+                    self.ordering,
+                ) {
+                    (Some(index), _) => Some(index),
+                    (None, Ordering::MostConstrained) => most_constrained(queue).await,
+                    (None, _) => (!queue.is_empty()).then_some(0),
+                };
+                let Some(index) = index else {
+                    return Ok(None);
+                };
+                let entry = queue.remove(index);
+                let candidates = self.candidates(&entry, entries, crossings).await?;
+                if !candidates.is_empty() {
+                    return Ok(Some((entry, candidates)));
+                }
+            },
+            Ordering::Confidence => {
+                // Ask about every remaining Entry so the most confident answer can be entered
+                // first. Answers are cached until a crossing changes the pattern, so this is
+                // only expensive the first time around
+                let mut ranked = vec![];
+                for entry in queue.drain(..) {
+                    let candidates = self.candidates(&entry, entries, crossings).await?;
+                    let Some(best) = candidates.first() else {
+                        continue;
+                    };
+                    let confidence = best.confidence();
+                    // A guess is likely to poison its crossings, so wait until a letter is known.
+                    // An unknown word is treated as a guess however confident the LLM is
+                    let unknown = self.validity(&entry, &best.answer).await == Validity::Unknown;
+                    let deferred = (confidence < self.confidence_threshold || unknown)
+                        && entry.cells().await.iter().all(Option::is_none);
+                    let first = linked.contains(&entry.id());
+                    ranked.push((entry, candidates, deferred && !first, !first, confidence));
+                }
+                // Sorting is stable, so ties are broken by queue order
+                ranked.sort_by_key(|(_, _, deferred, later, confidence)| {
+                    (*deferred, *later, Reverse(*confidence))
+                });
+                let mut ranked = ranked.into_iter();
+                let Some((entry, candidates, ..)) = ranked.next() else {
+                    return Ok(None);
+                };
+                *queue = ranked.map(|(entry, ..)| entry).collect();
+                Ok(Some((entry, candidates)))
+            }
+        }
+    }
+
+    /// Attempt to solve a Grid until we get stuck
+    async fn solve_grid<P: CrosswordPuzzle>(
+        &mut self,
+        entries: Vec<P::Entry>,
+        crossings: &CrossingMap,
+        links: &HashMap<ClueId, Vec<ClueId>>,
+        puzzle: &P,
+        usage: &Usage,
+    ) -> Result<(), GridSolveError> {
+        let grid = Grid::from_positions(entries.iter().flat_map(|e| e.positions()));
+        let mut next_entries = entries.clone();
+        let mut checkpoints = vec![];
+        // Clues that refer to the last answer entered, or that it refers to
+        let mut linked = vec![];
+        while let Some((entry, candidates)) = self
+            .next_entry(&mut next_entries, &entries, crossings, &linked)
+            .await?
+        {
+            if let Some(exceeded) = self.exceeded(usage) {
+                return Err(SolverError::Budget(exceeded).into());
+            }
+            // Entries without any candidates are never returned by next_entry
+            let mut candidates = candidates.into_iter();
+            let Some(candidate) = candidates.next() else {
+                continue;
+            };
+            let best = candidate.answer.clone();
+            let alternatives: Vec<String> = candidates.map(|c| c.answer).collect();
+            // If the best answer fits in our current Grid continue on
+            if entry.fits(&best).await {
+                // Capture the grid with each lower ranked answer in place of the one we enter,
+                // so backtracking can try them before clearing any crosses
+                for answer in alternatives {
+                    checkpoints
+                        .push(checkpoint(&entry, answer.clone(), &entries, crossings).await?);
+                    self.report.checkpoints += 1;
+                    self.emit(SolverEvent::CheckpointCreated {
+                        clue: entry.clue().clone(),
+                        answer,
+                    });
+                }
+                // Enter our new answer into the grid
+                entry.write(best.clone()).await?;
+                self.entered.insert(entry.id(), candidate);
+                // Rendering is only worth the time if anyone is listening
+                if !self.subscribers.is_empty() {
+                    self.emit(SolverEvent::EntryWritten {
+                        clue: entry.clue().clone(),
+                        answer: best,
+                        grid: render_entries(&grid, &entries).await,
+                    });
+                }
+                // Add any crosses to the front of our queue to try next. They have new information
+                // for us to send to ChatGPT
+                for cross in crossings.crossing_entries(&entry, &entries) {
+                    next_entries.retain(|e| e.id() != cross.id());
+                    next_entries.insert(0, cross);
+                }
+                linked = links.get(&entry.id()).cloned().unwrap_or_default();
+            } else {
+                // We want to capture the state of the system if we used each answer
+                // and cleared out any other answers that disagree. This is our "backtrack"
+                // that gives us a chance to clear out old bad answers
+                self.report.conflicts += 1;
+                self.emit(SolverEvent::ConflictDetected {
+                    clue: entry.clue().clone(),
+                    answer: best.clone(),
+                });
+                for answer in std::iter::once(best).chain(alternatives) {
+                    checkpoints
+                        .push(checkpoint(&entry, answer.clone(), &entries, crossings).await?);
+                    self.report.checkpoints += 1;
+                    self.emit(SolverEvent::CheckpointCreated {
+                        clue: entry.clue().clone(),
+                        answer,
+                    });
+                }
+            }
+        }
+        let feedback = puzzle.verify_entries(&entries).await?;
+        let solved = feedback.solved();
+        self.emit(SolverEvent::VerificationResult { solved });
+        if solved {
+            Ok(())
+        } else {
+            // We reverse here because we want to try states we discovered later in the solve first
+            checkpoints.reverse();
+            match feedback {
+                Feedback::Incorrect(clues) => {
+                    // Nothing is more promising than the grid with only the known mistakes
+                    // removed
+                    checkpoints.insert(0, self.clear_incorrect(&entries, &clues).await?);
+                }
+                _ => {
+                    if let Some(recovery) = self.clear_suspects(&entries).await? {
+                        checkpoints.insert(0, recovery);
+                    }
+                }
+            }
+            Err(GridSolveError::FailedToSolve(checkpoints))
+        }
+    }
+
+    /// Reject the answers the puzzle marked incorrect and capture the grid with their entries
+    /// cleared, so the next pass starts from everything else that was entered
+    async fn clear_incorrect<E: Entry>(
+        &mut self,
+        entries: &[E],
+        clues: &[ClueId],
+    ) -> Result<Checkpoint, GridSolveError> {
+        let state = GridSnapshot::capture(entries).await;
+        for entry in entries.iter().filter(|e| clues.contains(&e.id())) {
+            self.report.incorrect_entries += 1;
+            if entry.filled().await {
+                // Wrong whatever letters cross it, so it is rejected for any pattern
+                let unknown = "_".repeat(entry.expected_length());
+                self.rejected
+                    .push(Rejection::new(entry.clue(), &unknown, entry.value().await));
+            }
+            let forked = state.get(entry.id()).ok_or_else(|| {
+                SolverError::InconsistentState(format!(
+                    "{} is missing from the checkpoint",
+                    entry.clue()
+                ))
+            })?;
+            forked.clear().await?;
+        }
+        self.persist();
+        Ok(Checkpoint {
+            state,
+            rejection: None,
+        })
+    }
+
+    /// When a full grid is not accepted, reject the answers most likely to be wrong and capture
+    /// the grid without their letters. Letters of an answer that another entered answer agrees
+    /// with are kept. Nothing is captured for a grid with empty cells, as the solver only got
+    /// stuck and the checkpoints it captured are more promising
+    async fn clear_suspects<E: Entry>(
+        &mut self,
+        entries: &[E],
+    ) -> Result<Option<Checkpoint>, GridSolveError> {
+        if self.suspects == 0 {
+            return Ok(None);
+        }
+        for entry in entries {
+            if !entry.filled().await {
+                return Ok(None);
+            }
+        }
+        // Only answers still in the grid can be blamed, letters written by crossing answers
+        // are blamed on those
+        let mut answered = vec![];
+        for entry in entries {
+            if let Some(candidate) = self.entered.get(&entry.id()) {
+                if entry.value().await == candidate.answer {
+                    answered.push((entry, candidate.clone()));
+                }
+            }
+        }
+        let mut ranked: Vec<(usize, &E, Candidate)> = answered
+            .iter()
+            .map(|(entry, candidate)| {
+                let crossed = entry
+                    .positions()
+                    .into_iter()
+                    .filter(|p| {
+                        answered.iter().any(|(other, _)| {
+                            other.id() != entry.id() && other.positions().contains(p)
+                        })
+                    })
+                    .count();
+                // Abbreviations and phrases are not expected to be in the WordList
+                let wordlist = self
+                    .wordlist
+                    .as_ref()
+                    .filter(|_| entry.clue().hints().dictionary_word());
+                let score = suspicion(
+                    candidate,
+                    entry.expected_length() - crossed,
+                    entry.expected_length(),
+                    wordlist,
+                );
+                (score, *entry, candidate.clone())
+            })
+            .collect();
+        // Sorting is stable, so ties are broken by the order of the entries
+        ranked.sort_by_key(|(score, ..)| Reverse(*score));
+        ranked.truncate(self.suspects);
+        if ranked.is_empty() {
+            return Ok(None);
+        }
+
+        let state = GridSnapshot::capture(entries).await;
+        let suspects: Vec<ClueId> = ranked.iter().map(|(_, entry, _)| entry.id()).collect();
+        for (_, entry, candidate) in ranked {
+            // Keep the letters that a crossing answer that is not suspected agrees with
+            let cells: Vec<Option<String>> = entry
+                .positions()
+                .into_iter()
+                .zip(entry.cells().await)
+                .map(|(p, cell)| {
+                    answered
+                        .iter()
+                        .any(|(other, _)| {
+                            !suspects.contains(&other.id()) && other.positions().contains(&p)
+                        })
+                        .then_some(cell)
+                        .flatten()
+                })
+                .collect();
+            let pattern: String = cells
+                .iter()
+                .map(|c| match c {
+                    None => String::from("_"),
+                    Some(v) if v.chars().count() > 1 => format!("[{}]", v),
+                    Some(v) => v.clone(),
+                })
+                .collect();
+            let forked = state.get(entry.id()).ok_or_else(|| {
+                SolverError::InconsistentState(format!(
+                    "{} is missing from the checkpoint",
+                    entry.clue()
+                ))
+            })?;
+            forked.write_padded(cells).await?;
+            self.report.suspects_cleared += 1;
+            self.rejected
+                .push(Rejection::new(entry.clue(), &pattern, candidate.answer));
+        }
+        self.persist();
+        Ok(Some(Checkpoint {
+            state,
+            rejection: None,
+        }))
+    }
+}
+
+/// How likely an entered answer is to be wrong. Answers the LLM was unsure of, whose letters
+/// were not confirmed by crossing answers, or that are not in the WordList are suspected most
+fn suspicion(
+    candidate: &Candidate,
+    unconfirmed: usize,
+    length: usize,
+    wordlist: Option<&WordList>,
+) -> usize {
+    let doubt = 100 - usize::from(candidate.confidence().min(100));
+    let unconfirmed = 100 * unconfirmed / length.max(1);
+    let unknown = match wordlist {
+        Some(wordlist) if !known_word(wordlist, &candidate.answer) => 100,
+        _ => 0,
+    };
+    doubt + unconfirmed + unknown
+}
+
+/// Whether an answer is in the WordList or the common crossword answers, or a phrase made up of
+/// the words of either
+fn known_word(wordlist: &WordList, answer: &str) -> bool {
+    wordlist.recognizes(answer) || WordList::crossword_answers().recognizes(answer)
+}
+
+/// How an answer of the LLM compares to the words it is expected to be made of. Proper nouns
+/// are often missing from word lists, so an unknown word is tried later rather than rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Validity {
+    /// A known word or phrase, or an answer that is not expected to be one
+    Word,
+    /// Not a known word, but a letter of a crossing answer agrees with it
+    Confirmed,
+    /// Not a known word, and nothing in the grid agrees with it yet
+    Unknown,
+}
+
+/// Everything learned from asking the LLM about a single clue. This is kept apart from the
+/// solver so that several clues can be asked at once
+struct Asked {
+    /// Answers that can fill the Entry, empty if the LLM never gave one
+    candidates: Vec<Candidate>,
+    /// Every response, in the order they were received
+    responses: Vec<String>,
+    /// Prompts sent, including one that failed
+    calls: usize,
+    prompt_chars: usize,
+    latency: Duration,
+    /// Set if the LLM could not be reached for the last prompt
+    error: Option<LlmError>,
+    /// Conversation about the clue, to continue with its next question
+    session: Session,
+}
+
+/// Ask the LLM about an Entry, correcting it when none of its answers have the right length
+async fn ask<E: Entry>(
+    llm: &dyn LLMModel,
+    retries: usize,
+    entry: &E,
+    mut session: Session,
+    prompt: String,
+    pattern: &str,
+) -> Asked {
+    let started = Instant::now();
+    let mut asked = Asked {
+        candidates: vec![],
+        responses: vec![],
+        calls: 0,
+        prompt_chars: 0,
+        latency: Duration::ZERO,
+        error: None,
+        session: Session::default(),
+    };
+    let sent_chars = session.sent_chars();
+    let mut message = prompt;
+    for attempt in 0..=retries {
+        asked.calls += 1;
+        let response = if attempt == 0 {
+            session.ask(llm, message).await
+        } else {
+            session.send(llm, message).await
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                asked.error = Some(e);
+                break;
+            }
+        };
+        asked.candidates = accepted_candidates(entry, &response).await;
+        if !asked.candidates.is_empty() {
+            asked.responses.push(response);
+            break;
+        }
+        message = correction(&response, entry.expected_length(), pattern);
+        asked.responses.push(response);
+    }
+    asked.prompt_chars = session.sent_chars() - sent_chars;
+    asked.session = session;
+    asked.latency = started.elapsed();
+    asked
+}
+
+/// Index of the Entry with the largest fraction of its letters known, preferring the shortest
+/// Entry and then queue order on a tie
+async fn most_constrained<E: Entry>(queue: &[E]) -> Option<usize> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (index, entry) in queue.iter().enumerate() {
+        let cells = entry.cells().await;
+        let known = cells.iter().filter(|c| c.is_some()).count();
+        let length = cells.len();
+        // Compare known / length without floating point
+        let better = match best {
+            Some((_, best_known, best_length)) => {
+                (known * best_length, Reverse(length)) > (best_known * length, Reverse(best_length))
+            }
+            None => true,
+        };
+        if better {
+            best = Some((index, known, length));
+        }
+    }
+    best.map(|(index, _, _)| index)
+}
+
+/// A grid to backtrack to, captured with a different answer entered than the one the solver
+/// went on with, or with the answers the puzzle marked incorrect cleared
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    state: GridSnapshot,
+    /// The answer entered, which is rejected if the checkpoint leads to a dead end
+    rejection: Option<Rejection>,
+}
+
+impl Checkpoint {
+    /// Capture the current value of every cell of the entries
+    pub async fn capture<E: Entry>(entries: &[E]) -> Self {
+        Checkpoint {
+            state: GridSnapshot::capture(entries).await,
+            rejection: None,
+        }
+    }
+
+    /// Entries of the captured grid in Clue order. They share their cells with each other, but
+    /// not with the puzzle they were captured from
+    pub fn entries(&self) -> impl Iterator<Item = &InMemoryEntry> {
+        self.state.entries()
+    }
+
+    /// The cells to write to turn the `current` grid back into this one, with `None` for the
+    /// cells to clear
+    pub async fn diff(&self, current: &GridSnapshot) -> Vec<(Position, Option<String>)> {
+        self.state.diff(current).await
+    }
+
+    /// Answer entered when the checkpoint was captured, if it was captured for one
+    pub fn answer(&self) -> Option<&str> {
+        self.rejection.as_ref().map(|r| r.answer.as_str())
+    }
+
+    /// Number of cells in the grid with a value
+    pub async fn filled_count(&self) -> usize {
+        filled_cells(&self.owned_entries()).await
+    }
+
+    /// Hash of the values in the grid. Checkpoints of the same grid have the same fingerprint,
+    /// whatever answer they were captured for
+    pub async fn fingerprint(&self) -> u64 {
+        state_hash(&self.owned_entries()).await
+    }
+
+    fn owned_entries(&self) -> Vec<InMemoryEntry> {
+        self.state.entries().cloned().collect()
+    }
+}
+
+/// Ids of the entries linked to each Entry, see `Clue::linked`, in the order of the entries. The
+/// references of each clue are only read once, rather than every time an answer is entered
+fn linked_entries<E: Entry>(entries: &[E]) -> HashMap<ClueId, Vec<ClueId>> {
+    let references: Vec<(ClueId, Vec<ClueId>)> = entries
+        .iter()
+        .map(|entry| {
+            let named = entry
+                .clue()
+                .references()
+                .into_iter()
+                .map(|(number, direction)| ClueId { number, direction })
+                .collect();
+            (entry.id(), named)
+        })
+        .collect();
+    references
+        .iter()
+        .map(|(id, named)| {
+            let linked = references
+                .iter()
+                .filter(|(other, by_other)| named.contains(other) || by_other.contains(id))
+                .map(|(other, _)| *other)
+                .collect();
+            (*id, linked)
+        })
+        .collect()
+}
+
+/// Capture the current state of all the cells with `answer` entered, clearing any crossing
+/// answers that disagree with it
+async fn checkpoint<E: Entry>(
+    entry: &E,
+    answer: String,
+    entries: &[E],
+    crossings: &CrossingMap,
+) -> Result<Checkpoint, GridSolveError> {
+    let rejection = Rejection::new(entry.clue(), &entry.value().await, answer.clone());
+    let state = GridSnapshot::capture(entries).await;
+    let forked = |id: ClueId| {
+        state.get(id).ok_or_else(|| {
+            SolverError::InconsistentState(format!("{} is missing from the checkpoint", id))
+        })
+    };
+    // Find crossing clues that are creating the conflict and clear them out
+    for cross in crossings.conflicting_entries(entry, &answer, entries).await {
+        forked(cross.id())?.clear().await?;
+    }
+    // Write the new answer in to the old state
+    forked(entry.id())?.write(answer).await?;
+    Ok(Checkpoint {
+        state,
+        rejection: Some(rejection),
+    })
+}
+
+/// Wait for the future to finish, or give up on it once the deadline has passed
+async fn until<T>(deadline: Option<Instant>, future: impl Future<Output = T>) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Number of cells in the grid with a value
+async fn filled_cells<E: Entry>(entries: &[E]) -> usize {
+    grid_state(entries).await.0.read().await.len()
+}
+
+/// Capture the grid if more of its cells are filled than in the best one so far
+async fn remember_best<E: Entry>(entries: &[E], best: &mut Option<(usize, Checkpoint)>) {
+    let filled = filled_cells(entries).await;
+    if !best.as_ref().is_some_and(|(most, _)| *most >= filled) {
+        *best = Some((filled, Checkpoint::capture(entries).await));
+    }
+}
+
+/// Set every cell back to its value in the checkpoint, clearing the cells it left empty so
+/// nothing entered since it was captured survives. Entries share cells, so only the cells that
+/// changed are written, each once through the first entry that has it
+async fn restore<E: Entry>(entries: &[E], checkpoint: &Checkpoint) -> Result<(), SolverError> {
+    let current = GridSnapshot::capture(entries).await;
+    for (position, value) in checkpoint.diff(&current).await {
+        let entry = entries
+            .iter()
+            .find(|e| e.positions().contains(&position))
+            .ok_or_else(|| {
+                SolverError::InconsistentState(format!(
+                    "no entry has the cell at row {}, column {} of the checkpoint",
+                    position.row, position.column
+                ))
+            })?;
+        entry.write_cell(position, value).await?;
+    }
+    let restored = grid_state(entries).await;
+    let expected = grid_state(&checkpoint.owned_entries()).await;
+    if *restored.0.read().await != *expected.0.read().await {
+        return Err(SolverError::InconsistentState(String::from(
+            "the grid does not match the checkpoint it was restored to",
+        )));
+    }
+    Ok(())
+}
+
+/// A puzzle solved in memory by `LLMSolver::in_memory`. Full grids are copied to the entries of
+/// the real puzzle to be verified, and its feedback is passed back to the solver. The Clues of
+/// the two sets of entries are the same, so the entries it marks incorrect are too
+struct WriteOut<'a, P: CrosswordPuzzle> {
+    puzzle: &'a P,
+    entries: Vec<P::Entry>,
+}
+
+#[async_trait]
+impl<P: CrosswordPuzzle> CrosswordPuzzle for WriteOut<'_, P> {
+    type Entry = InMemoryEntry;
+
+    async fn generate_entries(&self) -> Result<Vec<InMemoryEntry>, PuzzleError> {
+        Ok(GridSnapshot::capture(&self.entries).await.into_entries())
+    }
+
+    async fn start_timer(&self) -> Result<(), WebDriverError> {
+        self.puzzle.start_timer().await
+    }
+
+    async fn elapsed_time(&self) -> Result<Option<Duration>, WebDriverError> {
+        self.puzzle.elapsed_time().await
+    }
+
+    async fn verify_entries(&self, entries: &[InMemoryEntry]) -> Result<Feedback, WebDriverError> {
+        // An unfinished grid is never solved, and writing it out would only waste time
+        for entry in entries {
+            if !entry.filled().await {
+                return Ok(Feedback::Unsolved);
+            }
+        }
+        for entry in &self.entries {
+            // Both entries cover the same cells, so there is always a value for each
+            if let Some(solved) = entries.iter().find(|e| e.id() == entry.id()) {
+                entry.write_cells(solved.cells().await).await?;
+            }
+        }
+        self.puzzle.verify_entries(&self.entries).await
+    }
+}
+
+/// Hash of the values in the grid, which is the same whatever order the cells are visited in
+async fn state_hash<E: Entry>(entries: &[E]) -> u64 {
+    let state = grid_state(entries).await;
+    let values = state.0.read().await;
+    values
+        .iter()
+        .map(|cell| {
+            let mut hasher = DefaultHasher::new();
+            cell.hash(&mut hasher);
+            hasher.finish()
+        })
+        .fold(0, u64::wrapping_add)
+}
+
+/// Failure while solving a puzzle
+#[derive(Debug)]
+pub enum SolverError {
+    Llm(LlmError),
+    WebDriver(WebDriverError),
+    /// The puzzle could not be read, or an answer could not be entered into it
+    Puzzle(PuzzleError),
+    /// Bookkeeping of the solver disagrees with the grid it is solving
+    InconsistentState(String),
+    /// A limit of the Budget was reached. `solve` reports this as an unsolved puzzle, with the
+    /// limit recorded in `SolveReport::budget_exceeded`
+    Budget(BudgetExceeded),
+    /// A hint given to `LLMSolver::hints` does not fit the puzzle
+    Hint(HintError),
+    /// A Question given to `LLMSolver::suggest` does not describe an answer that could exist
+    Question(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Llm(e) => write!(f, "LLM request failed: {}", e),
+            SolverError::WebDriver(e) => write!(f, "{}", e),
+            SolverError::Puzzle(e) => write!(f, "{}", e),
+            SolverError::InconsistentState(message) => {
+                write!(f, "solver state is inconsistent: {}", message)
+            }
+            SolverError::Budget(e) => write!(f, "solver {}", e),
+            SolverError::Hint(e) => write!(f, "{}", e),
+            SolverError::Question(message) => write!(f, "invalid question: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SolverError::Llm(e) => Some(e),
+            SolverError::WebDriver(e) => Some(e),
+            SolverError::Puzzle(e) => Some(e),
+            SolverError::Hint(e) => Some(e),
+            SolverError::InconsistentState(_)
+            | SolverError::Budget(_)
+            | SolverError::Question(_) => None,
+        }
+    }
+}
+
+impl From<LlmError> for SolverError {
+    fn from(value: LlmError) -> Self {
+        SolverError::Llm(value)
+    }
+}
+
+impl From<WebDriverError> for SolverError {
+    fn from(value: WebDriverError) -> Self {
+        SolverError::WebDriver(value)
+    }
+}
+
+impl From<PuzzleError> for SolverError {
+    fn from(value: PuzzleError) -> Self {
+        match value {
+            PuzzleError::WebDriver(e) => SolverError::WebDriver(e),
+            e => SolverError::Puzzle(e),
+        }
+    }
+}
+
+impl From<EntryError> for SolverError {
+    fn from(value: EntryError) -> Self {
+        PuzzleError::from(value).into()
+    }
+}
+
+impl From<HintError> for SolverError {
+    fn from(value: HintError) -> Self {
+        SolverError::Hint(value)
+    }
+}
+
+/// Reason a hint does not fit the puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HintError {
+    /// No clue has the number and direction of the hint
+    UnknownClue(ClueId),
+    /// The answer does not have a letter for every cell of the entry
+    Length {
+        id: ClueId,
+        expected: usize,
+        found: usize,
+    },
+    /// The hint disagrees with an earlier hint about the letter of a cell they share
+    Conflict(ClueId),
+}
+
+impl fmt::Display for HintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HintError::UnknownClue(id) => write!(f, "hint for {}: there is no such clue", id),
+            HintError::Length {
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "hint for {}: answer has {} letters but the entry has {} squares",
+                id, found, expected
+            ),
+            HintError::Conflict(id) => {
+                write!(f, "hint for {}: answer disagrees with another hint", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HintError {}
+
+/// Reason a single pass over the grid ended without a solution
+#[derive(Debug)]
+pub enum GridSolveError {
+    /// Checkpoints to backtrack to, with the most promising first
+    FailedToSolve(Vec<Checkpoint>),
+    Error(SolverError),
+}
+
+impl fmt::Display for GridSolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridSolveError::FailedToSolve(checkpoints) => write!(
+                f,
+                "grid was not solved, {} checkpoints to backtrack to",
+                checkpoints.len()
+            ),
+            GridSolveError::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GridSolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GridSolveError::FailedToSolve(_) => None,
+            GridSolveError::Error(e) => Some(e),
+        }
+    }
+}
+
+impl From<SolverError> for GridSolveError {
+    fn from(value: SolverError) -> Self {
+        GridSolveError::Error(value)
+    }
+}
+
+impl From<WebDriverError> for GridSolveError {
+    fn from(value: WebDriverError) -> Self {
+        GridSolveError::Error(value.into())
+    }
+}
+
+impl From<EntryError> for GridSolveError {
+    fn from(value: EntryError) -> Self {
+        GridSolveError::Error(value.into())
+    }
+}
+
+/// Failure to get a response from an LLM
+#[derive(Debug)]
+pub enum LlmError {
+    OpenAI(ChatGPTError),
+    /// The request could not be sent, or the response had an unexpected error status
+    Http(reqwest::Error),
+    /// The API is too busy, optionally saying how long to wait before trying again
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    /// The API key was not accepted
+    Auth(String),
+    /// The response could not be read
+    Malformed(String),
+    /// The conversation sent to the LLM was malformed
+    Conversation(&'static str),
+    /// The ModelConfig has an option the backend does not support
+    Config(String),
+    /// Nothing is listening at the address of a local LLM
+    Unreachable(String),
+    /// The LLM refused to answer, e.g. because of its safety filters
+    Blocked(String),
+    /// No response arrived within the timeout of the ModelConfig
+    Timeout,
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::OpenAI(e) => write!(f, "{}", e),
+            LlmError::Http(e) => write!(f, "{}", e),
+            LlmError::RateLimited {
+                retry_after: Some(delay),
+            } => write!(f, "rate limited, retry after {}s", delay.as_secs()),
+            LlmError::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            LlmError::Auth(message) => write!(f, "{}", message),
+            LlmError::Malformed(message) => write!(f, "unable to read response: {}", message),
+            LlmError::Conversation(message) => write!(f, "{}", message),
+            LlmError::Config(message) => write!(f, "{}", message),
+            LlmError::Unreachable(message) => write!(f, "{}", message),
+            LlmError::Blocked(reason) => write!(f, "the response was blocked: {}", reason),
+            LlmError::Timeout => write!(f, "the LLM did not respond in time"),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LlmError::OpenAI(e) => Some(e),
+            LlmError::Http(e) => Some(e),
+            LlmError::RateLimited { .. }
+            | LlmError::Auth(_)
+            | LlmError::Malformed(_)
+            | LlmError::Conversation(_)
+            | LlmError::Config(_)
+            | LlmError::Unreachable(_)
+            | LlmError::Blocked(_)
+            | LlmError::Timeout => None,
+        }
+    }
+}
+
+impl LlmError {
+    /// Whether sending the same message again later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LlmError::RateLimited { .. } | LlmError::Timeout => true,
+            LlmError::Http(e) => {
+                e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<ChatGPTError> for LlmError {
+    fn from(value: ChatGPTError) -> Self {
+        LlmError::OpenAI(value)
+    }
+}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(value: reqwest::Error) -> Self {
+        if value.is_timeout() {
+            LlmError::Timeout
+        } else {
+            LlmError::Http(value)
+        }
+    }
+}
+
+/// Fail with `LlmError::Timeout` if the request takes longer than `timeout`, for clients that
+/// cannot be trusted to give up on a hung connection themselves
+async fn within<T, E: Into<LlmError>>(
+    timeout: Duration,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, LlmError> {
+    match tokio::time::timeout(timeout, request).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(LlmError::Timeout),
+    }
+}
+
+/// A single turn of a conversation with an LLM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    User(String),
+    Assistant(String),
+}
+
+/// Conversation with an LLM about a single clue. Follow ups to a question always include the
+/// question and every answer since, so the LLM knows which of its answers were rejected. A
+/// Session can also remember earlier questions about the clue, so that when a crossing answer
+/// changes the pattern the LLM knows what it answered before
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    messages: Vec<Message>,
+    /// Messages kept after the first question, or `None` to forget earlier questions
+    limit: Option<usize>,
+    sent_chars: usize,
+}
+
+impl Session {
+    /// A Session that remembers earlier questions. Once more than `limit` messages follow the
+    /// first question the oldest are dropped, so a long solve does not grow without bound
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    /// Ask a new question, forgetting the previous one unless the Session remembers them
+    pub async fn ask(&mut self, llm: &dyn LLMModel, question: String) -> Result<String, LlmError> {
+        if self.limit.is_none() {
+            self.messages.clear();
+        }
+        self.send(llm, question).await
+    }
+
+    /// Send a message, along with the conversation so far
+    pub async fn send(&mut self, llm: &dyn LLMModel, message: String) -> Result<String, LlmError> {
+        self.messages.push(Message::User(message.clone()));
+        self.truncate();
+        self.sent_chars += self
+            .messages
+            .iter()
+            .map(|m| match m {
+                Message::User(text) | Message::Assistant(text) => text.chars().count(),
+            })
+            .sum::<usize>();
+        let response = if self.messages.len() == 1 {
+            llm.chat(message).await
+        } else {
+            llm.converse(self.messages.clone()).await
+        };
+        match response {
+            Ok(response) => {
+                self.messages.push(Message::Assistant(response.clone()));
+                Ok(response)
+            }
+            Err(e) => {
+                // Nothing was answered, so the message can be sent again cleanly
+                self.messages.pop();
+                Err(e)
+            }
+        }
+    }
+
+    /// Messages that will be sent before the next one
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Characters sent to the LLM, including every repeat of the conversation
+    pub fn sent_chars(&self) -> usize {
+        self.sent_chars
+    }
+
+    /// Drop the oldest exchanges after the first question. They are dropped in pairs so that
+    /// the messages still alternate between the user and the LLM
+    fn truncate(&mut self) {
+        if let Some(limit) = self.limit {
+            while self.messages.len() > limit + 1 && self.messages.len() > 3 {
+                self.messages.drain(1..3);
+            }
+        }
+    }
+}
+
+/// A Large Language Model that can answer crossword clues
+#[async_trait]
+pub trait LLMModel: Send + Sync {
+    async fn chat(&self, message: String) -> Result<String, LlmError>;
+
+    /// Start a conversation about a clue. By default it only lasts for a single question and
+    /// its follow ups, as `converse` sends the whole transcript as one message
+    fn start_session(&self) -> Session {
+        Session::default()
+    }
+
+    /// Continue a conversation that ends with a message from the user. By default the whole
+    /// transcript is sent as a single message
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        let transcript = messages
+            .iter()
+            .map(|m| match m {
+                Message::User(text) => format!("User: {}", text),
+                Message::Assistant(text) => format!("Assistant: {}", text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.chat(transcript).await
+    }
+
+    /// Work done by each backend behind this one, if it combines several
+    fn backends(&self) -> Vec<BackendReport> {
+        vec![]
+    }
+}
+
+/// Answers clues from a fixed map of clue text to answer
+///
+/// The clue is read from the first `<text>` block of the prompt. Clues missing from the map
+/// are answered with an empty string, which the solver will reject. Several candidates can be
+/// given for a clue as a comma separated list.
+#[derive(Clone, Default)]
+pub struct MockLLM {
+    answers: HashMap<String, String>,
+    /// Answers given instead of the real one until the prompt says they are wrong
+    wrong: HashMap<String, String>,
+    /// Clues that are never answered
+    failing: HashSet<String>,
+    delay: Duration,
+}
+
+impl MockLLM {
+    pub fn new(answers: HashMap<String, String>) -> Self {
+        Self {
+            answers,
+            ..Self::default()
+        }
+    }
+
+    /// Load answers from a YAML map of clue text to answer
+    pub fn from_yaml(reader: impl Read) -> Result<Self, serde_yaml::Error> {
+        Ok(Self::new(serde_yaml::from_reader(reader)?))
+    }
+
+    /// Answer the clue with `answer` until a prompt says it is not the answer, like an LLM
+    /// that is confidently wrong until corrected
+    pub fn wrong_answer(mut self, clue: impl Into<String>, answer: impl Into<String>) -> Self {
+        self.wrong.insert(clue.into(), answer.into());
+        self
+    }
+
+    /// Fail every prompt about the clue with an `LlmError::Unreachable`
+    pub fn failing(mut self, clue: impl Into<String>) -> Self {
+        self.failing.insert(clue.into());
+        self
+    }
+
+    /// Wait before every response, as a real LLM would
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[async_trait]
+impl LLMModel for MockLLM {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        let Some(clue) = message
+            .split("<text>")
+            .nth(1)
+            .and_then(|s| s.split("</text>").next())
+            .map(str::trim)
+        else {
+            return Ok(String::new());
+        };
+        if self.failing.contains(clue) {
+            return Err(LlmError::Unreachable(format!(
+                "MockLLM is set to fail for {}",
+                clue
+            )));
+        }
+        if let Some(wrong) = self.wrong.get(clue) {
+            let corrected = message
+                .lines()
+                .any(|line| line.contains("NOT any of:") && line.contains(wrong.as_str()));
+            if !corrected {
+                return Ok(wrong.clone());
+            }
+        }
+        Ok(self.answers.get(clue).cloned().unwrap_or_default())
+    }
+}
+
+/// Highest temperature accepted by each backend
+static OPENAI_MAX_TEMPERATURE: f32 = 2.0;
+static COHERE_MAX_TEMPERATURE: f32 = 5.0;
+static ANTHROPIC_MAX_TEMPERATURE: f32 = 1.0;
+static OLLAMA_MAX_TEMPERATURE: f32 = 2.0;
+static GEMINI_MAX_TEMPERATURE: f32 = 2.0;
+
+/// Overloaded, returned by Anthropic when it is too busy to answer
+static OVERLOADED: u16 = 529;
+
+/// Send a request, turning a response with an error status into the LlmError it stands for
+async fn send_request(request: reqwest::RequestBuilder) -> Result<reqwest::Response, LlmError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == OVERLOADED {
+        return Err(LlmError::RateLimited {
+            retry_after: retry_after(response.headers()),
+        });
+    }
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        let body = response.text().await.unwrap_or_default();
+        return Err(LlmError::Auth(format!(
+            "the API key was rejected: {} {}",
+            status,
+            body.trim()
+        )));
+    }
+    Ok(response.error_for_status()?)
+}
+
+/// Seconds to wait before trying again. Only the delay form of `Retry-After` is used by LLM APIs
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Body of a response, parsed as JSON
+async fn read_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, LlmError> {
+    let body = response.text().await?;
+    serde_json::from_str(&body).map_err(|e| LlmError::Malformed(e.to_string()))
+}
+
+/// Attempts made by Retrying before the error is returned
+static RETRY_ATTEMPTS: usize = 5;
+static RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+static RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Sends a message to an LLMModel again when it fails in a way that may succeed later, waiting
+/// twice as long after each failure. Used around every backend created by `LLMSolver::new`
+pub struct Retrying<M> {
+    inner: M,
+    attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl<M: LLMModel> Retrying<M> {
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            attempts: RETRY_ATTEMPTS,
+            base_delay: RETRY_BASE_DELAY,
+            max_delay: RETRY_MAX_DELAY,
+        }
+    }
+
+    /// Give up after sending a message this many times, including the first
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait up to `base` after the first failure, doubling each time up to `max`
+    pub fn delays(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+
+    async fn retry<F, Fut>(&self, send: F) -> Result<String, LlmError>
+    where
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<String, LlmError>> + Send,
+    {
+        let mut attempt = 1;
+        loop {
+            match send().await {
+                Err(e) if e.is_retryable() && attempt < self.attempts => {
+                    // The API knows best how long it needs, as long as that is not too long
+                    let delay = match e {
+                        LlmError::RateLimited {
+                            retry_after: Some(delay),
+                        } => delay.min(self.max_delay),
+                        _ => {
+                            let retry = u32::try_from(attempt - 1).unwrap_or(u32::MAX);
+                            backoff(retry, self.base_delay, self.max_delay, jitter())
+                        }
+                    };
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Delay before retry number `retry`, counting from 0. The delay doubles from `base` up to
+/// `max`, and a random part of up to half of it is skipped so clients that failed together do
+/// not all retry together. `jitter` is between 0 and 1
+fn backoff(retry: u32, base: Duration, max: Duration, jitter: f64) -> Duration {
+    let delay = base.saturating_mul(2u32.saturating_pow(retry)).min(max);
+    delay.mul_f64(1.0 - jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+/// A number between 0 and 1 that is different each time. Every RandomState has new keys, which
+/// is random enough to spread out retries
+fn jitter() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+#[async_trait]
+impl<M: LLMModel> LLMModel for Retrying<M> {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.retry(|| self.inner.chat(message.clone())).await
+    }
+
+    fn start_session(&self) -> Session {
+        self.inner.start_session()
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        self.retry(|| self.inner.converse(messages.clone())).await
+    }
+
+    fn backends(&self) -> Vec<BackendReport> {
+        self.inner.backends()
+    }
+}
+
+/// Asks several backends the same question at once and merges their answers. An answer ranked
+/// first by every backend is certain, and the rest are ranked by how many backends gave them.
+/// The solver still prefers whichever of them fits the letters already in the grid
+pub struct Ensemble {
+    members: Vec<(String, Box<dyn LLMModel>)>,
+    reports: Mutex<Vec<BackendReport>>,
+}
+
+impl Ensemble {
+    /// Backends along with the names they are reported under
+    pub fn new(members: Vec<(String, Box<dyn LLMModel>)>) -> Self {
+        let reports = members
+            .iter()
+            .map(|(name, _)| BackendReport {
+                name: name.clone(),
+                ..BackendReport::default()
+            })
+            .collect();
+        Self {
+            members,
+            reports: Mutex::new(reports),
+        }
+    }
+
+    async fn ask_all<'a, F, Fut>(&'a self, send: F) -> Result<String, LlmError>
+    where
+        F: Fn(&'a dyn LLMModel) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<String, LlmError>> + Send,
+    {
+        let responses = future::join_all(self.members.iter().map(|(_, llm)| {
+            let response = send(llm.as_ref());
+            async move {
+                let started = tokio::time::Instant::now();
+                let response = response.await;
+                (response, started.elapsed())
+            }
+        }))
+        .await;
+        let answered: Vec<&str> = responses
+            .iter()
+            .filter_map(|(response, _)| response.as_deref().ok())
+            .collect();
+        let merged = reconcile(&answered);
+        let first = |response: &str| {
+            parse_candidates(response)
+                .first()
+                .and_then(|raw| clean_answer(split_confidence(raw).0))
+        };
+        let firsts: Vec<Option<String>> = answered.iter().copied().map(&first).collect();
+        let agreed = firsts.len() > 1 && firsts.iter().all(|f| f.is_some() && *f == firsts[0]);
+        {
+            let mut reports = self.reports.lock().expect("Ensemble reports were poisoned");
+            for (report, (response, latency)) in reports.iter_mut().zip(&responses) {
+                report.calls += 1;
+                report.latency += *latency;
+                match response {
+                    Ok(response) => {
+                        let first = first(response);
+                        if first.is_some() && first == merged.first().map(|c| c.answer.clone()) {
+                            report.chosen += 1;
+                        }
+                        report.agreed += usize::from(agreed);
+                    }
+                    Err(_) => report.errors += 1,
+                }
+            }
+        }
+        if answered.is_empty() {
+            // Every backend failed, so report the first of them
+            if let Some((Err(e), _)) = responses.into_iter().next() {
+                return Err(e);
+            }
+        }
+        Ok(merged
+            .iter()
+            .map(Candidate::to_string)
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+}
+
+/// Merge the answers of several backends, ranking those given by the most backends first. An
+/// answer ranked first by every backend is certain, otherwise it is trusted as much as the
+/// backends trusted it on average
+fn reconcile(responses: &[&str]) -> Vec<Candidate> {
+    // Answer, number of backends that gave it, the sum of their confidence and the number of
+    // backends that gave it first
+    let mut merged: Vec<(String, usize, usize, usize)> = vec![];
+    for response in responses {
+        let mut seen = HashSet::new();
+        for raw in parse_candidates(response) {
+            let (raw, confidence) = split_confidence(&raw);
+            let Some(answer) = clean_answer(raw) else {
+                continue;
+            };
+            let first = usize::from(seen.is_empty());
+            if !seen.insert(answer.clone()) {
+                continue;
+            }
+            let confidence = usize::from(confidence.unwrap_or(100));
+            match merged.iter_mut().find(|(a, ..)| *a == answer) {
+                Some((_, votes, total, firsts)) => {
+                    *votes += 1;
+                    *total += confidence;
+                    *firsts += first;
+                }
+                None => merged.push((answer, 1, confidence, first)),
+            }
+        }
+    }
+    // Sorting is stable, so ties are broken by the order of the backends
+    merged.sort_by_key(|(_, votes, total, _)| Reverse((*votes, *total)));
+    let backends = responses.len().max(1);
+    merged
+        .into_iter()
+        .map(|(answer, _, total, firsts)| Candidate {
+            answer,
+            confidence: Some(if firsts == backends && backends > 1 {
+                100
+            } else {
+                u8::try_from(total / backends).unwrap_or(100)
+            }),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl LLMModel for Ensemble {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.ask_all(|llm| llm.chat(message.clone())).await
+    }
+
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        self.ask_all(|llm| llm.converse(messages.clone())).await
+    }
+
+    fn backends(&self) -> Vec<BackendReport> {
+        self.reports
+            .lock()
+            .expect("Ensemble reports were poisoned")
+            .clone()
+    }
+}
+
+static COHERE_URL: &str = "https://api.cohere.ai";
+
+struct Cohere {
+    client: reqwest::Client,
+    url: String,
+    key: String,
+    config: ModelConfig,
+}
+
+impl Cohere {
+    fn new(key: String, config: ModelConfig) -> Result<Self, LlmError> {
+        config.validate("Cohere", COHERE_MAX_TEMPERATURE)?;
+        Ok(Cohere {
+            client: reqwest::Client::builder().timeout(config.timeout).build()?,
+            url: COHERE_URL.to_string(),
+            key,
+            config,
+        })
+    }
+
+    fn request(&self, message: String, chat_history: Vec<CohereMessage>) -> CohereRequest {
+        CohereRequest {
+            message,
+            chat_history,
+            model: self.config.model.clone(),
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CohereChatResponse {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct CohereRequest {
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chat_history: Vec<CohereMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct CohereMessage {
+    role: &'static str,
+    message: String,
+}
+
+impl Cohere {
+    async fn send(&self, request: &CohereRequest) -> Result<String, LlmError> {
+        let request = self
+            .client
+            .post(format!("{}/v1/chat", self.url))
+            .header("Authorization", &format!("Bearer {}", self.key))
+            .json(request);
+        // Only 5 requests per minute are allowed on the trial license, so rate limits are common
+        // and left for Retrying to wait out
+        let response = send_request(request).await.map_err(|e| match e {
+            LlmError::Auth(_) => LlmError::Auth(String::from("invalid Cohere key")),
+            e => e,
+        })?;
+        Ok(read_json::<CohereChatResponse>(response).await?.text)
+    }
+}
+
+#[async_trait]
+impl LLMModel for Cohere {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.send(&self.request(message, vec![])).await
+    }
+
+    /// The history is replayed in each request
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, mut messages: Vec<Message>) -> Result<String, LlmError> {
+        let Some(Message::User(message)) = messages.pop() else {
+            return Err(LlmError::Conversation(
+                "conversation must end with a message from the user",
+            ));
+        };
+        let chat_history = messages
+            .into_iter()
+            .map(|m| match m {
+                Message::User(message) => CohereMessage {
+                    role: "USER",
+                    message,
+                },
+                Message::Assistant(message) => CohereMessage {
+                    role: "CHATBOT",
+                    message,
+                },
+            })
+            .collect();
+        self.send(&self.request(message, chat_history)).await
+    }
+}
+
+/// Model used when the ModelConfig does not name one. Anthropic has no default of its own
+static ANTHROPIC_MODEL: &str = "claude-3-5-sonnet-latest";
+/// The Messages API requires a limit on the length of each response
+static ANTHROPIC_MAX_TOKENS: u32 = 1024;
+static ANTHROPIC_VERSION: &str = "2023-06-01";
+
+struct Anthropic {
+    client: reqwest::Client,
+    key: String,
+    config: ModelConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicRequest {
+    model: String,
+    max_tokens: u32,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+}
+
+/// A block of the response. Only text is ever asked for
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContent {
+    Text {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl Anthropic {
+    fn new(key: String, config: ModelConfig) -> Result<Self, LlmError> {
+        config.validate("Anthropic", ANTHROPIC_MAX_TEMPERATURE)?;
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(config.timeout).build()?,
+            key,
+            config,
+        })
+    }
+
+    fn request(&self, messages: Vec<Message>) -> AnthropicRequest {
+        AnthropicRequest {
+            model: self
+                .config
+                .model
+                .clone()
+                .unwrap_or_else(|| String::from(ANTHROPIC_MODEL)),
+            max_tokens: self.config.max_tokens.unwrap_or(ANTHROPIC_MAX_TOKENS),
+            messages: messages
+                .into_iter()
+                .map(|m| match m {
+                    Message::User(content) => AnthropicMessage {
+                        role: "user",
+                        content,
+                    },
+                    Message::Assistant(content) => AnthropicMessage {
+                        role: "assistant",
+                        content,
+                    },
+                })
+                .collect(),
+            temperature: self.config.temperature,
+        }
+    }
+}
+
+impl AnthropicResponse {
+    /// Every text block of the response joined together
+    fn text(self) -> String {
+        self.content
+            .into_iter()
+            .filter_map(|block| match block {
+                AnthropicContent::Text { text } => Some(text),
+                AnthropicContent::Other => None,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LLMModel for Anthropic {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.converse(vec![Message::User(message)]).await
+    }
+
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        let request = self
+            .client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&self.request(messages));
+        let response = send_request(request).await?;
+        Ok(read_json::<AnthropicResponse>(response).await?.text())
+    }
+}
+
+/// Model used when the ModelConfig does not name one
+static GEMINI_MODEL: &str = "gemini-1.5-flash";
+
+struct Gemini {
+    client: reqwest::Client,
+    key: String,
+    config: ModelConfig,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "GeminiGenerationConfig::is_empty")]
+    generation_config: GeminiGenerationConfig,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+}
+
+impl GeminiGenerationConfig {
+    fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.max_output_tokens.is_none()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiPart {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    /// Missing when the candidate was blocked
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+impl Gemini {
+    fn new(key: String, config: ModelConfig) -> Result<Self, LlmError> {
+        config.validate("Gemini", GEMINI_MAX_TEMPERATURE)?;
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(config.timeout).build()?,
+            key,
+            config,
+        })
+    }
+
+    fn request(&self, messages: Vec<Message>) -> GeminiRequest {
+        GeminiRequest {
+            contents: messages
+                .into_iter()
+                .map(|m| {
+                    let (role, text) = match m {
+                        Message::User(text) => ("user", text),
+                        Message::Assistant(text) => ("model", text),
+                    };
+                    GeminiContent {
+                        role: String::from(role),
+                        parts: vec![GeminiPart { text }],
+                    }
+                })
+                .collect(),
+            generation_config: GeminiGenerationConfig {
+                temperature: self.config.temperature,
+                max_output_tokens: self.config.max_tokens,
+            },
+        }
+    }
+}
+
+impl GeminiResponse {
+    /// Text of the first candidate, or why there is none
+    fn text(self) -> Result<String, LlmError> {
+        let Some(candidate) = self.candidates.into_iter().next() else {
+            let reason = self
+                .prompt_feedback
+                .and_then(|f| f.block_reason)
+                .unwrap_or_else(|| String::from("no candidates were returned"));
+            return Err(LlmError::Blocked(reason));
+        };
+        match candidate.content {
+            Some(content) => Ok(content.parts.into_iter().map(|p| p.text).collect()),
+            None => {
+                Err(LlmError::Blocked(candidate.finish_reason.unwrap_or_else(
+                    || String::from("the candidate has no content"),
+                )))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LLMModel for Gemini {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.converse(vec![Message::User(message)]).await
+    }
+
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        let model = self.config.model.as_deref().unwrap_or(GEMINI_MODEL);
+        let request = self
+            .client
+            .post(format!(
+                "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+                model
+            ))
+            .query(&[("key", &self.key)])
+            .json(&self.request(messages));
+        let response = send_request(request).await?;
+        read_json::<GeminiResponse>(response).await?.text()
+    }
+}
+
+/// Where Ollama listens unless `OLLAMA_HOST` says otherwise
+static OLLAMA_URL: &str = "http://localhost:11434";
+/// Local generation is slow, so requests to Ollama wait at least this long
+static OLLAMA_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Base URL of Ollama from the value of `OLLAMA_HOST`, which Ollama allows without a scheme
+fn ollama_url(host: Option<String>) -> String {
+    match host.filter(|h| !h.is_empty()) {
+        Some(host) if host.contains("://") => host.trim_end_matches('/').to_string(),
+        Some(host) => format!("http://{}", host.trim_end_matches('/')),
+        None => String::from(OLLAMA_URL),
+    }
+}
+
+struct Ollama {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    config: ModelConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    /// The whole response is read at once
+    stream: bool,
+    #[serde(skip_serializing_if = "OllamaOptions::is_empty")]
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+impl OllamaOptions {
+    fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.num_predict.is_none()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaMessage,
+}
+
+impl Ollama {
+    fn new(base_url: String, model: String, config: ModelConfig) -> Result<Self, LlmError> {
+        config.validate("Ollama", OLLAMA_MAX_TEMPERATURE)?;
+        if config.model.is_some() {
+            return Err(LlmError::Config(String::from(
+                "Ollama takes its model from APIKey::Ollama, not the ModelConfig",
+            )));
+        }
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(config.timeout.max(OLLAMA_TIMEOUT))
+                .build()?,
+            base_url,
+            model,
+            config,
+        })
+    }
+
+    fn request(&self, messages: Vec<Message>) -> OllamaRequest {
+        OllamaRequest {
+            model: self.model.clone(),
+            messages: messages
+                .into_iter()
+                .map(|m| match m {
+                    Message::User(content) => OllamaMessage {
+                        role: String::from("user"),
+                        content,
+                    },
+                    Message::Assistant(content) => OllamaMessage {
+                        role: String::from("assistant"),
+                        content,
+                    },
+                })
+                .collect(),
+            stream: false,
+            options: OllamaOptions {
+                temperature: self.config.temperature,
+                num_predict: self.config.max_tokens,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl LLMModel for Ollama {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        self.converse(vec![Message::User(message)]).await
+    }
+
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        let request = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&self.request(messages));
+        let response = send_request(request).await.map_err(|e| match e {
+            LlmError::Http(e) if e.is_connect() => LlmError::Unreachable(format!(
+                "unable to connect to Ollama at {}, is Ollama running?",
+                self.base_url
+            )),
+            e => e,
+        })?;
+        Ok(read_json::<OllamaResponse>(response).await?.message.content)
+    }
+}
+
+struct OpenAI {
+    gpt: ChatGPT,
+    timeout: Duration,
+}
+
+impl OpenAI {
+    fn new(api_key: String, config: ModelConfig) -> Result<Self, LlmError> {
+        config.validate("OpenAI", OPENAI_MAX_TEMPERATURE)?;
+        let defaults = ModelConfiguration::default();
+        let configuration = ModelConfiguration {
+            // The client only accepts a static model name, and is created once per solver
+            engine: match config.model {
+                Some(model) => ChatGPTEngine::Custom(Box::leak(model.into_boxed_str())),
+                None => defaults.engine.clone(),
+            },
+            temperature: config.temperature.unwrap_or(defaults.temperature),
+            max_tokens: config.max_tokens.or(defaults.max_tokens),
+            timeout: config.timeout,
+            ..defaults
+        };
+        Ok(Self {
+            gpt: ChatGPT::new_with_config(api_key, configuration)?,
+            timeout: config.timeout,
+        })
+    }
+}
+
+#[async_trait]
+impl LLMModel for OpenAI {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        Ok(within(self.timeout, self.gpt.send_message(message))
+            .await?
+            .message()
+            .content
+            .clone())
+    }
+
+    fn start_session(&self) -> Session {
+        Session::new(SESSION_MESSAGES)
+    }
+
+    async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+        let history = messages
+            .into_iter()
+            .map(|m| match m {
+                Message::User(content) => ChatMessage {
+                    role: Role::User,
+                    content,
+                },
+                Message::Assistant(content) => ChatMessage {
+                    role: Role::Assistant,
+                    content,
+                },
+            })
+            .collect();
+        Ok(within(self.timeout, self.gpt.send_history(&history))
+            .await?
+            .message()
+            .content
+            .clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use thirtyfour::prelude::WebDriverResult;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, Request, ResponseTemplate,
+    };
+
+    use super::{
+        backoff, checkpoint, correction, jitter, linked_entries, most_constrained,
+        normalize_answer, ollama_url, parse_batch, parse_candidates, prompt_for_clue, reconcile,
+        remember_best, restore, split_confidence, state_hash, suspicion, APIKey, Anthropic,
+        AnthropicResponse, BatchStrategy, Budget, BudgetExceeded, CacheKey, Candidate, Checkpoint,
+        ClueContext, Cohere, Crossing, Ensemble, Gemini, GeminiResponse, GridSolveError, LLMModel,
+        LLMSolver, LlmError, Message, MockLLM, ModelConfig, Ollama, Ordering, PuzzleResult,
+        Question, Rejection, Retrying, Session, SolveReport, SolverError, SolverEvent,
+        SESSION_MESSAGES,
+    };
+    use crate::{
+        memory::{proposals, InMemoryPuzzle, Solution},
+        prompt::PromptTemplate,
+        puzzle::{CrossingMap, CrosswordPuzzle, Entry, Feedback, GridSnapshot, PuzzleError},
+        wordlist::WordList,
+        Clue, ClueId, Direction, InMemoryEntry, Position, Puzzle,
+    };
+
+    const MINI: &str = include_str!("../tests/fixtures/mini1.yaml");
+    const MINI_ANSWERS: &str = include_str!("../tests/fixtures/mini1_answers.yaml");
+    const MINI_CACHE: &str = include_str!("../tests/fixtures/mini1_cache.json");
+
+    /// Every clue of the puzzle paired with its answer read from the stored solution
+    fn oracle(puzzle: &InMemoryPuzzle) -> Vec<(String, String)> {
+        let solution = puzzle.solution().expect("Fixture is missing a solution");
+        let grid = puzzle.puzzle().grid();
+        puzzle
+            .puzzle()
+            .clues
+            .iter()
+            .map(|clue| {
+                let answer = grid
+                    .positions_for_clue(clue)
+                    .into_iter()
+                    .filter_map(|p| solution.get(p))
+                    .collect();
+                (clue.text.clone(), answer)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn prompt() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        entries[0].write(String::from("CASH")).await.unwrap();
+        // 1-Down shares its first cell with 1-Across
+        let context = ClueContext::new(&entries[5], &entries, &crossings).await;
+        assert_eq!(context.pattern, "C____");
+        assert_eq!(context.crossings.len(), 5);
+        assert_eq!(context.crossings[0].letter.as_deref(), Some("C"));
+        assert_eq!(
+            prompt_for_clue(&PromptTemplate::default(), &context, &[]),
+            "Determine the answer to the following crossword clue.
+<text>
+Paddled boat
+</text>
+The answer is 5 letters long and may match the pattern C____. Respond with your 3 best answers as a comma separated list, most likely first, each followed by how confident you are that it is correct from 0 to 100, e.g. ANSWER 90, OTHER 40. Include no other text. Do not include punctuation or hyphens in the answers.
+The answer crosses the clues below.
+<text>
+Letter 1 crosses 1-Across \"Money in hand\", which reads CASH so far, so letter 1 is likely C.
+Letter 2 crosses 5-Across \"Slapstick comedy\", which reads _____ so far.
+Letter 3 crosses 6-Across \"Heavenly messenger\", which reads _____ so far.
+Letter 4 crosses 7-Across \"Circular\", which reads _____ so far.
+Letter 5 crosses 8-Across \"Get together\", which reads ____ so far.
+</text>"
+        );
+        // Custom templates can still list every clue of the puzzle
+        let template = PromptTemplate::parse("{other_clues}").unwrap();
+        let other_clues = prompt_for_clue(&template, &context, &[]);
+        for entry in entries.iter() {
+            assert!(other_clues.contains(&format!("{}\n", entry.clue())));
+        }
+    }
+
+    #[test]
+    fn prompt_from_context() {
+        // Built by hand, as nothing about the puzzle is read while rendering
+        let clue = |number, direction, text: &str| Clue {
+            number,
+            direction,
+            text: String::from(text),
+            position: Position { row: 0, column: 0 },
+        };
+        let context = ClueContext {
+            clue: clue(1, Direction::Across, "Nile biters"),
+            length: 4,
+            pattern: String::from("_S_S"),
+            circled: vec![2, 4],
+            crossings: vec![Crossing {
+                index: 1,
+                clue: clue(2, Direction::Down, "Sound of a leak"),
+                pattern: String::from("S__"),
+                letter: Some(String::from("S")),
+            }],
+            references: vec![(
+                clue(3, Direction::Down, "See 1-Across"),
+                String::from("____"),
+            )],
+            clues: vec![],
+        };
+        let template =
+            PromptTemplate::parse("{clue} {pattern}\n{notes}{crossings}{rejected}").unwrap();
+        assert_eq!(
+            prompt_for_clue(&template, &context, &[String::from("ASPS")]),
+            "Nile biters _S_S
+Letters 2, 4 of the answer are in circled squares, which are usually part of the puzzle's theme.
+3-Down is \"See 1-Across\" and currently reads ____.
+Letter 2 crosses 2-Down \"Sound of a leak\", which reads S__ so far, so letter 2 is likely S.
+The answer is NOT any of: ASPS.
+"
+        );
+    }
+
+    #[tokio::test]
+    async fn prompt_with_hints() {
+        let yaml = MINI
+            .replace("Money in hand", "Money in hand: 2 wds.")
+            .replace("Circular", "Circular, for short");
+        let puzzle = InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let render = |context| prompt_for_clue(&PromptTemplate::default(), &context, &[]);
+        let two_words = render(ClueContext::new(&entries[0], &entries, &crossings).await);
+        assert!(two_words.contains("The answer is 2 words totaling 4 letters"));
+        assert!(!two_words.contains("abbreviation"));
+        let circular = entries.iter().find(|e| e.clue().number == 7).unwrap();
+        let short = render(ClueContext::new(circular, &entries, &crossings).await);
+        assert!(short.contains("The answer is an abbreviation"));
+        assert!(!short.contains("words totaling"));
+    }
+
+    #[tokio::test]
+    async fn linked_clues() {
+        let yaml = MINI
+            .replace("Grasped", "See 1-Across")
+            .replace("Slapstick comedy", "With 1- and 4-Down, a farm");
+        let puzzle = InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let links = linked_entries(&entries);
+        let id = |number, direction| ClueId { number, direction };
+        assert_eq!(links[&id(1, Direction::Across)], [id(4, Direction::Down)]);
+        assert_eq!(
+            links[&id(4, Direction::Down)],
+            [id(1, Direction::Across), id(5, Direction::Across)]
+        );
+        assert!(links[&id(5, Direction::Down)].is_empty());
+        // Links go both ways, whichever clue names the other
+        for entry in &entries {
+            for other in &entries {
+                assert_eq!(
+                    links[&entry.id()].contains(&other.id()),
+                    entry.clue().linked(other.clue())
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn prompt_with_circles() {
+        let saved = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut puzzle = saved.puzzle().clone();
+        puzzle.circled = vec![
+            Position { row: 1, column: 1 },
+            Position { row: 3, column: 1 },
+        ];
+        let puzzle = InMemoryPuzzle::new(puzzle, None);
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let context = ClueContext::new(&entries[5], &entries, &crossings).await;
+        assert_eq!(context.circled, [2, 4]);
+        let prompt = prompt_for_clue(&PromptTemplate::default(), &context, &[]);
+        assert!(prompt.contains("Letters 2, 4 of the answer are in circled squares"));
+    }
+
+    fn solver(answers: Vec<(String, String)>) -> LLMSolver {
+        LLMSolver::with_model(Box::new(MockLLM::new(answers.into_iter().collect())))
+    }
+
+    #[tokio::test]
+    async fn solve_in_memory() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let answers = oracle(&puzzle);
+        assert!(solver(answers).solve(&puzzle).await.unwrap().solved);
+    }
+
+    #[tokio::test]
+    async fn suggest_answers() {
+        let question: Question = serde_json::from_value(serde_json::json!({
+            "clue": "Money in hand",
+            "length": 4,
+            "pattern": "C___",
+            "crossings": [{"clue": "Grasped", "pattern": "h___", "index": 3, "at": 0}],
+        }))
+        .unwrap();
+        question.validate().unwrap();
+        let llm = MockLLM::new(HashMap::from([(
+            String::from("Money in hand"),
+            String::from("CASE, CASH"),
+        )]));
+        let mut solver = LLMSolver::with_model(Box::new(llm));
+        let answers: Vec<String> = solver
+            .suggest(&question)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.answer)
+            .collect();
+        // CASE is kept, but it does not agree with the crossing
+        assert_eq!(answers, ["CASH", "CASE"]);
+        assert_eq!(solver.report().llm_calls, 1);
+        solver.suggest(&question).await.unwrap();
+        assert_eq!(solver.report().cache_hits, 1);
+
+        let invalid = |change: fn(&mut Question)| {
+            let mut invalid = question.clone();
+            change(&mut invalid);
+            invalid.validate().unwrap_err()
+        };
+        assert_eq!(
+            invalid(|q| q.length = 5),
+            "pattern has 4 cells but length is 5"
+        );
+        assert_eq!(
+            invalid(|q| q.pattern = Some(String::from("C__T"))),
+            "crossing \"Grasped\" has h where the pattern has T"
+        );
+        assert_eq!(
+            invalid(|q| q.crossings[0].index = 4),
+            "crossing \"Grasped\" does not cross a cell of its own"
+        );
+        // An invalid Question is refused instead of asked about
+        let mut empty = question.clone();
+        empty.length = 0;
+        assert!(matches!(
+            solver.suggest(&empty).await,
+            Err(SolverError::Question(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn puzzle_result_json() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = solver(oracle(&puzzle));
+        let report = solver.solve(&puzzle).await.unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let result = PuzzleResult {
+            date: Some("2024-03-14".parse().unwrap()),
+            has_solution: true,
+            answers: proposals(&entries, solver.entered()).await,
+            report,
+            ..PuzzleResult::default()
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["date"], "2024-03-14");
+        // The counts of the SolveReport sit alongside the answers
+        assert_eq!(json["solved"], true);
+        assert!(json["llm_calls"].as_u64().unwrap() > 0);
+        assert_eq!(json["answers"][0]["answer"], "CASH");
+        assert_eq!(json["answers"][0]["clue"]["text"], "Money in hand");
+        assert!(json["error"].is_null());
+        // Without a date the field is left out
+        let json = serde_json::to_value(PuzzleResult::default()).unwrap();
+        assert!(json.get("date").is_none());
+    }
+
+    #[tokio::test]
+    async fn resume_partly_filled() {
+        let word = |row, start, word: &'static str| {
+            word.chars().enumerate().map(move |(i, c)| {
+                let position = Position {
+                    row,
+                    column: start + i,
+                };
+                (position, c.to_string())
+            })
+        };
+        let letters: HashMap<Position, String> =
+            word(0, 1, "CASH").chain(word(1, 0, "FARCE")).collect();
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes())
+            .unwrap()
+            .with_letters(letters);
+        // An earlier run entered ANGEE, and checking the puzzle marked the last E
+        let llm =
+            MockLLM::new(oracle(&puzzle).into_iter().collect()).wrong_answer("Grasped", "HEED");
+        let wrong = HashMap::from([(Position { row: 2, column: 4 }, String::from("E"))]);
+        let mut solver = LLMSolver::with_model(Box::new(llm)).wrong_letters(wrong);
+        let mut receiver = solver.subscribe();
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        let events = received(&mut receiver);
+        let asked: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                SolverEvent::ClueAttempted { clue, .. } => Some(clue.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(!asked.is_empty());
+        // Entries that were already filled are never asked about
+        assert!(!asked.contains(&"Money in hand"));
+        assert!(!asked.contains(&"Slapstick comedy"));
+        assert!(!events.iter().any(|e| matches!(
+            e,
+            SolverEvent::EntryWritten { answer, .. } if answer == "HEED"
+        )));
+    }
+
+    #[tokio::test]
+    async fn orderings() {
+        for ordering in [
+            Ordering::Queue,
+            Ordering::MostConstrained,
+            Ordering::Confidence,
+        ] {
+            let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+            let mut solver = solver(oracle(&puzzle)).ordering(ordering);
+            assert!(
+                solver.solve(&puzzle).await.unwrap().solved,
+                "{:?}",
+                ordering
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn most_constrained_entry() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        // With nothing entered the first of the shortest entries is chosen
+        let index = most_constrained(&entries).await.unwrap();
+        assert_eq!(entries[index].clue().text, "Money in hand");
+
+        // FARCE gives every down entry one letter. HELD and FARM are the shortest, and HELD is
+        // first in the queue
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let mut remaining = entries.clone();
+        remaining.remove(1);
+        let index = most_constrained(&remaining).await.unwrap();
+        assert_eq!(remaining[index].clue().text, "Grasped");
+        assert!(most_constrained::<InMemoryEntry>(&[]).await.is_none());
+    }
+
+    /// Mock answers where 1-Across and 4-Down are always wrong. Neither can be entered without
+    /// clearing a cross, so backtracking keeps returning to the same grids
+    fn wrong_answers(puzzle: &InMemoryPuzzle) -> LLMSolver {
+        solver(
+            oracle(puzzle)
+                .into_iter()
+                .map(|(clue, answer)| match answer.as_str() {
+                    "CASH" => (clue, String::from("CASE")),
+                    "HELD" => (clue, String::from("HOLD")),
+                    _ => (clue, answer),
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn deterministic_trace() {
+        let mut runs = vec![];
+        for run in 0..2 {
+            let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+            let path = std::env::temp_dir().join(format!(
+                "ainyt-trace-{}-{}.jsonl",
+                std::process::id(),
+                run
+            ));
+            let llm = MockLLM::new(oracle(&puzzle).into_iter().collect())
+                .wrong_answer("Money in hand", "CASE");
+            let mut solver = LLMSolver::with_model(Box::new(llm)).trace(&path).unwrap();
+            let mut receiver = solver.subscribe();
+            assert!(solver.solve(&puzzle).await.unwrap().solved);
+            assert!(solver.report().backtracks > 0);
+            let trace = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            runs.push((received(&mut receiver), trace));
+        }
+        // Backtracking takes the same path through the same checkpoints every time
+        assert_eq!(runs[0], runs[1]);
+        let (events, trace) = &runs[0];
+        assert_eq!(trace.lines().count(), events.len());
+        assert!(events.iter().any(|e| matches!(
+            e,
+            SolverEvent::CheckpointRestored {
+                answer: Some(_),
+                ..
+            }
+        )));
+        assert!(trace.lines().any(|l| l.contains("checkpoint-restored")));
+    }
+
+    #[tokio::test]
+    async fn duplicate_states() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        // Rejecting dead ends or suspected answers stops the solver from returning to the same
+        // grids
+        let mut solver = wrong_answers(&puzzle)
+            .reject_dead_ends(false)
+            .recover_suspects(0);
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert!(solver.report().duplicate_states > 0);
+        assert_eq!(solver.report().budget_exceeded, None);
+    }
+
+    /// Every event sent so far
+    fn received(receiver: &mut UnboundedReceiver<SolverEvent>) -> Vec<SolverEvent> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
+
+    #[tokio::test]
+    async fn events() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let clue = puzzle.generate_entries().await.unwrap()[0].clue().clone();
+        let mut solver = solver(oracle(&puzzle));
+        let mut receiver = solver.subscribe();
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        let events = received(&mut receiver);
+        assert_eq!(
+            events[..2],
+            [
+                SolverEvent::ClueAttempted {
+                    clue: clue.clone(),
+                    pattern: String::from("____"),
+                },
+                SolverEvent::AnswerReceived {
+                    clue: clue.clone(),
+                    answer: String::from("CASH"),
+                    cached: false,
+                },
+            ]
+        );
+        assert!(matches!(
+            &events[2],
+            SolverEvent::EntryWritten { clue: written, answer, .. }
+                if *written == clue && answer == "CASH"
+        ));
+        assert_eq!(
+            events.last(),
+            Some(&SolverEvent::VerificationResult { solved: true })
+        );
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, SolverEvent::ConflictDetected { .. })));
+    }
+
+    #[tokio::test]
+    async fn backtracking_events() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let count = Arc::new(Mutex::new(0));
+        let counted = count.clone();
+        let mut solver = wrong_answers(&puzzle)
+            .budget(Budget {
+                grid_attempts: Some(2),
+                ..Budget::default()
+            })
+            .on_event(move |_| *counted.lock().unwrap() += 1);
+        let mut receiver = solver.subscribe();
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(!report.solved);
+        let events = received(&mut receiver);
+        assert_eq!(*count.lock().unwrap(), events.len());
+
+        // The report counts exactly what the events describe
+        let events_where =
+            |matches: fn(&SolverEvent) -> bool| events.iter().filter(|e| matches(e)).count();
+        assert_eq!(
+            report.llm_calls,
+            events_where(|e| matches!(e, SolverEvent::AnswerReceived { cached: false, .. }))
+        );
+        assert_eq!(
+            report.cache_hits,
+            events_where(|e| matches!(e, SolverEvent::AnswerReceived { cached: true, .. }))
+        );
+        assert_eq!(
+            report.clue_latency.len(),
+            events_where(|e| matches!(e, SolverEvent::ClueAttempted { .. }))
+        );
+        assert_eq!(
+            report.conflicts,
+            events_where(|e| matches!(e, SolverEvent::ConflictDetected { .. }))
+        );
+        assert_eq!(
+            report.checkpoints,
+            events_where(|e| matches!(e, SolverEvent::CheckpointCreated { .. }))
+        );
+        assert_eq!(report.backtracks, 1);
+        assert!(report.conflicts > 0);
+
+        // Both passes end with a failed verification, and the second starts from a checkpoint
+        let passes: Vec<_> = events
+            .split(|e| matches!(e, SolverEvent::VerificationResult { solved: false }))
+            .collect();
+        assert_eq!(passes.len(), 3);
+        assert!(matches!(
+            passes[1][0],
+            SolverEvent::CheckpointRestored { .. }
+        ));
+        // Each conflict is followed by a checkpoint with the conflicting answer entered
+        for (index, event) in events.iter().enumerate() {
+            if let SolverEvent::ConflictDetected { clue, answer } = event {
+                assert_eq!(
+                    events[index + 1],
+                    SolverEvent::CheckpointCreated {
+                        clue: clue.clone(),
+                        answer: answer.clone(),
+                    }
+                );
+            }
+        }
+        assert_eq!(
+            passes[2],
+            [SolverEvent::GaveUp(BudgetExceeded::GridAttempts)]
+        );
+    }
+
+    /// Gives the same wrong answers as `wrong_answers` until it is told they are wrong
+    struct Stubborn(HashMap<String, String>);
+
+    #[async_trait]
+    impl LLMModel for Stubborn {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            let answer = MockLLM::new(self.0.clone()).chat(message.clone()).await?;
+            let wrong = match answer.as_str() {
+                "CASH" => "CASE",
+                "HELD" => "HOLD",
+                _ => return Ok(answer),
+            };
+            let told = message
+                .lines()
+                .any(|line| line.contains("NOT any of:") && line.contains(wrong));
+            if told {
+                Ok(answer)
+            } else {
+                Ok(String::from(wrong))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn escape_dead_ends() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let llm = || Box::new(Stubborn(oracle(&puzzle).into_iter().collect()));
+        // Without rejecting answers the solver keeps entering the same wrong ones. Answers are
+        // only rejected at dead ends here, not when the grid fills up wrong
+        let mut solver = LLMSolver::with_model(llm())
+            .reject_dead_ends(false)
+            .recover_suspects(0);
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = LLMSolver::with_model(llm()).recover_suspects(0);
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert!(report.rejected_answers >= 2);
+        let mut rejected: Vec<_> = solver.rejected.iter().map(|r| r.answer.as_str()).collect();
+        rejected.sort();
+        assert_eq!(rejected, ["CASE", "HOLD"]);
+    }
+
+    #[tokio::test]
+    async fn check_incorrect_entries() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes())
+            .unwrap()
+            .check(true);
+        let llm = Box::new(Stubborn(oracle(&puzzle).into_iter().collect()));
+        // Wrong answers are found by checking the grid, even without rejecting dead ends
+        let mut solver = LLMSolver::with_model(llm).reject_dead_ends(false);
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert!(report.incorrect_entries > 0);
+        assert_eq!(report.rejected_answers, 0);
+        assert!(!solver.rejected.is_empty());
+    }
+
+    /// Solve the mini with every answer known and the given hints
+    async fn solve_hinted(
+        puzzle: &InMemoryPuzzle,
+        hints: &[(usize, Direction, &str)],
+    ) -> Result<SolveReport, SolverError> {
+        let hints = hints
+            .iter()
+            .map(|&(number, direction, answer)| (ClueId { number, direction }, answer.to_string()))
+            .collect();
+        solver(oracle(puzzle)).hints(hints).solve(puzzle).await
+    }
+
+    #[tokio::test]
+    async fn hint_errors() {
+        let cases = [
+            (
+                vec![(9, Direction::Across, "CASH")],
+                "hint for 9-Across: there is no such clue",
+            ),
+            (
+                vec![(1, Direction::Across, "CASHES")],
+                "hint for 1-Across: answer has 6 letters but the entry has 4 squares",
+            ),
+            (
+                vec![
+                    (1, Direction::Across, "CASH"),
+                    (1, Direction::Down, "BANOE"),
+                ],
+                "hint for 1-Across: answer disagrees with another hint",
+            ),
+        ];
+        for (hints, message) in cases {
+            let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+            let error = solve_hinted(&puzzle, &hints).await.unwrap_err();
+            assert!(matches!(error, SolverError::Hint(_)));
+            assert_eq!(error.to_string(), message);
+            // Nothing is entered unless every hint fits
+            let rows = puzzle.rows().await;
+            assert!(rows
+                .iter()
+                .all(|row| row.chars().all(|c| c == '_' || c == '#')));
+        }
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let hints = [
+            (1, Direction::Across, "cash"),
+            (1, Direction::Down, "CANOE"),
+        ];
+        assert!(solve_hinted(&puzzle, &hints).await.unwrap().solved);
+    }
+
+    #[tokio::test]
+    async fn trusted_hints() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let llm = Box::new(Stubborn(oracle(&puzzle).into_iter().collect()));
+        // HELD rules out CASE for 1-Across without a conflict, and the wrong HOLD is never asked
+        // for
+        let grasped = ClueId {
+            number: 4,
+            direction: Direction::Down,
+        };
+        let hints = HashMap::from([(grasped, String::from("HELD"))]);
+        let mut solver = LLMSolver::with_model(llm).hints(hints);
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert_eq!((report.conflicts, report.backtracks), (0, 0));
+        let money = ClueId {
+            number: 1,
+            direction: Direction::Across,
+        };
+        assert!(solver
+            .entered
+            .keys()
+            .all(|id| *id != money && *id != grasped));
+
+        // Clearing a crossing answer clears a letter of the hint, which is entered again
+        let entries = puzzle.generate_entries().await.unwrap();
+        let across = entries.iter().find(|e| e.clue().text == "Money in hand");
+        across.unwrap().clear().await.unwrap();
+        assert_eq!(puzzle.rows().await[0], "#____");
+        solver.restore_hints(&entries).await.unwrap();
+        assert_eq!(puzzle.rows().await[0], "#___H");
+    }
+
+    /// Confident in every answer apart from a wrong one, which it takes back once told
+    struct Mistaken {
+        answers: HashMap<String, String>,
+        clue: &'static str,
+        wrong: &'static str,
+    }
+
+    #[async_trait]
+    impl LLMModel for Mistaken {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            let answer = MockLLM::new(self.answers.clone())
+                .chat(message.clone())
+                .await?;
+            let told = message.contains(&format!("NOT any of: {}", self.wrong));
+            if message.contains(&format!("<text>\n{}\n</text>", self.clue)) && !told {
+                Ok(format!("{} 30", self.wrong))
+            } else {
+                Ok(answer)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn recover_full_grid() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        // Only the across answers are known, so a wrong one fills the grid without a conflict
+        let answers: HashMap<String, String> = oracle(&puzzle).into_iter().take(5).collect();
+        let llm = || Mistaken {
+            answers: answers.clone(),
+            clue: "Get together",
+            wrong: "MELT",
+        };
+        let mut solver = LLMSolver::with_model(Box::new(llm())).recover_suspects(0);
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(puzzle.rows().await[4], "MELT#");
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = LLMSolver::with_model(Box::new(llm()));
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert_eq!(report.suspects_cleared, 1);
+        assert_eq!(solver.rejected.len(), 1);
+        assert_eq!(solver.rejected[0].answer, "MELT");
+    }
+
+    #[test]
+    fn suspicion_ranking() {
+        let candidate = |answer: &str, confidence| Candidate {
+            answer: answer.to_string(),
+            confidence,
+        };
+        let words = WordList::parse("meet\nmelt\n").unwrap();
+        let sure = suspicion(&candidate("MEET", Some(90)), 0, 4, Some(&words));
+        // Less confidence, fewer crossing answers and unknown words are all more suspicious
+        assert!(suspicion(&candidate("MEET", Some(40)), 0, 4, Some(&words)) > sure);
+        assert!(suspicion(&candidate("MEET", Some(90)), 2, 4, Some(&words)) > sure);
+        assert!(suspicion(&candidate("MEAT", Some(90)), 0, 4, Some(&words)) > sure);
+        assert!(suspicion(&candidate("MEET", None), 0, 4, None) < sure);
+    }
+
+    #[tokio::test]
+    async fn cross_reference_prompt() {
+        let yaml = MINI.replace("Money in hand", "With 5-Across, a slapstick prop");
+        let puzzle = InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let template = PromptTemplate::default();
+        let context = ClueContext::new(&entries[0], &entries, &crossings).await;
+        let prompt = prompt_for_clue(&template, &context, &[]);
+        assert!(prompt.contains("5-Across is \"Slapstick comedy\" and currently reads FARCE."));
+        let context = ClueContext::new(&entries[1], &entries, &crossings).await;
+        let prompt = prompt_for_clue(&template, &context, &[]);
+        assert!(!prompt.contains("currently reads"));
+
+        // The referenced entry is answered as soon as the one referring to it is entered
+        let answers = oracle(&puzzle);
+        let mut solver = solver(answers).ordering(Ordering::Queue);
+        let mut receiver = solver.subscribe();
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        let written: Vec<String> = received(&mut receiver)
+            .into_iter()
+            .filter_map(|e| match e {
+                SolverEvent::EntryWritten { clue, .. } => Some(clue.text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            written[..2],
+            ["With 5-Across, a slapstick prop", "Slapstick comedy"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_answers() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let grasped = &entries[8];
+        assert_eq!(grasped.clue().text, "Grasped");
+        let mut solver = solver(oracle(&puzzle));
+        solver
+            .rejected
+            .push(Rejection::new(grasped.clue(), "E___", String::from("HOLD")));
+        entries[0].write(String::from("CASE")).await.unwrap();
+        let key = solver.question(grasped).await;
+        assert_eq!(key.excluded, ["HOLD"]);
+        let context = ClueContext::new(grasped, &entries, &crossings).await;
+        let prompt = prompt_for_clue(&solver.template, &context, &key.excluded);
+        assert!(prompt.contains("The answer is NOT any of: HOLD."));
+
+        // A different first letter means HOLD may have been wrong because of CASE
+        entries[0].write(String::from("CASH")).await.unwrap();
+        assert!(solver.question(grasped).await.excluded.is_empty());
+        assert!(solver.rejected.is_empty());
+    }
+
+    #[tokio::test]
+    async fn budget() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = wrong_answers(&puzzle).budget(Budget {
+            grid_attempts: Some(2),
+            ..Budget::default()
+        });
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(
+            solver.report().budget_exceeded,
+            Some(BudgetExceeded::GridAttempts)
+        );
+        assert_eq!(solver.report().backtracks, 1);
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = wrong_answers(&puzzle).budget(Budget {
+            llm_calls: Some(3),
+            ..Budget::default()
+        });
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(
+            solver.report().budget_exceeded,
+            Some(BudgetExceeded::LlmCalls)
+        );
+        assert_eq!(solver.report().llm_calls, 3);
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = wrong_answers(&puzzle).budget(Budget {
+            time: Some(Duration::ZERO),
+            ..Budget::default()
+        });
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(solver.report().budget_exceeded, Some(BudgetExceeded::Time));
+        assert_eq!(solver.report().llm_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn give_up_on_best_grid() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let answers: HashMap<String, String> = oracle(&puzzle).into_iter().collect();
+        for entry in &entries[..3] {
+            entry
+                .write(answers[&entry.clue().text].clone())
+                .await
+                .unwrap();
+        }
+        let mut best = None;
+        remember_best(&entries, &mut best).await;
+        // A later pass that fills fewer cells is not kept
+        entries[2].clear().await.unwrap();
+        remember_best(&entries, &mut best).await;
+        assert_eq!(best.as_ref().unwrap().0, 14);
+
+        let mut solver = solver(vec![]);
+        assert!(!solver
+            .give_up(BudgetExceeded::Time, &entries, best)
+            .await
+            .unwrap());
+        assert!(solver.report().timed_out);
+        assert_eq!(entries[2].value().await, answers[&entries[2].clue().text]);
+    }
+
+    #[tokio::test]
+    async fn unanswered_clues_fail_to_solve() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        // Without 1-Across and 1-Down nothing can fill in their shared first cell
+        let answers = oracle(&puzzle)
+            .into_iter()
+            .filter(|(_, answer)| !["CASH", "CANOE"].contains(&answer.as_str()))
+            .collect();
+        let mut solver = solver(answers);
+        assert!(!solver.solve(&puzzle).await.unwrap().solved);
+        assert!(solver.report().llm_calls >= 8);
+    }
+
+    #[tokio::test]
+    async fn wordlist_fallback() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let answers = oracle(&puzzle)
+            .into_iter()
+            .filter(|(_, answer)| !["CASH", "CANOE"].contains(&answer.as_str()))
+            .collect();
+        // WASH is more common, but only CASH leads to a solution
+        let wordlist = WordList::parse("wash\ncash\ncanoe\n").unwrap();
+        let mut solver = solver(answers).wordlist(wordlist);
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert!(report.wordlist_answers > 0);
+    }
+
+    struct Offline;
+
+    #[async_trait]
+    impl LLMModel for Offline {
+        async fn chat(&self, _message: String) -> Result<String, LlmError> {
+            Err(LlmError::Conversation("offline"))
+        }
+    }
+
+    #[test]
+    fn model_config() {
+        let error = |config: ModelConfig| {
+            LLMSolver::new(APIKey::Cohere(String::from("key")), config)
+                .err()
+                .map(|e| e.to_string())
+        };
+        assert_eq!(error(ModelConfig::default()), None);
+        let config = ModelConfig {
+            model: Some(String::from("command-r")),
+            temperature: Some(0.2),
+            max_tokens: Some(20),
+            ..ModelConfig::default()
+        };
+        assert_eq!(error(config), None);
+        assert_eq!(
+            error(ModelConfig {
+                temperature: Some(5.5),
+                ..ModelConfig::default()
+            })
+            .as_deref(),
+            Some("Cohere temperature must be between 0 and 5, not 5.5")
+        );
+        assert_eq!(
+            error(ModelConfig {
+                model: Some(String::new()),
+                ..ModelConfig::default()
+            })
+            .as_deref(),
+            Some("Cohere model name is empty")
+        );
+        assert_eq!(
+            error(ModelConfig {
+                max_tokens: Some(0),
+                ..ModelConfig::default()
+            })
+            .as_deref(),
+            Some("Cohere max tokens must be at least 1")
+        );
+        let openai = LLMSolver::new(
+            APIKey::OpenAI(String::from("key")),
+            ModelConfig {
+                temperature: Some(2.5),
+                ..ModelConfig::default()
+            },
+        );
+        assert_eq!(
+            openai.err().map(|e| e.to_string()).as_deref(),
+            Some("OpenAI temperature must be between 0 and 2, not 2.5")
+        );
+
+        // Members of an Ensemble only get the options that apply to them
+        let ollama = || APIKey::Ollama {
+            base_url: String::from("http://localhost:11434"),
+            model: String::from("llama3"),
+        };
+        let openai = || APIKey::OpenAI(String::from("key"));
+        let anthropic = || APIKey::Anthropic(String::from("key"));
+        let ensemble = |keys, config| {
+            LLMSolver::with_keys(keys, config)
+                .err()
+                .map(|e| e.to_string())
+        };
+        let model = ModelConfig {
+            model: Some(String::from("gpt-4o")),
+            ..ModelConfig::default()
+        };
+        assert_eq!(ensemble(vec![openai(), ollama()], model.clone()), None);
+        assert_eq!(
+            ensemble(vec![openai(), anthropic()], model).as_deref(),
+            Some("a model can only be chosen when a single backend besides Ollama is selected")
+        );
+        let hot = ModelConfig {
+            temperature: Some(1.5),
+            ..ModelConfig::default()
+        };
+        assert_eq!(ensemble(vec![openai(), anthropic()], hot.clone()), None);
+        assert!(LLMSolver::new(anthropic(), hot).is_err());
+    }
+
+    #[test]
+    fn cohere_request() {
+        let cohere = Cohere::new(
+            String::from("key"),
+            ModelConfig {
+                model: Some(String::from("command-r")),
+                temperature: Some(0.5),
+                ..ModelConfig::default()
+            },
+        )
+        .unwrap();
+        let request = serde_json::to_value(cohere.request(String::from("Clue"), vec![])).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({"message": "Clue", "model": "command-r", "temperature": 0.5})
+        );
+        // Nothing is sent for the defaults, leaving them up to Cohere
+        let cohere = Cohere::new(String::from("key"), ModelConfig::default()).unwrap();
+        let request = serde_json::to_value(cohere.request(String::from("Clue"), vec![])).unwrap();
+        assert_eq!(request, serde_json::json!({"message": "Clue"}));
+    }
+
+    #[test]
+    fn anthropic_request() {
+        let anthropic = Anthropic::new(
+            String::from("key"),
+            ModelConfig {
+                temperature: Some(0.5),
+                ..ModelConfig::default()
+            },
+        )
+        .unwrap();
+        let request = anthropic.request(vec![
+            Message::User(String::from(
+                "Determine the answer to the following crossword clue.",
+            )),
+            Message::Assistant(String::from("CASHES 90")),
+            Message::User(String::from(
+                "That has 6 letters; the answer must be exactly 4 letters long.",
+            )),
+        ]);
+        let expected: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/anthropic/request.json")).unwrap();
+        assert_eq!(serde_json::to_value(request).unwrap(), expected);
+    }
+
+    #[test]
+    fn anthropic_response() {
+        let response: AnthropicResponse =
+            serde_json::from_str(include_str!("../tests/fixtures/anthropic/response.json"))
+                .unwrap();
+        assert_eq!(response.text(), "CASH 95, BILLS 30");
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{"content": [{"type": "tool_use", "id": "1"}, {"type": "text", "text": "CASH"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.text(), "CASH");
+    }
+
+    #[test]
+    fn gemini_request() {
+        let gemini = Gemini::new(
+            String::from("key"),
+            ModelConfig {
+                temperature: Some(0.5),
+                max_tokens: Some(50),
+                ..ModelConfig::default()
+            },
+        )
+        .unwrap();
+        let request = gemini.request(vec![
+            Message::User(String::from("Clue")),
+            Message::Assistant(String::from("CASHES")),
+            Message::User(String::from("Shorter")),
+        ]);
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({
+                "contents": [
+                    {"role": "user", "parts": [{"text": "Clue"}]},
+                    {"role": "model", "parts": [{"text": "CASHES"}]},
+                    {"role": "user", "parts": [{"text": "Shorter"}]},
+                ],
+                "generationConfig": {"temperature": 0.5, "maxOutputTokens": 50},
+            })
+        );
+    }
+
+    #[test]
+    fn gemini_responses() {
+        let text = |json: &str| serde_json::from_str::<GeminiResponse>(json).unwrap().text();
+        assert_eq!(
+            text(include_str!("../tests/fixtures/gemini/response.json")).unwrap(),
+            "CASH 95, BILLS 20\n"
+        );
+        let blocked = text(include_str!("../tests/fixtures/gemini/blocked.json"));
+        assert!(matches!(blocked, Err(LlmError::Blocked(reason)) if reason == "SAFETY"));
+        let blocked = text(include_str!(
+            "../tests/fixtures/gemini/candidate_blocked.json"
+        ));
+        assert!(matches!(blocked, Err(LlmError::Blocked(reason)) if reason == "SAFETY"));
+        assert!(matches!(text("{}"), Err(LlmError::Blocked(_))));
+    }
+
+    #[test]
+    fn ollama_host() {
+        assert_eq!(ollama_url(None), "http://localhost:11434");
+        assert_eq!(ollama_url(Some(String::new())), "http://localhost:11434");
+        assert_eq!(
+            ollama_url(Some(String::from("0.0.0.0:8080"))),
+            "http://0.0.0.0:8080"
+        );
+        assert_eq!(
+            ollama_url(Some(String::from("https://gpu.local/"))),
+            "https://gpu.local"
+        );
+    }
+
+    /// Ollama server that answers every clue of mini1 with several candidates
+    async fn ollama_server(puzzle: &InMemoryPuzzle) -> MockServer {
+        let answers: HashMap<String, String> = oracle(puzzle).into_iter().collect();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "llama3", "stream": false}),
+            ))
+            .respond_with(move |request: &Request| {
+                let body: serde_json::Value = request.body_json().unwrap();
+                let question = body["messages"].as_array().unwrap().last().unwrap()["content"]
+                    .as_str()
+                    .unwrap()
+                    .to_string();
+                let clue = question
+                    .split("<text>")
+                    .nth(1)
+                    .and_then(|s| s.split("</text>").next())
+                    .unwrap_or_default()
+                    .trim();
+                let answer = answers.get(clue).cloned().unwrap_or_default();
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "model": "llama3",
+                    "message": {"role": "assistant", "content": format!("{} 90, XYZZY 10", answer)},
+                    "done": true,
+                }))
+            })
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn ollama() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let server = ollama_server(&puzzle).await;
+        let key = APIKey::Ollama {
+            base_url: server.uri(),
+            model: String::from("llama3"),
+        };
+        let mut solver = LLMSolver::new(key, ModelConfig::default()).unwrap();
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert!(body.get("options").is_none());
+    }
+
+    #[tokio::test]
+    async fn ollama_not_running() {
+        let ollama = Ollama::new(
+            String::from("http://127.0.0.1:9"),
+            String::from("llama3"),
+            ModelConfig::default(),
+        )
+        .unwrap();
+        let error = ollama.chat(String::from("Clue")).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unable to connect to Ollama at http://127.0.0.1:9, is Ollama running?"
+        );
+    }
+
+    #[tokio::test]
+    async fn cohere_rate_limited() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "CASH 90",
+            })))
+            .mount(&server)
+            .await;
+        let mut cohere = Cohere::new(String::from("key"), ModelConfig::default()).unwrap();
+        cohere.url = server.uri();
+        let llm =
+            Retrying::new(cohere).delays(Duration::from_millis(10), Duration::from_millis(100));
+        assert_eq!(llm.chat(String::from("Clue")).await.unwrap(), "CASH 90");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        // A Retry-After longer than the longest delay is cut short
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "CASH 90",
+            })))
+            .mount(&server)
+            .await;
+        let mut cohere = Cohere::new(String::from("key"), ModelConfig::default()).unwrap();
+        cohere.url = server.uri();
+        let llm =
+            Retrying::new(cohere).delays(Duration::from_millis(10), Duration::from_millis(100));
+        let start = tokio::time::Instant::now();
+        assert_eq!(llm.chat(String::from("Clue")).await.unwrap(), "CASH 90");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn cohere_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"text": "CASH 90"}))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+        let config = ModelConfig {
+            timeout: Duration::from_millis(50),
+            ..ModelConfig::default()
+        };
+        let mut cohere = Cohere::new(String::from("key"), config).unwrap();
+        cohere.url = server.uri();
+        // A hung request is given up on and tried again
+        let llm = Retrying::new(cohere)
+            .attempts(2)
+            .delays(Duration::from_millis(10), Duration::from_millis(10));
+        assert!(matches!(
+            llm.chat(String::from("Clue")).await,
+            Err(LlmError::Timeout)
+        ));
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn cohere_invalid_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "message": "invalid api token",
+            })))
+            .mount(&server)
+            .await;
+        let mut cohere = Cohere::new(String::from("key"), ModelConfig::default()).unwrap();
+        cohere.url = server.uri();
+        let llm = Retrying::new(cohere);
+        let error = llm.chat(String::from("Clue")).await.unwrap_err();
+        assert_eq!(error.to_string(), "invalid Cohere key");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn restore_checkpoint() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[0].write(String::from("CASH")).await.unwrap();
+        let checkpoint = Checkpoint {
+            state: GridSnapshot::capture(&entries).await,
+            rejection: Some(Rejection::new(
+                entries[1].clue(),
+                "_____",
+                String::from("FORCE"),
+            )),
+        };
+        // Fill every cell, including the ones the checkpoint has empty
+        for entry in &entries {
+            entry
+                .write("Z".repeat(entry.expected_length()))
+                .await
+                .unwrap();
+        }
+        restore(&entries, &checkpoint).await.unwrap();
+        assert_eq!(entries[0].value().await, "CASH");
+        for entry in &entries[1..] {
+            assert!(!entry.value().await.contains('Z'));
+        }
+        assert_eq!(state_hash(&entries).await, checkpoint.fingerprint().await);
+
+        // Every changed cell has to be in one of the entries restored
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let wider = Checkpoint::capture(&entries).await;
+        entries[1].clear().await.unwrap();
+        assert!(matches!(
+            restore(&entries[..1], &wider).await,
+            Err(SolverError::InconsistentState(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn restore_changed_cells() {
+        let puzzle = counted(InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap());
+        let entries = puzzle.generate_entries().await.unwrap();
+        entries[0].write(String::from("CASH")).await.unwrap();
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let checkpoint = Checkpoint::capture(&entries).await;
+        // One answer was replaced and another entered since
+        entries[1].write(String::from("FORCE")).await.unwrap();
+        entries[2].write(String::from("ANGEL")).await.unwrap();
+        let diff = checkpoint
+            .diff(&GridSnapshot::capture(&entries).await)
+            .await;
+        assert_eq!(diff.len(), 6);
+        assert_eq!(
+            diff[0],
+            (Position { row: 1, column: 1 }, Some(String::from("A")))
+        );
+        assert!(diff[1..].iter().all(|(p, v)| p.row == 2 && v.is_none()));
+
+        *puzzle.writes.lock().unwrap() = 0;
+        restore(&entries, &checkpoint).await.unwrap();
+        // Each changed cell is written once, where rewriting every entry takes ten writes
+        assert_eq!(*puzzle.writes.lock().unwrap(), 6);
+        assert_eq!(
+            puzzle.puzzle.rows().await,
+            vec!["#CASH", "FARCE", "_____", "_____", "____#"]
+        );
+        assert_eq!(state_hash(&entries).await, checkpoint.fingerprint().await);
+
+        // A grid that already matches is not written to
+        restore(&entries, &checkpoint).await.unwrap();
+        assert_eq!(*puzzle.writes.lock().unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_fingerprint() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        entries[0].write(String::from("CASH")).await.unwrap();
+        let empty = Checkpoint::capture(&entries).await;
+        assert_eq!(empty.entries().count(), entries.len());
+        assert_eq!(empty.filled_count().await, 4);
+        assert_eq!(empty.answer(), None);
+        // Captured entries share a grid of their own
+        let first = empty.entries().next().unwrap();
+        assert!(empty.entries().all(|e| e.shares_state(first)));
+        assert!(!first.shares_state(&entries[0]));
+        assert_eq!(first.filled_cells().await, 4);
+
+        let proposed = checkpoint(&entries[1], String::from("FARCE"), &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(proposed.answer(), Some("FARCE"));
+        assert_eq!(proposed.filled_count().await, 9);
+        assert_ne!(proposed.fingerprint().await, empty.fingerprint().await);
+        // The same grid has the same fingerprint however it was filled
+        entries[1].write(String::from("FARCE")).await.unwrap();
+        let entered = Checkpoint::capture(&entries).await;
+        assert_eq!(entered.fingerprint().await, proposed.fingerprint().await);
+        assert_eq!(entered.fingerprint().await, state_hash(&entries).await);
+        // and keeps it as the puzzle changes
+        let fingerprint = entered.fingerprint().await;
+        entries[2].write(String::from("ANGEL")).await.unwrap();
+        assert_eq!(entered.fingerprint().await, fingerprint);
+        assert_ne!(state_hash(&entries).await, fingerprint);
+
+        let error = GridSolveError::FailedToSolve(vec![proposed, entered]);
+        assert_eq!(
+            error.to_string(),
+            "grid was not solved, 2 checkpoints to backtrack to"
+        );
+    }
+
+    /// Entry of a puzzle that is slow to write to, counting every write
+    #[derive(Clone)]
+    struct Counted {
+        entry: InMemoryEntry,
+        writes: Arc<Mutex<usize>>,
+    }
+
+    #[async_trait]
+    impl Entry for Counted {
+        fn clue(&self) -> &Clue {
+            self.entry.clue()
+        }
+
+        fn positions(&self) -> Vec<Position> {
+            self.entry.positions()
+        }
+
+        fn circled(&self) -> Vec<Position> {
+            self.entry.circled()
+        }
+
+        async fn cells(&self) -> Vec<Option<String>> {
+            self.entry.cells().await
+        }
+
+        async fn write_cells(&self, cells: Vec<Option<String>>) -> WebDriverResult<()> {
+            *self.writes.lock().unwrap() += 1;
+            self.entry.write_cells(cells).await
+        }
+    }
+
+    /// An InMemoryPuzzle counting every write to its entries and every verification
+    struct CountedPuzzle {
+        puzzle: InMemoryPuzzle,
+        writes: Arc<Mutex<usize>>,
+        verifications: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl CrosswordPuzzle for CountedPuzzle {
+        type Entry = Counted;
+
+        async fn generate_entries(&self) -> Result<Vec<Counted>, PuzzleError> {
+            Ok(self
+                .puzzle
+                .generate_entries()
+                .await?
+                .into_iter()
+                .map(|entry| Counted {
+                    entry,
+                    writes: self.writes.clone(),
+                })
+                .collect())
+        }
+
+        async fn verify_entries(&self, entries: &[Counted]) -> WebDriverResult<Feedback> {
+            *self.verifications.lock().unwrap() += 1;
+            let entries: Vec<InMemoryEntry> = entries.iter().map(|e| e.entry.clone()).collect();
+            self.puzzle.verify_entries(&entries).await
+        }
+    }
+
+    fn counted(puzzle: InMemoryPuzzle) -> CountedPuzzle {
+        CountedPuzzle {
+            puzzle,
+            writes: Arc::new(Mutex::new(0)),
+            verifications: Mutex::new(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn write_out_solved_grid() {
+        let puzzle = counted(InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap());
+        let mut in_memory = solver(oracle(&puzzle.puzzle)).in_memory(true);
+        let entries = puzzle.generate_entries().await.unwrap();
+        assert!(
+            in_memory
+                .solve_entries(&puzzle, entries)
+                .await
+                .unwrap()
+                .solved
+        );
+        // Every entry is written once, when the grid is full
+        assert_eq!(*puzzle.writes.lock().unwrap(), 10);
+        assert_eq!(*puzzle.verifications.lock().unwrap(), 1);
+        assert_eq!(
+            puzzle.puzzle.rows().await,
+            vec!["#CASH", "FARCE", "ANGEL", "ROUND", "MEET#"]
+        );
+
+        // Solving the puzzle itself writes each answer as it is found
+        let direct = counted(InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap());
+        let mut writing = solver(oracle(&direct.puzzle));
+        assert!(writing.solve(&direct).await.unwrap().solved);
+        assert_eq!(*direct.verifications.lock().unwrap(), 1);
+    }
+
+    /// An InMemoryPuzzle with a timer that only runs once started. Verifying the grid takes a
+    /// minute
+    struct TimedPuzzle {
+        puzzle: InMemoryPuzzle,
+        started: Mutex<Option<tokio::time::Instant>>,
+    }
+
+    #[async_trait]
+    impl CrosswordPuzzle for TimedPuzzle {
+        type Entry = InMemoryEntry;
+
+        async fn generate_entries(&self) -> Result<Vec<InMemoryEntry>, PuzzleError> {
+            self.puzzle.generate_entries().await
+        }
+
+        async fn verify_entries(&self, entries: &[InMemoryEntry]) -> WebDriverResult<Feedback> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            self.puzzle.verify_entries(entries).await
+        }
+
+        async fn start_timer(&self) -> WebDriverResult<()> {
+            self.started
+                .lock()
+                .unwrap()
+                .get_or_insert_with(tokio::time::Instant::now);
+            Ok(())
+        }
+
+        async fn elapsed_time(&self) -> WebDriverResult<Option<Duration>> {
+            Ok(self.started.lock().unwrap().map(|s| s.elapsed()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn page_time() {
+        for in_memory in [false, true] {
+            let puzzle = TimedPuzzle {
+                puzzle: InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap(),
+                started: Mutex::new(None),
+            };
+            let mut solver = solver(oracle(&puzzle.puzzle)).in_memory(in_memory);
+            let report = solver.solve(&puzzle).await.unwrap();
+            assert!(report.solved);
+            let time = report.page_time.expect("Timer was not started");
+            assert_eq!(time.started, Duration::ZERO);
+            assert_eq!(time.solve_time(), Duration::from_secs(60));
+        }
+        // Puzzles without a timer leave it out
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let report = solver(oracle(&puzzle)).solve(&puzzle).await.unwrap();
+        assert_eq!(report.page_time, None);
+    }
+
+    #[tokio::test]
+    async fn write_out_with_feedback() {
+        let puzzle = counted(
+            InMemoryPuzzle::from_yaml(MINI.as_bytes())
+                .unwrap()
+                .check(true),
+        );
+        // Only the across answers are known, so the wrong one fills the grid
+        let llm = Mistaken {
+            answers: oracle(&puzzle.puzzle).into_iter().take(5).collect(),
+            clue: "Get together",
+            wrong: "MELT",
+        };
+        let mut solver = LLMSolver::with_model(Box::new(llm))
+            .recover_suspects(0)
+            .in_memory(true);
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        // The entries the puzzle marked incorrect were cleared in memory and solved again
+        assert_eq!(report.incorrect_entries, 2);
+        assert_eq!(*puzzle.verifications.lock().unwrap(), 2);
+        assert_eq!(*puzzle.writes.lock().unwrap(), 20);
+    }
+
+    /// Returns each scripted result in turn, counting how many messages were sent
+    struct Scripted {
+        results: Mutex<VecDeque<Result<String, LlmError>>>,
+        calls: Arc<Mutex<usize>>,
+    }
+
+    impl Scripted {
+        fn new(results: Vec<Result<String, LlmError>>) -> (Self, Arc<Mutex<usize>>) {
+            let calls = Arc::new(Mutex::new(0));
+            let scripted = Self {
+                results: Mutex::new(results.into()),
+                calls: calls.clone(),
+            };
+            (scripted, calls)
+        }
+    }
+
+    #[async_trait]
+    impl LLMModel for Scripted {
+        async fn chat(&self, _message: String) -> Result<String, LlmError> {
+            *self.calls.lock().unwrap() += 1;
+            self.results
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("Sent more messages than were scripted")
+        }
+    }
+
+    fn rate_limited() -> Result<String, LlmError> {
+        Err(LlmError::RateLimited { retry_after: None })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_rate_limits() {
+        let (scripted, calls) = Scripted::new(vec![
+            rate_limited(),
+            rate_limited(),
+            Ok(String::from("CASH")),
+        ]);
+        let llm = Retrying::new(scripted).delays(Duration::from_secs(1), Duration::from_secs(10));
+        let started = tokio::time::Instant::now();
+        assert_eq!(llm.chat(String::from("Clue")).await.unwrap(), "CASH");
+        assert_eq!(*calls.lock().unwrap(), 3);
+        // Waited up to 1 and then 2 seconds, of which at most half is skipped
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(1500), "{:?}", waited);
+        assert!(waited <= Duration::from_secs(3), "{:?}", waited);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_after() {
+        let (scripted, calls) = Scripted::new(vec![
+            Err(LlmError::RateLimited {
+                retry_after: Some(Duration::from_secs(20)),
+            }),
+            Ok(String::from("CASH")),
+        ]);
+        let llm = Retrying::new(scripted);
+        let started = tokio::time::Instant::now();
+        assert_eq!(llm.chat(String::from("Clue")).await.unwrap(), "CASH");
+        assert_eq!(*calls.lock().unwrap(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_give_up() {
+        let (scripted, calls) = Scripted::new((0..3).map(|_| rate_limited()).collect());
+        let llm = Retrying::new(scripted).attempts(3);
+        assert!(matches!(
+            llm.chat(String::from("Clue")).await,
+            Err(LlmError::RateLimited { .. })
+        ));
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        // Errors that will happen again are returned straight away
+        let (scripted, calls) = Scripted::new(vec![Err(LlmError::Auth(String::from("401")))]);
+        let llm = Retrying::new(scripted);
+        assert!(matches!(
+            llm.chat(String::from("Clue")).await,
+            Err(LlmError::Auth(_))
+        ));
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn backoff_schedule() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(30);
+        let delays: Vec<u64> = (0..7)
+            .map(|retry| backoff(retry, base, max, 0.0).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff(2, base, max, 1.0), Duration::from_secs(2));
+        assert_eq!(backoff(100, base, max, 0.0), max);
+        for _ in 0..100 {
+            assert!((0.0..=1.0).contains(&jitter()));
+        }
+    }
+
+    #[tokio::test]
+    async fn llm_errors_stop_the_solve() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = LLMSolver::with_model(Box::new(Offline));
+        assert!(matches!(
+            solver.solve(&puzzle).await,
+            Err(SolverError::Llm(LlmError::Conversation("offline")))
+        ));
+        assert_eq!(solver.report().llm_calls, 1);
+    }
+
+    /// Mock answers that each take a fixed time to arrive
+    struct Slow(MockLLM);
+
+    static LATENCY: Duration = Duration::from_millis(100);
+
+    #[async_trait]
+    impl LLMModel for Slow {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            tokio::time::sleep(LATENCY).await;
+            self.0.chat(message).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_requests() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        // None of the across entries share a cell, so they can all be asked about at once
+        let across: Vec<_> = entries
+            .iter()
+            .filter(|e| e.clue().direction == Direction::Across)
+            .cloned()
+            .collect();
+        assert_eq!(across.len(), 5);
+        let crossings = CrossingMap::new(&entries);
+        for (concurrency, round_trips) in [(1, 5), (4, 2)] {
+            let llm = Slow(MockLLM::new(oracle(&puzzle).into_iter().collect()));
+            let mut solver = LLMSolver::with_model(Box::new(llm)).concurrency(concurrency);
+            let start = tokio::time::Instant::now();
+            solver
+                .prefetch(&entries, &entries, &crossings)
+                .await
+                .unwrap();
+            for entry in across.iter() {
+                assert_eq!(
+                    solver
+                        .candidates(entry, &entries, &crossings)
+                        .await
+                        .unwrap()
+                        .len(),
+                    1
+                );
+            }
+            let elapsed = start.elapsed();
+            assert!(
+                elapsed >= LATENCY * round_trips && elapsed < LATENCY * (round_trips + 1),
+                "{:?} with concurrency {}",
+                elapsed,
+                concurrency
+            );
+            // Prefetched answers are not counted as cache hits
+            assert_eq!(solver.report().llm_calls, 5);
+            assert_eq!(solver.report().cache_hits, 0);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deadline() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let llm = Slow(MockLLM::new(oracle(&puzzle).into_iter().collect()));
+        let mut solver = LLMSolver::with_model(Box::new(llm));
+        let deadline = LATENCY * 5 / 2;
+        let start = tokio::time::Instant::now();
+        let report = solver.solve_with_deadline(&puzzle, deadline).await.unwrap();
+        // The third question is abandoned when the deadline passes, without waiting for it
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed >= deadline && elapsed < LATENCY * 3,
+            "{:?}",
+            elapsed
+        );
+        assert_eq!(report.llm_calls, 2);
+        let mut filled = 0;
+        for entry in puzzle.generate_entries().await.unwrap() {
+            filled += usize::from(entry.filled().await);
+        }
+        assert_eq!(filled, 2);
+        // Its conversation is kept to ask again
+        assert_eq!(solver.sessions.len(), 3);
+        assert!(!report.solved);
+        assert!(report.timed_out);
+        assert_eq!(report.budget_exceeded, Some(BudgetExceeded::Time));
+        // The deadline only applies to the one solve
+        assert_eq!(solver.budget, Budget::default());
+    }
+
+    #[test]
+    fn reconcile_answers() {
+        let answers = |responses: &[&str]| -> Vec<String> {
+            reconcile(responses)
+                .into_iter()
+                .map(|c| c.to_string())
+                .collect()
+        };
+        // Backends agreeing on their best answer are certain, whatever they said
+        assert_eq!(
+            answers(&["CASH 90, CASE 40", "cash 70"]),
+            ["CASH 100", "CASE 20"]
+        );
+        // Otherwise the answer the backends were more confident in comes first
+        assert_eq!(answers(&["CASH 60", "CASE 80"]), ["CASE 40", "CASH 30"]);
+        // An answer every backend gave is not certain unless every backend ranked it first
+        assert_eq!(
+            answers(&["CASH 60, CASE 50", "CASE 30, COIN 90", "CASE, CASH 40"]),
+            ["CASE 60", "CASH 33", "COIN 30"]
+        );
+        assert_eq!(
+            answers(&["CASH 90, CASE 80", "CASE 70, CASH 60"]),
+            ["CASH 75", "CASE 75"]
+        );
+        // A single backend is trusted as much as it trusts itself
+        assert_eq!(answers(&["CASH 60, CASE"]), ["CASE 100", "CASH 60"]);
+        assert!(answers(&[]).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ensemble() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let answers: HashMap<String, String> = oracle(&puzzle).into_iter().collect();
+        let members: Vec<(String, Box<dyn LLMModel>)> = vec![
+            (
+                String::from("right"),
+                Box::new(Slow(MockLLM::new(answers.clone()))),
+            ),
+            (
+                String::from("wrong"),
+                Box::new(Slow(
+                    MockLLM::new(answers).wrong_answer("Money in hand", "CASE"),
+                )),
+            ),
+        ];
+        let ensemble = Ensemble::new(members);
+        // Earlier questions about a clue are remembered, as they are by every real backend
+        assert_eq!(ensemble.start_session().limit, Some(SESSION_MESSAGES));
+
+        // Both backends are asked at the same time
+        let start = tokio::time::Instant::now();
+        let response = ensemble
+            .chat(String::from("<text>\nMoney in hand\n</text>"))
+            .await
+            .unwrap();
+        assert_eq!(start.elapsed(), LATENCY);
+        assert_eq!(response, "CASH 50, CASE 50");
+        let backends = ensemble.backends();
+        assert_eq!((backends[0].calls, backends[1].calls), (1, 1));
+        assert_eq!((backends[0].chosen, backends[1].chosen), (1, 0));
+        assert_eq!(backends[0].latency, LATENCY);
+
+        let mut solver = LLMSolver::with_model(Box::new(ensemble));
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        // Only the work done by this solve is reported
+        let right = &report.backends[0];
+        assert_eq!(right.name, "right");
+        assert_eq!(right.calls, report.llm_calls);
+        assert_eq!(right.errors, 0);
+        assert_eq!(right.agreed, right.calls - 1);
+        assert_eq!(report.backends[1].chosen, right.calls - 1);
+        assert_eq!(solver.llm.backends()[0].calls, report.llm_calls + 1);
+
+        // Failing backends are counted, and only stop the solve if every one of them fails
+        let failing = |name: &str, clue: &str| -> (String, Box<dyn LLMModel>) {
+            (
+                String::from(name),
+                Box::new(MockLLM::default().failing(clue)),
+            )
+        };
+        let ensemble = Ensemble::new(vec![
+            failing("first", "Money in hand"),
+            failing("second", "Circular"),
+        ]);
+        let message = |clue| format!("<text>\n{}\n</text>", clue);
+        assert_eq!(ensemble.chat(message("Money in hand")).await.unwrap(), "");
+        assert!(matches!(
+            Ensemble::new(vec![failing("only", "Money in hand")])
+                .chat(message("Money in hand"))
+                .await,
+            Err(LlmError::Unreachable(_))
+        ));
+        assert_eq!(ensemble.backends()[0].errors, 1);
+        assert_eq!(ensemble.backends()[1].errors, 0);
+    }
+
+    #[tokio::test]
+    async fn solve_with_mock_answers() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let llm = MockLLM::from_yaml(MINI_ANSWERS.as_bytes()).unwrap();
+        assert!(
+            LLMSolver::with_model(Box::new(llm))
+                .solve(&puzzle)
+                .await
+                .unwrap()
+                .solved
+        );
+    }
+
+    const FENCED: &str = include_str!("../tests/fixtures/batch/fenced.txt");
+    const PROSE: &str = include_str!("../tests/fixtures/batch/prose.txt");
+    const NESTED: &str = include_str!("../tests/fixtures/batch/nested.txt");
+    const REFUSAL: &str = include_str!("../tests/fixtures/batch/refusal.txt");
+
+    #[test]
+    fn batch_responses() {
+        for response in [FENCED, NESTED] {
+            let answers = parse_batch(response).unwrap();
+            assert_eq!(answers.len(), 10);
+            assert_eq!(answers[&(1, Direction::Across)], "CASH");
+            assert_eq!(answers[&(5, Direction::Down)], "FARM");
+        }
+
+        // Keys in any format, a list of answers and an answer that is not a string
+        let answers = parse_batch(PROSE).unwrap();
+        assert_eq!(answers.len(), 9);
+        assert_eq!(answers[&(1, Direction::Across)], "cash");
+        assert_eq!(answers[&(6, Direction::Across)], "angel");
+        assert_eq!(answers[&(8, Direction::Across)], "MEET");
+        assert_eq!(answers[&(2, Direction::Down)], "ARGUE");
+        assert!(!answers.contains_key(&(5, Direction::Down)));
+        assert_eq!(
+            normalize_answer(&answers[&(7, Direction::Across)], 5).as_deref(),
+            Some("ROUND")
+        );
+
+        assert_eq!(parse_batch(REFUSAL), None);
+        assert_eq!(parse_batch("{\"1-Across\": \"CASH\""), None);
+    }
+
+    /// Answers a batched prompt with a fixed response, and every other prompt from the solution
+    struct Batched {
+        response: &'static str,
+        answers: MockLLM,
+    }
+
+    #[async_trait]
+    impl LLMModel for Batched {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            if message.contains("JSON object") {
+                Ok(String::from(self.response))
+            } else {
+                self.answers.chat(message).await
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_first_pass() {
+        for (response, written) in [(FENCED, 10), (PROSE, 9), (NESTED, 10), (REFUSAL, 0)] {
+            let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+            let llm = Batched {
+                response,
+                answers: MockLLM::new(oracle(&puzzle).into_iter().collect()),
+            };
+            let report = LLMSolver::with_model(Box::new(llm))
+                .batch(BatchStrategy::FirstPass)
+                .solve(&puzzle)
+                .await
+                .unwrap();
+            assert!(report.solved);
+            assert_eq!(report.batch_answers, written);
+            // The down entry missing from PROSE is filled in by the across answers
+            if written > 0 {
+                assert_eq!(report.llm_calls, 1);
+            } else {
+                assert!(report.llm_calls > 1);
+            }
+        }
+    }
+
+    #[test]
+    fn candidates() {
+        assert_eq!(
+            parse_candidates("\nCash, money ,, coin,bills\nAll of these are money"),
+            vec!["Cash", " money ", " coin"]
+        );
+        assert_eq!(
+            parse_candidates(r#"["ice_cream", "gelato"]"#),
+            vec!["ice_cream", "gelato"]
+        );
+        assert!(parse_candidates("").is_empty());
+    }
+
+    #[test]
+    fn normalize() {
+        let cases = [
+            ("CASH", 4, Some("CASH")),
+            ("cash", 4, Some("CASH")),
+            ("TEA SET", 6, Some("TEASET")),
+            ("IN ON", 4, Some("INON")),
+            ("T-BONE", 5, Some("TBONE")),
+            ("U.S.A.", 3, Some("USA")),
+            ("\u{201c}ANGEL\u{201d}", 5, Some("ANGEL")),
+            ("Don\u{2019}t", 4, Some("DONT")),
+            ("ice_cream", 8, Some("ICECREAM")),
+            ("  \n  HELD  ", 4, Some("HELD")),
+            (
+                "CANOE\nA canoe is a boat that is paddled.",
+                5,
+                Some("CANOE"),
+            ),
+            ("Answer: FARCE", 5, Some("FARCE")),
+            ("CASH (money in hand)", 4, Some("CASH")),
+            ("**SCENT**", 5, Some("SCENT")),
+            ("t[he]n", 3, Some("T[HE]N")),
+            ("CASH", 5, None),
+            ("The answer is CASH", 4, None),
+            ("CAF\u{c9}", 4, None),
+            ("4EVER", 5, None),
+            ("", 0, None),
+        ];
+        for (raw, length, expected) in cases {
+            assert_eq!(
+                normalize_answer(raw, length).as_deref(),
+                expected,
+                "{:?}",
+                raw
+            );
+        }
+    }
+
+    /// Always answers with a plural, unless the follow up message allows `corrected`
+    struct Plural {
+        corrected: bool,
+    }
+
+    #[async_trait]
+    impl LLMModel for Plural {
+        async fn chat(&self, _message: String) -> Result<String, LlmError> {
+            Ok(String::from("CASHES"))
+        }
+
+        async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+            let Some(Message::User(follow_up)) = messages.last() else {
+                panic!("Conversation does not end with a follow up");
+            };
+            assert!(follow_up.starts_with(
+                "That has 6 letters; the answer must be exactly 4 letters long and match the pattern ____."
+            ));
+            // The follow up is sent along with the question and the rejected answer
+            assert_eq!(messages.len(), 3);
+            assert!(
+                matches!(&messages[0], Message::User(prompt) if prompt.contains("Money in hand"))
+            );
+            assert_eq!(messages[1], Message::Assistant(String::from("CASHES")));
+            Ok(String::from(if self.corrected { "CASH" } else { "CASHES" }))
+        }
+    }
+
+    /// Answers from a MockLLM, recording every conversation longer than a single message
+    struct Remembers {
+        llm: MockLLM,
+        conversations: Arc<Mutex<Vec<Vec<Message>>>>,
+    }
+
+    #[async_trait]
+    impl LLMModel for Remembers {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            self.llm.chat(message).await
+        }
+
+        fn start_session(&self) -> Session {
+            Session::new(SESSION_MESSAGES)
+        }
+
+        async fn converse(&self, messages: Vec<Message>) -> Result<String, LlmError> {
+            self.conversations.lock().unwrap().push(messages.clone());
+            let Some(Message::User(last)) = messages.last() else {
+                panic!("Conversation does not end with a question");
+            };
+            self.llm.chat(last.clone()).await
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let conversations = Arc::new(Mutex::new(vec![]));
+        let mut solver = LLMSolver::with_model(Box::new(Remembers {
+            llm: MockLLM::new(oracle(&puzzle).into_iter().collect()),
+            conversations: conversations.clone(),
+        }));
+        solver
+            .solve_clue(&entries[5], &entries, &crossings)
+            .await
+            .unwrap();
+        assert!(conversations.lock().unwrap().is_empty());
+        // Once 1-Across is entered, 1-Down is asked about again with what it answered before
+        entries[0].write(String::from("CASH")).await.unwrap();
+        solver
+            .solve_clue(&entries[5], &entries, &crossings)
+            .await
+            .unwrap();
+        let conversations = conversations.lock().unwrap();
+        assert_eq!(conversations.len(), 1);
+        let messages = &conversations[0];
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1], Message::Assistant(String::from("CANOE")));
+        assert!(matches!(&messages[2], Message::User(prompt) if prompt.contains("pattern C____")));
+    }
+
+    #[tokio::test]
+    async fn session_limits() {
+        let llm = MockLLM::new(HashMap::from([(
+            String::from("Money in hand"),
+            String::from("CASH"),
+        )]));
+        let question = |n: usize| format!("<text>Money in hand</text> {}", n);
+        // Without a limit every question starts a new conversation
+        let mut session = Session::default();
+        for n in 0..3 {
+            assert_eq!(session.ask(&llm, question(n)).await.unwrap(), "CASH");
+        }
+        assert_eq!(session.messages().len(), 2);
+
+        // Old exchanges are dropped, but the first question is always kept
+        let mut session = Session::new(4);
+        for n in 0..10 {
+            session.ask(&llm, question(n)).await.unwrap();
+            assert!(session.messages().len() <= 6);
+        }
+        let messages = session.messages();
+        assert_eq!(messages[0], Message::User(question(0)));
+        assert_eq!(messages[messages.len() - 2], Message::User(question(9)));
+        assert!(messages
+            .iter()
+            .enumerate()
+            .all(|(i, m)| matches!(m, Message::User(_)) == (i % 2 == 0)));
+    }
+
+    fn candidate(answer: &str, confidence: Option<u8>) -> Candidate {
+        Candidate {
+            answer: String::from(answer),
+            confidence,
+        }
+    }
+
+    #[test]
+    fn confidence() {
+        let cases = [
+            ("CASH 90", ("CASH", Some(90))),
+            (" CASH (90%) ", ("CASH", Some(90))),
+            ("CASH: 75", ("CASH", Some(75))),
+            ("CASH - 5", ("CASH", Some(5))),
+            ("T[HE]N 80", ("T[HE]N", Some(80))),
+            ("CASH 250", ("CASH", Some(100))),
+            ("CASH", ("CASH", None)),
+            ("CASH90", ("CASH90", None)),
+            ("4EVER", ("4EVER", None)),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(split_confidence(raw), expected, "{:?}", raw);
+        }
+    }
+
+    /// Answers from a map of clue text, except `clue` which is guessed wrong with low confidence
+    /// until one of its letters is known
+    struct Ambiguous {
+        answers: HashMap<String, String>,
+        clue: String,
+        guess: String,
+    }
+
+    #[async_trait]
+    impl LLMModel for Ambiguous {
+        async fn chat(&self, message: String) -> Result<String, LlmError> {
+            let between = |start: &str, end: &str| {
+                let after = message.split(start).nth(1).expect("Prompt is missing text");
+                after
+                    .split(end)
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            };
+            let clue = between("<text>", "</text>");
+            if clue != self.clue {
+                return Ok(self.answers.get(&clue).cloned().unwrap_or_default());
+            }
+            let pattern = between("the pattern ", ".");
+            Ok(if pattern.chars().all(|c| c == '_') {
+                format!("{} 20", self.guess)
+            } else {
+                format!("{} 95", self.answers[&clue])
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn defer_low_confidence() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let llm = Ambiguous {
+            answers: oracle(&puzzle).into_iter().collect(),
+            clue: String::from("Circular"),
+            guess: String::from("ROUTE"),
+        };
+        let mut solver = LLMSolver::with_model(Box::new(llm)).ordering(Ordering::Confidence);
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        // The guess was never entered, so nothing had to be undone
+        assert_eq!(solver.report().backtracks, 0);
+        assert_eq!(puzzle.rows().await[3], "ROUND");
+    }
+
+    #[tokio::test]
+    async fn unknown_words_ranked_last() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let llm = || {
+            let answers = [("Money in hand", "CAHS 90, CASH 60")];
+            Box::new(MockLLM::new(
+                answers
+                    .into_iter()
+                    .map(|(clue, answer)| (clue.to_string(), answer.to_string()))
+                    .collect(),
+            ))
+        };
+        let wordlist = WordList::parse("cash\ncanoe\n").unwrap();
+        let mut solver = LLMSolver::with_model(llm()).wordlist(wordlist.clone());
+        let answers = solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(
+            answers,
+            vec![candidate("CASH", Some(60)), candidate("CAHS", Some(90))]
+        );
+        assert_eq!(solver.report().unknown_answers, 1);
+
+        // Without the check the order of the LLM is kept
+        let mut solver = LLMSolver::with_model(llm())
+            .wordlist(wordlist.clone())
+            .check_words(false);
+        let answers = solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(answers[0].answer, "CAHS");
+
+        // and a crossing letter that agrees with the unknown word lets it keep its place
+        let boat = entries
+            .iter()
+            .find(|e| e.clue().text == "Paddled boat")
+            .unwrap();
+        boat.write(String::from("CANOE")).await.unwrap();
+        let mut solver = LLMSolver::with_model(llm()).wordlist(wordlist);
+        let answers = solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(answers[0].answer, "CAHS");
+        assert_eq!(solver.report().unknown_answers, 0);
+    }
+
+    #[tokio::test]
+    async fn retry_wrong_length() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let key = CacheKey::for_clue(entries[0].clue(), "____");
+
+        let mut solver = LLMSolver::with_model(Box::new(Plural { corrected: true }));
+        let answers = solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(answers, vec![candidate("CASH", None)]);
+        assert_eq!((solver.report().llm_calls, solver.report().retries), (2, 1));
+        assert_eq!(solver.cache.len(), 1);
+        assert_eq!(solver.cache[&key], vec![candidate("CASH", None)]);
+
+        // Giving up is cached too, so the clue is not asked again for the same pattern
+        let mut solver = LLMSolver::with_model(Box::new(Plural { corrected: false })).retries(1);
+        assert!(solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!((solver.report().llm_calls, solver.report().retries), (2, 1));
+        assert!(solver.cache[&key].is_empty());
+        assert_eq!(solver.report().cache_hits, 1);
+        assert_eq!(solver.report().clue_latency.len(), 1);
+        // The follow up resends the prompt along with the rejected answer
+        let context = ClueContext::new(&entries[0], &entries, &crossings).await;
+        let prompt = prompt_for_clue(&solver.template, &context, &[])
+            .chars()
+            .count();
+        let follow_up = correction("CASHES", 4, "____").chars().count();
+        assert_eq!(
+            solver.report().prompt_chars,
+            prompt + prompt + "CASHES".len() + follow_up
+        );
+    }
+
+    #[test]
+    fn cache_key() {
+        assert_eq!(
+            CacheKey::new("  Money in\nhand ", "c_s.?"),
+            CacheKey::new("Money in hand", "C_S__")
+        );
+        assert_eq!(CacheKey::new("Clue", "t[he]n").pattern, "T[HE]N");
+        assert_ne!(
+            CacheKey::new("Clue", "C___"),
+            CacheKey::new("Clue", "C____")
+        );
+        let across = Clue {
+            text: String::from("Clue"),
+            direction: Direction::Across,
+            number: 1,
+            position: Position { row: 0, column: 0 },
+        };
+        let down = Clue {
+            direction: Direction::Down,
+            ..across.clone()
+        };
+        assert_ne!(
+            CacheKey::for_clue(&across, "____"),
+            CacheKey::for_clue(&down, "____")
+        );
+        assert!(CacheKey::new("Clue", "_[__]_").is_blank());
+        assert!(!CacheKey::new("Clue", "C___").is_blank());
+    }
+
+    #[tokio::test]
+    async fn unconstrained_guesses() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let entries = puzzle.generate_entries().await.unwrap();
+        let crossings = CrossingMap::new(&entries);
+        let clue = entries[0].clue();
+        let mut solver = solver(vec![(clue.text.clone(), String::from("CASE, CASH"))]);
+        assert_eq!(
+            solver
+                .solve_clue(&entries[0], &entries, &crossings)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Starting over with CASE rejected reuses the first answers instead of asking again
+        solver
+            .rejected
+            .push(Rejection::new(clue, "____", String::from("CASE")));
+        let answers = solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(answers, vec![candidate("CASH", None)]);
+        assert_eq!(solver.report().llm_calls, 1);
+        assert_eq!(solver.report().cache_hits, 1);
+        assert_eq!(solver.report().cache_hit_rate(), 0.5);
+
+        // Once every first answer is rejected the LLM has to be asked
+        solver
+            .rejected
+            .push(Rejection::new(clue, "____", String::from("CASH")));
+        solver
+            .solve_clue(&entries[0], &entries, &crossings)
+            .await
+            .unwrap();
+        assert_eq!(solver.report().llm_calls, 2);
+    }
+
+    #[tokio::test]
+    async fn cache_file() {
+        let path = std::env::temp_dir().join(format!("ainyt-cache-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut first = solver(oracle(&puzzle)).with_cache_file(&path).unwrap();
+        assert!(first.solve(&puzzle).await.unwrap().solved);
+        assert!(first.report().llm_calls > 0);
+
+        // A model without any answers can only solve the puzzle from the saved cache
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut second = solver(vec![]).with_cache_file(&path).unwrap();
+        assert!(second.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(second.report().llm_calls, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fork() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let mut solver = solver(oracle(&puzzle)).retries(0);
+        let mut fork = solver.fork();
+        assert_eq!(fork.retries, 0);
+        assert!(fork.solve(&puzzle).await.unwrap().solved);
+        // Answers of the fork are only cached by the solver once they are merged
+        assert!(solver.cache.is_empty());
+        solver.merge_cache(&fork);
+        assert_eq!(solver.cache, fork.cache);
+
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        assert_eq!(solver.report().llm_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn cache_file_rejections() {
+        let path =
+            std::env::temp_dir().join(format!("ainyt-rejections-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        let clue = puzzle.puzzle().clues[0].clone();
+        let mut first = solver(vec![]).with_cache_file(&path).unwrap();
+        first
+            .rejected
+            .push(Rejection::new(&clue, "___H", String::from("CASE")));
+        first.persist();
+        let second = solver(vec![]).with_cache_file(&path).unwrap();
+        assert_eq!(second.rejected, first.rejected);
+
+        // Files written before rejections were saved only hold the answers
+        std::fs::write(
+            &path,
+            r#"[{"clue": "Money in hand", "pattern": "____", "answers": [{"answer": "CASH"}]}]"#,
+        )
+        .unwrap();
+        let third = solver(vec![]).with_cache_file(&path).unwrap();
+        assert_eq!(
+            third.cache[&CacheKey::new("Money in hand", "____")],
+            vec![candidate("CASH", None)]
+        );
+        assert!(third.rejected.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cache_save_failed() {
+        let path = std::env::temp_dir()
+            .join(format!("ainyt-missing-{}", std::process::id()))
+            .join("cache.json");
+        let mut solver = solver(vec![]).with_cache_file(&path).unwrap();
+        let mut receiver = solver.subscribe();
+        solver.persist();
+        let events = received(&mut receiver);
+        assert!(matches!(
+            events.as_slice(),
+            [SolverEvent::CacheSaveFailed { path: failed, .. }] if *failed == path
+        ));
+    }
+
+    #[test]
+    fn cache_file_format() {
+        // Saving a cache file that was just loaded writes the same bytes
+        let path = std::env::temp_dir().join(format!("ainyt-format-{}.json", std::process::id()));
+        std::fs::write(&path, MINI_CACHE).unwrap();
+        let mut solver = solver(vec![]).with_cache_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        solver.persist();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved.trim_end(), MINI_CACHE.trim_end());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn solve_with_second_candidate() {
+        let puzzle = InMemoryPuzzle::from_yaml(MINI.as_bytes()).unwrap();
+        // Without SCENT nothing crosses the last letter of MEET, so MEEK is never contradicted
+        // and the grid can only be solved by trying the second candidate
+        let answers = oracle(&puzzle)
+            .into_iter()
+            .filter(|(_, answer)| answer != "SCENT")
+            .map(|(clue, answer)| match answer.as_str() {
+                "MEET" => (clue, String::from("MEEK, MEET")),
+                _ => (clue, answer),
+            })
+            .collect();
+        let mut solver = solver(answers);
+        assert!(solver.solve(&puzzle).await.unwrap().solved);
+        assert!(solver.report().backtracks > 0);
+        assert_eq!(puzzle.rows().await[4], "MEET#");
+    }
+
+    #[tokio::test]
+    async fn solve_rebus() {
+        // The middle square holds HE, which only 2-Down's crossings can place
+        let rows = [["B", "A", "T"], ["T", "HE", "N"], ["S", "R", "A"]];
+        let clue = |number, direction, row, column| Clue {
+            number,
+            direction,
+            text: format!("{}-{}", number, direction),
+            position: Position { row, column },
+        };
+        let puzzle = InMemoryPuzzle::new(
+            Puzzle {
+                width: 3,
+                height: 3,
+                shaded_squares: vec![],
+                circled: vec![],
+                clues: vec![
+                    clue(1, Direction::Across, 0, 0),
+                    clue(4, Direction::Across, 1, 0),
+                    clue(5, Direction::Across, 2, 0),
+                    clue(1, Direction::Down, 0, 0),
+                    clue(2, Direction::Down, 0, 1),
+                    clue(3, Direction::Down, 0, 2),
+                ],
+            },
+            Some(Solution(
+                rows.iter()
+                    .map(|row| row.iter().map(|c| Some(c.to_string())).collect())
+                    .collect(),
+            )),
+        );
+        // The LLM answers without brackets, as a solver would write the answer on paper
+        let answers = oracle(&puzzle);
+        assert!(answers.contains(&(String::from("4-Across"), String::from("THEN"))));
+        assert!(solver(answers).solve(&puzzle).await.unwrap().solved);
+        assert_eq!(puzzle.rows().await, vec!["BAT", "T[HE]N", "SRA"]);
+        let entries = puzzle.generate_entries().await.unwrap();
+        assert_eq!(entries[1].value().await, "T[HE]N");
+        assert_eq!(entries[1].length().await, 3);
     }
 }
@@ -1,200 +1,1637 @@
-use futures::future::try_join_all;
+use async_trait::async_trait;
+use clap::Args;
 use itertools::Itertools;
-use regex::Regex;
-use std::{collections::HashMap, time::Duration, vec};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+    vec,
+};
 use thirtyfour::{
     prelude::{WebDriverError, WebDriverResult},
-    By, ChromeCapabilities, Key, WebDriver,
+    By, Cookie, Key, WebDriver,
+};
+use webdriver_common::{
+    build_driver, dismiss_any, dismiss_present, modal_selectors, wait_until, DriverOptions,
+    Reporter,
 };
 
-use crate::{positions_for_clue, Clue, Direction, InMemoryCell, InMemoryEntry, Position};
+use crate::{
+    memory::{SavedPuzzle, Solution},
+    puzzle::{
+        circled_positions, positions_for_clues, CrosswordPuzzle, Entry, EntryError, Feedback,
+        PuzzleError,
+    },
+    Clue, Direction, GridState, InMemoryCell, Position, Puzzle, PuzzleDate,
+};
 
-static MINI_URL: &str = "https://www.nytimes.com/crosswords/game/mini";
+/// Times a WebDriver command that may succeed later is sent before the error is returned
+static WEBDRIVER_ATTEMPTS: usize = 3;
+static WEBDRIVER_RETRY_DELAY: Duration = Duration::from_millis(250);
+/// Page opened to add cookies before the puzzle, as they can only be set for the open site
+static HOME_URL: &str = "https://www.nytimes.com/crosswords";
+static COOKIE_DOMAIN: &str = "nytimes.com";
+static GAME_URL: &str = "https://www.nytimes.com/crosswords/game/";
+static API_URL: &str = "https://www.nytimes.com/svc/crosswords/v6/puzzle/";
+pub static MINI_URL: &str = "https://www.nytimes.com/crosswords/game/mini";
+pub static DAILY_URL: &str = "https://www.nytimes.com/crosswords/game/daily";
+/// Fetch JSON from a URL using the cookies of the current session
+static FETCH_SCRIPT: &str = r#"
+const done = arguments[arguments.length - 1];
+fetch(arguments[0], { credentials: "include" })
+    .then((response) => response.ok ? response.json() : Promise.reject(`status ${response.status}`))
+    .then(done, (error) => done({ error: String(error) }));
+"#;
+/// Read every cell of the grid in one round trip. The daily puzzle has hundreds of cells, and
+/// querying each one through the driver takes minutes
+static GRID_SCRIPT: &str = r#"
+return Array.from(document.querySelectorAll(".xwd__cell")).map((cell) => {
+    const rect = cell.querySelector('rect[role="cell"]');
+    return {
+        id: rect.id,
+        class: rect.getAttribute("class"),
+        x: rect.getAttribute("x"),
+        y: rect.getAttribute("y"),
+        circled: cell.querySelector(".xwd__cell--circle") !== null,
+        incorrect: cell.querySelector(".xwd__cell--incorrect") !== null,
+        confirmed: cell.querySelector(".xwd__cell--confirmed") !== null,
+        text: cell.textContent,
+    };
+});
+"#;
+/// Whether each cell of the grid is on the page and not hidden under something else, such as a
+/// modal. Cells scrolled out of view have nothing at their center and count as uncovered
+static GRID_READY_SCRIPT: &str = r#"
+return Array.from(document.querySelectorAll(".xwd__cell")).map((cell) => {
+    const box = cell.getBoundingClientRect();
+    const top = document.elementFromPoint(box.left + box.width / 2, box.top + box.height / 2);
+    return {
+        visible: box.width > 0 && box.height > 0,
+        covered: top !== null && !cell.contains(top),
+    };
+});
+"#;
+/// Text of the cell holding the rect with the given id, including its clue number
+static CELL_TEXT_SCRIPT: &str = r#"
+const cell = document.getElementById(arguments[0])?.closest(".xwd__cell");
+return cell ? cell.textContent : null;
+"#;
+/// Text of the cells holding each of the rects with the given ids
+static ENTRY_TEXT_SCRIPT: &str = r#"
+return arguments[0].map((id) => {
+    const cell = document.getElementById(id)?.closest(".xwd__cell");
+    return cell ? cell.textContent : null;
+});
+"#;
+/// Whether every rect with the given ids is part of the highlighted entry
+static ENTRY_SELECTED_SCRIPT: &str = r#"
+return arguments[0].every((id) =>
+    /xwd__cell--(highlighted|selected)/.test(document.getElementById(id)?.getAttribute("class"))
+);
+"#;
+/// Read every clue from both lists in one round trip. The HTML of the text is read, as clues such
+/// as "*Word* word" depend on which words are in italics
+static CLUES_SCRIPT: &str = r#"
+return Array.from(document.querySelectorAll(".xwd__clue-list--wrapper")).flatMap((list) => {
+    const header = list.querySelector("h3").textContent;
+    return Array.from(list.querySelectorAll("li")).map((clue) => ({
+        header,
+        label: clue.querySelector(".xwd__clue--label").textContent,
+        text: clue.querySelector(".xwd__clue--text").innerHTML,
+    }));
+});
+"#;
 static SHADED_SQUARE_CLS: &str = "xwd__cell--block xwd__cell--nested";
+/// Toolbar button opening the menu of things to check
+static CHECK_MENU: &str = "button[aria-label=\"Check\"]";
+/// Item of the open toolbar menu that acts on the whole grid: it checks every cell in the check
+/// menu, and empties every cell while leaving the timer running in the clear menu
+static PUZZLE_MENU_ITEM: &str =
+    "//li[contains(@class, \"xwd__menu--item\")][normalize-space()=\"Puzzle\"]";
+static CLEAR_MENU: &str = "button[aria-label=\"Clear\"]";
+/// Item of the clear menu that empties every cell and restarts the timer, which is the only way
+/// to play a solved puzzle again
+static CLEAR_PUZZLE_AND_TIMER: &str =
+    "//li[contains(@class, \"xwd__menu--item\")][normalize-space()=\"Puzzle & Timer\"]";
+/// Button of the modal asking to confirm clearing the puzzle
+static CLEAR_CONFIRM: [&str; 1] = [".xwd__modal--button"];
+static REVEAL_MENU: &str = "button[aria-label=\"Reveal\"]";
+/// Item of the reveal menu that fills in every cell of the grid
+static REVEAL_PUZZLE: &str =
+    "//li[contains(@class, \"xwd__menu--item\")][normalize-space()=\"Puzzle\"]";
+/// Button of the modal asking to confirm revealing the puzzle
+static REVEAL_CONFIRM: [&str; 1] = [".xwd__modal--button"];
+/// How long the page may take to draw every revealed letter
+static REVEAL_TIMEOUT: Duration = Duration::from_secs(5);
+static CONGRATS_MODAL: &str = ".xwd__congrats-modal--content";
+/// Value of `type` for a circled cell in the API response, normal cells are 1
+static CIRCLED_CELL_TYPE: u8 = 2;
+
+/// Shortest time spent on each key sent to a cell, so the page is not spammed
+pub static DEFAULT_ENTRY_DELAY: Duration = Duration::from_millis(50);
+/// Longest time to wait for a key sent to a cell to show in it
+static ENTRY_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often to look at a cell while waiting for a key to show
+static ENTRY_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Modals shown when first loading the puzzle, in the order they appear
+static STARTUP_MODALS: [&str; 4] = [
+    ".purr-blocker-card__button", // Updated Terms of Service
+    ".fides-reject-all-button",   // Privacy preferences
+    ".xwd__modal--subtle-button", // Play With Free Account
+    ".pz-ad-modal__close",        // Subscription offers
+];
+/// Additional modals shown before the daily puzzle
+static DAILY_MODALS: [&str; 1] = [
+    ".pz-moment__button", // Ready to get started?
+];
+/// How long to keep dismissing modals while waiting for the grid to become clickable
+/// Cells whose rects are drawn closer together than this, in pixels, share a row or column
+static COORDINATE_TOLERANCE: f64 = 1.0;
+/// Additional modals shown before a puzzle from the archive
+static ARCHIVE_MODALS: [&str; 1] = [
+    ".xwd__modal--archive .xwd__modal--button", // Start this puzzle
+];
+static MODAL_TIMEOUT: Duration = Duration::from_secs(10);
+static MODAL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Modals that pause the timer and cover the grid, taking every click meant for a cell
+static PAUSE_MODALS: [&str; 2] = [
+    "button[aria-label=\"Resume\"]", // Your puzzle is paused, after a while without input
+    ".pz-moment__button",            // Ready to keep going?, after the tab is focused again
+];
+/// Longest time to wait for a dismissed pause modal to go away
+static PAUSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Account menu of the toolbar, only shown to a logged in user
+static ACCOUNT_MENU: &str = "[data-testid=\"user-settings-button\"], .pz-nav__account";
+/// Longest time to wait for the page to show the account menu
+static LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+static LOGIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Timer of the toolbar, showing the time spent on the puzzle
+static TIMER: &str = ".timer-count";
+/// Longest time to wait for a running timer to tick over
+static TIMER_TICK: Duration = Duration::from_millis(1500);
+static TIMER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time shown by the timer, written as m:ss or h:mm:ss
+fn parse_timer(text: &str) -> Option<Duration> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    let number = |part: &str, digits: Option<usize>| {
+        if part.is_empty()
+            || digits.is_some_and(|d| part.len() != d)
+            || !part.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        part.parse::<u64>().ok()
+    };
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [minutes, seconds] => (0, number(minutes, None)?, number(seconds, Some(2))?),
+        [hours, minutes, seconds] => (
+            number(hours, None)?,
+            number(minutes, Some(2))?,
+            number(seconds, Some(2))?,
+        ),
+        _ => return None,
+    };
+    if seconds >= 60 || (parts.len() == 3 && minutes >= 60) {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Letters shown in a cell, without the clue number drawn before them
+fn cell_letters(text: &str) -> &str {
+    text.trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim()
+}
+
+/// URL of the puzzle published on the given day, from the archive of the puzzle at `puzzle_url`,
+/// e.g. https://www.nytimes.com/crosswords/game/mini/2024/03/14 for [`MINI_URL`]
+pub fn archive_url(puzzle_url: &str, date: PuzzleDate) -> String {
+    format!("{}/{}", puzzle_url.trim_end_matches('/'), date.path())
+}
+
+/// Whether the URL is of a puzzle from the archive, which has the day it was published
+fn is_archive(puzzle_url: &str) -> bool {
+    puzzle_url
+        .strip_prefix(GAME_URL)
+        .is_some_and(|path| path.trim_matches('/').split('/').count() == 4)
+}
+
+/// JSON API URL serving the puzzle shown at a game URL, e.g. `/crosswords/game/daily/2023/09/20`
+fn api_url(puzzle_url: &str) -> Option<String> {
+    let path = puzzle_url.strip_prefix(GAME_URL)?;
+    match path
+        .trim_matches('/')
+        .split('/')
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [kind] => Some(format!("{}{}.json", API_URL, kind)),
+        [kind, year, month, day] => Some(format!(
+            "{}{}/{}-{}-{}.json",
+            API_URL, kind, year, month, day
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct ScrapedCell {
+    id: String,
+    class: String,
+    x: String,
+    y: String,
+    #[serde(default)]
+    circled: bool,
+    /// Marked with a slash by the Check feature
+    #[serde(default)]
+    incorrect: bool,
+    /// Marked as correct, which every cell of a solved puzzle is
+    #[serde(default)]
+    confirmed: bool,
+    text: String,
+}
+
+/// Index of the row or column each coordinate falls in, counting from the smallest. Coordinates
+/// within `COORDINATE_TOLERANCE` of the first one in a row or column belong to it, as the page
+/// does not always draw a row at exactly the same height, and headless Chrome does not always
+/// format coordinates the same way as a window
+fn cluster_coordinates(values: &[f64]) -> Vec<usize> {
+    let mut starts: Vec<f64> = Vec::new();
+    for value in values.iter().copied().sorted_by(f64::total_cmp) {
+        if starts
+            .last()
+            .map_or(true, |start| value - start > COORDINATE_TOLERANCE)
+        {
+            starts.push(value);
+        }
+    }
+    values
+        .iter()
+        .map(|value| starts.partition_point(|start| start <= value) - 1)
+        .collect()
+}
+
+/// Read one coordinate of a cell's rect
+fn parse_coordinate(cell: &ScrapedCell, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|v: &f64| v.is_finite())
+        .ok_or_else(|| format!("cell {:?} is at {:?}, not a coordinate", cell.id, value))
+}
 
-static ENTRY_RATE_MS: u64 = 500;
+/// Whether a WebDriver command that failed with this error may succeed if sent again. The page
+/// re-renders the grid under us, leaving handles to its elements stale, and chromedriver times
+/// out now and then under load. A closed window or deleted session is gone for good
+pub fn is_retryable(error: &WebDriverError) -> bool {
+    matches!(
+        error,
+        WebDriverError::StaleElementReference(..)
+            | WebDriverError::Timeout(..)
+            | WebDriverError::ScriptTimeout(..)
+            | WebDriverError::JavascriptError(..)
+            | WebDriverError::ElementClickIntercepted(..)
+    )
+}
+
+/// Run the commands again while they fail with an error that `is_retryable`, up to `attempts`
+/// times in all. Elements must be found again inside `commands`, as handles from a failed
+/// attempt may be stale
+pub async fn with_retries<T, F, Fut>(attempts: usize, mut commands: F) -> WebDriverResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = WebDriverResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match commands().await {
+            Err(e) if is_retryable(&e) && attempt < attempts => {
+                tokio::time::sleep(WEBDRIVER_RETRY_DELAY).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
 
-async fn wait_on_entry() {
-    tokio::time::sleep(Duration::from_millis(ENTRY_RATE_MS)).await
+/// Error for a page that does not look the way it is expected to
+fn scrape_error(message: String) -> WebDriverError {
+    WebDriverError::CustomError(format!("unexpected page: {}", message))
+}
+
+/// Read the structure of the grid and any letters in it from its scraped cells
+fn parse_grid(cells: &[ScrapedCell]) -> Result<WebGridInfo, String> {
+    // The position of each cell comes from where its rect is drawn
+    let xs = cells
+        .iter()
+        .map(|c| parse_coordinate(c, &c.x))
+        .collect::<Result<Vec<_>, _>>()?;
+    let ys = cells
+        .iter()
+        .map(|c| parse_coordinate(c, &c.y))
+        .collect::<Result<Vec<_>, _>>()?;
+    let columns = cluster_coordinates(&xs);
+    let rows = cluster_coordinates(&ys);
+    let width = columns.iter().max().map_or(0, |c| c + 1);
+    let height = rows.iter().max().map_or(0, |r| r + 1);
+    if cells.is_empty() || width * height != cells.len() {
+        return Err(format!(
+            "{} cells do not fill a {}x{} grid",
+            cells.len(),
+            width,
+            height
+        ));
+    }
+
+    let mut grid_info = WebGridInfo {
+        width,
+        height,
+        ids: HashMap::new(),
+        clue_positions: HashMap::new(),
+        shaded_squares: vec![],
+        circled: vec![],
+        incorrect: vec![],
+        letters: HashMap::new(),
+        wrong: HashMap::new(),
+    };
+
+    for ((cell, column), row) in cells.iter().zip(columns).zip(rows) {
+        let position = Position { row, column };
+        if grid_info.ids.insert(position, cell.id.clone()).is_some() {
+            return Err(format!("two cells are drawn at {:?}", position));
+        }
+        // The number is drawn before any letter that has been entered in the cell
+        let label: String = cell
+            .text
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if cell.circled {
+            grid_info.circled.push(position)
+        }
+        if cell.incorrect {
+            grid_info.incorrect.push(position)
+        }
+        if let Some(letters) = entered_letters(cell) {
+            grid_info.letters.insert(position, letters);
+        }
+        if let Some(letters) = wrong_letters(cell) {
+            grid_info.wrong.insert(position, letters);
+        }
+        // Shaded squares have a specific class name
+        if cell.class == SHADED_SQUARE_CLS {
+            grid_info.shaded_squares.push(position)
+        }
+        // Save cells with numbers so we can locate our clues
+        else if !label.is_empty() {
+            let number = label.parse().map_err(|_| {
+                format!(
+                    "cell {:?} has label {:?}, not a clue number",
+                    cell.id, label
+                )
+            })?;
+            grid_info.clue_positions.insert(number, position);
+        }
+    }
+    Ok(grid_info)
+}
+
+/// Convert the scraped clue lists into Clues, located by the numbers in the grid
+fn parse_clues(
+    scraped: Vec<ScrapedClue>,
+    clue_positions: &HashMap<usize, Position>,
+) -> Result<Vec<Clue>, String> {
+    scraped
+        .into_iter()
+        .map(|clue| {
+            // Determine the direction by the header at the top of the list
+            let direction = match clue.header.trim().to_ascii_uppercase().as_str() {
+                "ACROSS" => Direction::Across,
+                "DOWN" => Direction::Down,
+                _ => return Err(format!("clue list has header {:?}", clue.header.trim())),
+            };
+            let number: usize = clue.label.trim().parse().map_err(|_| {
+                format!(
+                    "{} clue has label {:?}, not a number",
+                    direction,
+                    clue.label.trim()
+                )
+            })?;
+            Ok(Clue {
+                number,
+                direction,
+                text: sanitize_clue_text(&clue.text),
+                // Look where the clue is in the puzzle in our HashMap above
+                position: *clue_positions.get(&number).ok_or_else(|| {
+                    format!("{}-{} is not numbered in the grid", number, direction)
+                })?,
+            })
+        })
+        .collect()
+}
+
+/// Clean up clue text read from the page or the API: italics become `_word_`, other HTML is
+/// dropped, entities are decoded, typographic quotes, dashes and spaces become their plain
+/// versions and runs of whitespace collapse to one space. The same clue then reads the same in
+/// a prompt and in a cache key, wherever it came from
+pub fn sanitize_clue_text(text: &str) -> String {
+    static ITALICS: OnceLock<Regex> = OnceLock::new();
+    static LINE_BREAK: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let italics = ITALICS
+        .get_or_init(|| Regex::new(r"(?i)</?(?:i|em)\b[^>]*>").expect("Invalid italics regex"));
+    let line_break =
+        LINE_BREAK.get_or_init(|| Regex::new(r"(?i)<br\s*/?>").expect("Invalid line break regex"));
+    let tag = TAG.get_or_init(|| Regex::new(r"</?[a-zA-Z][^>]*>").expect("Invalid tag regex"));
+    let entity = ENTITY.get_or_init(|| {
+        Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").expect("Invalid entity regex")
+    });
+
+    let text = italics.replace_all(text, "_");
+    let text = line_break.replace_all(&text, " ");
+    let text = tag.replace_all(&text, "");
+    let text = entity.replace_all(&text, |c: &Captures| {
+        let name = &c[1];
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "lsquo" | "rsquo" => Some('\''),
+            "ldquo" | "rdquo" => Some('"'),
+            "ndash" | "mdash" => Some('-'),
+            "hellip" => Some('…'),
+            _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => name
+                    .strip_prefix('#')
+                    .and_then(|n| n.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        // Leave anything unknown as it was written
+        decoded.map_or_else(|| c[0].to_string(), String::from)
+    });
+    let text: String = text
+        .chars()
+        .flat_map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => vec!['\''],
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => vec!['"'],
+            '\u{2010}'..='\u{2015}' | '\u{2212}' => vec!['-'],
+            '\u{2026}' => vec!['.'; 3],
+            '\u{00AD}' | '\u{200B}' | '\u{FEFF}' => vec![],
+            c if c.is_whitespace() => vec![' '],
+            c => vec![c],
+        })
+        .collect();
+    text.split_whitespace().join(" ")
+}
+
+/// Puzzle structures saved to a directory by day, so that running again on the same puzzle
+/// doesn't have to read it from NYT
+#[derive(Debug, Clone)]
+pub struct ScrapeCache {
+    dir: PathBuf,
+    date: PuzzleDate,
+}
+
+impl ScrapeCache {
+    /// Cache of the puzzles published on `date`
+    pub fn new(dir: impl Into<PathBuf>, date: PuzzleDate) -> Self {
+        Self {
+            dir: dir.into(),
+            date,
+        }
+    }
+
+    /// File of the puzzle at the URL, e.g. mini-2024-03-14.yaml
+    fn path(&self, puzzle_url: &str) -> PathBuf {
+        let kind = puzzle_url
+            .strip_prefix(GAME_URL)
+            .and_then(|path| path.split('/').next())
+            .filter(|kind| !kind.is_empty())
+            .unwrap_or("puzzle");
+        self.dir.join(format!("{}-{}.yaml", kind, self.date))
+    }
+
+    /// The saved puzzle, unless there is none or it was saved for another day
+    fn load(&self, puzzle_url: &str) -> Option<Puzzle> {
+        let file = fs::File::open(self.path(puzzle_url)).ok()?;
+        let saved: SavedPuzzle = serde_yaml::from_reader(file).ok()?;
+        (saved.date == Some(self.date)).then_some(saved.puzzle)
+    }
+
+    fn save(&self, puzzle_url: &str, puzzle: &Puzzle) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let saved = SavedPuzzle::new(puzzle.clone(), None).date(Some(self.date));
+        let yaml = serde_yaml::to_string(&saved)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(self.path(puzzle_url), yaml)
+    }
+}
+
+/// Solution of a grid from the letters in every cell, or the first cell without one
+fn solution_from_grid(grid: &WebGridInfo) -> Result<Solution, Position> {
+    (0..grid.height)
+        .map(|row| {
+            (0..grid.width)
+                .map(|column| {
+                    let position = Position { row, column };
+                    if grid.shaded_squares.contains(&position) {
+                        return Ok(None);
+                    }
+                    grid.letters
+                        .get(&position)
+                        .cloned()
+                        .map(Some)
+                        .ok_or(position)
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()
+        .map(Solution)
+}
+
+/// Whether a cached puzzle is the one on the page: the same size, with the same shaded squares
+/// and every clue starting where the grid numbers it
+fn matches_grid(puzzle: &Puzzle, grid: &WebGridInfo) -> bool {
+    (puzzle.width, puzzle.height) == (grid.width, grid.height)
+        && puzzle
+            .shaded_squares
+            .iter()
+            .sorted()
+            .eq(grid.shaded_squares.iter().sorted())
+        && grid
+            .clue_positions
+            .keys()
+            .all(|n| puzzle.clues.iter().any(|c| c.number == *n))
+        && puzzle
+            .clues
+            .iter()
+            .all(|c| grid.clue_positions.get(&c.number) == Some(&c.position))
+}
+
+/// A cell for every open square of the grid, made from the position and HTML id it was scraped
+/// with
+fn open_cells<C>(
+    grid: &WebGridInfo,
+    mut cell: impl FnMut(Position, &str) -> C,
+) -> HashMap<Position, C> {
+    grid.ids
+        .iter()
+        .filter(|(position, _)| !grid.shaded_squares.contains(position))
+        .map(|(position, id)| (*position, cell(*position, id)))
+        .collect()
+}
+
+/// Cells of the entry of each clue, looked up by position. Fails naming every clue with a
+/// position that has no cell
+fn entry_cells<C: Clone>(
+    clues: &[Clue],
+    positions: &[Vec<Position>],
+    cells: &HashMap<Position, C>,
+) -> Result<Vec<Vec<C>>, PuzzleError> {
+    let mut missing = vec![];
+    let entries = clues
+        .iter()
+        .zip(positions)
+        .map(|(clue, positions)| {
+            positions
+                .iter()
+                .map(|p| cells.get(p).cloned())
+                .collect::<Option<Vec<C>>>()
+                .unwrap_or_else(|| {
+                    missing.push((clue.number, clue.direction));
+                    vec![]
+                })
+        })
+        .collect();
+    if missing.is_empty() {
+        Ok(entries)
+    } else {
+        Err(PuzzleError::MissingCells(missing))
+    }
+}
+
+/// How far the puzzle on the page has been solved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionState {
+    /// Every cell is empty
+    NotStarted,
+    /// Some cells have letters in them, typed by hand or by an earlier run
+    InProgress,
+    /// NYT has accepted the grid as solved
+    Completed,
+}
+
+/// Tell how far the puzzle has been solved from its cells, and whether the congratulations
+/// modal is shown. The modal is gone once dismissed, but every cell of a solved puzzle stays
+/// marked as correct
+fn classify_completion(congrats: bool, cells: &[ScrapedCell]) -> CompletionState {
+    let mut open = cells
+        .iter()
+        .filter(|c| c.class != SHADED_SQUARE_CLS)
+        .peekable();
+    let confirmed =
+        open.peek().is_some() && open.all(|c| c.confirmed && entered_letters(c).is_some());
+    if congrats || confirmed {
+        CompletionState::Completed
+    } else if cells.iter().any(|c| entered_letters(c).is_some()) {
+        CompletionState::InProgress
+    } else {
+        CompletionState::NotStarted
+    }
+}
+
+/// Letters shown in a cell, typed by hand or by an earlier run. Letters the Check feature marked
+/// incorrect are as good as empty
+fn entered_letters(cell: &ScrapedCell) -> Option<String> {
+    let letters = cell_letters(&cell.text);
+    (!letters.is_empty() && !cell.incorrect && cell.class != SHADED_SQUARE_CLS)
+        .then(|| letters.to_ascii_uppercase())
+}
+
+/// Letters shown in a cell that the Check feature marked incorrect
+fn wrong_letters(cell: &ScrapedCell) -> Option<String> {
+    let letters = cell_letters(&cell.text);
+    (!letters.is_empty() && cell.incorrect).then(|| letters.to_ascii_uppercase())
+}
+
+/// State of a grid cell while the page loads
+#[derive(Deserialize)]
+struct CellVisibility {
+    visible: bool,
+    covered: bool,
+}
+
+/// The grid can be played once every cell is shown and none are hidden under a modal
+fn grid_ready(cells: &[CellVisibility]) -> bool {
+    !cells.is_empty() && cells.iter().all(|c| c.visible && !c.covered)
+}
+
+/// Where to click and what to type to fill an entry in one go, given what its cells show. The
+/// page moves the cursor along the entry after each letter, skipping over filled cells, so only
+/// the empty cells are typed into. `None` if there is nothing to type, or if the word can't be
+/// typed in one go because a cell shows a different letter or the answer has a rebus
+fn fast_keys(shown: &[Option<String>], letters: &[String]) -> Option<(usize, String)> {
+    if letters.iter().any(|l| l.chars().count() != 1) {
+        return None;
+    }
+    let mut start = None;
+    let mut keys = String::new();
+    for (i, (shown, letter)) in shown.iter().zip(letters).enumerate() {
+        match shown {
+            Some(shown) if shown == letter => continue,
+            Some(_) => return None,
+            None => {
+                start.get_or_insert(i);
+                keys.push_str(letter);
+            }
+        }
+    }
+    start.map(|start| (start, keys))
+}
+
+#[derive(Deserialize)]
+struct ScrapedClue {
+    header: String,
+    label: String,
+    text: String,
 }
 
 #[derive(Debug)]
 struct WebGridInfo {
     width: usize,
     height: usize,
+    /// HTML id of the rect of each cell, to click it by
+    ids: HashMap<Position, String>,
     clue_positions: HashMap<usize, Position>,
     shaded_squares: Vec<Position>,
+    circled: Vec<Position>,
+    incorrect: Vec<Position>,
+    /// Letters already shown in the grid
+    letters: HashMap<Position, String>,
+    /// Letters shown in the cells marked incorrect, which are left out of `letters`
+    wrong: HashMap<Position, String>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    body: Vec<ApiPuzzle>,
+}
+
+#[derive(Deserialize)]
+struct ApiPuzzle {
+    cells: Vec<ApiCell>,
+    clues: Vec<ApiClue>,
+    dimensions: ApiDimensions,
+}
+
+/// Shaded squares are sent as an empty object
+#[derive(Deserialize)]
+struct ApiCell {
+    #[serde(rename = "type")]
+    kind: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct ApiClue {
+    cells: Vec<usize>,
+    direction: String,
+    label: String,
+    text: Vec<ApiClueText>,
+}
+
+#[derive(Deserialize)]
+struct ApiClueText {
+    plain: String,
+    /// HTML of the clue, only sent for clues with formatting such as italics
+    formatted: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiDimensions {
+    width: usize,
+    height: usize,
+}
+
+/// Convert the puzzle JSON served by NYT into a Puzzle
+fn parse_api_puzzle(json: serde_json::Value) -> Result<Puzzle, serde_json::Error> {
+    use serde::de::Error;
+
+    if let Some(error) = json.get("error") {
+        return Err(serde_json::Error::custom(error));
+    }
+    let response: ApiResponse = serde_json::from_value(json)?;
+    let puzzle = response
+        .body
+        .into_iter()
+        .next()
+        .ok_or_else(|| serde_json::Error::custom("response has no puzzle"))?;
+    let width = puzzle.dimensions.width;
+    let clues = puzzle
+        .clues
+        .into_iter()
+        .map(|clue| {
+            Ok(Clue {
+                number: clue.label.parse().map_err(serde_json::Error::custom)?,
+                direction: match clue.direction.as_str() {
+                    "Across" => Direction::Across,
+                    "Down" => Direction::Down,
+                    d => {
+                        return Err(serde_json::Error::custom(format!(
+                            "unknown direction {}",
+                            d
+                        )))
+                    }
+                },
+                text: sanitize_clue_text(
+                    &clue
+                        .text
+                        .into_iter()
+                        .map(|t| t.formatted.unwrap_or(t.plain))
+                        .join(" "),
+                ),
+                position: Position::from_cell_id(
+                    *clue
+                        .cells
+                        .first()
+                        .ok_or_else(|| serde_json::Error::custom("clue has no cells"))?,
+                    width,
+                ),
+            })
+        })
+        .collect::<Result<_, serde_json::Error>>()?;
+    Ok(Puzzle {
+        width,
+        height: puzzle.dimensions.height,
+        shaded_squares: puzzle
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.kind.is_none())
+            .map(|(i, _)| Position::from_cell_id(i, width))
+            .collect(),
+        circled: puzzle
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.kind == Some(CIRCLED_CELL_TYPE))
+            .map(|(i, _)| Position::from_cell_id(i, width))
+            .collect(),
+        clues,
+    })
+}
+
+/// Reasons a puzzle could not be opened as a logged in subscriber
+#[derive(Debug)]
+pub enum LoginError {
+    /// The cookie file could not be read
+    Io(io::Error),
+    /// The cookie file is not in a format that is understood
+    InvalidCookies(String),
+    /// The page does not show an account after adding the cookies, usually because they have
+    /// expired
+    LoggedOut,
+    WebDriver(WebDriverError),
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginError::Io(e) => write!(f, "unable to read cookies: {}", e),
+            LoginError::InvalidCookies(reason) => write!(f, "invalid cookie file: {}", reason),
+            LoginError::LoggedOut => write!(
+                f,
+                "not logged in to NYT with the provided cookies, export them again from a \
+                 browser that is logged in"
+            ),
+            LoginError::WebDriver(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoginError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoginError::Io(e) => Some(e),
+            LoginError::WebDriver(e) => Some(e),
+            LoginError::InvalidCookies(_) | LoginError::LoggedOut => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoginError {
+    fn from(value: io::Error) -> Self {
+        LoginError::Io(value)
+    }
+}
+
+impl From<WebDriverError> for LoginError {
+    fn from(value: WebDriverError) -> Self {
+        LoginError::WebDriver(value)
+    }
+}
+
+/// Cookie of a logged in browser session. The value is a credential, so it is never printed
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+}
+
+impl fmt::Debug for SessionCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionCookie")
+            .field("name", &self.name)
+            .field("value", &"<redacted>")
+            .field("domain", &self.domain)
+            .field("path", &self.path)
+            .field("secure", &self.secure)
+            .field("http_only", &self.http_only)
+            .finish()
+    }
+}
+
+impl SessionCookie {
+    /// Read the cookies exported from a browser into a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<Self>, LoginError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse either a JSON list of cookies, as exported by browser extensions, or a Netscape
+    /// cookies.txt file
+    pub fn parse(text: &str) -> Result<Vec<Self>, LoginError> {
+        if text.trim_start().starts_with('[') {
+            // Only say where the error is, as the message can quote a cookie value
+            return serde_json::from_str(text).map_err(|e| {
+                LoginError::InvalidCookies(format!(
+                    "JSON error at line {} column {}",
+                    e.line(),
+                    e.column()
+                ))
+            });
+        }
+        text.lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                // HttpOnly cookies are marked by a prefix on what would otherwise be a comment
+                let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                    Some(line) => (line, true),
+                    None => (line, false),
+                };
+                if line.trim().is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+                let [domain, _, path, secure, _, name, value] = fields[..] else {
+                    return Some(Err(LoginError::InvalidCookies(format!(
+                        "line {} does not have 7 fields separated by tabs",
+                        index + 1
+                    ))));
+                };
+                Some(Ok(Self {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    domain: Some(domain.to_string()),
+                    path: Some(path.to_string()),
+                    secure: secure.eq_ignore_ascii_case("true"),
+                    http_only,
+                }))
+            })
+            .collect()
+    }
+
+    /// Whether the cookie is sent to NYT. Others cannot be added while NYT is open
+    fn for_nytimes(&self) -> bool {
+        self.domain.as_deref().is_some_and(|domain| {
+            let domain = domain.trim_start_matches('.');
+            domain == COOKIE_DOMAIN || domain.ends_with(&format!(".{}", COOKIE_DOMAIN))
+        })
+    }
+
+    fn cookie(&self) -> Cookie<'static> {
+        let mut cookie = Cookie::new(self.name.clone(), self.value.clone());
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        if let Some(path) = &self.path {
+            cookie.set_path(path.clone());
+        }
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+        cookie
+    }
+}
+
+/// Command line flags to open puzzles as a logged in subscriber
+#[derive(Args, Debug)]
+pub struct LoginArgs {
+    #[clap(
+        long,
+        help = "Cookies of a browser logged in to NYT, needed for the daily puzzle and the \
+                archive. Either a JSON list of objects with name, value, domain, path, secure \
+                and httpOnly, as exported by browser extensions, or a Netscape cookies.txt"
+    )]
+    pub cookies_file: Option<PathBuf>,
+}
+
+impl LoginArgs {
+    /// Cookies to log in with, if a file was given
+    pub fn cookies(&self) -> Result<Option<Vec<SessionCookie>>, LoginError> {
+        self.cookies_file
+            .as_ref()
+            .map(SessionCookie::load)
+            .transpose()
+    }
 }
 
+/// Driver for an NYT crossword page. Defaults to the mini, but any puzzle URL can be opened
 #[derive(Clone)]
-pub struct MiniCrosswordWebDriver(WebDriver);
+pub struct MiniCrosswordWebDriver {
+    driver: WebDriver,
+    puzzle_url: String,
+    entry_delay: Duration,
+    /// Where to save the page when something fails
+    debug_dir: Option<PathBuf>,
+    /// Modals to dismiss when the puzzle loads
+    modals: Vec<String>,
+    reporter: Option<Reporter>,
+}
 
 impl MiniCrosswordWebDriver {
+    /// Open today's mini, or the one published on `date`
     pub async fn create(
         chromedriver_server_url: &str,
-        binary_path: Option<&str>,
+        date: Option<PuzzleDate>,
+        options: &DriverOptions,
     ) -> WebDriverResult<Self> {
-        let mut options = ChromeCapabilities::new();
-        //         options.add_chrome_arg("--incognito")?;
-        options.add_chrome_arg("--start-maximized")?;
-        if let Some(p) = binary_path {
-            options.set_binary(p)?;
-        }
-        let driver = WebDriver::new(chromedriver_server_url, options).await?;
-        driver.goto(MINI_URL).await?;
-        for button_cls in [
-            // "purr-blocker-card__button", // Updated Terms of Service
-            "fides-reject-all-button", // Privacy preferences
-            "xwd__modal--subtle-button" // Play With Free Account
-        ] {
-            wait_on_entry().await;
-            driver
-                .find(By::ClassName(button_cls))
-                .await?
-                .click()
-                .await?;
-            // TODO: Use a wait instead of a sleep
+        let url = match date {
+            Some(date) => archive_url(MINI_URL, date),
+            None => MINI_URL.to_string(),
+        };
+        Self::open(chromedriver_server_url, &url, options).await
+    }
+
+    /// Open the puzzle at the provided URL, such as [`DAILY_URL`]
+    pub async fn open(
+        chromedriver_server_url: &str,
+        puzzle_url: &str,
+        options: &DriverOptions,
+    ) -> WebDriverResult<Self> {
+        let driver = build_driver(chromedriver_server_url, options).await?;
+        Self::start(driver, puzzle_url, options).await
+    }
+
+    /// Open the puzzle as the subscriber the cookies were exported from, for puzzles behind the
+    /// paywall. Fails with `LoginError::LoggedOut` if the page does not show them logged in
+    pub async fn login(
+        chromedriver_server_url: &str,
+        puzzle_url: &str,
+        options: &DriverOptions,
+        cookies: &[SessionCookie],
+    ) -> Result<Self, LoginError> {
+        let driver = build_driver(chromedriver_server_url, options).await?;
+        driver.goto(HOME_URL).await?;
+        for cookie in cookies.iter().filter(|c| c.for_nytimes()) {
+            driver.add_cookie(cookie.cookie()).await?;
         }
-        Ok(Self(driver))
+        let crossword = Self::start(driver, puzzle_url, options).await?;
+        if !crossword.logged_in().await? {
+            return crossword
+                .diagnose("login", Err(LoginError::LoggedOut))
+                .await;
+        }
+        Ok(crossword)
     }
 
-    // Get the information about the Grid itself
-    async fn get_grid_info(&self) -> WebDriverResult<WebGridInfo> {
-        // HTML id() attributes of the cell tells us the position in the crossword grid
-        let re = Regex::new(r"cell-id-(\d*)").unwrap();
-        // Get the size of the grid by seeing the number of unique X and Y values
-        let cells = self.0.find_all(By::ClassName("xwd__cell")).await?;
-        let columns = try_join_all(
-            cells
-                .iter()
-                .map(|wbe| async move { wbe.find(By::Tag("RECT")).await?.css_value("x").await }),
-        )
-        .await?
-        .into_iter()
-        .unique()
-        .count();
-
-        let mut grid_info = WebGridInfo {
-            width: columns,
-            height: cells.len() / columns,
-            clue_positions: HashMap::new(),
-            shaded_squares: vec![],
+    async fn start(
+        driver: WebDriver,
+        puzzle_url: &str,
+        options: &DriverOptions,
+    ) -> WebDriverResult<Self> {
+        let mut defaults = STARTUP_MODALS.to_vec();
+        if puzzle_url.contains("/daily") {
+            defaults.extend(DAILY_MODALS);
+        }
+        if is_archive(puzzle_url) {
+            defaults.extend(ARCHIVE_MODALS);
+        }
+        let crossword = Self {
+            driver,
+            puzzle_url: puzzle_url.to_string(),
+            entry_delay: DEFAULT_ENTRY_DELAY,
+            debug_dir: options.debug_dir.clone(),
+            modals: modal_selectors(&defaults, options)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            reporter: options.reporter.clone(),
         };
+        let loaded = crossword.load().await;
+        crossword.diagnose("open", loaded).await?;
+        Ok(crossword)
+    }
 
-        // All cells have the same HTML class name
-        for cell in cells.iter() {
-            let r = cell.find(By::Css("rect[role=\"cell\"]")).await?;
-            // Look at the id() of the rect inside the cell. If we can't interpret it
-            // into a Position we are in trouble
-            let position = Position::from_cell_id(
-                re.captures_iter(&r.id().await?.expect("Cell missing ID"))
-                    .map(|c| {
-                        let (_, [s]) = c.extract();
-                        s
-                    })
-                    .next()
-                    .expect("Cell id does not match regex")
-                    .parse()
-                    .expect("Cell id is not valid usize"),
-                columns,
-            );
-            // Shaded squares have a specific class name
-            if r.class_name().await?.expect("Missing class name") == SHADED_SQUARE_CLS {
-                grid_info.shaded_squares.push(position)
-            }
-            // Save cells with numbers so we can locate our clues
-            else if !cell.text().await?.is_empty() {
-                grid_info.clue_positions.insert(
-                    cell.text()
-                        .await?
-                        .parse()
-                        .expect("Cell text is not a clue number"),
-                    position,
-                );
+    /// Go to the puzzle and dismiss whichever modals show up until the grid can be clicked.
+    /// Modals that never appear are ignored
+    async fn load(&self) -> WebDriverResult<()> {
+        self.driver.goto(&self.puzzle_url).await?;
+        let modals: Vec<&str> = self.modals.iter().map(String::as_str).collect();
+        let ready = wait_until(MODAL_TIMEOUT, MODAL_POLL_INTERVAL, || async {
+            dismiss_present(&self.driver, &modals).await?;
+            self.grid_ready().await
+        })
+        .await?;
+        if !ready {
+            // Without a subscription the archive shows a paywall where the grid would be
+            if is_archive(&self.puzzle_url) && !self.logged_in().await? {
+                return Err(WebDriverError::CustomError(String::from(
+                    "puzzles from the archive are only open to subscribers. Log in with \
+                     --cookies-file to open one",
+                )));
             }
+            return Err(scrape_error(String::from(
+                "the grid is still covered. Pass its CSS selector with --modal to dismiss it",
+            )));
         }
-        Ok(grid_info)
+        Ok(())
+    }
+
+    async fn grid_ready(&self) -> WebDriverResult<bool> {
+        let cells: Vec<CellVisibility> = serde_json::from_value(
+            self.driver
+                .execute(GRID_READY_SCRIPT, vec![])
+                .await?
+                .json()
+                .clone(),
+        )
+        .map_err(|e| scrape_error(format!("unexpected grid cell data: {}", e)))?;
+        Ok(grid_ready(&cells))
+    }
+
+    /// Pass a problem that was worked around on to the reporter, if there is one
+    fn report(&self, message: &str) {
+        if let Some(reporter) = &self.reporter {
+            reporter.report(message);
+        }
+    }
+
+    /// Save the page into the debug directory, if there is one, when the result is an error, and
+    /// report where it was saved
+    async fn diagnose<T, E: fmt::Display>(
+        &self,
+        label: &str,
+        result: Result<T, E>,
+    ) -> Result<T, E> {
+        let (result, capture) =
+            webdriver_common::diagnose(&self.driver, self.debug_dir.as_deref(), label, result)
+                .await;
+        if let (Err(e), Some(capture)) = (&result, capture) {
+            self.report(&match capture {
+                Ok(path) => format!(
+                    "{} failed: {}. Saved the page to {}",
+                    label,
+                    e,
+                    path.display()
+                ),
+                Err(capture) => format!(
+                    "{} failed: {}. Unable to save the page: {}",
+                    label, e, capture
+                ),
+            });
+        }
+        result
+    }
+
+    // Get the information about the Grid itself
+    async fn get_grid_info(&self) -> WebDriverResult<WebGridInfo> {
+        parse_grid(&self.scrape_cells().await?).map_err(scrape_error)
+    }
+
+    async fn scrape_cells(&self) -> WebDriverResult<Vec<ScrapedCell>> {
+        serde_json::from_value(self.run_script(GRID_SCRIPT).await?)
+            .map_err(|e| scrape_error(format!("unexpected grid cell data: {}", e)))
     }
 
     async fn get_clues(
         &self,
-        clue_positions: HashMap<usize, Position>,
+        clue_positions: &HashMap<usize, Position>,
     ) -> WebDriverResult<Vec<Clue>> {
-        let mut clues = vec![];
-        for clue_list in self
-            .0
-            .find_all(By::ClassName("xwd__clue-list--wrapper"))
-            .await?
+        let scraped: Vec<ScrapedClue> =
+            serde_json::from_value(self.run_script(CLUES_SCRIPT).await?)
+                .map_err(|e| scrape_error(format!("unexpected clue data: {}", e)))?;
+        parse_clues(scraped, clue_positions).map_err(scrape_error)
+    }
+
+    /// Result of a script that reads the page, run again if the page changes while it runs
+    async fn run_script(&self, script: &str) -> WebDriverResult<serde_json::Value> {
+        with_retries(WEBDRIVER_ATTEMPTS, || async {
+            Ok(self.driver.execute(script, vec![]).await?.json().clone())
+        })
+        .await
+    }
+
+    /// Scrape the structure of the puzzle from the page
+    pub async fn get_puzzle(&self) -> WebDriverResult<Puzzle> {
+        let puzzle = self.scrape_puzzle().await;
+        self.diagnose("scrape", puzzle).await
+    }
+
+    async fn scrape_puzzle(&self) -> WebDriverResult<Puzzle> {
+        let grid_info = self.get_grid_info().await?;
+        Ok(Puzzle {
+            width: grid_info.width,
+            height: grid_info.height,
+            clues: self.get_clues(&grid_info.clue_positions).await?,
+            shaded_squares: grid_info.shaded_squares,
+            circled: grid_info.circled,
+        })
+    }
+
+    /// Load the structure of the puzzle from the NYT JSON API, which is much faster than
+    /// scraping the page. Falls back to scraping if the request fails
+    pub async fn get_puzzle_via_api(&self) -> WebDriverResult<Puzzle> {
+        let Some(url) = api_url(&self.puzzle_url) else {
+            return self.get_puzzle().await;
+        };
+        let puzzle = match self
+            .driver
+            .execute_async(FETCH_SCRIPT, vec![serde_json::Value::from(url)])
+            .await
         {
-            // Determine the direction by the header at the top of the div
-            let direction = match clue_list.find(By::Tag("H3")).await?.text().await?.as_str() {
-                "ACROSS" => Direction::Across,
-                "DOWN" => Direction::Down,
-                s => panic!("Unexpected clue header {}", s),
-            };
-            // Scan through each clue in the list, grabbing number and text
-            for clue in clue_list.find_all(By::Tag("LI")).await? {
-                // Number and Text are in two different spans with different class names
-                let number: usize = clue
-                    .find(By::ClassName("xwd__clue--label"))
-                    .await?
-                    .text()
-                    .await?
-                    .parse()
-                    .expect("Unable to turn clue label into number");
-                let text = clue
-                    .find(By::ClassName("xwd__clue--text"))
-                    .await?
-                    .text()
-                    .await?;
-                clues.push(Clue {
-                    number,
-                    direction,
-                    text,
-                    // Look where the clue is in the puzzle in our HashMap above
-                    position: *clue_positions
-                        .get(&number)
-                        .expect("Clue not found in puzzle"),
-                });
+            Ok(ret) => parse_api_puzzle(ret.json().clone()).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match puzzle {
+            Ok(puzzle) => Ok(puzzle),
+            Err(e) => {
+                self.report(&format!(
+                    "Unable to load puzzle from API ({}), scraping page instead",
+                    e
+                ));
+                self.get_puzzle().await
             }
         }
-        Ok(clues)
     }
 
-    async fn enter_in_cell(&self, cell_id: usize, text: impl AsRef<str>) -> WebDriverResult<()> {
-        // Get cell based on position
-        let cell = self.0.find(By::Id(&format!("cell-id-{}", cell_id))).await?;
-        // Enter character into the cell
-        self.0
+    /// Type into the cell whose rect has the given id, waiting until it shows `letters`
+    async fn enter_in_cell(
+        &self,
+        id: &str,
+        text: impl AsRef<str>,
+        letters: &str,
+    ) -> WebDriverResult<()> {
+        let text = text.as_ref();
+        let typed = with_retries(WEBDRIVER_ATTEMPTS, || self.type_in_cell(id, text, letters)).await;
+        self.diagnose("enter", typed).await
+    }
+
+    async fn type_in_cell(
+        &self,
+        id: &str,
+        text: impl AsRef<str>,
+        letters: &str,
+    ) -> WebDriverResult<()> {
+        self.ensure_interactable().await?;
+        let cell = self.driver.find(By::Id(id)).await?;
+        // Enter character into the cell
+        self.driver
+            .action_chain()
+            .click_element(&cell)
+            .send_keys(text)
+            .perform()
+            .await?;
+        // The page draws each key as soon as it is typed, so there is rarely anything to wait
+        // for. A cell that never shows it is left for verifying the grid to catch
+        let floor = tokio::time::sleep(self.entry_delay);
+        wait_until(ENTRY_TIMEOUT, ENTRY_POLL_INTERVAL, || {
+            self.cell_shows(id, letters)
+        })
+        .await?;
+        floor.await;
+        Ok(())
+    }
+
+    /// Type a word into an entry in one go, from the cell with the id `ids[start]`, letting the
+    /// page move the cursor along the entry. Returns whether the entry ends up showing `letters`
+    async fn enter_word(
+        &self,
+        ids: &[String],
+        start: usize,
+        keys: &str,
+        letters: &[String],
+    ) -> WebDriverResult<bool> {
+        let typed = with_retries(WEBDRIVER_ATTEMPTS, || {
+            self.type_word(ids, start, keys, letters)
+        })
+        .await;
+        self.diagnose("enter", typed).await
+    }
+
+    async fn type_word(
+        &self,
+        ids: &[String],
+        start: usize,
+        keys: &str,
+        letters: &[String],
+    ) -> WebDriverResult<bool> {
+        self.ensure_interactable().await?;
+        let cell = self.driver.find(By::Id(&ids[start])).await?;
+        cell.click().await?;
+        // Clicking the selected cell again switches between its across and down entries
+        if !self.entry_selected(ids).await? {
+            cell.click().await?;
+            if !self.entry_selected(ids).await? {
+                return Ok(false);
+            }
+        }
+        self.driver.action_chain().send_keys(keys).perform().await?;
+        let floor = tokio::time::sleep(self.entry_delay);
+        let shown = wait_until(ENTRY_TIMEOUT, ENTRY_POLL_INTERVAL, || {
+            self.entry_shows(ids, letters)
+        })
+        .await?;
+        floor.await;
+        Ok(shown)
+    }
+
+    /// Whether the cells with the given ids make up the highlighted entry
+    async fn entry_selected(&self, ids: &[String]) -> WebDriverResult<bool> {
+        let selected = self
+            .driver
+            .execute(ENTRY_SELECTED_SCRIPT, vec![serde_json::json!(ids)])
+            .await?;
+        Ok(selected.json().as_bool().unwrap_or(false))
+    }
+
+    /// Whether the cells with the given ids show exactly these letters
+    async fn entry_shows(&self, ids: &[String], letters: &[String]) -> WebDriverResult<bool> {
+        let texts = self
+            .driver
+            .execute(ENTRY_TEXT_SCRIPT, vec![serde_json::json!(ids)])
+            .await?;
+        let Some(texts) = texts.json().as_array().cloned() else {
+            return Ok(false);
+        };
+        Ok(texts.len() == letters.len()
+            && texts.iter().zip(letters).all(|(text, letters)| {
+                text.as_str()
+                    .is_some_and(|text| cell_letters(text).eq_ignore_ascii_case(letters))
+            }))
+    }
+
+    /// Resume the puzzle if a modal has paused it. The solver can spend minutes between two
+    /// letters waiting on the LLM, which is long enough for the page to pause itself. Only looks
+    /// for the modals once when none are shown, so it is cheap to call before every click
+    async fn ensure_interactable(&self) -> WebDriverResult<()> {
+        if dismiss_present(&self.driver, &PAUSE_MODALS).await? == 0 {
+            return Ok(());
+        }
+        // The modal fades out, and takes clicks until it has
+        wait_until(PAUSE_TIMEOUT, ENTRY_POLL_INTERVAL, || async {
+            for selector in PAUSE_MODALS {
+                if !self.driver.find_all(By::Css(selector)).await?.is_empty() {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Whether the cell with the given id shows exactly these letters
+    async fn cell_shows(&self, id: &str, letters: &str) -> WebDriverResult<bool> {
+        let text = self
+            .driver
+            .execute(CELL_TEXT_SCRIPT, vec![serde_json::Value::from(id)])
+            .await?;
+        Ok(text
+            .json()
+            .as_str()
+            .is_some_and(|text| cell_letters(text).eq_ignore_ascii_case(letters)))
+    }
+
+    /// Whether the page shows the account menu of a logged in user
+    pub async fn logged_in(&self) -> WebDriverResult<bool> {
+        wait_until(LOGIN_TIMEOUT, LOGIN_POLL_INTERVAL, || async {
+            Ok(!self
+                .driver
+                .find_all(By::Css(ACCOUNT_MENU))
+                .await?
+                .is_empty())
+        })
+        .await
+    }
+
+    /// Time on the timer of the page, or None if the page does not show one
+    pub async fn elapsed_time(&self) -> WebDriverResult<Option<Duration>> {
+        let timers = self.driver.find_all(By::Css(TIMER)).await?;
+        let Some(timer) = timers.first() else {
+            return Ok(None);
+        };
+        let text = timer.text().await?;
+        parse_timer(&text)
+            .map(Some)
+            .ok_or_else(|| scrape_error(format!("unexpected timer {:?}", text)))
+    }
+
+    /// Make sure the timer is running before the first letter is typed. The pause modals are
+    /// dismissed, but the page also sometimes starts with the timer stopped until the board is
+    /// clicked
+    pub async fn start_timer(&self) -> WebDriverResult<()> {
+        self.ensure_interactable().await?;
+        let Some(shown) = self.elapsed_time().await? else {
+            return Ok(());
+        };
+        let ticking = wait_until(TIMER_TICK, TIMER_POLL_INTERVAL, || async {
+            Ok(self.elapsed_time().await? != Some(shown))
+        })
+        .await?;
+        if ticking {
+            return Ok(());
+        }
+        // Clicking a cell only selects it, so it is safe whichever one is clicked
+        let grid_info = self.get_grid_info().await?;
+        if let Some(id) = grid_info.ids.values().next() {
+            self.driver.find(By::Id(id)).await?.click().await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the puzzle has been marked complete by NYT
+    pub async fn is_complete(&self) -> WebDriverResult<bool> {
+        with_retries(WEBDRIVER_ATTEMPTS, || async {
+            Ok(!self
+                .driver
+                .find_all(By::Css(CONGRATS_MODAL))
+                .await?
+                .is_empty())
+        })
+        .await
+    }
+
+    /// How far the puzzle has been solved, e.g. to not solve one again that was already solved
+    /// in this browser or account
+    pub async fn completion_state(&self) -> WebDriverResult<CompletionState> {
+        let congrats = self.is_complete().await?;
+        let cells = self.scrape_cells().await;
+        let cells = self.diagnose("read-grid", cells).await?;
+        Ok(classify_completion(congrats, &cells))
+    }
+
+    /// Letters in the grid that an earlier use of the Check feature marked incorrect
+    pub async fn wrong_letters(&self) -> WebDriverResult<HashMap<Position, String>> {
+        let grid_info = self.get_grid_info().await;
+        Ok(self.diagnose("read-grid", grid_info).await?.wrong)
+    }
+
+    /// Empty every cell with Clear → Puzzle in the toolbar, so a solve starts from a blank grid
+    /// rather than letters left by hand. Deletes the letters with the keyboard when the menu
+    /// can't be found
+    pub async fn clear_grid(&self) -> WebDriverResult<()> {
+        let cleared = clear_grid_with(self).await;
+        self.diagnose("clear", cleared).await
+    }
+
+    /// Empty every cell and restart the timer with the Clear feature of the toolbar, which also
+    /// makes a solved puzzle playable again
+    pub async fn clear_puzzle(&self) -> WebDriverResult<()> {
+        let cleared = self.clear_puzzle_and_timer().await;
+        self.diagnose("clear", cleared).await
+    }
+
+    async fn clear_puzzle_and_timer(&self) -> WebDriverResult<()> {
+        self.ensure_interactable().await?;
+        self.driver.find(By::Css(CLEAR_MENU)).await?.click().await?;
+        self.driver
+            .find(By::XPath(CLEAR_PUZZLE_AND_TIMER))
+            .await?
+            .click()
+            .await?;
+        dismiss_any(&self.driver, &CLEAR_CONFIRM, PAUSE_TIMEOUT).await?;
+        let state = self.completion_state().await?;
+        if state != CompletionState::NotStarted {
+            return Err(scrape_error(format!(
+                "the puzzle is {:?} after clearing it",
+                state
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read the solution with the Reveal feature of the toolbar, which fills in every cell. The
+    /// puzzle is left revealed, so it no longer counts as solved by NYT
+    pub async fn reveal_solution(&self) -> WebDriverResult<Solution> {
+        let revealed = self.reveal_puzzle().await;
+        self.diagnose("reveal", revealed).await
+    }
+
+    async fn reveal_puzzle(&self) -> WebDriverResult<Solution> {
+        self.ensure_interactable().await?;
+        self.driver
+            .find(By::Css(REVEAL_MENU))
+            .await?
+            .click()
+            .await?;
+        self.driver
+            .find(By::XPath(REVEAL_PUZZLE))
+            .await?
+            .click()
+            .await?;
+        dismiss_any(&self.driver, &REVEAL_CONFIRM, PAUSE_TIMEOUT).await?;
+        // The letters are drawn in as the modal closes
+        wait_until(REVEAL_TIMEOUT, ENTRY_POLL_INTERVAL, || async {
+            Ok(solution_from_grid(&self.get_grid_info().await?).is_ok())
+        })
+        .await?;
+        solution_from_grid(&self.get_grid_info().await?)
+            .map_err(|position| scrape_error(format!("no letter was revealed at {:?}", position)))
+    }
+
+    /// Sometimes the keep trying box pops-up if we have filled the Grid incorrectly
+    pub async fn maybe_keep_trying(&self) -> WebDriverResult<()> {
+        with_retries(WEBDRIVER_ATTEMPTS, || async {
+            for wbe in self
+                .driver
+                .find_all(By::Css("button[aria-label=\"Keep trying\"]"))
+                .await?
+            {
+                wbe.click().await?
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Check every cell with the Check feature of the toolbar, returning the Positions of the
+    /// cells it marks incorrect. Empty cells are never marked
+    pub async fn check_puzzle(&self) -> WebDriverResult<Vec<Position>> {
+        self.ensure_interactable().await?;
+        self.driver.find(By::Css(CHECK_MENU)).await?.click().await?;
+        self.driver
+            .find(By::XPath(PUZZLE_MENU_ITEM))
+            .await?
+            .click()
+            .await?;
+        Ok(self.get_grid_info().await?.incorrect)
+    }
+}
+
+/// The steps of clearing the grid, kept apart from the page so that the fallback can be tested
+#[async_trait]
+trait ClearGrid: Sync {
+    /// Empty every cell with Clear → Puzzle in the toolbar. Returns false, without touching the
+    /// grid, when the menu can't be found
+    async fn clear_with_menu(&self) -> WebDriverResult<bool>;
+
+    /// Select the last open cell and press Backspace `presses` times
+    async fn clear_with_keys(&self, presses: usize) -> WebDriverResult<()>;
+
+    /// Number of cells that can hold a letter
+    async fn cell_count(&self) -> WebDriverResult<usize>;
+
+    /// Positions of the cells that show a letter, including those marked incorrect
+    async fn filled_cells(&self) -> WebDriverResult<Vec<Position>>;
+}
+
+/// Empty every cell of the grid, deleting the letters one by one when the menu is missing, and
+/// make sure none are left
+async fn clear_grid_with(grid: &impl ClearGrid) -> WebDriverResult<()> {
+    if !grid.clear_with_menu().await? {
+        // Backspace empties the selected cell, or moves back to the one before it when it is
+        // already empty, so two presses per cell always reach the first one
+        let presses = 2 * grid.cell_count().await?;
+        grid.clear_with_keys(presses).await?;
+    }
+    let filled = grid.filled_cells().await?;
+    if !filled.is_empty() {
+        return Err(scrape_error(format!(
+            "{:?} still have letters after clearing the grid",
+            filled
+        )));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ClearGrid for MiniCrosswordWebDriver {
+    async fn clear_with_menu(&self) -> WebDriverResult<bool> {
+        self.ensure_interactable().await?;
+        let Some(menu) = self.driver.find_all(By::Css(CLEAR_MENU)).await?.pop() else {
+            return Ok(false);
+        };
+        menu.click().await?;
+        let Some(item) = self
+            .driver
+            .find_all(By::XPath(PUZZLE_MENU_ITEM))
+            .await?
+            .pop()
+        else {
+            // Close the menu again so it doesn't cover the grid
+            menu.click().await?;
+            return Ok(false);
+        };
+        item.click().await?;
+        dismiss_any(&self.driver, &CLEAR_CONFIRM, PAUSE_TIMEOUT).await?;
+        Ok(true)
+    }
+
+    async fn clear_with_keys(&self, presses: usize) -> WebDriverResult<()> {
+        self.ensure_interactable().await?;
+        let grid = self.get_grid_info().await?;
+        let Some(id) = open_cells(&grid, |_, id| id.to_string())
+            .into_iter()
+            .max_by_key(|(position, _)| *position)
+            .map(|(_, id)| id)
+        else {
+            return Ok(());
+        };
+        let cell = self.driver.find(By::Id(&id)).await?;
+        self.driver
             .action_chain()
             .click_element(&cell)
-            .send_keys(text)
+            .send_keys(Key::Backspace.to_string().repeat(presses))
             .perform()
             .await?;
-        // We wait here to not spam thru
-        wait_on_entry().await;
         Ok(())
     }
 
-    /// Whether the puzzle has been marked complete by NYT
-    pub async fn is_complete(&self) -> WebDriverResult<bool> {
-        Ok(!self
-            .0
-            .find_all(By::ClassName("xwd__congrats-modal--content"))
-            .await?
-            .is_empty())
+    async fn cell_count(&self) -> WebDriverResult<usize> {
+        Ok(open_cells(&self.get_grid_info().await?, |_, _| ()).len())
     }
 
-    /// Sometimes the keep trying box pops-up if we have filled the Grid incorrectly
-    pub async fn maybe_keep_trying(&self) -> WebDriverResult<()> {
-        for wbe in self
-            .0
-            .find_all(By::Css("button[aria-label=\"Keep trying\"]"))
-            .await?
-        {
-            wbe.click().await?
-        }
-        Ok(())
+    async fn filled_cells(&self) -> WebDriverResult<Vec<Position>> {
+        let grid = self.get_grid_info().await?;
+        Ok(grid
+            .letters
+            .keys()
+            .chain(grid.wrong.keys())
+            .copied()
+            .sorted()
+            .collect())
     }
 }
 
@@ -202,28 +1639,39 @@ impl MiniCrosswordWebDriver {
 pub struct WebCell {
     cell: InMemoryCell,
     driver: MiniCrosswordWebDriver,
-    cell_id: usize,
+    /// HTML id of the cell's rect
+    id: String,
 }
 
 impl WebCell {
-    pub fn new(position: Position, driver: MiniCrosswordWebDriver, cell_id: usize) -> Self {
+    /// Cell of the page whose value is mirrored in the shared GridState
+    pub fn new(
+        position: Position,
+        state: &GridState,
+        driver: MiniCrosswordWebDriver,
+        id: String,
+    ) -> Self {
         Self {
             driver,
-            cell: InMemoryCell::new(position, None),
-            cell_id,
+            cell: InMemoryCell::new(position, state),
+            id,
         }
     }
 
-    async fn value(&self) -> Option<char> {
+    async fn value(&self) -> Option<String> {
         self.cell.value().await
     }
 
-    async fn write(&self, c: char) -> WebDriverResult<()> {
-        if self.value().await != Some(c) {
-            self.driver
-                .enter_in_cell(self.cell_id, c.to_string())
-                .await?;
-            self.cell.write(c).await;
+    async fn write(&self, value: String) -> WebDriverResult<()> {
+        if self.value().await.as_ref() != Some(&value) {
+            let keys = if value.chars().count() > 1 {
+                // Insert opens the rebus editor for the selected cell and Enter closes it
+                format!("{}{}{}", Key::Insert, value, Key::Enter)
+            } else {
+                value.clone()
+            };
+            self.driver.enter_in_cell(&self.id, keys, &value).await?;
+            self.cell.write(value).await;
         }
         Ok(())
     }
@@ -231,7 +1679,7 @@ impl WebCell {
     async fn clear(&self) -> WebDriverResult<()> {
         if self.value().await.is_some() {
             self.driver
-                .enter_in_cell(self.cell_id, Key::Backspace.to_string())
+                .enter_in_cell(&self.id, Key::Backspace.to_string(), "")
                 .await?;
             self.cell.clear().await;
         }
@@ -246,194 +1694,969 @@ impl WebCell {
 #[derive(Clone)]
 pub struct WebEntry {
     cells: Vec<WebCell>,
+    circled: Vec<Position>,
     clue: Clue,
 }
 
-impl WebEntry {
-    pub fn clue(&self) -> Clue {
-        self.clue.clone()
+#[async_trait]
+impl Entry for WebEntry {
+    fn clue(&self) -> &Clue {
+        &self.clue
     }
+
     fn positions(&self) -> Vec<Position> {
         self.cells.iter().map(|c| c.position()).collect()
     }
 
-    pub async fn chars(&self) -> Vec<Option<char>> {
+    fn circled(&self) -> Vec<Position> {
+        self.circled.clone()
+    }
+
+    async fn cells(&self) -> Vec<Option<String>> {
         futures::future::join_all(self.cells.iter().map(|c| c.value()))
             .await
             .into_iter()
             .collect()
     }
 
-    pub async fn write(&self, answer: String) -> Result<(), WebDriverError> {
-        self.write_chars(answer.chars().map(Some).collect()).await
-    }
-
-    pub async fn write_chars(&self, chars: Vec<Option<char>>) -> Result<(), WebDriverError> {
-        for (cell, char) in self.cells.iter().zip(chars) {
-            if let Some(c) = char {
-                cell.write(c).await?;
-            } else {
-                cell.clear().await?;
+    async fn write_cells(&self, cells: Vec<Option<String>>) -> Result<(), WebDriverError> {
+        match cells.iter().cloned().collect::<Option<Vec<String>>>() {
+            Some(letters) if letters.iter().all(|l| l.chars().count() == 1) => {
+                self.write_fast(&letters.concat()).await
             }
+            _ => self.write_each(cells).await,
         }
-        Ok(())
     }
 
-    pub async fn clear(&self) -> Result<(), WebDriverError> {
-        for cell in self.cells.iter() {
-            cell.clear().await?;
+    async fn write_cell(
+        &self,
+        position: Position,
+        value: Option<String>,
+    ) -> Result<(), EntryError> {
+        let Some(cell) = self.cells.iter().find(|c| c.position() == position) else {
+            return Err(EntryError::OutsideEntry(position));
+        };
+        match value {
+            Some(value) => cell.write(value).await?,
+            None => cell.clear().await?,
         }
         Ok(())
     }
-    pub async fn value(&self) -> String {
-        self.chars()
-            .await
-            .iter()
-            .map(|c| c.unwrap_or('_'))
-            .collect()
-    }
-
-    /// Length of the full Entry
-    pub async fn length(&self) -> usize {
-        self.chars().await.len()
-    }
+}
 
-    /// Return a boolean if the provided answer fits with letters already populated in the Entry
-    pub async fn fits(&self, ans: &str) -> bool {
-        self.chars()
-            .await
-            .iter()
-            .zip(ans.chars())
-            .all(|(cell, c)| cell.is_none() || cell == &Some(c))
-    }
-    /// Whether the Entry has been fully populated
-    pub async fn filled(&self) -> bool {
-        self.chars().await.iter().all(|c| c.is_some())
-    }
-
-    // Return any Entries that conflict with entering the provided answer
-    pub async fn conflicting_entries(&self, answer: &str, entries: &[Self]) -> Vec<Self> {
-        let mut conflicts = vec![];
-        for (c, ans) in self.cells.iter().zip(answer.chars()) {
-            if c.value().await.is_some_and(|ch| ch != ans) {
-                conflicts.push(entry_for_cell(
-                    c.position(),
-                    self.clue().direction.cross(),
-                    entries,
-                ));
+impl WebEntry {
+    /// Write the answer by clicking into the entry once and typing the whole word, instead of
+    /// clicking every cell. Falls back to writing cell by cell if the page does not show the
+    /// answer afterwards
+    pub async fn write_fast(&self, answer: &str) -> WebDriverResult<()> {
+        let letters: Vec<String> = answer.chars().map(String::from).collect();
+        if letters.len() != self.cells.len() {
+            return Err(WebDriverError::CustomError(format!(
+                "{} does not fit in {} cells",
+                answer,
+                self.cells.len()
+            )));
+        }
+        if let Some((start, keys)) = fast_keys(&self.cells().await, &letters) {
+            let ids = self.cells.iter().map(|c| c.id.clone()).collect_vec();
+            if self.cells[start]
+                .driver
+                .enter_word(&ids, start, &keys, &letters)
+                .await?
+            {
+                for (cell, letter) in self.cells.iter().zip(letters) {
+                    cell.cell.write(letter).await;
+                }
+                return Ok(());
+            }
+            // Letters may have landed anywhere along the entry, so retype every cell
+            for cell in self.cells.iter() {
+                cell.cell.clear().await;
             }
         }
-
-        conflicts
+        self.write_each(letters.into_iter().map(Some).collect())
+            .await
     }
 
-    // Get all crossing Entry values
-    pub fn crossing_entries(&self, entries: Vec<Self>) -> Vec<Self> {
-        let positions = self.positions();
-        entries
-            .into_iter()
-            .filter_map(|e| {
-                if e.clue().direction != self.clue().direction
-                    && e.positions().iter().any(|p| positions.contains(p))
-                {
-                    Some(e)
-                } else {
-                    None
-                }
-            })
-            .rev()
-            .collect()
+    /// Click each cell and type its letters, skipping cells that already show them
+    async fn write_each(&self, cells: Vec<Option<String>>) -> WebDriverResult<()> {
+        for (cell, value) in self.cells.iter().zip(cells) {
+            if let Some(c) = value {
+                cell.write(c).await?;
+            } else {
+                cell.clear().await?;
+            }
+        }
+        Ok(())
     }
 }
 
 pub struct MiniWebPuzzle {
     driver: MiniCrosswordWebDriver,
+    use_check: bool,
+    scrape_cache: Option<ScrapeCache>,
 }
 
 impl MiniWebPuzzle {
     pub async fn new(
         chromedriver_server_url: &str,
-        binary_path: Option<&str>,
+        options: &DriverOptions,
+    ) -> WebDriverResult<Self> {
+        Self::open(chromedriver_server_url, MINI_URL, options).await
+    }
+
+    /// Solve the puzzle at the provided URL, such as [`DAILY_URL`]
+    pub async fn open(
+        chromedriver_server_url: &str,
+        puzzle_url: &str,
+        options: &DriverOptions,
     ) -> WebDriverResult<Self> {
         Ok(MiniWebPuzzle {
-            driver: MiniCrosswordWebDriver::create(chromedriver_server_url, binary_path).await?,
+            driver: MiniCrosswordWebDriver::open(chromedriver_server_url, puzzle_url, options)
+                .await?,
+            use_check: false,
+            scrape_cache: None,
+        })
+    }
+
+    /// Solve the puzzle at the provided URL as the subscriber the cookies were exported from
+    pub async fn login(
+        chromedriver_server_url: &str,
+        puzzle_url: &str,
+        options: &DriverOptions,
+        cookies: &[SessionCookie],
+    ) -> Result<Self, LoginError> {
+        Ok(MiniWebPuzzle {
+            driver: MiniCrosswordWebDriver::login(
+                chromedriver_server_url,
+                puzzle_url,
+                options,
+                cookies,
+            )
+            .await?,
+            use_check: false,
+            scrape_cache: None,
         })
     }
 
-    pub async fn generate_entries(&self) -> WebDriverResult<Vec<WebEntry>> {
-        let mut cells: HashMap<Position, WebCell> = HashMap::new();
+    /// Shortest time spent typing each letter, whether or not the page has already shown it.
+    /// Defaults to [`DEFAULT_ENTRY_DELAY`]
+    pub fn entry_delay(mut self, delay: Duration) -> Self {
+        self.driver.entry_delay = delay;
+        self
+    }
 
-        // Generate new Cells for the Grid, but we want to make sure only one Cell is created for
-        // each position
-        let grid_info = self.driver.get_grid_info().await?;
-        Ok(self
-            .driver
-            .get_clues(grid_info.clue_positions)
-            .await?
+    /// Reuse the structure of the puzzle saved by an earlier run on the same day, instead of
+    /// reading it from NYT again. The cache is refreshed if it does not match the page
+    pub fn scrape_cache(mut self, cache: ScrapeCache) -> Self {
+        self.scrape_cache = Some(cache);
+        self
+    }
+
+    /// Use the Check feature to find the wrong answers when the grid is not solved. Checked
+    /// puzzles are not counted as solved without help by NYT
+    pub fn use_check(mut self, use_check: bool) -> Self {
+        self.use_check = use_check;
+        self
+    }
+
+    /// Structure of the puzzle being solved
+    pub async fn puzzle(&self) -> WebDriverResult<Puzzle> {
+        self.driver.get_puzzle_via_api().await
+    }
+
+    /// Structure of the puzzle on the page, from the scrape cache if it has it
+    async fn structure(&self, grid: &WebGridInfo) -> WebDriverResult<Puzzle> {
+        let Some(cache) = &self.scrape_cache else {
+            return self.driver.get_puzzle_via_api().await;
+        };
+        let url = &self.driver.puzzle_url;
+        if let Some(puzzle) = cache.load(url).filter(|p| matches_grid(p, grid)) {
+            return Ok(puzzle);
+        }
+        let puzzle = self.driver.get_puzzle_via_api().await?;
+        // The run can go on without it, only the next one is slower
+        if let Err(e) = cache.save(url, &puzzle) {
+            self.driver.report(&format!(
+                "Unable to save the puzzle to the scrape cache: {}",
+                e
+            ));
+        }
+        Ok(puzzle)
+    }
+
+    /// How far the puzzle has already been solved
+    pub async fn completion_state(&self) -> WebDriverResult<CompletionState> {
+        self.driver.completion_state().await
+    }
+
+    /// Empty every cell and restart the timer, to solve a puzzle again from scratch
+    pub async fn clear_puzzle(&self) -> WebDriverResult<()> {
+        self.driver.clear_puzzle().await
+    }
+
+    /// Empty every cell without touching the timer, so no letters on the page are built on
+    pub async fn clear_grid(&self) -> WebDriverResult<()> {
+        self.driver.clear_grid().await
+    }
+
+    /// Letters marked incorrect by checking the puzzle before it was opened, e.g. in an earlier
+    /// run that is being resumed. These cells are read as empty by `generate_entries`
+    pub async fn wrong_letters(&self) -> WebDriverResult<HashMap<Position, String>> {
+        self.driver.wrong_letters().await
+    }
+}
+
+#[async_trait]
+impl CrosswordPuzzle for MiniWebPuzzle {
+    type Entry = WebEntry;
+
+    async fn generate_entries(&self) -> Result<Vec<WebEntry>, PuzzleError> {
+        // Every cell mirrors its value into the same state, so cells shared by an across and a
+        // down entry always agree. It starts from the letters already on the page, so they are
+        // built on rather than typed again
+        let grid_info = self.driver.get_grid_info().await;
+        let mut grid_info = self.driver.diagnose("read-grid", grid_info).await?;
+        let puzzle = self.structure(&grid_info).await?;
+        let state = GridState::new(std::mem::take(&mut grid_info.letters));
+        // Cells are made once for the whole grid, with the ids they were scraped with, and only
+        // then shared out between the entries
+        let cells = open_cells(&grid_info, |position, id| {
+            WebCell::new(position, &state, self.driver.clone(), id.to_string())
+        });
+        let positions = positions_for_clues(&puzzle.grid(), &puzzle.clues)?;
+        let cells = entry_cells(&puzzle.clues, &positions, &cells)?;
+        Ok(puzzle
+            .clues
             .into_iter()
-            .map(|clue| WebEntry {
-                clue: clue.clone(),
-                cells: positions_for_clue(
-                    &clue,
-                    grid_info.width,
-                    grid_info.height,
-                    &grid_info.shaded_squares,
-                )
-                .into_iter()
-                .map(|p| {
-                    cells.get(&p).cloned().unwrap_or_else(|| {
-                        let cell = WebCell::new(
-                            p,
-                            self.driver.clone(),
-                            p.row * grid_info.width + p.column,
-                        );
-                        cells.insert(p, cell.clone());
-                        cell
-                    })
-                })
-                .collect(),
+            .zip(positions)
+            .zip(cells)
+            .map(|((clue, positions), cells)| WebEntry {
+                clue,
+                circled: circled_positions(&positions, &puzzle.circled),
+                cells,
             })
             .collect())
     }
 
-    pub async fn verify_entries(&self, _entries: &[WebEntry]) -> Result<bool, WebDriverError> {
+    async fn verify_entries(&self, entries: &[WebEntry]) -> Result<Feedback, WebDriverError> {
         if self.driver.is_complete().await? {
-            Ok(true)
-        } else {
-            self.driver.maybe_keep_trying().await?;
-            Ok(false)
+            return Ok(Feedback::Solved);
+        }
+        self.driver.maybe_keep_trying().await?;
+        if !self.use_check {
+            return Ok(Feedback::Unsolved);
         }
+        let incorrect = self.driver.check_puzzle().await;
+        let incorrect = self.driver.diagnose("check", incorrect).await?;
+        Ok(Feedback::incorrect(&incorrect, entries))
+    }
+
+    async fn start_timer(&self) -> Result<(), WebDriverError> {
+        let started = self.driver.start_timer().await;
+        self.driver.diagnose("timer", started).await
+    }
+
+    async fn elapsed_time(&self) -> Result<Option<Duration>, WebDriverError> {
+        self.driver.elapsed_time().await
     }
 }
 
-pub fn entry_for_cell(position: Position, direction: Direction, entries: &[WebEntry]) -> WebEntry {
-    entries
-        .iter()
-        .find(|e| e.positions().contains(&position) && e.clue().direction == direction)
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::HashMap, sync::Mutex, time::Duration};
+
+    use async_trait::async_trait;
+    use thirtyfour::{
+        error::{WebDriverError, WebDriverErrorInfo},
+        prelude::WebDriverResult,
+    };
+
+    use super::{
+        api_url, archive_url, cell_letters, classify_completion, clear_grid_with,
+        cluster_coordinates, entered_letters, entry_cells, fast_keys, grid_ready, is_archive,
+        is_retryable, matches_grid, open_cells, parse_api_puzzle, parse_clues, parse_grid,
+        parse_timer, sanitize_clue_text, solution_from_grid, with_retries, wrong_letters,
+        CellVisibility, ClearGrid, CompletionState, LoginError, ScrapeCache, ScrapedCell,
+        ScrapedClue, SessionCookie, WebGridInfo, DAILY_URL, MINI_URL, WEBDRIVER_RETRY_DELAY,
+    };
+    use crate::{
+        puzzle::{positions_for_clues, PuzzleError},
+        Direction, Position, Puzzle,
+    };
+
+    const MINI_JSON: &str = include_str!("../tests/fixtures/mini1.json");
+    const MINI: &str = include_str!("../tests/fixtures/mini1.yaml");
+
+    #[test]
+    fn parse_api() {
+        let puzzle = parse_api_puzzle(serde_json::from_str(MINI_JSON).unwrap()).unwrap();
+        assert_eq!(puzzle, serde_yaml::from_str::<Puzzle>(MINI).unwrap());
+    }
+
+    #[test]
+    fn timer_formats() {
+        let time = |h: u64, m: u64, s: u64| Some(Duration::from_secs(h * 3600 + m * 60 + s));
+        assert_eq!(parse_timer("0:00"), time(0, 0, 0));
+        assert_eq!(parse_timer("0:42"), time(0, 0, 42));
+        assert_eq!(parse_timer(" 12:05\n"), time(0, 12, 5));
+        assert_eq!(parse_timer("75:00"), time(1, 15, 0));
+        assert_eq!(parse_timer("1:02:03"), time(1, 2, 3));
+        assert_eq!(parse_timer("10:59:59"), time(10, 59, 59));
+        for invalid in [
+            "",
+            "5",
+            "1:5",
+            "1:60",
+            "1:60:00",
+            "1:2:03",
+            "a:bc",
+            "1:02:03:04",
+            ":30",
+        ] {
+            assert_eq!(parse_timer(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn api_urls() {
+        assert_eq!(
+            api_url(MINI_URL).unwrap(),
+            "https://www.nytimes.com/svc/crosswords/v6/puzzle/mini.json"
+        );
+        assert_eq!(
+            api_url(&format!("{}/2023/09/20", DAILY_URL)).unwrap(),
+            "https://www.nytimes.com/svc/crosswords/v6/puzzle/daily/2023-09-20.json"
+        );
+        assert_eq!(api_url("https://example.com/mini"), None);
+    }
+
+    #[test]
+    fn archive_urls() {
+        let date = "2024-03-14".parse().unwrap();
+        let url = archive_url(MINI_URL, date);
+        assert_eq!(
+            url,
+            "https://www.nytimes.com/crosswords/game/mini/2024/03/14"
+        );
+        assert_eq!(
+            archive_url(&format!("{}/", DAILY_URL), date),
+            format!("{}/2024/03/14", DAILY_URL)
+        );
+        assert!(is_archive(&url));
+        assert!(!is_archive(MINI_URL));
+        assert!(!is_archive("https://example.com/a/2024/03/14"));
+        assert_eq!(
+            api_url(&url).unwrap(),
+            "https://www.nytimes.com/svc/crosswords/v6/puzzle/mini/2024-03-14.json"
+        );
+    }
+
+    fn cells(coordinates: &[(&str, &str)]) -> Vec<ScrapedCell> {
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                serde_json::from_value(serde_json::json!({
+                    "id": format!("cell-id-{}", i),
+                    "class": "xwd__cell--cell",
+                    "x": x,
+                    "y": y,
+                    "text": "",
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clustering() {
+        assert_eq!(cluster_coordinates(&[3.0, 36.0, 3.0, 36.0]), [0, 1, 0, 1]);
+        // Cells drawn a fraction of a pixel apart are in the same column, in any order
+        assert_eq!(
+            cluster_coordinates(&[69.2, 3.0, 35.6, 2.8, 36.4, 68.9, 3.3]),
+            [2, 0, 1, 0, 1, 2, 0]
+        );
+        assert_eq!(cluster_coordinates(&[-0.5, 0.4, 33.0]), [0, 0, 1]);
+        assert!(cluster_coordinates(&[]).is_empty());
+    }
+
+    #[test]
+    fn fast_entry_keys() {
+        let letters = |word: &str| word.chars().map(String::from).collect::<Vec<_>>();
+        let shown = |cells: &[Option<&str>]| {
+            cells
+                .iter()
+                .map(|c| c.map(String::from))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            fast_keys(&shown(&[None, None, None]), &letters("CAT")),
+            Some((0, String::from("CAT")))
+        );
+        // The cursor skips over letters that are already there
+        assert_eq!(
+            fast_keys(
+                &shown(&[Some("C"), None, Some("T"), None]),
+                &letters("CATS")
+            ),
+            Some((1, String::from("AS")))
+        );
+        assert_eq!(
+            fast_keys(&shown(&[Some("C"), Some("A"), Some("T")]), &letters("CAT")),
+            None
+        );
+        // Letters to replace and rebus cells are typed one cell at a time
+        assert_eq!(
+            fast_keys(&shown(&[None, Some("O"), None]), &letters("CAT")),
+            None
+        );
+        assert_eq!(
+            fast_keys(
+                &shown(&[None, None]),
+                &[String::from("C"), String::from("AT")]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn ready_grid() {
+        let visibility = |visible, covered| CellVisibility { visible, covered };
+        assert!(grid_ready(&[
+            visibility(true, false),
+            visibility(true, false)
+        ]));
+        // Still loading
+        assert!(!grid_ready(&[]));
+        assert!(!grid_ready(&[
+            visibility(true, false),
+            visibility(false, false)
+        ]));
+        // A modal over part of the grid takes the clicks meant for those cells
+        assert!(!grid_ready(&[
+            visibility(true, true),
+            visibility(true, false)
+        ]));
+    }
+
+    fn cell(class: &str, text: &str, incorrect: bool) -> ScrapedCell {
+        serde_json::from_value(serde_json::json!({
+            "id": "cell-id-0",
+            "class": class,
+            "x": "3",
+            "y": "3",
+            "incorrect": incorrect,
+            "text": text,
+        }))
         .unwrap()
-        .clone()
-}
+    }
+
+    fn clue(header: &str, label: &str) -> ScrapedClue {
+        serde_json::from_value(serde_json::json!({
+            "header": header,
+            "label": label,
+            "text": "Money in hand",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn scrape_grid() {
+        // Cells are not always listed in order, and their ids say nothing of where they are
+        let mut grid = cells(&[("36", "3.0"), ("3", "3"), ("36.4", "35.8"), ("2.7", "36")]);
+        grid[0].text = String::from("1");
+        grid[0].id = String::from("xwd-rect-a");
+        let info = parse_grid(&grid).unwrap();
+        assert_eq!((info.width, info.height), (2, 2));
+        let position = Position { row: 0, column: 1 };
+        assert_eq!(info.clue_positions[&1], position);
+        assert_eq!(info.ids[&position], "xwd-rect-a");
+        assert_eq!(info.ids[&Position { row: 1, column: 0 }], "cell-id-3");
+
+        // The cell that could not be read is named
+        grid[2].y = String::from("auto");
+        let error = parse_grid(&grid).unwrap_err();
+        assert!(error.contains("\"cell-id-2\""), "{}", error);
+        // Cells missing from the grid, or drawn on top of one another
+        assert!(parse_grid(&cells(&[("3", "3"), ("36", "3"), ("3", "36")])).is_err());
+        let error =
+            parse_grid(&cells(&[("3", "3"), ("36", "3"), ("36", "3"), ("3", "36")])).unwrap_err();
+        assert!(error.contains("two cells"), "{}", error);
+        assert!(parse_grid(&[]).is_err());
+    }
+
+    fn stale() -> WebDriverError {
+        WebDriverError::StaleElementReference(WebDriverErrorInfo::new("stale element reference"))
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(is_retryable(&stale()));
+        assert!(is_retryable(&WebDriverError::ScriptTimeout(
+            WebDriverErrorInfo::new("script timeout")
+        )));
+        assert!(!is_retryable(&WebDriverError::NoSuchWindow(
+            WebDriverErrorInfo::new("no such window")
+        )));
+        assert!(!is_retryable(&WebDriverError::CustomError(String::from(
+            "unexpected page"
+        ))));
+    }
 
-// Fork a set of entries into ones that exist in Memory alone
-pub async fn fork_entries(entries: &[WebEntry]) -> HashMap<Clue, InMemoryEntry> {
-    let mut cells = HashMap::new();
-    let mut forked_entries = HashMap::new();
-    for entry in entries {
-        for cell in entry.cells.iter() {
-            if let std::collections::hash_map::Entry::Vacant(e) = cells.entry(cell.position()) {
-                e.insert(InMemoryCell::new(cell.position(), cell.value().await));
+    #[tokio::test(start_paused = true)]
+    async fn retries() {
+        // Fails twice before the page settles
+        let calls = Cell::new(0);
+        let start = tokio::time::Instant::now();
+        let result = with_retries(3, || async {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 {
+                Err(stale())
+            } else {
+                Ok(calls.get())
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(start.elapsed(), WEBDRIVER_RETRY_DELAY * 2);
+
+        // Out of attempts
+        calls.set(0);
+        let result: Result<(), _> = with_retries(2, || async {
+            calls.set(calls.get() + 1);
+            Err(stale())
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(WebDriverError::StaleElementReference(..))
+        ));
+        assert_eq!(calls.get(), 2);
+
+        // Errors that won't go away are returned straight away
+        calls.set(0);
+        let result: Result<(), _> = with_retries(3, || async {
+            calls.set(calls.get() + 1);
+            Err(WebDriverError::CustomError(String::from("unexpected page")))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn completion() {
+        // Cells as the grid script reads them from the page: a clue number, a shaded square and
+        // the letter of a solved cell, marked as correct
+        let scraped = |confirmed: bool, letters: [&str; 3]| -> Vec<ScrapedCell> {
+            serde_json::from_value(serde_json::json!([
+                {"id": "cell-id-0", "class": "xwd__cell--cell", "x": "3", "y": "3",
+                 "confirmed": confirmed, "text": format!("1{}", letters[0])},
+                {"id": "cell-id-1", "class": "xwd__cell--block xwd__cell--nested", "x": "36",
+                 "y": "3", "text": ""},
+                {"id": "cell-id-2", "class": "xwd__cell--cell", "x": "69", "y": "3",
+                 "confirmed": confirmed, "text": letters[1]},
+                {"id": "cell-id-3", "class": "xwd__cell--cell", "x": "102", "y": "3",
+                 "confirmed": confirmed, "text": letters[2]},
+            ]))
+            .unwrap()
+        };
+        assert_eq!(
+            classify_completion(false, &scraped(false, ["", "", ""])),
+            CompletionState::NotStarted
+        );
+        assert_eq!(
+            classify_completion(false, &scraped(false, ["A", "", "C"])),
+            CompletionState::InProgress
+        );
+        // A full grid is not solved until NYT says so
+        assert_eq!(
+            classify_completion(false, &scraped(false, ["A", "B", "C"])),
+            CompletionState::InProgress
+        );
+        assert_eq!(
+            classify_completion(true, &scraped(false, ["A", "B", "C"])),
+            CompletionState::Completed
+        );
+        // The modal was dismissed, but the grid stays marked
+        assert_eq!(
+            classify_completion(false, &scraped(true, ["A", "B", "C"])),
+            CompletionState::Completed
+        );
+        assert_eq!(classify_completion(false, &[]), CompletionState::NotStarted);
+    }
+
+    #[test]
+    fn sanitize_clues() {
+        for (messy, clean) in [
+            ("Money in hand", "Money in hand"),
+            ("  Money\u{a0}in   hand \n", "Money in hand"),
+            ("<i>Word</i> word", "_Word_ word"),
+            (
+                "<em class=\"italic\">Jeopardy!</em> host",
+                "_Jeopardy!_ host",
+            ),
+            ("Tom &amp; Jerry", "Tom & Jerry"),
+            ("&quot;Hi!&quot;", "\"Hi!\""),
+            ("&ldquo;Hi!&rdquo; &mdash; briefly", "\"Hi!\" - briefly"),
+            ("Caf&#233; order", "Café order"),
+            ("Caf&#xE9; order", "Café order"),
+            ("Less than &lt;3", "Less than <3"),
+            ("&lt;i&gt; tag", "<i> tag"),
+            ("&bogus; stays", "&bogus; stays"),
+            ("\u{201c}Yes, chef!\u{201d}", "\"Yes, chef!\""),
+            ("Rock \u{2018}n\u{2019} roll", "Rock 'n' roll"),
+            ("2010\u{2013}2020", "2010-2020"),
+            ("Wait for it\u{2026}", "Wait for it..."),
+            ("Line<br>break<br/>here", "Line break here"),
+            ("<span>Nested <b>bold</b></span> text", "Nested bold text"),
+            ("Soft\u{ad}hyphen", "Softhyphen"),
+            ("Fill in the ___", "Fill in the ___"),
+        ] {
+            assert_eq!(sanitize_clue_text(messy), clean, "{:?}", messy);
+        }
+    }
+
+    #[test]
+    fn fixtures_are_sanitized() {
+        // Saved puzzles hold the same text the page and the API are read into
+        for fixture in [
+            MINI,
+            include_str!("../tests/fixtures/mini2.yaml"),
+            include_str!("../tests/fixtures/mini3.yaml"),
+            include_str!("../tests/fixtures/daily1.yaml"),
+        ] {
+            let puzzle: Puzzle = serde_yaml::from_str(fixture).unwrap();
+            for clue in puzzle.clues {
+                assert_eq!(sanitize_clue_text(&clue.text), clue.text);
             }
         }
-        forked_entries.insert(
-            entry.clue(),
-            InMemoryEntry {
-                cells: entry
-                    .positions()
-                    .iter()
-                    .map(|p| cells.get(p).expect("Missing position!").clone())
-                    .collect(),
-            },
+    }
+
+    /// What reading the page of the puzzle would find
+    fn grid_of(puzzle: &Puzzle) -> WebGridInfo {
+        WebGridInfo {
+            width: puzzle.width,
+            height: puzzle.height,
+            ids: HashMap::new(),
+            clue_positions: puzzle
+                .clues
+                .iter()
+                .map(|c| (c.number, c.position))
+                .collect(),
+            shaded_squares: puzzle.shaded_squares.iter().rev().copied().collect(),
+            circled: vec![],
+            incorrect: vec![],
+            letters: HashMap::new(),
+            wrong: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn cells_of_entries() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let mut grid = grid_of(&puzzle);
+        // The ids drawn on the page need not follow the order of the cells
+        for row in 0..5 {
+            for column in 0..5 {
+                let id = format!("cell-id-{}", 24 - (row * 5 + column));
+                grid.ids.insert(Position { row, column }, id);
+            }
+        }
+        let cells = open_cells(&grid, |position, id| (position, id.to_string()));
+        assert_eq!(cells.len(), 23);
+        assert!(!cells.contains_key(&Position { row: 4, column: 4 }));
+        let positions = positions_for_clues(&puzzle.grid(), &puzzle.clues).unwrap();
+        let entries = entry_cells(&puzzle.clues, &positions, &cells).unwrap();
+        for (entry, positions) in entries.iter().zip(&positions) {
+            assert!(entry.iter().map(|(p, _)| p).eq(positions));
+        }
+        // 8-Across runs into the shaded square in the corner
+        assert_eq!(puzzle.clues[4].text, "Get together");
+        let ids: Vec<&str> = entries[4].iter().map(|(_, id)| id.as_str()).collect();
+        assert_eq!(ids, ["cell-id-4", "cell-id-3", "cell-id-2", "cell-id-1"]);
+
+        // Every clue through a square without a cell is named
+        grid.ids.remove(&Position { row: 2, column: 2 });
+        let cells = open_cells(&grid, |_, id| id.to_string());
+        let error = entry_cells(&puzzle.clues, &positions, &cells).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no cell is drawn for every square of 6-Across, 2-Down"
+        );
+        // as is one through a square outside of the grid
+        grid.ids
+            .insert(Position { row: 2, column: 2 }, String::from("cell-id-12"));
+        let mut outside = positions.clone();
+        outside[0].push(Position { row: 0, column: 5 });
+        let cells = open_cells(&grid, |_, id| id.to_string());
+        assert!(matches!(
+            entry_cells(&puzzle.clues, &outside, &cells),
+            Err(PuzzleError::MissingCells(clues)) if clues == vec![(1, Direction::Across)]
+        ));
+    }
+
+    #[test]
+    fn cached_puzzle_matches_grid() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        assert!(matches_grid(&puzzle, &grid_of(&puzzle)));
+
+        // Another day's puzzle of the same size
+        let mut grid = grid_of(&puzzle);
+        grid.shaded_squares = vec![Position { row: 0, column: 4 }];
+        assert!(!matches_grid(&puzzle, &grid));
+        let mut grid = grid_of(&puzzle);
+        grid.clue_positions
+            .insert(1, Position { row: 0, column: 2 });
+        assert!(!matches_grid(&puzzle, &grid));
+        let mut grid = grid_of(&puzzle);
+        grid.clue_positions
+            .insert(11, Position { row: 4, column: 0 });
+        assert!(!matches_grid(&puzzle, &grid));
+        let mut grid = grid_of(&puzzle);
+        grid.width += 1;
+        assert!(!matches_grid(&puzzle, &grid));
+    }
+
+    #[test]
+    fn revealed_solution() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let mut grid = grid_of(&puzzle);
+        for row in 0..5 {
+            for column in 0..5 {
+                let letter = if (row, column) == (2, 2) { "REB" } else { "A" };
+                grid.letters
+                    .insert(Position { row, column }, letter.to_string());
+            }
+        }
+        // Shaded squares have no letters, even if something is read from them
+        let solution = solution_from_grid(&grid).unwrap();
+        assert_eq!(solution.get(Position { row: 0, column: 0 }), None);
+        assert_eq!(solution.get(Position { row: 0, column: 1 }), Some("A"));
+        assert_eq!(solution.get(Position { row: 2, column: 2 }), Some("REB"));
+        assert_eq!(solution.0.len(), 5);
+        assert!(solution.0.iter().all(|row| row.len() == 5));
+
+        // Still being drawn
+        grid.letters.remove(&Position { row: 3, column: 1 });
+        assert_eq!(
+            solution_from_grid(&grid).unwrap_err(),
+            Position { row: 3, column: 1 }
+        );
+    }
+
+    #[test]
+    fn scrape_cache() {
+        let puzzle: Puzzle = serde_yaml::from_str(MINI).unwrap();
+        let dir = std::env::temp_dir().join(format!("ainyt-scrape-{}", std::process::id()));
+        let today = ScrapeCache::new(&dir, "2024-03-14".parse().unwrap());
+        assert_eq!(today.load(MINI_URL), None);
+        today.save(MINI_URL, &puzzle).unwrap();
+        assert!(dir.join("mini-2024-03-14.yaml").exists());
+        assert_eq!(today.load(MINI_URL), Some(puzzle.clone()));
+        assert_eq!(today.load(DAILY_URL), None);
+
+        // A file copied over from another day is not used
+        let tomorrow = ScrapeCache::new(&dir, "2024-03-15".parse().unwrap());
+        std::fs::copy(
+            dir.join("mini-2024-03-14.yaml"),
+            dir.join("mini-2024-03-15.yaml"),
+        )
+        .unwrap();
+        assert_eq!(tomorrow.load(MINI_URL), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn scrape_clues() {
+        let positions = HashMap::from([(1, Position { row: 0, column: 1 })]);
+        let clues = parse_clues(vec![clue(" Across ", "1")], &positions).unwrap();
+        assert_eq!(clues[0].direction, Direction::Across);
+        assert_eq!(clues[0].position, positions[&1]);
+
+        let error = parse_clues(vec![clue("Sideways", "1")], &positions).unwrap_err();
+        assert_eq!(error, "clue list has header \"Sideways\"");
+        let error = parse_clues(vec![clue("Down", "1a")], &positions).unwrap_err();
+        assert_eq!(error, "Down clue has label \"1a\", not a number");
+        let error = parse_clues(vec![clue("Down", "7")], &positions).unwrap_err();
+        assert_eq!(error, "7-Down is not numbered in the grid");
+    }
+
+    #[test]
+    fn existing_letters() {
+        let open = "xwd__cell--cell";
+        assert_eq!(
+            entered_letters(&cell(open, "1c", false)),
+            Some(String::from("C"))
+        );
+        assert_eq!(
+            entered_letters(&cell(open, "HEART", false)),
+            Some(String::from("HEART"))
+        );
+        assert_eq!(entered_letters(&cell(open, "1", false)), None);
+        assert_eq!(entered_letters(&cell(open, "", false)), None);
+        // Marked wrong by the Check feature
+        assert_eq!(entered_letters(&cell(open, "1C", true)), None);
+        assert_eq!(
+            wrong_letters(&cell(open, "1c", true)),
+            Some(String::from("C"))
+        );
+        assert_eq!(wrong_letters(&cell(open, "1C", false)), None);
+        assert_eq!(wrong_letters(&cell(open, "1", true)), None);
+        assert_eq!(
+            entered_letters(&cell(super::SHADED_SQUARE_CLS, "", false)),
+            None
         );
     }
-    forked_entries
+
+    #[test]
+    fn letters_in_cell() {
+        assert_eq!(cell_letters("1C"), "C");
+        assert_eq!(cell_letters("12"), "");
+        assert_eq!(cell_letters("A"), "A");
+        assert_eq!(cell_letters(" 17 HEART "), "HEART");
+        assert_eq!(cell_letters(""), "");
+    }
+
+    #[test]
+    fn json_cookies() {
+        let cookies = SessionCookie::parse(
+            r#"[
+                {"name": "NYT-S", "value": "secret", "domain": ".nytimes.com", "path": "/",
+                 "secure": true, "httpOnly": true, "expirationDate": 1700000000},
+                {"name": "other", "value": "1", "domain": "example.com"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name, "NYT-S");
+        assert!(cookies[0].secure && cookies[0].http_only);
+        assert!(cookies[0].for_nytimes());
+        assert!(!cookies[1].for_nytimes());
+        assert!(!format!("{:?}", cookies[0]).contains("secret"));
+
+        let error = SessionCookie::parse(r#"[{"name": "NYT-S", "value": 12345}]"#).unwrap_err();
+        assert!(matches!(error, LoginError::InvalidCookies(_)));
+        assert!(!error.to_string().contains("12345"));
+    }
+
+    #[test]
+    fn netscape_cookies() {
+        let cookies = SessionCookie::parse(
+            "# Netscape HTTP Cookie File\n\
+             \n\
+             #HttpOnly_.nytimes.com\tTRUE\t/\tTRUE\t1700000000\tNYT-S\tsecret\n\
+             www.nytimes.com\tFALSE\t/crosswords\tFALSE\t0\tnyt-a\tabc\r\n",
+        )
+        .unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].domain.as_deref(), Some(".nytimes.com"));
+        assert!(cookies[0].secure && cookies[0].http_only);
+        assert_eq!(cookies[1].path.as_deref(), Some("/crosswords"));
+        assert_eq!(cookies[1].value, "abc");
+        assert!(!cookies[1].secure && !cookies[1].http_only);
+        assert!(cookies.iter().all(SessionCookie::for_nytimes));
+
+        assert!(matches!(
+            SessionCookie::parse(".nytimes.com\tTRUE\t/"),
+            Err(LoginError::InvalidCookies(_))
+        ));
+    }
+
+    /// What the Clear menu of a FakeGrid does
+    enum ClearMenu {
+        Missing,
+        Clears,
+        Ignored,
+    }
+
+    /// A single row of cells, full where true, that is cleared from its last cell like the page
+    struct FakeGrid {
+        menu: ClearMenu,
+        cells: Mutex<Vec<bool>>,
+        presses: Mutex<usize>,
+    }
+
+    impl FakeGrid {
+        fn new(menu: ClearMenu, cells: &[bool]) -> Self {
+            Self {
+                menu,
+                cells: Mutex::new(cells.to_vec()),
+                presses: Mutex::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ClearGrid for FakeGrid {
+        async fn clear_with_menu(&self) -> WebDriverResult<bool> {
+            match self.menu {
+                ClearMenu::Missing => Ok(false),
+                ClearMenu::Clears => {
+                    self.cells.lock().unwrap().fill(false);
+                    Ok(true)
+                }
+                ClearMenu::Ignored => Ok(true),
+            }
+        }
+
+        async fn clear_with_keys(&self, presses: usize) -> WebDriverResult<()> {
+            *self.presses.lock().unwrap() += presses;
+            let mut cells = self.cells.lock().unwrap();
+            let mut cursor = cells.len() - 1;
+            for _ in 0..presses {
+                // Backspace empties a full cell, and only steps back from an empty one
+                if cells[cursor] {
+                    cells[cursor] = false;
+                } else {
+                    cursor = cursor.saturating_sub(1);
+                }
+            }
+            Ok(())
+        }
+
+        async fn cell_count(&self) -> WebDriverResult<usize> {
+            Ok(self.cells.lock().unwrap().len())
+        }
+
+        async fn filled_cells(&self) -> WebDriverResult<Vec<Position>> {
+            let cells = self.cells.lock().unwrap();
+            Ok((0..cells.len())
+                .filter(|column| cells[*column])
+                .map(|column| Position { row: 0, column })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn clear_grid_menu() {
+        let grid = FakeGrid::new(ClearMenu::Clears, &[true, false, true]);
+        clear_grid_with(&grid).await.unwrap();
+        assert!(grid.filled_cells().await.unwrap().is_empty());
+        assert_eq!(*grid.presses.lock().unwrap(), 0);
+
+        // Letters left behind are reported rather than built on
+        let grid = FakeGrid::new(ClearMenu::Ignored, &[true, false, true]);
+        let error = clear_grid_with(&grid).await.unwrap_err();
+        assert!(error.to_string().contains("still have letters"));
+    }
+
+    #[tokio::test]
+    async fn clear_grid_fallback() {
+        let grid = FakeGrid::new(ClearMenu::Missing, &[true, true, false, true, true]);
+        clear_grid_with(&grid).await.unwrap();
+        assert!(grid.filled_cells().await.unwrap().is_empty());
+        assert_eq!(*grid.presses.lock().unwrap(), 10);
+
+        let grid = FakeGrid::new(ClearMenu::Missing, &[true; 5]);
+        clear_grid_with(&grid).await.unwrap();
+        assert!(grid.filled_cells().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_api_error() {
+        let error = parse_api_puzzle(serde_json::json!({ "error": "status 403" })).unwrap_err();
+        assert!(error.to_string().contains("status 403"));
+    }
 }
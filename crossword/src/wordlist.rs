@@ -0,0 +1,199 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs, io,
+    path::Path,
+    sync::OnceLock,
+};
+
+/// Answers common in crosswords that few English word lists include, such as crosswordese and
+/// abbreviations
+static CROSSWORD_ANSWERS: &str = include_str!("../wordlists/crossword_answers.txt");
+
+/// Shortest word of a phrase matched by `WordList::recognizes`. Word lists are full of single
+/// letters, which would let almost anything through
+static MIN_PHRASE_WORD: usize = 2;
+
+/// English words grouped by length, each group ranked from most to least common. Used to fill
+/// entries the LLM has no answer for once most of their letters are known
+#[derive(Debug, Clone, Default)]
+pub struct WordList {
+    words: HashMap<usize, Vec<String>>,
+    /// Every word of `words`, to look them up without a scan
+    known: HashSet<String>,
+}
+
+#[derive(Debug)]
+pub enum WordListError {
+    Io(io::Error),
+    /// Word contains something other than ASCII letters
+    InvalidWord {
+        line: usize,
+        word: String,
+    },
+    Empty,
+}
+
+impl fmt::Display for WordListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordListError::Io(e) => write!(f, "unable to read word list: {}", e),
+            WordListError::InvalidWord { line, word } => {
+                write!(f, "line {}: {:?} is not a word", line, word)
+            }
+            WordListError::Empty => write!(f, "word list contains no words"),
+        }
+    }
+}
+
+impl std::error::Error for WordListError {}
+
+impl From<io::Error> for WordListError {
+    fn from(value: io::Error) -> Self {
+        WordListError::Io(value)
+    }
+}
+
+impl WordList {
+    /// Parse a list with one word per line, most common first. Only the rank of a word is kept,
+    /// so frequency lists need no more than their first column. Blank lines are skipped, and a
+    /// word repeated in a different case keeps its first rank
+    pub fn parse(text: &str) -> Result<Self, WordListError> {
+        let mut seen = HashSet::new();
+        let mut words: HashMap<usize, Vec<String>> = HashMap::new();
+        for (i, l) in text.lines().enumerate() {
+            let Some(word) = l.split_whitespace().next() else {
+                continue;
+            };
+            if !word.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(WordListError::InvalidWord {
+                    line: i + 1,
+                    word: word.to_string(),
+                });
+            }
+            let word = word.to_ascii_uppercase();
+            if seen.insert(word.clone()) {
+                words.entry(word.len()).or_default().push(word);
+            }
+        }
+        if words.is_empty() {
+            return Err(WordListError::Empty);
+        }
+        Ok(Self { words, known: seen })
+    }
+
+    /// Common crossword answers bundled with the crate, checked alongside a WordList
+    pub fn crossword_answers() -> &'static WordList {
+        static WORDS: OnceLock<WordList> = OnceLock::new();
+        WORDS.get_or_init(|| {
+            WordList::parse(CROSSWORD_ANSWERS).expect("Invalid bundled crossword answers")
+        })
+    }
+
+    /// Load a word list from a file, in the format read by `WordList::parse`
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, WordListError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Words that match every known letter of the pattern, most common first. Letters are
+    /// matched regardless of case
+    pub fn candidates(&self, pattern: &[Option<char>]) -> Vec<&str> {
+        let Some(words) = self.words.get(&pattern.len()) else {
+            return vec![];
+        };
+        words
+            .iter()
+            .filter(|word| {
+                word.chars().zip(pattern).all(|(letter, known)| {
+                    known.map_or(true, |known| known.eq_ignore_ascii_case(&letter))
+                })
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Whether the word is in the list, regardless of case
+    pub fn contains(&self, word: &str) -> bool {
+        self.known.contains(&word.to_ascii_uppercase())
+    }
+
+    /// Whether the answer is a word of the list or a phrase made up of them, as answers are
+    /// written without spaces
+    pub fn recognizes(&self, answer: &str) -> bool {
+        if self.contains(answer) {
+            return true;
+        }
+        if answer.is_empty() || !answer.is_ascii() {
+            return false;
+        }
+        let answer = answer.to_ascii_uppercase();
+        // Whether the answer up to each index can be split into words
+        let mut split = vec![false; answer.len() + 1];
+        split[0] = true;
+        for end in MIN_PHRASE_WORD..=answer.len() {
+            split[end] = (0..=end - MIN_PHRASE_WORD)
+                .any(|start| split[start] && self.known.contains(&answer[start..end]));
+        }
+        split[answer.len()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WordList, WordListError};
+
+    fn pattern(text: &str) -> Vec<Option<char>> {
+        text.chars().map(|c| (c != '_').then_some(c)).collect()
+    }
+
+    #[test]
+    fn candidates() {
+        let words = WordList::parse("the\nsuave\nstave\nshave\ntrave\ncrave\n").unwrap();
+        assert_eq!(words.len(), 6);
+        assert_eq!(words.candidates(&pattern("T_AVE")), vec!["TRAVE"]);
+        assert_eq!(
+            words.candidates(&pattern("S_AVE")),
+            vec!["SUAVE", "STAVE", "SHAVE"]
+        );
+        assert_eq!(words.candidates(&pattern("__AVE")).len(), 5);
+        assert_eq!(words.candidates(&pattern("t_e")), vec!["THE"]);
+        assert!(words.candidates(&pattern("Q____")).is_empty());
+        assert!(words.candidates(&pattern("____")).is_empty());
+        assert!(words.contains("Shave"));
+        assert!(!words.contains("SHAVEN"));
+    }
+
+    #[test]
+    fn recognizes() {
+        let words = WordList::parse("at\nease\ntea\nset\na\ni\n").unwrap();
+        assert!(words.recognizes("ease"));
+        assert!(words.recognizes("ATEASE"));
+        assert!(words.recognizes("TEASET"));
+        // Single letters are never words of a phrase
+        assert!(!words.recognizes("EASEA"));
+        assert!(!words.recognizes("EASY"));
+        assert!(!words.recognizes(""));
+
+        let answers = WordList::crossword_answers();
+        assert!(answers.contains("ETUI"));
+        assert!(answers.contains("asap"));
+        assert!(!answers.contains("PLEASINGY"));
+    }
+
+    #[test]
+    fn parse() {
+        // Frequency columns are ignored, and the first of a repeated word keeps its rank
+        let words = WordList::parse("Ends 900\n\nends 100\nnest 50\n").unwrap();
+        assert_eq!(words.candidates(&pattern("____")), vec!["ENDS", "NEST"]);
+        let error = WordList::parse("ends\nit's\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: \"it's\" is not a word");
+        assert!(matches!(WordList::parse("\n"), Err(WordListError::Empty)));
+    }
+}
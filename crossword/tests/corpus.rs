@@ -0,0 +1,168 @@
+//! Saved puzzles checked in as fixtures, guarding the formats they are written in. Every file
+//! saved by an earlier version has to keep loading, and saving it again must not change it
+use std::fs;
+
+use crossword::{
+    memory::{InMemoryPuzzle, SavedPuzzle},
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{LLMSolver, MockLLM},
+    Direction, Position,
+};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+/// Saved mini, with the cells of each of its entries
+struct Fixture {
+    name: &'static str,
+    yaml: &'static str,
+    entries: &'static str,
+}
+
+const MINIS: [Fixture; 3] = [
+    Fixture {
+        name: "mini1",
+        yaml: include_str!("fixtures/mini1.yaml"),
+        entries: include_str!("fixtures/mini1_entries.yaml"),
+    },
+    Fixture {
+        name: "mini2",
+        yaml: include_str!("fixtures/mini2.yaml"),
+        entries: include_str!("fixtures/mini2_entries.yaml"),
+    },
+    Fixture {
+        name: "mini3",
+        yaml: include_str!("fixtures/mini3.yaml"),
+        entries: include_str!("fixtures/mini3_entries.yaml"),
+    },
+];
+const MINI_ANSWERS: &str = include_str!("fixtures/mini1_answers.yaml");
+const MINI_CACHE: &str = include_str!("fixtures/mini1_cache.json");
+
+#[derive(Debug, Deserialize)]
+struct ExpectedEntry {
+    number: usize,
+    direction: Direction,
+    length: usize,
+    positions: Vec<Position>,
+}
+
+fn saved(fixture: &Fixture) -> SavedPuzzle {
+    serde_yaml::from_str(fixture.yaml).unwrap()
+}
+
+fn expected_entries(fixture: &Fixture) -> Vec<ExpectedEntry> {
+    serde_yaml::from_str(fixture.entries).unwrap()
+}
+
+#[test]
+fn load_fixtures() {
+    let [mini1, mini2, mini3] = MINIS.map(|fixture| saved(&fixture));
+    let dimensions = |saved: &SavedPuzzle| (saved.puzzle.width, saved.puzzle.height);
+    assert_eq!(dimensions(&mini1), (5, 5));
+    assert_eq!(dimensions(&mini2), (5, 5));
+    assert_eq!(dimensions(&mini3), (4, 4));
+    assert_eq!(mini1.puzzle.clues.len(), 10);
+    assert_eq!(mini2.puzzle.clues.len(), 10);
+    assert_eq!(mini3.puzzle.clues.len(), 8);
+    assert_eq!(mini1.puzzle.shaded_squares.len(), 2);
+    assert_eq!(mini2.puzzle.shaded_squares.len(), 4);
+    assert!(mini3.puzzle.shaded_squares.is_empty());
+
+    assert_eq!(mini1.puzzle.clues[0].text, "Money in hand");
+    assert_eq!(mini1.puzzle.clues[9].text, "Where hogs and cows live");
+    assert_eq!(mini1.puzzle.clues[9].direction, Direction::Down);
+    assert_eq!(mini2.puzzle.clues[6].text, "Spoken, not written");
+    assert_eq!(mini3.puzzle.clues[3].text, "Pub game missile");
+
+    // Files saved before the format was versioned are version 1
+    assert_eq!(mini1.version, 1);
+    assert_eq!(mini2.version, 2);
+    for saved in [mini1, mini2, mini3] {
+        assert!(saved.solution.is_some());
+        assert_eq!(saved.date, None);
+    }
+}
+
+#[tokio::test]
+async fn regenerate_entries() {
+    for fixture in &MINIS {
+        let saved = saved(fixture);
+        let expected = expected_entries(fixture);
+        assert_eq!(expected.len(), saved.puzzle.clues.len(), "{}", fixture.name);
+        let grid = saved.puzzle.grid();
+        for (clue, entry) in saved.puzzle.clues.iter().zip(&expected) {
+            assert_eq!(
+                (clue.number, clue.direction),
+                (entry.number, entry.direction),
+                "{}",
+                fixture.name
+            );
+            assert_eq!(entry.positions.len(), entry.length, "{}", clue);
+            assert_eq!(grid.positions_for_clue(clue), entry.positions, "{}", clue);
+        }
+
+        // The entries the solver works on cover the same cells
+        let puzzle = InMemoryPuzzle::from_yaml(fixture.yaml.as_bytes()).unwrap();
+        let mut entries = puzzle.generate_entries().await.unwrap();
+        entries.sort_by_key(|e| {
+            let clue = e.clue();
+            (clue.direction == Direction::Down, clue.number)
+        });
+        let generated: Vec<_> = entries
+            .iter()
+            .map(|e| (e.clue().number, e.clue().direction, e.positions()))
+            .collect();
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|e| (e.number, e.direction, e.positions))
+            .collect();
+        assert_eq!(generated, expected, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn saved_again_unchanged() {
+    for fixture in &MINIS {
+        let mut original: Value = serde_yaml::from_str(fixture.yaml).unwrap();
+        // The version is written even for files saved before it was
+        if let Value::Mapping(fields) = &mut original {
+            if !fields.contains_key("version") {
+                fields.insert(Value::from("version"), Value::from(1));
+            }
+        }
+        let yaml = serde_yaml::to_string(&saved(fixture)).unwrap();
+        let resaved: Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(resaved, original, "{} changed", fixture.name);
+        // and loads back to the same puzzle
+        assert_eq!(
+            serde_yaml::from_str::<SavedPuzzle>(&yaml).unwrap(),
+            saved(fixture)
+        );
+    }
+}
+
+#[tokio::test]
+async fn solve_from_saved_cache() {
+    // The cache has a wrong answer for the first clue ahead of the right one, which only its
+    // crossings give away
+    let path = std::env::temp_dir().join(format!("ainyt-corpus-{}.json", std::process::id()));
+    fs::write(&path, MINI_CACHE).unwrap();
+    let puzzle = InMemoryPuzzle::from_yaml(MINIS[0].yaml.as_bytes()).unwrap();
+    let llm = MockLLM::from_yaml(MINI_ANSWERS.as_bytes()).unwrap();
+    let mut solver = LLMSolver::with_model(Box::new(llm))
+        .with_cache_file(&path)
+        .unwrap();
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(report.solved);
+    assert!(report.cache_hits > 0);
+    assert_eq!(puzzle.rows().await[0], "#CASH");
+
+    // Every cached answer is still in the file, written the same way
+    let original: serde_json::Value = serde_json::from_str(MINI_CACHE).unwrap();
+    let saved: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    let saved_answers = saved["answers"].as_array().unwrap();
+    for cached in original["answers"].as_array().unwrap() {
+        assert!(saved_answers.contains(cached), "{} is missing", cached);
+    }
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crossword::{
+    memory::InMemoryPuzzle,
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{LLMSolver, MockLLM},
+    Direction,
+};
+
+const DAILY: &str = include_str!("fixtures/daily1.yaml");
+
+#[tokio::test]
+async fn daily_entries() {
+    let puzzle = InMemoryPuzzle::from_yaml(DAILY.as_bytes()).unwrap();
+    let entries = puzzle.generate_entries().await.unwrap();
+    assert_eq!(entries.len(), puzzle.puzzle().clues.len());
+    assert_eq!(entries.iter().map(|e| e.positions().len()).max(), Some(10));
+    // Every open square is part of exactly one across and one down entry
+    let mut counts = HashMap::new();
+    for entry in entries.iter() {
+        for position in entry.positions() {
+            *counts
+                .entry((position, entry.clue().direction))
+                .or_insert(0) += 1;
+        }
+    }
+    let open = 15 * 15 - puzzle.puzzle().shaded_squares.len();
+    assert_eq!(counts.len(), open * 2);
+    assert!(counts.values().all(|count| *count == 1));
+    assert_eq!(
+        counts
+            .keys()
+            .filter(|(_, direction)| *direction == Direction::Across)
+            .count(),
+        open
+    );
+}
+
+#[tokio::test]
+async fn solve_daily() {
+    let puzzle = InMemoryPuzzle::from_yaml(DAILY.as_bytes()).unwrap();
+    let solution = puzzle.solution().unwrap();
+    let mut answers = HashMap::new();
+    for entry in puzzle.generate_entries().await.unwrap() {
+        let answer = entry
+            .positions()
+            .into_iter()
+            .filter_map(|p| solution.get(p))
+            .collect();
+        answers.insert(entry.clue().text.clone(), answer);
+    }
+    let mut solver = LLMSolver::with_model(Box::new(MockLLM::new(answers)));
+    assert!(solver.solve(&puzzle).await.unwrap().solved);
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, time::Duration};
+
+use crossword::{
+    memory::InMemoryPuzzle,
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{Budget, BudgetExceeded, LLMSolver, LlmError, MockLLM, SolverError, SolverEvent},
+};
+
+const MINIS: [&str; 3] = [
+    include_str!("fixtures/mini1.yaml"),
+    include_str!("fixtures/mini2.yaml"),
+    include_str!("fixtures/mini3.yaml"),
+];
+
+fn load(yaml: &str) -> InMemoryPuzzle {
+    InMemoryPuzzle::from_yaml(yaml.as_bytes()).unwrap()
+}
+
+/// Answer to every clue of the puzzle, read from its solution
+async fn answers(puzzle: &InMemoryPuzzle) -> HashMap<String, String> {
+    let solution = puzzle.solution().unwrap();
+    let mut answers = HashMap::new();
+    for entry in puzzle.generate_entries().await.unwrap() {
+        let answer = entry
+            .positions()
+            .into_iter()
+            .filter_map(|p| solution.get(p))
+            .collect();
+        answers.insert(entry.clue().text.clone(), answer);
+    }
+    answers
+}
+
+#[tokio::test]
+async fn clean_solve() {
+    for yaml in MINIS {
+        let puzzle = load(yaml);
+        let mut solver = LLMSolver::with_model(Box::new(MockLLM::new(answers(&puzzle).await)));
+        let report = solver.solve(&puzzle).await.unwrap();
+        assert!(report.solved);
+        assert_eq!(report.conflicts, 0);
+        assert_eq!(report.backtracks, 0);
+        assert!(puzzle.verify().await.solved);
+    }
+}
+
+#[tokio::test]
+async fn backtrack_from_wrong_answer() {
+    let puzzle = load(MINIS[0]);
+    let llm = MockLLM::new(answers(&puzzle).await).wrong_answer("Money in hand", "CASE");
+    let mut solver = LLMSolver::with_model(Box::new(llm));
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(report.solved);
+    assert!(report.backtracks >= 1);
+    assert_eq!(puzzle.rows().await[0], "#CASH");
+}
+
+#[tokio::test]
+async fn ask_once_per_pattern() {
+    let puzzle = load(MINIS[0]);
+    let llm = MockLLM::new(answers(&puzzle).await).wrong_answer("Money in hand", "CASE");
+    let mut solver = LLMSolver::with_model(Box::new(llm));
+    let mut receiver = solver.subscribe();
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(report.solved);
+    assert!(report.backtracks >= 1);
+    assert!(report.cache_hit_rate() > 0.0);
+
+    let mut asked: HashMap<_, usize> = HashMap::new();
+    while let Ok(event) = receiver.try_recv() {
+        if let SolverEvent::ClueAttempted { clue, pattern } = event {
+            *asked
+                .entry((clue.text, clue.direction, pattern))
+                .or_default() += 1;
+        }
+    }
+    for ((clue, _, pattern), count) in asked {
+        // The wrong answer is asked about again once, telling the LLM it is wrong
+        let limit = if clue == "Money in hand" { 2 } else { 1 };
+        assert!(
+            count <= limit,
+            "{} ({}) asked {} times",
+            clue,
+            pattern,
+            count
+        );
+    }
+}
+
+#[tokio::test]
+async fn exhaust_budget() {
+    let puzzle = load(MINIS[0]);
+    let llm = MockLLM::new(answers(&puzzle).await).wrong_answer("Money in hand", "CASE");
+    let mut solver = LLMSolver::with_model(Box::new(llm)).budget(Budget {
+        grid_attempts: Some(1),
+        ..Budget::default()
+    });
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(!report.solved);
+    assert_eq!(report.budget_exceeded, Some(BudgetExceeded::GridAttempts));
+    assert!(!puzzle.verify().await.solved);
+
+    // A slow LLM runs out of time after its first answer
+    let puzzle = load(MINIS[1]);
+    let llm = MockLLM::new(answers(&puzzle).await).delay(Duration::from_millis(20));
+    let mut solver = LLMSolver::with_model(Box::new(llm)).budget(Budget {
+        time: Some(Duration::from_millis(1)),
+        ..Budget::default()
+    });
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(!report.solved);
+    assert_eq!(report.budget_exceeded, Some(BudgetExceeded::Time));
+}
+
+#[tokio::test]
+async fn llm_errors() {
+    let puzzle = load(MINIS[2]);
+    let llm = MockLLM::new(answers(&puzzle).await).failing("Deck member");
+    let mut solver = LLMSolver::with_model(Box::new(llm));
+    assert!(matches!(
+        solver.solve(&puzzle).await,
+        Err(SolverError::Llm(LlmError::Unreachable(_)))
+    ));
+}
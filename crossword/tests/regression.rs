@@ -0,0 +1,174 @@
+//! Guards the quality of the backtracking and ordering of the solver. Each scenario solves a
+//! fixture with an LLM scripted clue by clue and bounds the work it took. The bounds are what
+//! the solver needs today, so loosening one is a deliberate change to this file
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use crossword::{
+    memory::InMemoryPuzzle,
+    puzzle::{CrosswordPuzzle, Entry},
+    solver::{LLMModel, LLMSolver, LlmError, SolveReport},
+};
+
+const MINI: &str = include_str!("fixtures/mini1.yaml");
+
+/// How the scripted LLM answers a clue
+#[derive(Debug, Clone, Copy)]
+enum Script {
+    /// The answer from the solution of the puzzle
+    Correct,
+    /// `answer` for the first `asks` questions about the clue, and the correct one after
+    WrongFor { answer: &'static str, asks: usize },
+    /// Never any answer, so the entry can only be filled by its crossings
+    Silent,
+}
+
+/// Answers every clue from its Script, counting the questions about each
+struct ScriptedLLM {
+    answers: HashMap<String, String>,
+    scripts: HashMap<String, Script>,
+    asked: Mutex<HashMap<String, usize>>,
+}
+
+#[async_trait]
+impl LLMModel for ScriptedLLM {
+    async fn chat(&self, message: String) -> Result<String, LlmError> {
+        let Some(clue) = message
+            .split("<text>")
+            .nth(1)
+            .and_then(|s| s.split("</text>").next())
+            .map(str::trim)
+        else {
+            return Ok(String::new());
+        };
+        let asks = {
+            let mut asked = self.asked.lock().unwrap();
+            let asks = asked.entry(clue.to_string()).or_default();
+            *asks += 1;
+            *asks
+        };
+        let correct = self.answers.get(clue).cloned().unwrap_or_default();
+        Ok(
+            match self.scripts.get(clue).copied().unwrap_or(Script::Correct) {
+                Script::Correct => correct,
+                Script::WrongFor { answer, asks: n } if asks <= n => answer.to_string(),
+                Script::WrongFor { .. } => correct,
+                Script::Silent => String::new(),
+            },
+        )
+    }
+}
+
+/// A fixture, how the LLM answers its clues and the most work the solver may take
+struct Scenario {
+    puzzle: &'static str,
+    scripts: &'static [(&'static str, Script)],
+    max_llm_calls: usize,
+    max_backtracks: usize,
+}
+
+/// Every clue answered correctly the first time
+static CLEAN: Scenario = Scenario {
+    puzzle: MINI,
+    scripts: &[],
+    max_llm_calls: 10,
+    max_backtracks: 0,
+};
+
+/// CASE fits every crossing until 4-Down, so it stays in the grid until that conflict clears it
+static PERSISTENT_WRONG_ANSWER: Scenario = Scenario {
+    puzzle: MINI,
+    scripts: &[(
+        "Money in hand",
+        Script::WrongFor {
+            answer: "CASE",
+            asks: 1,
+        },
+    )],
+    max_llm_calls: 16,
+    max_backtracks: 4,
+};
+
+/// CASE and EYED agree with each other, so only 5- and 6-Across show them to be wrong, and the
+/// entry of 5-Down is only ever filled by its crossings
+static INTERACTING_WRONG_ANSWERS: Scenario = Scenario {
+    puzzle: MINI,
+    scripts: &[
+        (
+            "Money in hand",
+            Script::WrongFor {
+                answer: "CASE",
+                asks: 1,
+            },
+        ),
+        (
+            "Grasped",
+            Script::WrongFor {
+                answer: "EYED",
+                asks: 1,
+            },
+        ),
+        ("Where hogs and cows live", Script::Silent),
+    ],
+    max_llm_calls: 30,
+    max_backtracks: 10,
+};
+
+/// Solve the scenario's puzzle, checking the result against its solution
+async fn run(scenario: &Scenario) -> SolveReport {
+    let puzzle = InMemoryPuzzle::from_yaml(scenario.puzzle.as_bytes()).unwrap();
+    let solution = puzzle.solution().unwrap();
+    let mut answers = HashMap::new();
+    for entry in puzzle.generate_entries().await.unwrap() {
+        let answer = entry
+            .positions()
+            .into_iter()
+            .filter_map(|p| solution.get(p))
+            .collect();
+        answers.insert(entry.clue().text.clone(), answer);
+    }
+    let llm = ScriptedLLM {
+        answers,
+        scripts: scenario
+            .scripts
+            .iter()
+            .map(|(clue, script)| (clue.to_string(), *script))
+            .collect(),
+        asked: Mutex::new(HashMap::new()),
+    };
+    let mut solver = LLMSolver::with_model(Box::new(llm));
+    let report = solver.solve(&puzzle).await.unwrap();
+    assert!(report.solved, "{:?}", report);
+    assert!(puzzle.verify().await.solved);
+    assert!(
+        report.llm_calls <= scenario.max_llm_calls,
+        "{} LLM calls, expected at most {}",
+        report.llm_calls,
+        scenario.max_llm_calls
+    );
+    assert!(
+        report.backtracks <= scenario.max_backtracks,
+        "{} backtracks, expected at most {}",
+        report.backtracks,
+        scenario.max_backtracks
+    );
+    report
+}
+
+#[tokio::test]
+async fn clean() {
+    let report = run(&CLEAN).await;
+    assert_eq!(report.conflicts, 0);
+}
+
+#[tokio::test]
+async fn persistent_wrong_answer() {
+    let report = run(&PERSISTENT_WRONG_ANSWER).await;
+    assert!(report.conflicts > 0);
+}
+
+#[tokio::test]
+async fn interacting_wrong_answers() {
+    let report = run(&INTERACTING_WRONG_ANSWERS).await;
+    assert!(report.conflicts > 0);
+}
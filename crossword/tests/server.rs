@@ -0,0 +1,129 @@
+#![cfg(feature = "server")]
+
+use std::{net::SocketAddr, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    Router,
+};
+use crossword::{
+    server::{router, RateLimit},
+    solver::{LLMSolver, MockLLM},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+const MINI_ANSWERS: &str = include_str!("fixtures/mini1_answers.yaml");
+const MINI_IPUZ: &str = include_str!("fixtures/mini1.ipuz");
+
+fn app(limit: RateLimit) -> Router {
+    let llm = MockLLM::from_yaml(MINI_ANSWERS.as_bytes()).unwrap();
+    router(LLMSolver::with_model(Box::new(llm)), limit)
+}
+
+fn unlimited() -> Router {
+    app(RateLimit {
+        requests: u32::MAX,
+        per: Duration::from_secs(60),
+    })
+}
+
+/// Send a request from the given client, returning the status and JSON body of the response
+async fn post(app: Router, client: [u8; 4], uri: &str, body: String) -> (StatusCode, Value) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let response = app
+        .layer(MockConnectInfo(SocketAddr::from((client, 4000))))
+        .oneshot(request)
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn solve_clue() {
+    let question = json!({
+        "clue": "Money in hand",
+        "length": 4,
+        "pattern": "C___",
+        "crossings": [{"clue": "Grasped", "pattern": "H___", "index": 3, "at": 0}],
+    });
+    let (status, body) = post(
+        unlimited(),
+        [127, 0, 0, 1],
+        "/solve-clue",
+        question.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["candidates"][0]["answer"], "CASH");
+    assert_eq!(body["candidates"][0]["confidence"], 100);
+}
+
+#[tokio::test]
+async fn invalid_question() {
+    let question = json!({"clue": "Money in hand", "length": 4, "pattern": "C__"});
+    let (status, body) = post(
+        unlimited(),
+        [127, 0, 0, 1],
+        "/solve-clue",
+        question.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "pattern has 3 cells but length is 4");
+}
+
+#[tokio::test]
+async fn solve_puzzle() {
+    let (status, body) = post(
+        unlimited(),
+        [127, 0, 0, 1],
+        "/solve-puzzle",
+        MINI_IPUZ.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["solved"], true);
+    assert_eq!(
+        body["grid"],
+        json!(["#CASH", "FARCE", "ANGEL", "ROUND", "MEET#"])
+    );
+    assert_eq!(body["answers"][0]["answer"], "CASH");
+
+    let (status, _) = post(
+        unlimited(),
+        [127, 0, 0, 1],
+        "/solve-puzzle",
+        String::from("{}"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_limit() {
+    let app = app(RateLimit {
+        requests: 2,
+        per: Duration::from_secs(60),
+    });
+    let question = json!({"clue": "Money in hand", "length": 4}).to_string();
+    for _ in 0..2 {
+        let (status, _) = post(app.clone(), [10, 0, 0, 1], "/solve-clue", question.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _) = post(app.clone(), [10, 0, 0, 1], "/solve-clue", question.clone()).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    // Every client has its own limit
+    let (status, _) = post(app.clone(), [10, 0, 0, 2], "/solve-clue", question.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    tokio::time::advance(Duration::from_secs(60)).await;
+    let (status, _) = post(app, [10, 0, 0, 1], "/solve-clue", question).await;
+    assert_eq!(status, StatusCode::OK);
+}
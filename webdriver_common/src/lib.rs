@@ -0,0 +1,342 @@
+use std::{
+    fmt, fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use thirtyfour::{prelude::WebDriverResult, By, ChromeCapabilities, WebDriver};
+
+/// How often to look for modals while waiting on them to appear
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Window size of a headless browser unless another is given. Without a screen to fill, Chrome
+/// opens a window small enough for pages to switch to their mobile layout
+const HEADLESS_WINDOW_SIZE: (u32, u32) = (1920, 1080);
+
+/// Options used to launch Chrome
+#[derive(Debug, Clone, Default)]
+pub struct DriverOptions {
+    /// Run without a visible browser window
+    pub headless: bool,
+    pub incognito: bool,
+    /// Window width and height in pixels. The window is maximized if not provided
+    pub window_size: Option<(u32, u32)>,
+    /// Additional arguments passed directly to Chrome
+    pub extra_args: Vec<String>,
+    /// Path to the Chrome binary. The 'thirtyfour' library will attempt to
+    /// find the binary itself, but certain installations may require this
+    /// to be passed explicitly.
+    pub binary_path: Option<String>,
+    pub page_load_timeout: Option<Duration>,
+    /// Chrome profile directory, to keep cookies and settings between runs
+    pub user_data_dir: Option<PathBuf>,
+    /// Directory to save a screenshot and the source of the page into when something fails
+    pub debug_dir: Option<PathBuf>,
+    /// CSS selectors of additional modals to dismiss when a page loads
+    pub modals: Vec<String>,
+    /// Only dismiss the modals given in `modals`, ignoring those each game knows about
+    pub skip_default_modals: bool,
+    /// Where to send messages about problems that were worked around. They are dropped without
+    /// one
+    pub reporter: Option<Reporter>,
+}
+
+/// Receives messages about problems a driver worked around without failing, so each binary
+/// decides where they go instead of the library printing them
+#[derive(Clone)]
+pub struct Reporter(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl Reporter {
+    pub fn new(report: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, message: &str) {
+        (self.0)(message)
+    }
+}
+
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Reporter")
+    }
+}
+
+/// Command line flags shared by every binary that drives Chrome
+#[derive(Args, Debug)]
+pub struct DriverArgs {
+    // Path to the Chrome binary. The 'thirtyfour' library will attempt to
+    // find the binary itself, but certain installations may require this
+    // to be passed explicitly.
+    #[clap(short, long)]
+    pub chrome_binary_path: Option<String>,
+    // URL of running chromedriver application. -c is taken by --chrome-binary-path
+    #[clap(short = 'u', long, default_value = "http://localhost:9515")]
+    pub chromedriver_server_url: String,
+    // Run Chrome without a visible window, e.g. on a server or in a container
+    #[clap(long)]
+    pub headless: bool,
+    // Additional argument passed directly to Chrome. May be repeated
+    #[clap(long = "chrome-arg", allow_hyphen_values = true)]
+    pub chrome_args: Vec<String>,
+    // Chrome profile directory, to keep cookies and settings between runs
+    #[clap(long)]
+    pub user_data_dir: Option<PathBuf>,
+    // Directory to save a screenshot and the source of the page into when something fails
+    #[clap(long)]
+    pub debug_dir: Option<PathBuf>,
+    // CSS selector of a modal to dismiss when the page loads, for when NYT adds a new one. May
+    // be repeated
+    #[clap(long = "modal")]
+    pub modals: Vec<String>,
+    // Only dismiss the modals passed with --modal, for when NYT changes a built-in one
+    #[clap(long)]
+    pub skip_default_modals: bool,
+}
+
+impl DriverArgs {
+    pub fn options(&self) -> DriverOptions {
+        DriverOptions {
+            binary_path: self.chrome_binary_path.clone(),
+            headless: self.headless,
+            extra_args: self.chrome_args.clone(),
+            user_data_dir: self.user_data_dir.clone(),
+            debug_dir: self.debug_dir.clone(),
+            modals: self.modals.clone(),
+            skip_default_modals: self.skip_default_modals,
+            ..Default::default()
+        }
+    }
+}
+
+/// Create a new Chrome session on the running chromedriver
+pub async fn build_driver(
+    chromedriver_server_url: &str,
+    options: &DriverOptions,
+) -> WebDriverResult<WebDriver> {
+    let mut capabilities = ChromeCapabilities::new();
+    if options.headless {
+        capabilities.add_chrome_arg("--headless=new")?;
+    }
+    if options.incognito {
+        capabilities.add_chrome_arg("--incognito")?;
+    }
+    let window_size = options
+        .window_size
+        .or(options.headless.then_some(HEADLESS_WINDOW_SIZE));
+    match window_size {
+        Some((width, height)) => {
+            capabilities.add_chrome_arg(&format!("--window-size={},{}", width, height))?
+        }
+        None => capabilities.add_chrome_arg("--start-maximized")?,
+    }
+    if let Some(dir) = &options.user_data_dir {
+        capabilities.add_chrome_arg(&format!("--user-data-dir={}", dir.display()))?;
+    }
+    for arg in options.extra_args.iter() {
+        capabilities.add_chrome_arg(arg)?;
+    }
+    if let Some(p) = &options.binary_path {
+        capabilities.set_binary(p)?;
+    }
+    let driver = WebDriver::new(chromedriver_server_url, capabilities).await?;
+    if let Some(timeout) = options.page_load_timeout {
+        driver.set_page_load_timeout(timeout).await?;
+    }
+    Ok(driver)
+}
+
+/// Save a screenshot and the HTML source of the current page into `dir`, named after `label` and
+/// the time, returning the path of the screenshot. The source is saved next to it
+pub async fn capture_page(driver: &WebDriver, dir: &Path, label: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let screenshot = dir.join(format!("{}-{}.png", label, millis));
+    driver
+        .screenshot(&screenshot)
+        .await
+        .map_err(io::Error::other)?;
+    let source = driver.source().await.map_err(io::Error::other)?;
+    fs::write(screenshot.with_extension("html"), source)?;
+    Ok(screenshot)
+}
+
+/// Pass the result through, first saving the page into `dir` if it is an error. Used around
+/// anything that may fail because the page is not what was expected, so it can be looked at
+/// afterwards. Where the page was saved, or why it could not be, is returned alongside the
+/// result for the caller to report. A failure to save never replaces the original error
+pub async fn diagnose<T, E>(
+    driver: &WebDriver,
+    dir: Option<&Path>,
+    label: &str,
+    result: Result<T, E>,
+) -> (Result<T, E>, Option<io::Result<PathBuf>>) {
+    let capture = match (&result, dir) {
+        (Err(_), Some(dir)) => Some(capture_page(driver, dir, label).await),
+        _ => None,
+    };
+    (result, capture)
+}
+
+/// Modals to dismiss on page load: a game's defaults, unless skipped, followed by any extra
+/// selectors from the options. Each selector appears once, in the order first given
+pub fn modal_selectors<'a>(defaults: &[&'a str], options: &'a DriverOptions) -> Vec<&'a str> {
+    let defaults: &[&str] = if options.skip_default_modals {
+        &[]
+    } else {
+        defaults
+    };
+    let mut selectors: Vec<&str> = Vec::new();
+    for selector in defaults
+        .iter()
+        .copied()
+        .chain(options.modals.iter().map(String::as_str))
+    {
+        if !selectors.contains(&selector) {
+            selectors.push(selector);
+        }
+    }
+    selectors
+}
+
+/// Click every element matching the provided CSS selectors that can be clicked right now,
+/// without waiting for any to appear. Returns the number of elements that were clicked
+pub async fn dismiss_present(driver: &WebDriver, selectors: &[&str]) -> WebDriverResult<usize> {
+    let mut dismissed = 0;
+    for selector in selectors {
+        for element in driver.find_all(By::Css(*selector)).await? {
+            if element.is_clickable().await? && element.click().await.is_ok() {
+                dismissed += 1;
+            }
+        }
+    }
+    Ok(dismissed)
+}
+
+/// Check the condition every `interval` until it holds or the timeout passes, returning
+/// whether it held. The condition is always checked at least once
+pub async fn wait_until<F, Fut>(
+    timeout: Duration,
+    interval: Duration,
+    mut condition: F,
+) -> WebDriverResult<bool>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = WebDriverResult<bool>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if condition().await? {
+            return Ok(true);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Click any elements matching the provided CSS selectors that appear before the timeout
+///
+/// Each selector is clicked at most once, and we stop waiting as soon as all of them have
+/// been dismissed. Selectors that never appear are ignored. Returns the number of elements
+/// that were clicked.
+pub async fn dismiss_any(
+    driver: &WebDriver,
+    selectors: &[&str],
+    timeout: Duration,
+) -> WebDriverResult<usize> {
+    let deadline = Instant::now() + timeout;
+    let mut remaining = selectors.to_vec();
+    let mut dismissed = 0;
+    while !remaining.is_empty() && Instant::now() < deadline {
+        let mut clicked = None;
+        for (i, selector) in remaining.iter().enumerate() {
+            for element in driver.find_all(By::Css(*selector)).await? {
+                // Modals animate in, so a click may be intercepted. Try again next poll
+                if element.is_clickable().await? && element.click().await.is_ok() {
+                    clicked = Some(i);
+                    break;
+                }
+            }
+            if clicked.is_some() {
+                break;
+            }
+        }
+        match clicked {
+            Some(i) => {
+                remaining.remove(i);
+                dismissed += 1;
+            }
+            None => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+    Ok(dismissed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use super::{modal_selectors, wait_until, DriverOptions};
+
+    static INTERVAL: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn modal_selector_merging() {
+        let defaults = [".terms", ".consent"];
+        let mut options = DriverOptions::default();
+        assert_eq!(modal_selectors(&defaults, &options), defaults);
+
+        // Extra selectors follow the defaults, without repeating any
+        options.modals = vec![".promo".into(), ".consent".into(), ".promo".into()];
+        assert_eq!(
+            modal_selectors(&defaults, &options),
+            [".terms", ".consent", ".promo"]
+        );
+
+        options.skip_default_modals = true;
+        assert_eq!(modal_selectors(&defaults, &options), [".promo", ".consent"]);
+        options.modals.clear();
+        assert!(modal_selectors(&defaults, &options).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_until_condition() {
+        // Met on the third check, after waiting two intervals
+        let checks = Cell::new(0);
+        let start = tokio::time::Instant::now();
+        let met = wait_until(Duration::from_secs(1), INTERVAL, || async {
+            checks.set(checks.get() + 1);
+            Ok(checks.get() == 3)
+        })
+        .await
+        .unwrap();
+        assert!(met);
+        assert_eq!(checks.get(), 3);
+        assert_eq!(start.elapsed(), INTERVAL * 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_until_timeout() {
+        let start = tokio::time::Instant::now();
+        let met = wait_until(INTERVAL * 3, INTERVAL, || async { Ok(false) })
+            .await
+            .unwrap();
+        assert!(!met);
+        assert!(start.elapsed() >= INTERVAL * 3 && start.elapsed() < INTERVAL * 4);
+
+        // A condition that is already met never waits
+        let start = tokio::time::Instant::now();
+        assert!(wait_until(Duration::ZERO, INTERVAL, || async { Ok(true) })
+            .await
+            .unwrap());
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,35 @@
+use std::borrow::Cow;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use wordle::{algorithm::OPENER, Correctness, Dictionary, Guess, Word, WordleSolver};
+
+/// Filter the whole dictionary by the opener's feedback, which is the largest filtering step of
+/// a game
+fn filter_dictionary(c: &mut Criterion) {
+    let dictionary = Dictionary::embedded();
+    let words: Vec<Word> = dictionary.iter().map(|(word, _)| *word).collect();
+    let guess = Guess {
+        word: Cow::Owned(OPENER),
+        mask: Correctness::compute(b"pious", &OPENER),
+    };
+    let mut group = c.benchmark_group("filter dictionary");
+    group.bench_function("exact", |b| {
+        b.iter(|| words.iter().filter(|w| guess.matches(black_box(w))).count())
+    });
+    group.bench_function("prefiltered", |b| {
+        let filter = guess.filter();
+        b.iter(|| {
+            words
+                .iter()
+                .filter(|w| filter.matches(black_box(w)))
+                .count()
+        })
+    });
+    group.bench_function("observe", |b| {
+        b.iter(|| WordleSolver::with_dictionary(dictionary.clone()).observe(black_box(&guess)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, filter_dictionary);
+criterion_main!(benches);
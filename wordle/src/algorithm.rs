@@ -1,31 +1,55 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, path::Path, sync::Arc};
 
-use crate::{Correctness, Guess, Guesser, Word};
+use crate::{Correctness, Dictionary, DictionaryError, Guess, Guesser, Word};
 
+/// The first guess of every game. Computing it requires a pass over the entire
+/// dictionary so it is hardcoded instead.
+pub const OPENER: Word = *b"crate";
+
+/// Guesses the answer from the words of a dictionary. Without the `embedded-dictionary` feature
+/// there is no dictionary for `WordleSolver::new` or `Default`: load one with
+/// `WordleSolver::try_new_from_path`
 pub struct WordleSolver {
-    remaining: Vec<(&'static Word, usize)>,
+    dictionary: Arc<Dictionary>,
+    /// Positions in the dictionary of the words consistent with every Guess so far
+    remaining: Vec<usize>,
     patterns: Vec<[Correctness; 5]>,
 }
 
+#[cfg(feature = "embedded-dictionary")]
 impl Default for WordleSolver {
     fn default() -> Self {
         Self::new()
     }
 }
 impl WordleSolver {
+    /// Create a solver using the dictionary embedded in the crate
+    #[cfg(feature = "embedded-dictionary")]
+    pub fn new() -> Self {
+        Self::with_dictionary(Dictionary::embedded())
+    }
+
+    /// There is no embedded dictionary to create a solver with, so this always panics
+    #[cfg(not(feature = "embedded-dictionary"))]
+    #[deprecated(note = "enable `embedded-dictionary` or use try_new_from_path")]
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        panic!("WordleSolver::new needs the 'embedded-dictionary' feature, use try_new_from_path")
+    }
+
+    /// Create a solver using a dictionary loaded from a file at runtime. This replaces
+    /// `WordleSolver::new` when the crate is built without the `embedded-dictionary` feature
+    pub fn try_new_from_path(path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        Ok(Self::with_dictionary(Arc::new(Dictionary::from_path(
+            path,
+        )?)))
+    }
+
+    /// Create a solver guessing from a dictionary, which can be shared with a game
+    pub fn with_dictionary(dictionary: Arc<Dictionary>) -> Self {
         Self {
-            remaining: Vec::from_iter(crate::DICTIONARY.lines().map(|l| {
-                let (word, count) = l
-                    .split_once(' ')
-                    .expect("Every line is word + space + frequency");
-                let count: usize = count.parse().expect("every count is a number");
-                let word = word
-                    .as_bytes()
-                    .try_into()
-                    .expect("every dictionary  word is 5 characters");
-                (word, count)
-            })),
+            remaining: (0..dictionary.len()).collect(),
+            dictionary,
             patterns: Correctness::permutations().collect(),
         }
     }
@@ -33,28 +57,44 @@ impl WordleSolver {
 
 #[derive(Debug, Copy, Clone)]
 struct Candidate {
-    word: &'static Word,
+    /// Position of the word in the dictionary
+    index: usize,
     goodness: f64,
 }
 
-impl Guesser for WordleSolver {
-    fn guess(&mut self, history: &[Guess]) -> Word {
-        if history.is_empty() {
-            return *b"crate";
-        }
-        if let Some(last) = history.last() {
-            self.remaining.retain(|(word, _count)| last.matches(word));
-        }
-        let remaining_count: usize = self.remaining.iter().map(|&(_, c)| c).sum();
+impl WordleSolver {
+    /// Remove any remaining words that are inconsistent with the provided Guess
+    pub fn observe(&mut self, guess: &Guess) {
+        let dictionary = &self.dictionary;
+        let filter = guess.filter();
+        self.remaining.retain(|&index| {
+            dictionary
+                .get(index)
+                .is_some_and(|(word, _count)| filter.matches(word))
+        });
+    }
 
-        let mut best: Option<Candidate> = None;
-        for &(word, count) in &self.remaining {
+    /// Rank the remaining words by their expected information, returning the best `n`
+    ///
+    /// This is quadratic in the number of remaining words, so it is slow to call
+    /// before any guesses have narrowed down the dictionary.
+    pub fn suggestions(&mut self, n: usize) -> Vec<Word> {
+        let dictionary = &self.dictionary;
+        let remaining: Vec<(&Word, usize)> = self
+            .remaining
+            .iter()
+            .filter_map(|&index| dictionary.get(index))
+            .collect();
+        let remaining_count: usize = remaining.iter().map(|&(_, c)| c).sum();
+
+        let mut candidates = Vec::with_capacity(remaining.len());
+        for (&index, &(word, count)) in self.remaining.iter().zip(&remaining) {
             // consider a world where we did guess word and got pattern
             // as the Correctness match. Now, compute what then is left.
             let mut sum = 0.0;
             self.patterns.retain(|pattern| {
                 let mut in_pattern_total = 0;
-                for (candidate, c) in &self.remaining {
+                for (candidate, c) in &remaining {
                     let g = Guess {
                         word: Cow::Borrowed(word),
                         mask: *pattern,
@@ -79,15 +119,29 @@ impl Guesser for WordleSolver {
             // us more information
             let p_word = count as f64 / remaining_count as f64;
             let goodness = -sum * p_word;
-            if let Some(c) = best {
-                // Is this one better
-                if goodness > c.goodness {
-                    best = Some(Candidate { word, goodness })
-                }
-            } else {
-                best = Some(Candidate { word, goodness })
-            }
+            candidates.push(Candidate { index, goodness });
+        }
+        // Stable sort so that ties are broken by dictionary order
+        candidates.sort_by(|a, b| b.goodness.total_cmp(&a.goodness));
+        candidates
+            .into_iter()
+            .take(n)
+            .filter_map(|c| dictionary.get(c.index))
+            .map(|(word, _)| *word)
+            .collect()
+    }
+}
+
+impl Guesser for WordleSolver {
+    fn guess(&mut self, history: &[Guess]) -> Word {
+        if history.is_empty() {
+            return OPENER;
+        }
+        if let Some(last) = history.last() {
+            self.observe(last);
         }
-        *best.unwrap().word
+        self.suggestions(1)
+            .pop()
+            .expect("No remaining words are consistent with the history")
     }
 }
@@ -0,0 +1,142 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use wordle::Dictionary;
+
+/// Build a dictionary in the format of `dictionary.txt` from word lists and word frequencies
+#[derive(Parser)]
+struct Opts {
+    // Files of accepted words, one per line. Only words in one of them are kept
+    #[clap(long, required = true)]
+    words: Vec<PathBuf>,
+    // Files of a word, a tab and how often it occurs, e.g. an extract of the Google Books
+    // n-grams. Counts of the same word are added up, whatever its case
+    #[clap(long)]
+    frequencies: Vec<PathBuf>,
+    // Drop words seen fewer times than this in the frequency files
+    #[clap(long, default_value_t = 0)]
+    min_count: usize,
+    // Added to the count of every word, so that words missing from the frequency files can
+    // still be the answer
+    #[clap(long, default_value_t = 1)]
+    smoothing: usize,
+    // File to write the dictionary to
+    #[clap(long)]
+    output: PathBuf,
+}
+
+/// Why a word was left out of the dictionary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Dropped {
+    NotFiveLetters,
+    Duplicate,
+    Rare,
+}
+
+/// Words of a word list, lowercased, that are five ASCII letters
+fn accepted_words(
+    text: &str,
+    words: &mut BTreeSet<String>,
+    dropped: &mut BTreeMap<Dropped, usize>,
+) {
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let word = line.to_ascii_lowercase();
+        let reason = if word.len() != 5 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+            Dropped::NotFiveLetters
+        } else if words.insert(word) {
+            continue;
+        } else {
+            Dropped::Duplicate
+        };
+        *dropped.entry(reason).or_default() += 1;
+    }
+}
+
+/// Add up the counts of a frequency file into `counts`, keyed by the lowercased word
+fn add_frequencies(
+    path: &Path,
+    text: &str,
+    counts: &mut HashMap<String, usize>,
+) -> Result<(), String> {
+    for (i, line) in text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+    {
+        let (word, count) = line
+            .split_once('\t')
+            .and_then(|(word, count)| Some((word, count.trim().parse::<usize>().ok()?)))
+            .ok_or_else(|| {
+                format!(
+                    "{}:{}: expected a word, a tab and a count",
+                    path.display(),
+                    i + 1
+                )
+            })?;
+        *counts.entry(word.trim().to_ascii_lowercase()).or_default() += count;
+    }
+    Ok(())
+}
+
+fn build(opts: Opts) -> Result<(), Box<dyn Error>> {
+    let mut words = BTreeSet::new();
+    let mut dropped = BTreeMap::new();
+    for path in &opts.words {
+        accepted_words(&fs::read_to_string(path)?, &mut words, &mut dropped);
+    }
+    let mut counts = HashMap::new();
+    for path in &opts.frequencies {
+        add_frequencies(path, &fs::read_to_string(path)?, &mut counts)?;
+    }
+    let unlisted = counts.keys().filter(|w| !words.contains(*w)).count();
+
+    let mut output = String::new();
+    let mut kept = 0;
+    for word in &words {
+        let count = counts.get(word).copied().unwrap_or(0);
+        if count < opts.min_count {
+            *dropped.entry(Dropped::Rare).or_default() += 1;
+            continue;
+        }
+        writeln!(output, "{} {}", word, count + opts.smoothing)?;
+        kept += 1;
+    }
+    // Anything the crate would fail to load is never written
+    Dictionary::parse(&output)?;
+    fs::write(&opts.output, output)?;
+
+    println!("Kept {} words", kept);
+    for (reason, n) in dropped {
+        let why = match reason {
+            Dropped::NotFiveLetters => String::from("that are not five letters"),
+            Dropped::Duplicate => String::from("listed more than once"),
+            Dropped::Rare => format!("seen fewer than {} times", opts.min_count),
+        };
+        println!("Dropped {} words {}", n, why);
+    }
+    if unlisted > 0 {
+        println!(
+            "Ignored {} counted words missing from the word lists",
+            unlisted
+        );
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let opts: Opts = Opts::parse();
+    match build(opts) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error building dictionary: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
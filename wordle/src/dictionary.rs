@@ -0,0 +1,191 @@
+use std::{collections::HashSet, fmt, fs, io, path::Path, sync::Arc};
+
+use crate::Word;
+
+#[cfg(feature = "embedded-dictionary")]
+const EMBEDDED: &str = include_str!("../dictionary.txt");
+
+/// Words accepted as Wordle guesses along with how common each word is
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    words: Vec<(Word, usize)>,
+    /// Every word of `words`, to look them up without a scan
+    known: HashSet<Word>,
+}
+
+#[derive(Debug)]
+pub enum DictionaryError {
+    Io(io::Error),
+    /// Line is not a word and frequency separated by a space
+    MissingCount {
+        line: usize,
+    },
+    /// Word is not five lowercase ASCII letters
+    InvalidWord {
+        line: usize,
+        word: String,
+    },
+    InvalidCount {
+        line: usize,
+        count: String,
+    },
+    DuplicateWord {
+        line: usize,
+        word: String,
+    },
+    Empty,
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictionaryError::Io(e) => write!(f, "unable to read dictionary: {}", e),
+            DictionaryError::MissingCount { line } => {
+                write!(f, "line {}: expected a word and frequency", line)
+            }
+            DictionaryError::InvalidWord { line, word } => {
+                write!(f, "line {}: {:?} is not a five letter word", line, word)
+            }
+            DictionaryError::InvalidCount { line, count } => {
+                write!(f, "line {}: {:?} is not a valid frequency", line, count)
+            }
+            DictionaryError::DuplicateWord { line, word } => {
+                write!(f, "line {}: {} appears more than once", line, word)
+            }
+            DictionaryError::Empty => write!(f, "dictionary contains no words"),
+        }
+    }
+}
+
+impl std::error::Error for DictionaryError {}
+
+impl From<io::Error> for DictionaryError {
+    fn from(value: io::Error) -> Self {
+        DictionaryError::Io(value)
+    }
+}
+
+impl Dictionary {
+    /// Parse a dictionary where every line is a word, a space, and its frequency
+    pub fn parse(text: &str) -> Result<Self, DictionaryError> {
+        let mut seen = HashSet::new();
+        let mut words = vec![];
+        for (i, l) in text.lines().enumerate() {
+            let line = i + 1;
+            let (word, count) = l
+                .split_once(' ')
+                .ok_or(DictionaryError::MissingCount { line })?;
+            let parsed: Word = word
+                .as_bytes()
+                .try_into()
+                .ok()
+                .filter(|w: &Word| w.iter().all(u8::is_ascii_lowercase))
+                .ok_or_else(|| DictionaryError::InvalidWord {
+                    line,
+                    word: word.to_string(),
+                })?;
+            let count: usize = count.parse().map_err(|_| DictionaryError::InvalidCount {
+                line,
+                count: count.to_string(),
+            })?;
+            if !seen.insert(parsed) {
+                return Err(DictionaryError::DuplicateWord {
+                    line,
+                    word: word.to_string(),
+                });
+            }
+            words.push((parsed, count));
+        }
+        if words.is_empty() {
+            return Err(DictionaryError::Empty);
+        }
+        Ok(Self { words, known: seen })
+    }
+
+    /// Load a dictionary from a file in the same format as `dictionary.txt`
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The dictionary compiled into the crate, parsed on first use and shared after that
+    #[cfg(feature = "embedded-dictionary")]
+    pub fn embedded() -> Arc<Self> {
+        static DICTIONARY: std::sync::OnceLock<Arc<Dictionary>> = std::sync::OnceLock::new();
+        DICTIONARY
+            .get_or_init(|| Arc::new(Self::parse(EMBEDDED).expect("Embedded dictionary is valid")))
+            .clone()
+    }
+
+    /// Every word along with its frequency
+    pub fn iter(&self) -> impl Iterator<Item = (&Word, usize)> {
+        self.words.iter().map(|(w, c)| (w, *c))
+    }
+
+    /// Word and frequency at a position of `iter`
+    pub fn get(&self, index: usize) -> Option<(&Word, usize)> {
+        self.words.get(index).map(|(w, c)| (w, *c))
+    }
+
+    /// Whether the word is in the dictionary
+    pub fn contains(&self, word: &Word) -> bool {
+        self.known.contains(word)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dictionary, DictionaryError};
+
+    #[test]
+    fn parse() {
+        let dictionary = Dictionary::parse("crate 10\nspoil 3\n").unwrap();
+        assert_eq!(
+            dictionary.iter().collect::<Vec<_>>(),
+            vec![(b"crate", 10), (b"spoil", 3)]
+        );
+        assert_eq!(dictionary.get(1), Some((b"spoil", 3)));
+        assert_eq!(dictionary.get(2), None);
+        assert!(dictionary.contains(b"spoil"));
+        assert!(!dictionary.contains(b"sport"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text| Dictionary::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("crate 10\nspoil"),
+            "line 2: expected a word and frequency"
+        );
+        assert_eq!(
+            error("crates 10"),
+            "line 1: \"crates\" is not a five letter word"
+        );
+        assert_eq!(
+            error("CRATE 10"),
+            "line 1: \"CRATE\" is not a five letter word"
+        );
+        assert_eq!(
+            error("crate ten"),
+            "line 1: \"ten\" is not a valid frequency"
+        );
+        assert_eq!(
+            error("crate 1\ncrate 2"),
+            "line 2: crate appears more than once"
+        );
+        assert!(matches!(Dictionary::parse(""), Err(DictionaryError::Empty)));
+    }
+
+    #[cfg(feature = "embedded-dictionary")]
+    #[test]
+    fn embedded() {
+        assert!(Dictionary::embedded().len() > 10_000);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{Correctness, Guess, Word, Wordle};
+
+/// Error produced when a compact guess history can not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryError {
+    /// One-indexed position of the guess in the history
+    pub guess: usize,
+    pub kind: HistoryErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryErrorKind {
+    MissingSeparator,
+    WordLength(usize),
+    UnknownWord(String),
+    MaskLength(usize),
+    MaskCharacter(char),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "guess {}: ", self.guess)?;
+        match &self.kind {
+            HistoryErrorKind::MissingSeparator => write!(f, "expected word=mask"),
+            HistoryErrorKind::WordLength(n) => write!(f, "word has {} characters", n),
+            HistoryErrorKind::UnknownWord(w) => write!(f, "{} is not in the dictionary", w),
+            HistoryErrorKind::MaskLength(n) => write!(f, "mask has {} characters", n),
+            HistoryErrorKind::MaskCharacter(c) => write!(f, "unrecognized mask character {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Parse a word, ignoring case and surrounding whitespace
+fn parse_word(word: &str, wordle: &Wordle) -> Result<Word, HistoryErrorKind> {
+    let word = word.trim().to_ascii_lowercase();
+    let count = word.chars().count();
+    if count != 5 {
+        return Err(HistoryErrorKind::WordLength(count));
+    }
+    match word.as_bytes().try_into() {
+        Ok(w) if wordle.contains(&w) => Ok(w),
+        _ => Err(HistoryErrorKind::UnknownWord(word)),
+    }
+}
+
+/// Parse a mask written either with the letters C(orrect), M(isplaced) and W(rong),
+/// or with the emoji squares the game uses when sharing results
+fn parse_mask(mask: &str) -> Result<[Correctness; 5], HistoryErrorKind> {
+    let mask = mask
+        .trim()
+        .chars()
+        // Emoji squares are often followed by an invisible variation selector
+        .filter(|c| *c != '\u{FE0F}')
+        .map(|c| match c.to_ascii_uppercase() {
+            'C' | '🟩' => Ok(Correctness::Correct),
+            'M' | '🟨' => Ok(Correctness::Misplaced),
+            'W' | '⬛' | '⬜' => Ok(Correctness::Wrong),
+            _ => Err(HistoryErrorKind::MaskCharacter(c)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let count = mask.len();
+    mask.try_into()
+        .map_err(|_| HistoryErrorKind::MaskLength(count))
+}
+
+/// Parse a history of compact "word=mask" strings (e.g. "crate=WWMCW") into Guesses
+pub fn parse_history(
+    history: &[&str],
+    wordle: &Wordle,
+) -> Result<Vec<Guess<'static>>, HistoryError> {
+    history
+        .iter()
+        .enumerate()
+        .map(|(i, s)| -> Result<Guess<'static>, HistoryError> {
+            let error = |kind| HistoryError { guess: i + 1, kind };
+            let (word, mask) = s
+                .split_once('=')
+                .ok_or_else(|| error(HistoryErrorKind::MissingSeparator))?;
+            Ok(Guess {
+                word: Cow::Owned(parse_word(word, wordle).map_err(error)?),
+                mask: parse_mask(mask).map_err(error)?,
+            })
+        })
+        .collect()
+}
+
+/// Suggest the `n` best next guesses given a history of compact "word=mask" strings
+///
+/// A history that no dictionary word is consistent with produces no suggestions. An empty
+/// history suggests `OPENER` first, followed by the best of the rest of the dictionary. Ranking
+/// the whole dictionary takes a long time, so it is only done when `n` is more than 1.
+#[cfg(feature = "embedded-dictionary")]
+pub fn suggest_from_strings(history: &[&str], n: usize) -> Result<Vec<Word>, HistoryError> {
+    suggest_from_dictionary(history, n, crate::Dictionary::embedded())
+}
+
+#[cfg(feature = "embedded-dictionary")]
+fn suggest_from_dictionary(
+    history: &[&str],
+    n: usize,
+    dictionary: std::sync::Arc<crate::Dictionary>,
+) -> Result<Vec<Word>, HistoryError> {
+    let guesses = parse_history(history, &Wordle::with_dictionary(dictionary.clone()))?;
+    let mut solver = crate::WordleSolver::with_dictionary(dictionary);
+    if guesses.is_empty() {
+        let opener = std::iter::once(crate::algorithm::OPENER);
+        if n <= 1 {
+            return Ok(opener.take(n).collect());
+        }
+        let ranked = solver
+            .suggestions(n)
+            .into_iter()
+            .filter(|w| *w != crate::algorithm::OPENER);
+        return Ok(opener.chain(ranked).take(n).collect());
+    }
+    for guess in guesses.iter() {
+        solver.observe(guess);
+    }
+    Ok(solver.suggestions(n))
+}
+
+#[cfg(all(test, feature = "embedded-dictionary"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{algorithm::OPENER, Dictionary};
+
+    #[test]
+    fn letter_masks() {
+        assert_eq!(
+            suggest_from_strings(&["crate=WMWMW", "spoil=CCCWW"], 3),
+            Ok(vec![*b"sport"])
+        );
+    }
+
+    #[test]
+    fn emoji_masks() {
+        assert_eq!(
+            suggest_from_strings(&["crate=⬛🟨⬛🟨⬜", "spoil=🟩🟩🟩⬛️⬛️"], 3),
+            Ok(vec![*b"sport"])
+        );
+    }
+
+    #[test]
+    fn uppercase_input() {
+        assert_eq!(
+            suggest_from_strings(&["CRATE=wmwmw", " Spoil = cccww "], 3),
+            Ok(vec![*b"sport"])
+        );
+    }
+
+    #[test]
+    fn top_n() {
+        let suggestions = suggest_from_strings(&["crate=WWWWW", "sonly=CMWMW"], 3).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert!(suggestions.iter().all(|w| w[0] == b's'));
+    }
+
+    #[test]
+    fn empty_history_suggests_opener_first() {
+        assert_eq!(suggest_from_strings(&[], 1), Ok(vec![OPENER]));
+        assert_eq!(suggest_from_strings(&[], 0), Ok(vec![]));
+        // Ranking the embedded dictionary takes too long for a test
+        let dictionary = Dictionary::parse("sport 50\ncrate 10\nspoil 30\n").unwrap();
+        let suggestions = suggest_from_dictionary(&[], 5, Arc::new(dictionary)).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0], OPENER);
+    }
+
+    #[test]
+    fn inconsistent_history() {
+        assert_eq!(
+            suggest_from_strings(&["crate=CCCCW", "spoil=CCCCC"], 3),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |history: &[&str]| suggest_from_strings(history, 1).unwrap_err().to_string();
+        assert_eq!(
+            error(&["crate=WWMCW", "spoil=MWCW"]),
+            "guess 2: mask has 4 characters"
+        );
+        assert_eq!(error(&["crate"]), "guess 1: expected word=mask");
+        assert_eq!(error(&["crates=WWMCW"]), "guess 1: word has 6 characters");
+        assert_eq!(
+            error(&["zzzzz=WWMCW"]),
+            "guess 1: zzzzz is not in the dictionary"
+        );
+        assert_eq!(
+            error(&["crate=WWMCX"]),
+            "guess 1: unrecognized mask character 'X'"
+        );
+    }
+}
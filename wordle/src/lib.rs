@@ -1,16 +1,61 @@
-use std::{borrow::Cow, collections::HashSet};
+use std::{borrow::Cow, fmt, path::Path, sync::Arc};
 pub mod algorithm;
 pub use algorithm::WordleSolver;
+pub mod dictionary;
+pub use dictionary::{Dictionary, DictionaryError};
+pub mod history;
+#[cfg(feature = "embedded-dictionary")]
+pub use history::suggest_from_strings;
 pub mod web;
 
-const DICTIONARY: &str = include_str!("../dictionary.txt");
-
 pub type Word = [u8; 5];
 
+/// Most of the remaining words listed in a Failure. Games that go badly early can leave
+/// thousands
+pub const MAX_REMAINING: usize = 20;
+
+/// A game that ended without finding the answer, and how close the Guesser was
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Dictionary words consistent with every guess in alphabetical order, up to `MAX_REMAINING`
+    pub remaining: Vec<Word>,
+    /// Number of dictionary words consistent with every guess, including those not listed
+    pub total: usize,
+    /// Whether the answer is consistent with every guess. If it is the Guesser failed to search
+    /// the remaining words well, otherwise the masks or how they are matched are wrong
+    pub answer_remaining: bool,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} words remained and the answer {} one of them",
+            self.total,
+            if self.answer_remaining {
+                "was"
+            } else {
+                "was not"
+            }
+        )?;
+        for (i, word) in self.remaining.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { ", " };
+            write!(f, "{}{}", separator, String::from_utf8_lossy(word))?;
+        }
+        if self.total > self.remaining.len() {
+            write!(f, ", ...")?;
+        }
+        Ok(())
+    }
+}
+
+/// A game of Wordle against a known answer. Without the `embedded-dictionary` feature there is
+/// no dictionary for `Wordle::new` or `Default`: load one with `Wordle::try_new_from_path`
 pub struct Wordle {
-    dictionary: HashSet<&'static Word>,
+    dictionary: Arc<Dictionary>,
 }
 
+#[cfg(feature = "embedded-dictionary")]
 impl Default for Wordle {
     fn default() -> Self {
         Self::new()
@@ -18,27 +63,47 @@ impl Default for Wordle {
 }
 
 impl Wordle {
+    /// Create a game using the dictionary embedded in the crate
+    #[cfg(feature = "embedded-dictionary")]
     pub fn new() -> Self {
-        Self {
-            dictionary: HashSet::from_iter(DICTIONARY.lines().map(|l| {
-                l.split_once(' ')
-                    .expect("Every line is word + space + frequency")
-                    .0
-                    .as_bytes()
-                    .try_into()
-                    .expect("Every word must be 5 bytes")
-            })),
-        }
+        Self::with_dictionary(Dictionary::embedded())
+    }
+
+    /// There is no embedded dictionary to create a game with, so this always panics
+    #[cfg(not(feature = "embedded-dictionary"))]
+    #[deprecated(note = "enable `embedded-dictionary` or use try_new_from_path")]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        panic!("Wordle::new needs the 'embedded-dictionary' feature, use try_new_from_path")
     }
 
-    // Play six rounds where it invokes the Guesser each round
-    pub fn play<G: Guesser>(&self, answer: &'static Word, mut guesser: G) -> Option<usize> {
+    /// Create a game using a dictionary loaded from a file at runtime. This replaces
+    /// `Wordle::new` when the crate is built without the `embedded-dictionary` feature
+    pub fn try_new_from_path(path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        Ok(Self::with_dictionary(Arc::new(Dictionary::from_path(
+            path,
+        )?)))
+    }
+
+    /// Create a game accepting the words of a dictionary, which can be shared with a solver
+    pub fn with_dictionary(dictionary: Arc<Dictionary>) -> Self {
+        Self { dictionary }
+    }
+
+    /// Whether the word is an accepted Wordle guess
+    pub fn contains(&self, word: &Word) -> bool {
+        self.dictionary.contains(word)
+    }
+
+    // Play six rounds where it invokes the Guesser each round, returning the number of guesses it
+    // took or the words that were left if the answer was never found
+    pub fn play<G: Guesser>(&self, answer: &Word, mut guesser: G) -> Result<usize, Failure> {
         let mut history = Vec::new();
         // Wordle only allows 6 guesses but we want to allow more so we can see the peformance tail in failure cases
         for i in 1..=32 {
             let guess: [u8; 5] = guesser.guess(&history);
             if &guess == answer {
-                return Some(i);
+                return Ok(i);
             }
             assert!(self.dictionary.contains(&guess));
             let correctness = Correctness::compute(answer, &guess);
@@ -47,7 +112,26 @@ impl Wordle {
                 mask: correctness,
             })
         }
-        None
+        Err(self.failure(answer, &history))
+    }
+
+    /// Find the words that are still consistent with every guess of a failed game
+    fn failure(&self, answer: &Word, history: &[Guess]) -> Failure {
+        let mut remaining: Vec<Word> = self
+            .dictionary
+            .iter()
+            .map(|(word, _)| *word)
+            .filter(|word| history.iter().all(|guess| guess.matches(word)))
+            .collect();
+        remaining.sort_unstable();
+        let total = remaining.len();
+        let answer_remaining = remaining.binary_search(answer).is_ok();
+        remaining.truncate(MAX_REMAINING);
+        Failure {
+            remaining,
+            total,
+            answer_remaining,
+        }
     }
 }
 
@@ -110,6 +194,67 @@ impl Guess<'_> {
     pub fn matches(&self, word: &Word) -> bool {
         Correctness::compute(word, &self.word) == self.mask
     }
+
+    /// Precompute the checks that reject a word without computing its mask, to test many
+    /// words against the same Guess
+    pub fn filter(&self) -> GuessFilter {
+        let mut counts: Vec<(u8, usize, bool)> = vec![];
+        for (&letter, &c) in self.word.iter().zip(&self.mask) {
+            let i = match counts.iter().position(|&(l, _, _)| l == letter) {
+                Some(i) => i,
+                None => {
+                    counts.push((letter, 0, false));
+                    counts.len() - 1
+                }
+            };
+            match c {
+                Correctness::Correct | Correctness::Misplaced => counts[i].1 += 1,
+                Correctness::Wrong => counts[i].2 = true,
+            }
+        }
+        GuessFilter {
+            word: *self.word,
+            mask: self.mask,
+            counts,
+        }
+    }
+}
+
+/// A Guess with cheap checks that reject most words before their mask is computed. Matches
+/// exactly the same words as the Guess
+pub struct GuessFilter {
+    word: Word,
+    mask: [Correctness; 5],
+    /// Fewest times each letter of the guess is in a matching word, and whether a Wrong mark
+    /// makes that the exact number
+    counts: Vec<(u8, usize, bool)>,
+}
+
+impl GuessFilter {
+    pub fn matches(&self, word: &Word) -> bool {
+        self.allows(word) && Correctness::compute(word, &self.word) == self.mask
+    }
+
+    /// Whether the word passes the cheap checks. Only Correct letters can be where they are in
+    /// the guess, and a letter is in the word once for each time it is Correct or Misplaced,
+    /// and no more if it is also Wrong
+    fn allows(&self, word: &Word) -> bool {
+        let placed = self
+            .word
+            .iter()
+            .zip(word)
+            .zip(&self.mask)
+            .all(|((g, w), &c)| (g == w) == (c == Correctness::Correct));
+        placed
+            && self.counts.iter().all(|&(letter, least, exact)| {
+                let n = word.iter().filter(|&&w| w == letter).count();
+                if exact {
+                    n == least
+                } else {
+                    n >= least
+                }
+            })
+    }
 }
 pub trait Guesser {
     fn guess(&mut self, history: &[Guess]) -> Word;
@@ -126,7 +271,8 @@ macro_rules! mask {
 #[cfg(test)]
 mod tests {
     mod guess_matches {
-        use crate::Guess;
+        use crate::{Correctness, Guess, Word};
+        use proptest::prelude::*;
         use std::borrow::Cow;
 
         macro_rules! check {
@@ -156,9 +302,40 @@ mod tests {
             check!(b"abcde" + [W W W W W] disallows b"bcdea");
             check!(b"tares" + [W M M W W] disallows b"brink");
         }
+
+        /// Words from a few letters, so that they often share some
+        fn word() -> impl Strategy<Value = Word> {
+            proptest::array::uniform5(b'a'..=b'e')
+        }
+
+        fn mask() -> impl Strategy<Value = [Correctness; 5]> {
+            proptest::array::uniform5(prop_oneof![
+                Just(Correctness::Correct),
+                Just(Correctness::Misplaced),
+                Just(Correctness::Wrong),
+            ])
+        }
+
+        proptest! {
+            #[test]
+            fn filter_agrees(guess in word(), mask in mask(), candidate in word()) {
+                let guess = Guess { word: Cow::Owned(guess), mask };
+                prop_assert_eq!(guess.filter().matches(&candidate), guess.matches(&candidate));
+            }
+
+            #[test]
+            fn filter_agrees_on_played(guess in word(), answer in word(), candidate in word()) {
+                let mask = Correctness::compute(&answer, &guess);
+                let guess = Guess { word: Cow::Owned(guess), mask };
+                let filter = guess.filter();
+                prop_assert!(filter.matches(&answer));
+                prop_assert_eq!(filter.matches(&candidate), guess.matches(&candidate));
+            }
+        }
     }
+    #[cfg(feature = "embedded-dictionary")]
     mod game {
-        use crate::{Guess, Wordle};
+        use crate::{Guess, Wordle, MAX_REMAINING};
 
         macro_rules! guesser {
             (|$history:ident| $impl:block) => {{
@@ -175,7 +352,7 @@ mod tests {
         fn genius() {
             let wordle = Wordle::new();
             let guesser = guesser!(|_history| { *b"moved" });
-            assert_eq!(wordle.play(b"moved", guesser), Some(1));
+            assert_eq!(wordle.play(b"moved", guesser), Ok(1));
         }
 
         #[test]
@@ -188,7 +365,7 @@ mod tests {
                     *b"wrong"
                 }
             });
-            assert_eq!(wordle.play(b"right", guesser), Some(2));
+            assert_eq!(wordle.play(b"right", guesser), Ok(2));
         }
         #[test]
         fn impressive() {
@@ -200,7 +377,7 @@ mod tests {
                     *b"wrong"
                 }
             });
-            assert_eq!(wordle.play(b"right", guesser), Some(3));
+            assert_eq!(wordle.play(b"right", guesser), Ok(3));
         }
         #[test]
         fn splendid() {
@@ -212,7 +389,7 @@ mod tests {
                     *b"wrong"
                 }
             });
-            assert_eq!(wordle.play(b"right", guesser), Some(4));
+            assert_eq!(wordle.play(b"right", guesser), Ok(4));
         }
 
         #[test]
@@ -225,7 +402,7 @@ mod tests {
                     *b"wrong"
                 }
             });
-            assert_eq!(wordle.play(b"right", guesser), Some(5));
+            assert_eq!(wordle.play(b"right", guesser), Ok(5));
         }
 
         #[test]
@@ -238,14 +415,31 @@ mod tests {
                     *b"wrong"
                 }
             });
-            assert_eq!(wordle.play(b"right", guesser), Some(6));
+            assert_eq!(wordle.play(b"right", guesser), Ok(6));
         }
 
         #[test]
         fn oops() {
             let wordle = Wordle::new();
             let guesser = guesser!(|_history| { *b"wrong" });
-            assert_eq!(wordle.play(b"right", guesser), None);
+            let failure = wordle.play(b"right", guesser).unwrap_err();
+            assert!(failure.answer_remaining);
+            assert!(failure.total > 1);
+            assert!(failure.remaining.len() <= MAX_REMAINING);
+        }
+
+        #[test]
+        fn remaining_words() {
+            // Repeating the first guess leaves every word ending in "ight" apart from it
+            let wordle = Wordle::new();
+            let guesser = guesser!(|_history| { *b"night" });
+            let failure = wordle.play(b"right", guesser).unwrap_err();
+            assert!(failure.answer_remaining);
+            assert!(failure.remaining.contains(b"right"));
+            assert!(failure.remaining.contains(b"light"));
+            assert!(!failure.remaining.contains(b"night"));
+            assert_eq!(failure.total, failure.remaining.len());
+            assert!(failure.to_string().contains("the answer was one of them: "));
         }
     }
     mod correctness {
@@ -1,57 +1,139 @@
 use std::borrow::Cow;
+use std::path::PathBuf;
 use std::str::from_utf8;
+use std::time::Duration;
 
 use clap::Parser;
+use webdriver_common::{DriverArgs, DriverOptions};
+use wordle::algorithm::OPENER;
 use wordle::web::WordleWebDriver;
-use wordle::{Correctness, Guess, Guesser};
+use wordle::{Correctness, Guess, Guesser, Word, WordleSolver};
+
+/// Time between looks at the board while watching a game played by hand
+static WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// Suggestions printed for each guess while watching
+static WATCH_SUGGESTIONS: usize = 5;
 
 #[derive(Parser)]
 struct Opts {
-    // Path to the Chrome binary. The 'thirtyfour' library will attempt to
-    // find the binary itself, but certain installations may require this
-    // to be passed explicitly.
-    #[clap(short, long)]
-    chrome_binary_path: Option<String>,
-    // URL of running chromedriver application
-    #[clap(short, long, default_value = "http://localhost:9515")]
-    chromedriver_server_url: String,
+    #[clap(flatten)]
+    driver: DriverArgs,
+    // Path to a dictionary file to use in place of the embedded dictionary. Required
+    // when built without the 'embedded-dictionary' feature
+    #[clap(long)]
+    #[cfg_attr(not(feature = "embedded-dictionary"), clap(required = true))]
+    dictionary: Option<PathBuf>,
+    // Print suggestions for a game played by hand instead of typing the guesses
+    #[clap(long)]
+    watch: bool,
+}
+
+/// Word with each letter colored by its mask
+fn colored(word: &str, mask: [Correctness; 5]) -> String {
+    word.chars()
+        .zip(mask)
+        .map(|(c, m)| match m {
+            Correctness::Correct => format!("\x1b[92;1m{}\x1b[0m", c),
+            Correctness::Misplaced => format!("\x1b[33;1m{}\x1b[0m", c),
+            Correctness::Wrong => format!("\x1b[37;1m{}\x1b[0m", c),
+        })
+        .collect()
+}
+
+fn upper(word: &Word) -> String {
+    from_utf8(word)
+        .expect("Guess in not utf8 string!")
+        .to_ascii_uppercase()
+}
+
+/// Follow a game typed by hand, printing the best next guesses each time a row is submitted.
+/// Letters typed into a row are ignored until it is evaluated
+async fn watch(driver: &WordleWebDriver, mut solver: WordleSolver) {
+    println!("Suggested: {}", upper(&OPENER));
+    let mut seen = 0;
+    loop {
+        let rows = driver
+            .evaluated_rows()
+            .await
+            .expect("Unable to read the board");
+        // A row counts once all of its tiles have flipped over
+        while seen < rows {
+            let Some((word, mask)) = driver
+                .read_row(seen + 1)
+                .await
+                .expect("Unable to read the board")
+            else {
+                break;
+            };
+            seen += 1;
+            println!("Guessed: {}", colored(&upper(&word), mask));
+            if mask.iter().all(|c| c == &Correctness::Correct) {
+                println!("Puzzle complete, Word was {}", upper(&word));
+                return;
+            }
+            solver.observe(&Guess {
+                word: Cow::Owned(word),
+                mask,
+            });
+            if seen == 6 {
+                println!("Out of guesses");
+                return;
+            }
+            let suggestions: Vec<String> = solver
+                .suggestions(WATCH_SUGGESTIONS)
+                .iter()
+                .map(upper)
+                .collect();
+            if suggestions.is_empty() {
+                println!("No words are consistent with the board");
+            } else {
+                println!("Suggested: {}", suggestions.join(", "));
+            }
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
 }
 
 #[tokio::main]
 async fn main() {
     let opts: Opts = Opts::parse();
-    let driver = WordleWebDriver::create(
-        &opts.chromedriver_server_url,
-        opts.chrome_binary_path.as_deref(),
-    )
-    .await
-    .expect("Failed to create WebDriver");
-    let mut guesser = wordle::WordleSolver::new();
+    let mut guesser = match &opts.dictionary {
+        Some(path) => {
+            wordle::WordleSolver::try_new_from_path(path).expect("Failed to load dictionary")
+        }
+        #[cfg(feature = "embedded-dictionary")]
+        None => wordle::WordleSolver::new(),
+        #[cfg(not(feature = "embedded-dictionary"))]
+        None => {
+            unreachable!("clap requires --dictionary without the 'embedded-dictionary' feature")
+        }
+    };
+    let options = if opts.watch {
+        // Games played by hand count towards the streak of the profile they are played in
+        opts.driver.options()
+    } else {
+        DriverOptions {
+            incognito: true,
+            ..opts.driver.options()
+        }
+    };
+    let driver = WordleWebDriver::create(&opts.driver.chromedriver_server_url, &options)
+        .await
+        .expect("Failed to create WebDriver");
+    if opts.watch {
+        watch(&driver, guesser).await;
+        return;
+    }
     let mut guess_history = Vec::new();
     for i in 1..=6 {
         let guess = guesser.guess(&guess_history);
-        let guess_str = from_utf8(&guess)
-            .expect("Guess in not utf8 string!")
-            .to_ascii_uppercase();
+        let guess_str = upper(&guess);
         let mask = driver
             .guess(&guess_str, i)
             .await
             .expect("Unable to make guess");
         // Print mask result
-        println!(
-            "Guessed: {}",
-            guess_str
-                .chars()
-                .zip(mask)
-                .map(|(c, m)| {
-                    match m {
-                        Correctness::Correct => format!("\x1b[92;1m{}\x1b[0m", c),
-                        Correctness::Misplaced => format!("\x1b[33;1m{}\x1b[0m", c),
-                        Correctness::Wrong => format!("\x1b[37;1m{}\x1b[0m", c),
-                    }
-                })
-                .collect::<String>()
-        );
+        println!("Guessed: {}", colored(&guess_str, mask));
         // Win condition
         if mask.iter().all(|c| c == &Correctness::Correct) {
             println!("Puzzle complete, Word was {guess_str}");
@@ -1,41 +1,36 @@
 use std::time::Duration;
 
-use thirtyfour::{prelude::WebDriverResult, By, ChromeCapabilities, WebDriver, WebElement};
+use thirtyfour::{prelude::WebDriverResult, By, WebDriver, WebElement};
+use webdriver_common::{build_driver, dismiss_any, modal_selectors, DriverOptions};
 
-use crate::Correctness;
+use crate::{Correctness, Word};
 
 static WORDLE_URL: &str = "https://www.nytimes.com/games/wordle/index.html";
 static WORLD_GAME_CSS_ID: &str = "wordle-app-game";
 static TILE_CSS: &str = "div[aria-roledescription=\"tile\"]";
+/// Tiles of rows that have been submitted and evaluated
+static EVALUATED_TILE_CSS: &str = "div[aria-roledescription=\"tile\"][data-state=\"absent\"], \
+    div[aria-roledescription=\"tile\"][data-state=\"present\"], \
+    div[aria-roledescription=\"tile\"][data-state=\"correct\"]";
+/// Modals shown when first loading the game, in the order they appear
+static STARTUP_MODALS: [&str; 3] = [
+    ".purr-blocker-card__button",
+    ".Welcome-module_buttonContainer__K4GEw .Welcome-module_button__ZG0Zh",
+    ".Modal-module_closeIcon__TcEKb",
+];
+static MODAL_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct WordleWebDriver(WebDriver);
 
 impl WordleWebDriver {
     pub async fn create(
         chromedriver_server_url: &str,
-        binary_path: Option<&str>,
+        options: &DriverOptions,
     ) -> WebDriverResult<Self> {
-        let mut options = ChromeCapabilities::new();
-        options.add_chrome_arg("--incognito")?;
-        options.add_chrome_arg("--start-maximized")?;
-        if let Some(p) = binary_path {
-            options.set_binary(p)?;
-        }
-        let driver = WebDriver::new(chromedriver_server_url, options).await?;
+        let driver = build_driver(chromedriver_server_url, options).await?;
         driver.goto(WORDLE_URL).await?;
-        for button_cls in [
-            "purr-blocker-card__button",
-            "Welcome-module_buttonContainer__K4GEw .Welcome-module_button__ZG0Zh",
-            "Modal-module_closeIcon__TcEKb",
-        ] {
-            driver
-                .find(By::ClassName(button_cls))
-                .await?
-                .click()
-                .await?;
-            // TODO: Use a wait instead of a sleep
-            tokio::time::sleep(Duration::from_millis(500)).await;
-        }
+        let modals = modal_selectors(&STARTUP_MODALS, options);
+        dismiss_any(&driver, &modals, MODAL_TIMEOUT).await?;
         Ok(Self(driver))
     }
 
@@ -71,19 +66,83 @@ impl WordleWebDriver {
 
     /// Get the mask produced by the last Nth guess
     async fn get_mask(&self, row: usize) -> WebDriverResult<[Correctness; 5]> {
+        let (_, mask) = self.read_row(row).await?.expect("Unrecognized tile state");
+        Ok(mask)
+    }
+
+    /// Number of rows that have been submitted and evaluated. This only counts tiles, so it is
+    /// cheap enough to poll
+    pub async fn evaluated_rows(&self) -> WebDriverResult<usize> {
+        let game = self.get_game().await?;
+        Ok(game.find_all(By::Css(EVALUATED_TILE_CSS)).await?.len() / 5)
+    }
+
+    /// Word and mask of the Nth row, None until it has been submitted and evaluated
+    pub async fn read_row(&self, row: usize) -> WebDriverResult<Option<(Word, [Correctness; 5])>> {
         let game = self.get_game().await?;
         let row = game
             .find(By::Css(&format!("div[aria-label=\"Row {row}\"")))
             .await?;
-        let mut mask = vec![];
-        for c in row.find_all(By::Css(TILE_CSS)).await? {
-            match c.attr("data-state").await?.unwrap().as_str() {
-                "absent" => mask.push(Correctness::Wrong),
-                "present" => mask.push(Correctness::Misplaced),
-                "correct" => mask.push(Correctness::Correct),
-                _ => panic!("Unrecognized tile state"),
-            }
+        let mut letters = vec![];
+        let mut states = vec![];
+        for tile in row.find_all(By::Css(TILE_CSS)).await? {
+            letters.push(tile.text().await?);
+            states.push(tile.attr("data-state").await?.unwrap_or_default());
         }
-        Ok(mask.try_into().unwrap())
+        let word: Option<Word> = letters
+            .concat()
+            .trim()
+            .to_ascii_lowercase()
+            .as_bytes()
+            .try_into()
+            .ok();
+        Ok(word.zip(row_mask(&states)))
+    }
+}
+
+/// Mask of a row from the data-state of each of its tiles, None unless every tile has been
+/// evaluated. Tiles that have been typed but not submitted are "tbd"
+fn row_mask(states: &[String]) -> Option<[Correctness; 5]> {
+    let mask: Vec<Correctness> = states
+        .iter()
+        .map(|state| match state.as_str() {
+            "absent" => Some(Correctness::Wrong),
+            "present" => Some(Correctness::Misplaced),
+            "correct" => Some(Correctness::Correct),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    mask.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::row_mask;
+    use crate::Correctness::{Correct, Misplaced, Wrong};
+
+    fn states(states: &[&str]) -> Vec<String> {
+        states.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn row_masks() {
+        assert_eq!(
+            row_mask(&states(&[
+                "correct", "present", "absent", "absent", "correct"
+            ])),
+            Some([Correct, Misplaced, Wrong, Wrong, Correct])
+        );
+        // Letters typed but not submitted, and rows not reached yet
+        assert_eq!(
+            row_mask(&states(&["tbd", "tbd", "tbd", "empty", "empty"])),
+            None
+        );
+        assert_eq!(row_mask(&states(&["empty"; 5])), None);
+        // Still flipping over
+        assert_eq!(
+            row_mask(&states(&["correct", "correct", "tbd", "tbd", "tbd"])),
+            None
+        );
+        assert_eq!(row_mask(&states(&["correct"; 4])), None);
     }
 }
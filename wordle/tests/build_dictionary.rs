@@ -0,0 +1,109 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use wordle::Dictionary;
+
+const FIXTURES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/build_dictionary"
+);
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(FIXTURES).join(name)
+}
+
+/// Run build_dictionary, writing to a file of its own in the temporary directory
+fn build(name: &str, args: &[&str]) -> (Output, PathBuf) {
+    let output = std::env::temp_dir().join(format!(
+        "build_dictionary_{}_{}.txt",
+        name,
+        std::process::id()
+    ));
+    let run = Command::new(env!("CARGO_BIN_EXE_build_dictionary"))
+        .args(args)
+        .arg("--output")
+        .arg(&output)
+        .output()
+        .unwrap();
+    (run, output)
+}
+
+#[test]
+fn builds_expected_dictionary() {
+    let words = fixture("words.txt");
+    let new_words = fixture("new_words.txt");
+    let frequencies = fixture("frequencies.tsv");
+    let (run, output) = build(
+        "expected",
+        &[
+            "--words",
+            words.to_str().unwrap(),
+            "--words",
+            new_words.to_str().unwrap(),
+            "--frequencies",
+            frequencies.to_str().unwrap(),
+            "--min-count",
+            "5",
+        ],
+    );
+    assert!(run.status.success());
+    let built = fs::read_to_string(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert_eq!(built, fs::read_to_string(fixture("expected.txt")).unwrap());
+    assert_eq!(Dictionary::parse(&built).unwrap().len(), 4);
+
+    let stats = String::from_utf8(run.stdout).unwrap();
+    assert_eq!(
+        stats.lines().collect::<Vec<_>>(),
+        vec![
+            "Kept 4 words",
+            "Dropped 2 words that are not five letters",
+            "Dropped 1 words listed more than once",
+            "Dropped 2 words seen fewer than 5 times",
+            "Ignored 1 counted words missing from the word lists",
+        ]
+    );
+}
+
+#[test]
+fn smooths_missing_words() {
+    let new_words = fixture("new_words.txt");
+    let frequencies = fixture("frequencies.tsv");
+    let (run, output) = build(
+        "smoothing",
+        &[
+            "--words",
+            new_words.to_str().unwrap(),
+            "--frequencies",
+            frequencies.to_str().unwrap(),
+            "--smoothing",
+            "2",
+        ],
+    );
+    assert!(run.status.success());
+    let built = fs::read_to_string(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert_eq!(built, "plant 52\nzonks 2\n");
+}
+
+#[test]
+fn rejects_invalid_frequencies() {
+    let words = fixture("words.txt");
+    let (run, output) = build(
+        "invalid",
+        &[
+            "--words",
+            words.to_str().unwrap(),
+            "--frequencies",
+            words.to_str().unwrap(),
+        ],
+    );
+    assert_eq!(run.status.code(), Some(2));
+    assert!(String::from_utf8(run.stderr)
+        .unwrap()
+        .contains("words.txt:1: expected a word, a tab and a count"));
+    assert!(!output.exists());
+}
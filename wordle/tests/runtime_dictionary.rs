@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use wordle::{Dictionary, DictionaryError, Wordle, WordleSolver};
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/small_dictionary.txt"
+);
+const WORDS: &str = include_str!("fixtures/small_dictionary.txt");
+
+#[test]
+fn complete_solves_with_runtime_dictionary() {
+    let wordle = Wordle::try_new_from_path(FIXTURE).unwrap();
+    for line in WORDS.lines() {
+        let (answer, _) = line.split_once(' ').unwrap();
+        let guesser = WordleSolver::try_new_from_path(FIXTURE).unwrap();
+        if let Err(failure) = wordle.play(answer.as_bytes().try_into().unwrap(), guesser) {
+            panic!("{} was not found: {}", answer, failure);
+        }
+    }
+}
+
+#[test]
+fn shared_dictionary() {
+    // Parsed from a string that is gone before the games are played
+    let dictionary = Arc::new(Dictionary::parse(&WORDS.to_string()).unwrap());
+    let wordle = Wordle::with_dictionary(dictionary.clone());
+    for (answer, _) in dictionary.iter() {
+        let guesser = WordleSolver::with_dictionary(dictionary.clone());
+        if let Err(failure) = wordle.play(answer, guesser) {
+            panic!(
+                "{} was not found: {}",
+                String::from_utf8_lossy(answer),
+                failure
+            );
+        }
+    }
+    drop(wordle);
+    assert_eq!(Arc::strong_count(&dictionary), 1);
+}
+
+#[test]
+fn missing_dictionary() {
+    assert!(matches!(
+        WordleSolver::try_new_from_path("does/not/exist.txt"),
+        Err(DictionaryError::Io(_))
+    ));
+}